@@ -0,0 +1,164 @@
+//! Baselines: named snapshots of the planned task dates, kept to measure later changes against.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::task::{Task, TaskId};
+use crate::validation::{require_span, require_text, Validate, ValidationError};
+
+pub type BaselineId = usize;
+
+/// The planned dates of every task at the time the baseline was taken.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub id: BaselineId,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub tasks: Vec<BaselineTask>,
+}
+
+/// One task as it was planned in a baseline. The name is kept so tasks removed since can
+/// still be reported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineTask {
+    pub task_id: TaskId,
+    pub name: String,
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+}
+
+impl Baseline {
+    /// Freezes the current dates of `tasks`.
+    pub fn capture(id: BaselineId, name: &str, created_at: NaiveDateTime, tasks: &[Task]) -> Self {
+        Baseline {
+            id,
+            name: name.trim().to_string(),
+            created_at,
+            tasks: tasks
+                .iter()
+                .map(|task| BaselineTask {
+                    task_id: task.id,
+                    name: task.name.clone(),
+                    start_date: task.start_date,
+                    end_date: task.end_date,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn task(&self, id: TaskId) -> Option<&BaselineTask> {
+        self.tasks.iter().find(|task| task.task_id == id)
+    }
+}
+
+impl Validate for Baseline {
+    fn validate(&self) -> Result<(), ValidationError> {
+        require_text("baseline name", &self.name)?;
+        for task in &self.tasks {
+            require_span(task.start_date, task.end_date)?;
+        }
+        Ok(())
+    }
+}
+
+/// How far one task has moved from its baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskVariance {
+    pub task_id: TaskId,
+    pub name: String,
+    /// Start and end in the baseline; `None` for tasks added since.
+    pub baseline: Option<(NaiveDateTime, NaiveDateTime)>,
+    /// Start and end now; `None` for tasks removed since.
+    pub current: Option<(NaiveDateTime, NaiveDateTime)>,
+}
+
+impl TaskVariance {
+    /// Hours the start has moved, positive when it is now later.
+    pub fn start_hours(&self) -> Option<f64> {
+        Some(hours_between(self.baseline?.0, self.current?.0))
+    }
+
+    /// Hours the end has moved, positive when it is now later.
+    pub fn finish_hours(&self) -> Option<f64> {
+        Some(hours_between(self.baseline?.1, self.current?.1))
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        self.baseline == self.current
+    }
+}
+
+fn hours_between(from: NaiveDateTime, to: NaiveDateTime) -> f64 {
+    (to - from).num_minutes() as f64 / 60.0
+}
+
+/// Compares `tasks` with `baseline`: the current tasks in order, followed by the tasks that
+/// have been removed since the baseline was taken.
+pub fn variance(baseline: &Baseline, tasks: &[Task]) -> Vec<TaskVariance> {
+    let planned: HashMap<TaskId, &BaselineTask> = baseline.tasks.iter().map(|task| (task.task_id, task)).collect();
+    let current: HashSet<TaskId> = tasks.iter().map(|task| task.id).collect();
+    let mut rows: Vec<TaskVariance> = tasks
+        .iter()
+        .map(|task| TaskVariance {
+            task_id: task.id,
+            name: task.name.clone(),
+            baseline: planned.get(&task.id).map(|planned| (planned.start_date, planned.end_date)),
+            current: Some((task.start_date, task.end_date)),
+        })
+        .collect();
+    rows.extend(
+        baseline
+            .tasks
+            .iter()
+            .filter(|planned| !current.contains(&planned.task_id))
+            .map(|planned| TaskVariance {
+                task_id: planned.task_id,
+                name: planned.name.clone(),
+                baseline: Some((planned.start_date, planned.end_date)),
+                current: None,
+            }),
+    );
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+
+    fn at(hour: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 3).unwrap().and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn task(id: TaskId, start_hour: i64, hours: i64) -> Task {
+        Task {
+            id,
+            name: format!("Task {}", id),
+            start_date: at(start_hour),
+            end_date: at(start_hour + hours),
+            color: "#4CAF50".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reports_moved_added_and_removed_tasks() {
+        let baseline = Baseline::capture(1, " Monday ", at(0), &[task(1, 8, 4), task(2, 12, 2), task(3, 0, 1)]);
+        assert_eq!(baseline.name, "Monday");
+
+        // task 1 slipped 2.5h and got 1h longer, task 2 is unchanged, task 3 was removed, task 4 is new
+        let mut moved = task(1, 8, 4);
+        moved.start_date += Duration::minutes(150);
+        moved.end_date += Duration::minutes(210);
+        let rows = variance(&baseline, &[moved, task(2, 12, 2), task(4, 20, 1)]);
+
+        let ids: Vec<TaskId> = rows.iter().map(|row| row.task_id).collect();
+        assert_eq!(ids, vec![1, 2, 4, 3]);
+        assert_eq!((rows[0].start_hours(), rows[0].finish_hours()), (Some(2.5), Some(3.5)));
+        assert!(rows[1].is_unchanged());
+        assert_eq!((rows[2].baseline, rows[2].start_hours()), (None, None));
+        assert_eq!((rows[3].name.as_str(), rows[3].current), ("Task 3", None));
+    }
+}
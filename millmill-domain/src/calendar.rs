@@ -0,0 +1,381 @@
+//! Working calendars: the shifts a machine runs, and the days it does not.
+
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::validation::{require_text, Validate, ValidationError};
+
+pub type CalendarId = usize;
+
+/// How far `add_working_minutes` and friends look before giving up on finding working time.
+const SEARCH_DAYS: i64 = 366 * 5;
+
+/// A working period within one day. A shift whose end is not after its start runs over
+/// midnight into the next day, so 00:00-00:00 is a full day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shift {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl Shift {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Shift { start, end }
+    }
+
+    /// Reads a shift written as "06:00-14:00".
+    pub fn parse(text: &str) -> Option<Self> {
+        let (start, end) = text.split_once('-')?;
+        let time = |text: &str| NaiveTime::parse_from_str(text.trim(), "%H:%M").ok();
+        Some(Shift { start: time(start)?, end: time(end)? })
+    }
+
+    pub fn minutes(&self) -> i64 {
+        let (start, end) = self.span_on(NaiveDate::default());
+        (end - start).num_minutes()
+    }
+
+    /// The start and end of the shift when it starts on `date`.
+    fn span_on(&self, date: NaiveDate) -> (NaiveDateTime, NaiveDateTime) {
+        let start = date.and_time(self.start);
+        let mut end = date.and_time(self.end);
+        if end <= start {
+            end += Duration::days(1);
+        }
+        (start, end)
+    }
+}
+
+impl fmt::Display for Shift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+/// A day that does not follow the weekly pattern. Without shifts it is a holiday.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalendarException {
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub name: String,
+    /// The shifts worked that day instead of the weekday's.
+    #[serde(default)]
+    pub shifts: Vec<Shift>,
+}
+
+/// The working time of one or more machines.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Calendar {
+    pub id: CalendarId,
+    pub name: String,
+    /// Shifts of each weekday, Monday first.
+    pub week: [Vec<Shift>; 7],
+    #[serde(default)]
+    pub exceptions: Vec<CalendarException>,
+}
+
+impl Calendar {
+    /// The shifts that start on `date`.
+    pub fn shifts_on(&self, date: NaiveDate) -> &[Shift] {
+        match self.exceptions.iter().find(|exception| exception.date == date) {
+            Some(exception) => &exception.shifts,
+            None => &self.week[date.weekday().num_days_from_monday() as usize],
+        }
+    }
+
+    /// Whether the calendar has any working time at all. Calendars without it are ignored, so
+    /// durations stay wall-clock spans.
+    pub fn has_working_time(&self) -> bool {
+        self.week.iter().any(|shifts| !shifts.is_empty())
+            || self.exceptions.iter().any(|exception| !exception.shifts.is_empty())
+    }
+
+    /// Adds or replaces the exception for its date, keeping the list in date order.
+    pub fn set_exception(&mut self, exception: CalendarException) {
+        self.exceptions.retain(|existing| existing.date != exception.date);
+        let index = self.exceptions.partition_point(|existing| existing.date < exception.date);
+        self.exceptions.insert(index, exception);
+    }
+
+    /// The working periods between `from` and `to`, clipped to them, in order and merged where
+    /// shifts touch or overlap.
+    pub fn working_intervals(&self, from: NaiveDateTime, to: NaiveDateTime) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        let mut spans: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::new();
+        // the day before is included for shifts running over midnight into `from`
+        let mut date = from.date() - Duration::days(1);
+        while date <= to.date() {
+            spans.extend(self.shifts_on(date).iter().map(|shift| shift.span_on(date)));
+            date += Duration::days(1);
+        }
+        spans.sort();
+
+        let mut merged: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::new();
+        for (start, end) in spans {
+            let (start, end) = (start.max(from), end.min(to));
+            if start >= end {
+                continue;
+            }
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+
+    /// The gaps between working periods from `from` to `to`; used to shade the chart.
+    pub fn non_working_intervals(&self, from: NaiveDateTime, to: NaiveDateTime) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        let mut gaps = Vec::new();
+        let mut cursor = from;
+        for (start, end) in self.working_intervals(from, to) {
+            if cursor < start {
+                gaps.push((cursor, start));
+            }
+            cursor = end;
+        }
+        if cursor < to {
+            gaps.push((cursor, to));
+        }
+        gaps
+    }
+
+    /// Working minutes between `start` and `end`.
+    pub fn working_minutes(&self, start: NaiveDateTime, end: NaiveDateTime) -> i64 {
+        if !self.has_working_time() {
+            return (end - start).num_minutes();
+        }
+        self.working_intervals(start, end)
+            .iter()
+            .map(|(start, end)| (*end - *start).num_minutes())
+            .sum()
+    }
+
+    /// The time at which `minutes` of work starting at `start` is finished, skipping
+    /// non-working time. Falls back to wall-clock time when the calendar has no working time.
+    pub fn add_working_minutes(&self, start: NaiveDateTime, minutes: i64) -> NaiveDateTime {
+        if minutes <= 0 || !self.has_working_time() {
+            return start + Duration::minutes(minutes.max(0));
+        }
+        let mut remaining = minutes;
+        let mut cursor = start;
+        let horizon = start + Duration::days(SEARCH_DAYS);
+        while cursor < horizon {
+            let week_end = cursor + Duration::days(7);
+            for (from, to) in self.working_intervals(cursor, week_end) {
+                let available = (to - from).num_minutes();
+                if remaining <= available {
+                    return from + Duration::minutes(remaining);
+                }
+                remaining -= available;
+            }
+            cursor = week_end;
+        }
+        start + Duration::minutes(minutes)
+    }
+
+    /// The time at which `minutes` of work must start to finish at `end`; the reverse of
+    /// `add_working_minutes`.
+    pub fn subtract_working_minutes(&self, end: NaiveDateTime, minutes: i64) -> NaiveDateTime {
+        if minutes <= 0 || !self.has_working_time() {
+            return end - Duration::minutes(minutes.max(0));
+        }
+        let mut remaining = minutes;
+        let mut cursor = end;
+        let horizon = end - Duration::days(SEARCH_DAYS);
+        while cursor > horizon {
+            let week_start = cursor - Duration::days(7);
+            for (from, to) in self.working_intervals(week_start, cursor).into_iter().rev() {
+                let available = (to - from).num_minutes();
+                if remaining <= available {
+                    return to - Duration::minutes(remaining);
+                }
+                remaining -= available;
+            }
+            cursor = week_start;
+        }
+        end - Duration::minutes(minutes)
+    }
+
+    /// The end of the last working period at or before `time`, or `time` itself if it is
+    /// working time or none is found.
+    pub fn previous_working_time(&self, time: NaiveDateTime) -> NaiveDateTime {
+        let mut cursor = time;
+        let horizon = time - Duration::days(SEARCH_DAYS);
+        while self.has_working_time() && cursor > horizon {
+            let week_start = cursor - Duration::days(7);
+            if let Some((_, end)) = self.working_intervals(week_start, cursor).last() {
+                return *end;
+            }
+            cursor = week_start;
+        }
+        time
+    }
+
+    /// The start of the first working period at or after `time`, or `time` itself if it is
+    /// working time or none is found.
+    pub fn next_working_time(&self, time: NaiveDateTime) -> NaiveDateTime {
+        let mut cursor = time;
+        let horizon = time + Duration::days(SEARCH_DAYS);
+        while self.has_working_time() && cursor < horizon {
+            let week_end = cursor + Duration::days(7);
+            if let Some((start, _)) = self.working_intervals(cursor, week_end).first() {
+                return *start;
+            }
+            cursor = week_end;
+        }
+        time
+    }
+}
+
+impl Validate for Calendar {
+    fn validate(&self) -> Result<(), ValidationError> {
+        require_text("calendar name", &self.name)
+    }
+}
+
+/// Reads the all-day events of an iCalendar file (such as a public holiday feed) as holidays.
+/// An event spanning several days gives one holiday per day; events without a readable
+/// DTSTART are skipped.
+pub fn holidays_from_ical(text: &str) -> Vec<CalendarException> {
+    let mut holidays = Vec::new();
+    let mut event: Option<(Option<NaiveDate>, Option<NaiveDate>, String)> = None;
+    for (_, line) in unfold_ical(text) {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let name = key.split(';').next().unwrap_or_default().to_ascii_uppercase();
+        let date = || parse_ical_date_time(value).map(|time| time.date());
+        match (name.as_str(), event.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => event = Some((None, None, String::new())),
+            ("DTSTART", Some(event)) => event.0 = date(),
+            ("DTEND", Some(event)) => event.1 = date(),
+            ("SUMMARY", Some(event)) => event.2 = unescape_ical(value.trim()).replace('\n', " "),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some((Some(start), end, name)) = event.take() {
+                    // DTEND is exclusive
+                    let end = end.filter(|end| *end > start).unwrap_or(start + Duration::days(1));
+                    let mut date = start;
+                    while date < end {
+                        holidays.push(CalendarException { date, name: name.clone(), shifts: Vec::new() });
+                        date += Duration::days(1);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    holidays
+}
+
+/// The content lines of an iCalendar file with folded lines joined, each with the line number
+/// it starts on. Folded lines continue with a leading space or tab.
+pub fn unfold_ical(text: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().1.push_str(rest),
+            _ => lines.push((index + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+/// An iCalendar DATE or DATE-TIME value ("20250101" or "20250101T090000"). A date is
+/// midnight, UTC times ("...Z") are turned into local time and times with a TZID are taken
+/// as they are.
+pub fn parse_ical_date_time(value: &str) -> Option<NaiveDateTime> {
+    const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let time = NaiveDateTime::parse_from_str(utc, DATE_TIME_FORMAT).ok()?;
+        return Some(DateTime::<Utc>::from_naive_utc_and_offset(time, Utc).with_timezone(&Local).naive_local());
+    }
+    NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y%m%d").ok()?.and_hms_opt(0, 0, 0))
+}
+
+/// The text of an iCalendar TEXT value, with escaped newlines as line breaks.
+pub fn unescape_ical(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2025-03-03 is a Monday
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    /// Two shifts on weekdays, the early one on Saturday, Sunday off, and a holiday on Thursday.
+    fn plant() -> Calendar {
+        let early = Shift::parse("06:00-14:00").unwrap();
+        let late = Shift::parse("14:00 - 22:00").unwrap();
+        let mut week: [Vec<Shift>; 7] = Default::default();
+        for shifts in &mut week[..5] {
+            *shifts = vec![early, late];
+        }
+        week[5] = vec![early];
+        let mut calendar = Calendar { id: 1, name: "Two shifts".to_string(), week, exceptions: Vec::new() };
+        calendar.set_exception(CalendarException { date: at(6, 0, 0).date(), name: "Holiday".to_string(), shifts: Vec::new() });
+        calendar
+    }
+
+    #[test]
+    fn counts_and_adds_working_time_across_gaps() {
+        let calendar = plant();
+        assert_eq!(calendar.working_intervals(at(3, 0, 0), at(4, 0, 0)), vec![(at(3, 6, 0), at(3, 22, 0))]);
+        // Wednesday 20:00 to Friday 08:00: 2h on Wednesday, Thursday off, 2h on Friday
+        assert_eq!(calendar.working_minutes(at(5, 20, 0), at(7, 8, 0)), 240);
+        // 10h from Friday 20:00: 2h Friday, 8h Saturday, then Sunday is skipped
+        assert_eq!(calendar.add_working_minutes(at(7, 20, 0), 600), at(8, 14, 0));
+        assert_eq!(calendar.add_working_minutes(at(7, 20, 0), 601), at(10, 6, 1));
+        assert_eq!(calendar.next_working_time(at(8, 15, 0)), at(10, 6, 0));
+        assert_eq!(calendar.subtract_working_minutes(at(10, 8, 0), 240), at(8, 12, 0));
+        assert_eq!(calendar.previous_working_time(at(10, 3, 0)), at(8, 14, 0));
+        assert_eq!(calendar.non_working_intervals(at(8, 0, 0), at(9, 12, 0)), vec![
+            (at(8, 0, 0), at(8, 6, 0)),
+            (at(8, 14, 0), at(9, 12, 0)),
+        ]);
+
+        // overnight shifts belong to the day they start on
+        let night = Calendar { week: std::array::from_fn(|_| vec![Shift::parse("22:00-06:00").unwrap()]), ..Calendar::default() };
+        assert_eq!(night.working_minutes(at(3, 0, 0), at(3, 12, 0)), 360);
+
+        // without working time, durations stay wall-clock
+        assert_eq!(Calendar::default().add_working_minutes(at(3, 0, 0), 90), at(3, 1, 30));
+    }
+
+    #[test]
+    fn reads_holidays_from_ical() {
+        let ical = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20250101\r\nDTEND;VALUE=DATE:20250102\r\n\
+                    SUMMARY:New Year\\, \r\n Day\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20250503\r\n\
+                    DTEND;VALUE=DATE:20250506\r\nSUMMARY:Golden Week\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nSUMMARY:No date\r\n\
+                    END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let holidays = holidays_from_ical(ical);
+        let dates: Vec<String> = holidays.iter().map(|holiday| holiday.date.to_string()).collect();
+        assert_eq!(dates, vec!["2025-01-01", "2025-05-03", "2025-05-04", "2025-05-05"]);
+        assert_eq!(holidays[0].name, "New Year, Day");
+        assert!(holidays.iter().all(|holiday| holiday.shifts.is_empty()));
+
+        assert_eq!(parse_ical_date_time("20250303T093000"), Some(at(3, 9, 30)));
+        assert_eq!(parse_ical_date_time("20250303"), Some(at(3, 0, 0)));
+        assert_eq!(unescape_ical("Line 1\\nLine 2\\; more"), "Line 1\nLine 2; more");
+    }
+}
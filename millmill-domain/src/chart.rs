@@ -0,0 +1,212 @@
+//! What the window asks for when the chart is rendered to a file. The drawing itself is done
+//! by the backend, so it works without the window.
+
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::task::TaskId;
+use crate::validation::{require_range, require_span, Validate, ValidationError};
+
+/// Narrowest and widest day the chart can be drawn with (px), as the zoom on screen.
+const MIN_DAY_WIDTH: f64 = 1.0;
+const MAX_DAY_WIDTH: f64 = 2880.0;
+
+/// A file format the chart can be rendered to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartFormat {
+    /// Pages of the chosen paper size, with a title block on each.
+    #[default]
+    Pdf,
+    /// One drawing of the whole chart.
+    Svg,
+    /// One image of the whole chart.
+    Png,
+}
+
+impl ChartFormat {
+    pub const ALL: [ChartFormat; 3] = [ChartFormat::Pdf, ChartFormat::Svg, ChartFormat::Png];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            ChartFormat::Pdf => "pdf",
+            ChartFormat::Svg => "svg",
+            ChartFormat::Png => "png",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        ChartFormat::ALL.into_iter().find(|format| format.code() == code)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChartFormat::Pdf => "PDF",
+            ChartFormat::Svg => "SVG",
+            ChartFormat::Png => "PNG",
+        }
+    }
+
+    /// The file extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        self.code()
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ChartFormat::Pdf => "application/pdf",
+            ChartFormat::Svg => "image/svg+xml",
+            ChartFormat::Png => "image/png",
+        }
+    }
+
+    /// The format of a file going by its name.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let (_, extension) = name.rsplit_once('.')?;
+        ChartFormat::ALL.into_iter().find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaperSize {
+    A4,
+    #[default]
+    A3,
+}
+
+impl PaperSize {
+    pub const ALL: [PaperSize; 2] = [PaperSize::A4, PaperSize::A3];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            PaperSize::A4 => "a4",
+            PaperSize::A3 => "a3",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        PaperSize::ALL.into_iter().find(|paper| paper.code() == code)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::A3 => "A3",
+        }
+    }
+
+    /// Width and height of a sheet in millimetres.
+    pub fn size_mm(self, orientation: Orientation) -> (f64, f64) {
+        let (short, long) = match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A3 => (297.0, 420.0),
+        };
+        match orientation {
+            Orientation::Landscape => (long, short),
+            Orientation::Portrait => (short, long),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    #[default]
+    Landscape,
+    Portrait,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 2] = [Orientation::Landscape, Orientation::Portrait];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Orientation::Landscape => "landscape",
+            Orientation::Portrait => "portrait",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Orientation::ALL.into_iter().find(|orientation| orientation.code() == code)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Orientation::Landscape => "Landscape",
+            Orientation::Portrait => "Portrait",
+        }
+    }
+}
+
+/// The part of the chart to render and how.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartExport {
+    pub format: ChartFormat,
+    /// First and last day shown, both included.
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Width of one day in pixels, as the zoom on screen. PDF pages are tiled at this scale.
+    pub day_width: f64,
+    /// One row per task in outline order instead of one lane per machine.
+    #[serde(default)]
+    pub outline: bool,
+    /// Summary tasks whose children are left out of the outline.
+    #[serde(default)]
+    pub collapsed: Vec<TaskId>,
+    /// Shown above the chart and in the title block. Empty for the default title.
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub paper: PaperSize,
+    #[serde(default)]
+    pub orientation: Orientation,
+}
+
+impl ChartExport {
+    /// The days `from`..=`to` at the default zoom, as a PDF on the default paper.
+    pub fn new(from: NaiveDate, to: NaiveDate) -> Self {
+        ChartExport {
+            format: ChartFormat::default(),
+            from,
+            to,
+            day_width: 100.0,
+            outline: false,
+            collapsed: Vec::new(),
+            title: String::new(),
+            paper: PaperSize::default(),
+            orientation: Orientation::default(),
+        }
+    }
+}
+
+impl Validate for ChartExport {
+    fn validate(&self) -> Result<(), ValidationError> {
+        require_span(self.from.and_time(NaiveTime::MIN), self.to.and_time(NaiveTime::MIN))?;
+        require_range("day width", self.day_width, MIN_DAY_WIDTH, MAX_DAY_WIDTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_paper_for_orientation() {
+        assert_eq!(PaperSize::A4.size_mm(Orientation::Portrait), (210.0, 297.0));
+        assert_eq!(PaperSize::A3.size_mm(Orientation::Landscape), (420.0, 297.0));
+        assert_eq!(ChartFormat::from_file_name("board.PDF"), Some(ChartFormat::Pdf));
+        assert_eq!(ChartFormat::from_file_name("board.ics"), None);
+    }
+
+    #[test]
+    fn rejects_reversed_range_and_zoom_out_of_range() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 4, d).unwrap();
+        let mut export = ChartExport::new(day(7), day(13));
+        assert_eq!(export.validate(), Ok(()));
+        export.day_width = 0.0;
+        assert!(matches!(export.validate(), Err(ValidationError::OutOfRange { .. })));
+        export = ChartExport::new(day(13), day(7));
+        assert!(matches!(export.validate(), Err(ValidationError::EndBeforeStart { .. })));
+    }
+}
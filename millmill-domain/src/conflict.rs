@@ -0,0 +1,223 @@
+//! Detection of jobs that cannot run as planned on their machine.
+
+use std::collections::HashSet;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::machine::{Machine, MachineId};
+use crate::task::{Task, TaskId, TaskStatus};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// More jobs overlap than the machine has capacity for.
+    OverCapacity { load: u32, capacity: u32 },
+    /// A job overlaps a maintenance window.
+    Maintenance { reason: String },
+}
+
+/// A period on one machine where the plan cannot be carried out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conflict {
+    pub machine_id: MachineId,
+    pub kind: ConflictKind,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// The tasks involved, in id order.
+    pub task_ids: Vec<TaskId>,
+}
+
+impl Conflict {
+    pub fn involves(&self, id: TaskId) -> bool {
+        self.task_ids.contains(&id)
+    }
+}
+
+/// Finds over-capacity periods and maintenance clashes on every machine, ordered by
+/// machine and then by start. Unassigned, zero-length, cancelled and summary tasks never
+/// conflict; a summary only spans the jobs below it.
+pub fn find_conflicts(tasks: &[Task], machines: &[Machine]) -> Vec<Conflict> {
+    let summaries: HashSet<TaskId> = tasks.iter().filter_map(|task| task.parent_id).collect();
+    let mut conflicts = Vec::new();
+    for machine in machines {
+        let jobs: Vec<&Task> = tasks
+            .iter()
+            .filter(|task| {
+                task.machine_id == Some(machine.id)
+                    && task.start_date < task.end_date
+                    && task.status != TaskStatus::Cancelled
+                    && !summaries.contains(&task.id)
+            })
+            .collect();
+        conflicts.extend(over_capacity(machine, &jobs));
+        for window in &machine.maintenance {
+            let mut task_ids: Vec<TaskId> = jobs
+                .iter()
+                .filter(|task| window.overlaps(task.start_date, task.end_date))
+                .map(|task| task.id)
+                .collect();
+            if task_ids.is_empty() {
+                continue;
+            }
+            task_ids.sort_unstable();
+            conflicts.push(Conflict {
+                machine_id: machine.id,
+                kind: ConflictKind::Maintenance { reason: window.reason.clone() },
+                start: window.start_date,
+                end: window.end_date,
+                task_ids,
+            });
+        }
+    }
+    conflicts.sort_by_key(|conflict| (conflict.machine_id, conflict.start));
+    conflicts
+}
+
+/// Sweeps over the start and end times of `jobs` and reports every maximal period in which
+/// more than `machine.capacity` of them run at once.
+fn over_capacity(machine: &Machine, jobs: &[&Task]) -> Vec<Conflict> {
+    // (time, +1 for start / -1 for end, task id); ends sort before starts at the same time
+    let mut events: Vec<(NaiveDateTime, i32, TaskId)> = jobs
+        .iter()
+        .flat_map(|task| [(task.start_date, 1, task.id), (task.end_date, -1, task.id)])
+        .collect();
+    events.sort();
+
+    let mut conflicts = Vec::new();
+    let mut active: Vec<TaskId> = Vec::new();
+    let mut current: Option<Conflict> = None;
+    let mut index = 0;
+    while index < events.len() {
+        let time = events[index].0;
+        while index < events.len() && events[index].0 == time {
+            let (_, delta, id) = events[index];
+            if delta > 0 {
+                active.push(id);
+            } else {
+                active.retain(|active_id| *active_id != id);
+            }
+            index += 1;
+        }
+
+        let load = active.len() as u32;
+        if load > machine.capacity {
+            let conflict = current.get_or_insert_with(|| Conflict {
+                machine_id: machine.id,
+                kind: ConflictKind::OverCapacity { load, capacity: machine.capacity },
+                start: time,
+                end: time,
+                task_ids: Vec::new(),
+            });
+            if let ConflictKind::OverCapacity { load: peak, .. } = &mut conflict.kind {
+                *peak = (*peak).max(load);
+            }
+            for id in &active {
+                if !conflict.task_ids.contains(id) {
+                    conflict.task_ids.push(*id);
+                }
+            }
+        } else if let Some(mut conflict) = current.take() {
+            conflict.end = time;
+            conflict.task_ids.sort_unstable();
+            conflicts.push(conflict);
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::MaintenanceWindow;
+    use chrono::{Duration, NaiveDate};
+
+    fn at(hour: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 3).unwrap().and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn job(id: TaskId, start: i64, end: i64) -> Task {
+        Task {
+            id,
+            name: format!("Job {}", id),
+            start_date: at(start),
+            end_date: at(end),
+            color: "#4CAF50".to_string(),
+            machine_id: Some(1),
+            ..Default::default()
+        }
+    }
+
+    fn lathe() -> Machine {
+        Machine { id: 1, name: "Lathe".to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn summaries_do_not_clash_with_their_children() {
+        let tasks = vec![
+            job(1, 0, 8),
+            Task { parent_id: Some(1), ..job(2, 0, 4) },
+            Task { parent_id: Some(1), ..job(3, 4, 8) },
+        ];
+        assert_eq!(find_conflicts(&tasks, &[lathe()]), vec![]);
+    }
+
+    #[test]
+    fn capacity_decides_how_many_jobs_may_overlap() {
+        let tasks = vec![job(1, 0, 4), job(2, 2, 6), job(3, 3, 5)];
+        let conflict = |start, end, load, capacity| Conflict {
+            machine_id: 1,
+            kind: ConflictKind::OverCapacity { load, capacity },
+            start: at(start),
+            end: at(end),
+            task_ids: vec![1, 2, 3],
+        };
+        assert_eq!(find_conflicts(&tasks, &[lathe()]), vec![conflict(2, 5, 3, 1)]);
+        let pallets = Machine { capacity: 2, ..lathe() };
+        assert_eq!(find_conflicts(&tasks, &[pallets]), vec![conflict(3, 4, 3, 2)]);
+        let cell = Machine { capacity: 3, ..lathe() };
+        assert_eq!(find_conflicts(&tasks, &[cell]), vec![]);
+    }
+
+    #[test]
+    fn jobs_back_to_back_do_not_overlap() {
+        assert_eq!(find_conflicts(&[job(1, 0, 4), job(2, 4, 8)], &[lathe()]), vec![]);
+        // The third job starts as the first ends, so only it and the second clash
+        let tasks = vec![job(1, 0, 4), job(2, 4, 8), job(3, 4, 6)];
+        assert_eq!(
+            find_conflicts(&tasks, &[lathe()]),
+            vec![Conflict {
+                machine_id: 1,
+                kind: ConflictKind::OverCapacity { load: 2, capacity: 1 },
+                start: at(4),
+                end: at(6),
+                task_ids: vec![2, 3],
+            }]
+        );
+    }
+
+    #[test]
+    fn jobs_in_maintenance_windows_clash() {
+        let window = MaintenanceWindow { start_date: at(3), end_date: at(5), reason: "Spindle".to_string() };
+        let lathe = Machine { maintenance: vec![window], ..lathe() };
+        let tasks = vec![
+            // ends as the window opens
+            job(1, 0, 3),
+            job(2, 4, 8),
+            Task { status: TaskStatus::Cancelled, ..job(3, 3, 5) },
+            // a point in time takes no machine time
+            job(4, 4, 4),
+            Task { machine_id: Some(2), ..job(5, 3, 5) },
+        ];
+        assert_eq!(
+            find_conflicts(&tasks, &[lathe]),
+            vec![Conflict {
+                machine_id: 1,
+                kind: ConflictKind::Maintenance { reason: "Spindle".to_string() },
+                start: at(3),
+                end: at(5),
+                task_ids: vec![2],
+            }]
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! Critical path analysis (CPM) over the dependency graph.
+//!
+//! The forward pass never places a task earlier than its scheduled start, so the float of a
+//! manually placed task is measured from where it actually sits. The backward pass runs from
+//! the latest early finish in the list.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::dependency::{topological_order, DependencyError, DependencyKind};
+use crate::task::{Task, TaskId};
+
+/// The CPM result for one task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskTiming {
+    pub task_id: TaskId,
+    pub earliest_start: NaiveDateTime,
+    pub earliest_finish: NaiveDateTime,
+    pub latest_start: NaiveDateTime,
+    pub latest_finish: NaiveDateTime,
+    /// How far the task can slip without moving the project finish.
+    pub total_float_minutes: i64,
+    /// How far the task can slip without moving any successor.
+    pub free_float_minutes: i64,
+    /// The predecessors whose links leave the task no slack, so they set its earliest start.
+    #[serde(default)]
+    pub driving_predecessors: Vec<TaskId>,
+}
+
+impl TaskTiming {
+    pub fn total_float(&self) -> Duration {
+        Duration::minutes(self.total_float_minutes)
+    }
+
+    pub fn free_float(&self) -> Duration {
+        Duration::minutes(self.free_float_minutes)
+    }
+
+    /// Tasks with no total float drive the project finish.
+    pub fn is_critical(&self) -> bool {
+        self.total_float_minutes <= 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleAnalysis {
+    pub project_finish: Option<NaiveDateTime>,
+    pub timings: Vec<TaskTiming>,
+}
+
+impl ScheduleAnalysis {
+    pub fn timing(&self, id: TaskId) -> Option<&TaskTiming> {
+        self.timings.iter().find(|timing| timing.task_id == id)
+    }
+
+    pub fn is_critical(&self, id: TaskId) -> bool {
+        self.timing(id).is_some_and(TaskTiming::is_critical)
+    }
+
+    /// Whether the link from `predecessor` to `successor` is on the critical path: both tasks
+    /// are critical and the link is what holds the successor back. Two critical tasks can be
+    /// linked with slack when each is critical through another path.
+    pub fn is_critical_link(&self, predecessor: TaskId, successor: TaskId) -> bool {
+        self.is_critical(predecessor)
+            && self
+                .timing(successor)
+                .is_some_and(|timing| timing.is_critical() && timing.driving_predecessors.contains(&predecessor))
+    }
+
+    /// Ids of the critical tasks, in list order.
+    pub fn critical_path(&self) -> Vec<TaskId> {
+        self.timings
+            .iter()
+            .filter(|timing| timing.is_critical())
+            .map(|timing| timing.task_id)
+            .collect()
+    }
+}
+
+/// Runs the forward and backward passes. Links to tasks missing from `tasks` are ignored.
+pub fn analyze_schedule(tasks: &[Task]) -> Result<ScheduleAnalysis, DependencyError> {
+    let order = topological_order(tasks)?;
+    let by_id: HashMap<TaskId, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+    let duration = |id: TaskId| by_id[&id].end_date - by_id[&id].start_date;
+
+    // Forward pass
+    let mut early: HashMap<TaskId, (NaiveDateTime, NaiveDateTime)> = HashMap::new();
+    for &id in &order {
+        let task = by_id[&id];
+        let mut start = task.start_date;
+        for dependency in &task.dependencies {
+            let Some(&(p_start, p_finish)) = early.get(&dependency.predecessor_id) else {
+                continue;
+            };
+            let anchor = if dependency.kind.from_finish() { p_finish } else { p_start } + dependency.lag();
+            let bound = if dependency.kind.to_finish() { anchor - duration(id) } else { anchor };
+            start = start.max(bound);
+        }
+        early.insert(id, (start, start + duration(id)));
+    }
+
+    let Some(project_finish) = early.values().map(|&(_, finish)| finish).max() else {
+        return Ok(ScheduleAnalysis { project_finish: None, timings: Vec::new() });
+    };
+
+    // Successor links of each task: (successor id, kind, lag)
+    let mut successors: HashMap<TaskId, Vec<(TaskId, DependencyKind, Duration)>> = HashMap::new();
+    for task in tasks {
+        for dependency in &task.dependencies {
+            if by_id.contains_key(&dependency.predecessor_id) {
+                successors
+                    .entry(dependency.predecessor_id)
+                    .or_default()
+                    .push((task.id, dependency.kind, dependency.lag()));
+            }
+        }
+    }
+
+    // Backward pass
+    let mut late: HashMap<TaskId, (NaiveDateTime, NaiveDateTime)> = HashMap::new();
+    for &id in order.iter().rev() {
+        let own = duration(id);
+        let mut finish = project_finish;
+        for &(successor, kind, lag) in successors.get(&id).into_iter().flatten() {
+            let (s_start, s_finish) = late[&successor];
+            let target = if kind.to_finish() { s_finish } else { s_start } - lag;
+            let bound = if kind.from_finish() { target } else { target + own };
+            finish = finish.min(bound);
+        }
+        late.insert(id, (finish - own, finish));
+    }
+
+    let timings = tasks
+        .iter()
+        .map(|task| {
+            let (earliest_start, earliest_finish) = early[&task.id];
+            let (latest_start, latest_finish) = late[&task.id];
+            let free_float = successors
+                .get(&task.id)
+                .into_iter()
+                .flatten()
+                .map(|&(successor, kind, lag)| {
+                    let (s_start, s_finish) = early[&successor];
+                    let target = if kind.to_finish() { s_finish } else { s_start };
+                    let anchor = if kind.from_finish() { earliest_finish } else { earliest_start };
+                    target - (anchor + lag)
+                })
+                .min()
+                .unwrap_or(project_finish - earliest_finish);
+            let driving_predecessors = task
+                .dependencies
+                .iter()
+                .filter(|dependency| {
+                    let Some(&(p_start, p_finish)) = early.get(&dependency.predecessor_id) else {
+                        return false;
+                    };
+                    let target = if dependency.kind.to_finish() { earliest_finish } else { earliest_start };
+                    let anchor = if dependency.kind.from_finish() { p_finish } else { p_start };
+                    target <= anchor + dependency.lag()
+                })
+                .map(|dependency| dependency.predecessor_id)
+                .collect();
+            TaskTiming {
+                task_id: task.id,
+                earliest_start,
+                earliest_finish,
+                latest_start,
+                latest_finish,
+                total_float_minutes: (latest_start - earliest_start).num_minutes(),
+                free_float_minutes: free_float.num_minutes(),
+                driving_predecessors,
+            }
+        })
+        .collect();
+
+    Ok(ScheduleAnalysis { project_finish: Some(project_finish), timings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dependency::Dependency;
+    use chrono::NaiveDate;
+    use DependencyKind::*;
+
+    fn at(hour: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 1).unwrap().and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn task(id: TaskId, start_hour: i64, hours: i64, links: &[(TaskId, DependencyKind, i64)]) -> Task {
+        Task {
+            id,
+            name: format!("Task {}", id),
+            start_date: at(start_hour),
+            end_date: at(start_hour + hours),
+            color: "#4CAF50".to_string(),
+            dependencies: links
+                .iter()
+                .map(|&(predecessor_id, kind, lag_minutes)| Dependency { predecessor_id, kind, lag_minutes })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn chain_is_entirely_critical() {
+        let tasks = vec![
+            task(1, 0, 4, &[]),
+            task(2, 4, 2, &[(1, FinishToStart, 0)]),
+            task(3, 6, 3, &[(2, FinishToStart, 0)]),
+        ];
+        let analysis = analyze_schedule(&tasks).unwrap();
+        assert_eq!(analysis.project_finish, Some(at(9)));
+        assert_eq!(analysis.critical_path(), vec![1, 2, 3]);
+        for timing in &analysis.timings {
+            assert_eq!(timing.total_float_minutes, 0);
+            assert_eq!(timing.free_float_minutes, 0);
+        }
+    }
+
+    #[test]
+    fn shorter_branch_has_float() {
+        // 1 → 2 (8h) → 4, 1 → 3 (2h) → 4
+        let tasks = vec![
+            task(1, 0, 1, &[]),
+            task(2, 1, 8, &[(1, FinishToStart, 0)]),
+            task(3, 1, 2, &[(1, FinishToStart, 0)]),
+            task(4, 9, 1, &[(2, FinishToStart, 0), (3, FinishToStart, 0)]),
+        ];
+        let analysis = analyze_schedule(&tasks).unwrap();
+        assert_eq!(analysis.critical_path(), vec![1, 2, 4]);
+        let timing = analysis.timing(3).unwrap();
+        assert_eq!(timing.total_float(), Duration::hours(6));
+        assert_eq!(timing.free_float(), Duration::hours(6));
+        assert_eq!(timing.latest_start, at(7));
+    }
+
+    #[test]
+    fn free_float_is_limited_by_the_next_task_only() {
+        // 3 can slip 2h before it delays 4, but 4 itself has 4h more of float
+        let tasks = vec![
+            task(1, 0, 10, &[]),
+            task(3, 0, 2, &[]),
+            task(4, 4, 2, &[(3, FinishToStart, 0)]),
+        ];
+        let analysis = analyze_schedule(&tasks).unwrap();
+        let timing = analysis.timing(3).unwrap();
+        assert_eq!(timing.free_float(), Duration::hours(2));
+        assert_eq!(timing.total_float(), Duration::hours(6));
+        assert_eq!(analysis.timing(4).unwrap().total_float(), Duration::hours(4));
+    }
+
+    #[test]
+    fn forward_pass_pushes_late_links_and_honours_lag() {
+        // 2 is placed too early; the forward pass moves it behind 1 plus 30 minutes
+        let tasks = vec![task(1, 0, 4, &[]), task(2, 0, 2, &[(1, FinishToStart, 30)])];
+        let analysis = analyze_schedule(&tasks).unwrap();
+        let timing = analysis.timing(2).unwrap();
+        assert_eq!(timing.earliest_start, at(4) + Duration::minutes(30));
+        assert_eq!(analysis.project_finish, Some(at(6) + Duration::minutes(30)));
+        assert!(analysis.is_critical(1));
+        assert!(analysis.is_critical(2));
+    }
+
+    #[test]
+    fn start_and_finish_links() {
+        // 2 starts with 1 (SS), 3 must finish with 1 (FF)
+        let tasks = vec![
+            task(1, 0, 6, &[]),
+            task(2, 0, 2, &[(1, StartToStart, 0)]),
+            task(3, 0, 2, &[(1, FinishToFinish, 0)]),
+        ];
+        let analysis = analyze_schedule(&tasks).unwrap();
+        assert_eq!(analysis.timing(2).unwrap().total_float(), Duration::hours(4));
+        let finish_linked = analysis.timing(3).unwrap();
+        assert_eq!(finish_linked.earliest_start, at(4));
+        assert!(finish_linked.is_critical());
+    }
+
+    #[test]
+    fn start_to_finish_link_backward_pass() {
+        // 2 must finish no earlier than 1 starts + 3h
+        let tasks = vec![task(1, 0, 5, &[]), task(2, 0, 1, &[(1, StartToFinish, 180)])];
+        let analysis = analyze_schedule(&tasks).unwrap();
+        let timing = analysis.timing(2).unwrap();
+        assert_eq!(timing.earliest_start, at(2));
+        assert_eq!(timing.total_float(), Duration::hours(2));
+        assert_eq!(analysis.timing(1).unwrap().total_float(), Duration::zero());
+    }
+
+    #[test]
+    fn only_links_without_slack_are_critical() {
+        // 1 → 2 → 3 drives the finish; the direct link 1 → 3 has two hours to spare
+        let tasks = vec![
+            task(1, 0, 4, &[]),
+            task(2, 4, 2, &[(1, FinishToStart, 0)]),
+            task(3, 6, 3, &[(2, FinishToStart, 0), (1, FinishToStart, 0)]),
+        ];
+        let analysis = analyze_schedule(&tasks).unwrap();
+        assert_eq!(analysis.critical_path(), vec![1, 2, 3]);
+        assert_eq!(analysis.timing(3).unwrap().driving_predecessors, vec![2]);
+        assert!(analysis.is_critical_link(1, 2));
+        assert!(analysis.is_critical_link(2, 3));
+        assert!(!analysis.is_critical_link(1, 3));
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let tasks = vec![task(1, 0, 1, &[(2, FinishToStart, 0)]), task(2, 0, 1, &[(1, FinishToStart, 0)])];
+        assert!(matches!(analyze_schedule(&tasks), Err(DependencyError::Cycle(_))));
+    }
+
+    #[test]
+    fn empty_schedule() {
+        let analysis = analyze_schedule(&[]).unwrap();
+        assert_eq!(analysis.project_finish, None);
+        assert!(analysis.critical_path().is_empty());
+    }
+}
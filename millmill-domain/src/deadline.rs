@@ -0,0 +1,119 @@
+//! Due dates of work orders and the tasks planned to finish after them.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDateTime;
+
+use crate::task::{Task, TaskId, TaskStatus};
+use crate::work_order::{WorkOrder, WorkOrderId};
+
+/// A task that ends after the due date of its work order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissedDeadline {
+    pub task_id: TaskId,
+    pub work_order_id: WorkOrderId,
+    pub due_date: NaiveDateTime,
+    /// How long after the due date the task ends.
+    pub late_minutes: i64,
+}
+
+/// The work order each task is planned for: its own, or else that of the nearest summary task
+/// above it. Tasks without one are left out.
+pub fn linked_work_orders(tasks: &[Task]) -> HashMap<TaskId, WorkOrderId> {
+    let by_id: HashMap<TaskId, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+    tasks
+        .iter()
+        .filter_map(|task| {
+            let mut current = Some(task);
+            let mut seen = HashSet::new();
+            while let Some(candidate) = current.filter(|candidate| seen.insert(candidate.id)) {
+                if let Some(id) = candidate.work_order_id {
+                    return Some((task.id, id));
+                }
+                current = candidate.parent_id.and_then(|id| by_id.get(&id).copied());
+            }
+            None
+        })
+        .collect()
+}
+
+/// Tasks that end after the due date of their linked work order, in task order. Cancelled tasks
+/// and orders without a due date are skipped.
+pub fn missed_deadlines(tasks: &[Task], work_orders: &[WorkOrder]) -> Vec<MissedDeadline> {
+    let due_dates: HashMap<WorkOrderId, NaiveDateTime> = work_orders
+        .iter()
+        .filter_map(|order| Some((order.id, order.due_date?)))
+        .collect();
+    let links = linked_work_orders(tasks);
+    tasks
+        .iter()
+        .filter(|task| task.status != TaskStatus::Cancelled)
+        .filter_map(|task| {
+            let work_order_id = *links.get(&task.id)?;
+            let due_date = *due_dates.get(&work_order_id)?;
+            (task.end_date > due_date).then(|| MissedDeadline {
+                task_id: task.id,
+                work_order_id,
+                due_date,
+                late_minutes: (task.end_date - due_date).num_minutes(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+
+    fn at(hour: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 3).unwrap().and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn task(id: TaskId, parent_id: Option<TaskId>, work_order_id: Option<WorkOrderId>, end_hour: i64) -> Task {
+        Task {
+            id,
+            name: format!("Task {}", id),
+            start_date: at(0),
+            end_date: at(end_hour),
+            color: "#4CAF50".to_string(),
+            parent_id,
+            work_order_id,
+            ..Default::default()
+        }
+    }
+
+    fn order(id: WorkOrderId, due_hour: Option<i64>) -> WorkOrder {
+        WorkOrder {
+            id,
+            number: format!("WO-{}", id),
+            customer: String::new(),
+            part_name: "Bracket".to_string(),
+            quantity: 10,
+            due_date: due_hour.map(at),
+        }
+    }
+
+    #[test]
+    fn flags_tasks_ending_after_their_orders_due_date() {
+        // order 1 is due at 10h: summary 1 carries it, 2 and 3 inherit it, 4 names order 2 itself
+        let mut cancelled = task(5, Some(1), None, 30);
+        cancelled.status = TaskStatus::Cancelled;
+        let tasks = vec![
+            task(1, None, Some(1), 12),
+            task(2, Some(1), None, 8),
+            task(3, Some(1), None, 12),
+            task(4, Some(1), Some(2), 40),
+            cancelled,
+            task(6, None, None, 50),
+        ];
+        let orders = vec![order(1, Some(10)), order(2, None)];
+
+        let links = linked_work_orders(&tasks);
+        assert_eq!((links[&3], links[&4], links.get(&6)), (1, 2, None));
+
+        let missed = missed_deadlines(&tasks, &orders);
+        let ids: Vec<(TaskId, i64)> = missed.iter().map(|m| (m.task_id, m.late_minutes)).collect();
+        assert_eq!(ids, vec![(1, 120), (3, 120)]);
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::task::{Task, TaskId};
+
+/// How the dates of a successor are tied to those of its predecessor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    /// The successor starts after the predecessor finishes.
+    FinishToStart,
+    /// The successor starts after the predecessor starts.
+    StartToStart,
+    /// The successor finishes after the predecessor finishes.
+    FinishToFinish,
+    /// The successor finishes after the predecessor starts.
+    StartToFinish,
+}
+
+impl DependencyKind {
+    pub const ALL: [DependencyKind; 4] = [
+        DependencyKind::FinishToStart,
+        DependencyKind::StartToStart,
+        DependencyKind::FinishToFinish,
+        DependencyKind::StartToFinish,
+    ];
+
+    /// Short label used in the UI and in stored data ("FS", "SS", "FF", "SF").
+    pub fn code(self) -> &'static str {
+        match self {
+            DependencyKind::FinishToStart => "FS",
+            DependencyKind::StartToStart => "SS",
+            DependencyKind::FinishToFinish => "FF",
+            DependencyKind::StartToFinish => "SF",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        DependencyKind::ALL.into_iter().find(|kind| kind.code() == code)
+    }
+
+    /// Whether the link hangs off the predecessor's finish rather than its start.
+    pub fn from_finish(self) -> bool {
+        matches!(self, DependencyKind::FinishToStart | DependencyKind::FinishToFinish)
+    }
+
+    /// Whether the link constrains the successor's finish rather than its start.
+    pub fn to_finish(self) -> bool {
+        matches!(self, DependencyKind::FinishToFinish | DependencyKind::StartToFinish)
+    }
+}
+
+/// A link from a predecessor to the task that holds it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub predecessor_id: TaskId,
+    pub kind: DependencyKind,
+    /// Extra wait after the predecessor's anchor date; negative values are lead time.
+    #[serde(default)]
+    pub lag_minutes: i64,
+}
+
+impl Dependency {
+    pub fn lag(&self) -> Duration {
+        Duration::minutes(self.lag_minutes)
+    }
+
+    /// The earliest start this link allows for `successor`, given its predecessor.
+    pub fn earliest_start(&self, predecessor: &Task, successor: &Task) -> NaiveDateTime {
+        let anchor = if self.kind.from_finish() {
+            predecessor.end_date
+        } else {
+            predecessor.start_date
+        } + self.lag();
+        if self.kind.to_finish() {
+            anchor - (successor.end_date - successor.start_date)
+        } else {
+            anchor
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DependencyError {
+    /// A task is linked to itself.
+    SelfLink(TaskId),
+    /// A link points at a task that is not in the list.
+    UnknownPredecessor { task: TaskId, predecessor: TaskId },
+    /// The links form a loop; the ids are the tasks on it, in order.
+    Cycle(Vec<TaskId>),
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyError::SelfLink(id) => write!(f, "task {} cannot depend on itself", id),
+            DependencyError::UnknownPredecessor { task, predecessor } => {
+                write!(f, "task {} depends on missing task {}", task, predecessor)
+            }
+            DependencyError::Cycle(ids) => match ids.first() {
+                Some(first) => {
+                    let path: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                    write!(f, "dependency cycle: {} → {}", path.join(" → "), first)
+                }
+                None => write!(f, "dependency cycle"),
+            },
+        }
+    }
+}
+
+impl std::error::Error for DependencyError {}
+
+/// Checks that every link points at an existing, different task and that there are no cycles.
+pub fn validate_dependencies(tasks: &[Task]) -> Result<(), DependencyError> {
+    let ids: HashSet<TaskId> = tasks.iter().map(|task| task.id).collect();
+    for task in tasks {
+        for dependency in &task.dependencies {
+            if dependency.predecessor_id == task.id {
+                return Err(DependencyError::SelfLink(task.id));
+            }
+            if !ids.contains(&dependency.predecessor_id) {
+                return Err(DependencyError::UnknownPredecessor {
+                    task: task.id,
+                    predecessor: dependency.predecessor_id,
+                });
+            }
+        }
+    }
+    topological_order(tasks).map(|_| ())
+}
+
+/// Orders tasks so that every predecessor comes before its successors.
+/// Links to unknown tasks are ignored.
+pub fn topological_order(tasks: &[Task]) -> Result<Vec<TaskId>, DependencyError> {
+    let ids: HashSet<TaskId> = tasks.iter().map(|task| task.id).collect();
+    let mut in_degree: HashMap<TaskId, usize> = tasks.iter().map(|task| (task.id, 0)).collect();
+    let mut successors: HashMap<TaskId, Vec<TaskId>> = HashMap::new();
+    for task in tasks {
+        for dependency in &task.dependencies {
+            if ids.contains(&dependency.predecessor_id) {
+                *in_degree.entry(task.id).or_default() += 1;
+                successors.entry(dependency.predecessor_id).or_default().push(task.id);
+            }
+        }
+    }
+
+    // Walk in list order so the result is stable
+    let mut ready: Vec<TaskId> = tasks
+        .iter()
+        .map(|task| task.id)
+        .filter(|id| in_degree[id] == 0)
+        .rev()
+        .collect();
+    let mut order = Vec::with_capacity(tasks.len());
+    while let Some(id) = ready.pop() {
+        order.push(id);
+        for successor in successors.get(&id).into_iter().flatten() {
+            let degree = in_degree.get_mut(successor).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                ready.push(*successor);
+            }
+        }
+    }
+
+    if order.len() < tasks.len() {
+        let remaining: HashSet<TaskId> = in_degree
+            .into_iter()
+            .filter(|(_, degree)| *degree > 0)
+            .map(|(id, _)| id)
+            .collect();
+        return Err(DependencyError::Cycle(find_cycle(tasks, &remaining)));
+    }
+    Ok(order)
+}
+
+/// Follows predecessor links inside `remaining` (tasks left over by Kahn's algorithm,
+/// all of which have a predecessor in the set) until one repeats.
+fn find_cycle(tasks: &[Task], remaining: &HashSet<TaskId>) -> Vec<TaskId> {
+    let by_id: HashMap<TaskId, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+    let Some(mut current) = tasks.iter().map(|task| task.id).find(|id| remaining.contains(id)) else {
+        return Vec::new();
+    };
+    let mut path: Vec<TaskId> = Vec::new();
+    loop {
+        if let Some(position) = path.iter().position(|id| *id == current) {
+            let mut cycle = path.split_off(position);
+            cycle.reverse();
+            return cycle;
+        }
+        path.push(current);
+        current = by_id[&current]
+            .dependencies
+            .iter()
+            .map(|dependency| dependency.predecessor_id)
+            .find(|id| remaining.contains(id))
+            .expect("every task left by Kahn's algorithm has a remaining predecessor");
+    }
+}
+
+/// Moves the successors of `moved_id` (directly or through other tasks) later wherever a
+/// link is no longer satisfied, keeping their durations. Tasks are never pulled earlier.
+/// Returns the ids of the tasks whose dates changed.
+pub fn reschedule_successors(tasks: &mut [Task], moved_id: TaskId) -> Result<Vec<TaskId>, DependencyError> {
+    let order = topological_order(tasks)?;
+    let index: HashMap<TaskId, usize> = tasks.iter().enumerate().map(|(i, task)| (task.id, i)).collect();
+
+    let mut affected: HashSet<TaskId> = HashSet::from([moved_id]);
+    let mut changed = Vec::new();
+    for id in order {
+        let task = &tasks[index[&id]];
+        let links: Vec<&Dependency> = task
+            .dependencies
+            .iter()
+            .filter(|dependency| affected.contains(&dependency.predecessor_id))
+            .collect();
+        if links.is_empty() {
+            continue;
+        }
+        affected.insert(id);
+
+        let earliest = links
+            .iter()
+            .filter_map(|dependency| {
+                let predecessor = &tasks[*index.get(&dependency.predecessor_id)?];
+                Some(dependency.earliest_start(predecessor, task))
+            })
+            .max();
+        if let Some(earliest) = earliest {
+            if task.start_date < earliest {
+                let task = &mut tasks[index[&id]];
+                let duration = task.end_date - task.start_date;
+                task.start_date = earliest;
+                task.end_date = earliest + duration;
+                changed.push(id);
+            }
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn task(id: TaskId, start: NaiveDateTime, end: NaiveDateTime, links: &[(TaskId, DependencyKind, i64)]) -> Task {
+        Task {
+            id,
+            name: format!("Op {}", id),
+            start_date: start,
+            end_date: end,
+            color: "#4CAF50".to_string(),
+            dependencies: links
+                .iter()
+                .map(|&(predecessor_id, kind, lag_minutes)| Dependency { predecessor_id, kind, lag_minutes })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn each_kind_of_link_holds_back_its_successor() {
+        let predecessor = task(1, at(3, 8), at(3, 12), &[]);
+        let cases = [
+            (DependencyKind::FinishToStart, 30, at(3, 12) + Duration::minutes(30)),
+            (DependencyKind::StartToStart, 60, at(3, 9)),
+            // the successor takes two hours, so finishing an hour before the predecessor starts it at 9:00
+            (DependencyKind::FinishToFinish, -60, at(3, 9)),
+            (DependencyKind::StartToFinish, 0, at(3, 6)),
+        ];
+        for (kind, lag, start) in cases {
+            let mut tasks = vec![predecessor.clone(), task(2, at(3, 0), at(3, 2), &[(1, kind, lag)])];
+            assert_eq!(reschedule_successors(&mut tasks, 1).unwrap(), vec![2], "{:?}", kind);
+            assert_eq!((tasks[1].start_date, tasks[1].end_date), (start, start + Duration::hours(2)), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn moving_a_predecessor_pushes_the_chain_but_never_pulls_it() {
+        let fs = DependencyKind::FinishToStart;
+        let mut tasks = vec![
+            task(1, at(4, 8), at(4, 12), &[]),
+            task(2, at(3, 13), at(3, 17), &[(1, fs, 0)]),
+            task(3, at(4, 8), at(4, 10), &[(2, fs, 60)]),
+            // enough slack to absorb the move
+            task(4, at(10, 8), at(10, 9), &[(3, fs, 0)]),
+            task(5, at(3, 8), at(3, 9), &[]),
+        ];
+        assert_eq!(reschedule_successors(&mut tasks, 1).unwrap(), vec![2, 3]);
+        let spans: Vec<_> = tasks.iter().map(|task| (task.start_date, task.end_date)).collect();
+        assert_eq!(
+            spans,
+            vec![
+                (at(4, 8), at(4, 12)),
+                (at(4, 12), at(4, 16)),
+                (at(4, 17), at(4, 19)),
+                (at(10, 8), at(10, 9)),
+                (at(3, 8), at(3, 9)),
+            ]
+        );
+
+        // Moving the first task back leaves the others where they are
+        tasks[0].start_date = at(1, 8);
+        tasks[0].end_date = at(1, 12);
+        assert_eq!(reschedule_successors(&mut tasks, 1).unwrap(), Vec::<TaskId>::new());
+        assert_eq!(tasks[1].start_date, at(4, 12));
+    }
+
+    #[test]
+    fn predecessors_come_first_otherwise_list_order_is_kept() {
+        let fs = DependencyKind::FinishToStart;
+        let tasks = vec![
+            task(3, at(3, 8), at(3, 9), &[(2, fs, 0)]),
+            task(1, at(3, 8), at(3, 9), &[]),
+            task(2, at(3, 8), at(3, 9), &[(1, fs, 0), (9, fs, 0)]),
+            task(4, at(3, 8), at(3, 9), &[]),
+        ];
+        assert_eq!(topological_order(&tasks).unwrap(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn cycles_are_reported_with_the_tasks_on_them() {
+        let fs = DependencyKind::FinishToStart;
+        let mut tasks = vec![
+            task(1, at(3, 8), at(3, 9), &[(3, fs, 0)]),
+            task(2, at(3, 8), at(3, 9), &[(1, fs, 0)]),
+            task(3, at(3, 8), at(3, 9), &[(2, fs, 0)]),
+            // behind the loop, but not on it
+            task(4, at(3, 8), at(3, 9), &[(3, fs, 0)]),
+        ];
+        let cycle = DependencyError::Cycle(vec![2, 3, 1]);
+        assert_eq!(topological_order(&tasks), Err(cycle.clone()));
+        assert_eq!(validate_dependencies(&tasks), Err(cycle.clone()));
+        assert_eq!(reschedule_successors(&mut tasks, 1), Err(cycle.clone()));
+        assert_eq!(cycle.to_string(), "dependency cycle: 2 → 3 → 1 → 2");
+        assert_eq!(DependencyError::Cycle(Vec::new()).to_string(), "dependency cycle");
+    }
+
+    #[test]
+    fn links_must_point_at_other_known_tasks() {
+        let fs = DependencyKind::FinishToStart;
+        let first = task(1, at(3, 8), at(3, 9), &[]);
+        assert_eq!(validate_dependencies(&[first.clone(), task(2, at(3, 9), at(3, 10), &[(1, fs, 0)])]), Ok(()));
+        assert_eq!(
+            validate_dependencies(&[first.clone(), task(2, at(3, 9), at(3, 10), &[(2, fs, 0)])]),
+            Err(DependencyError::SelfLink(2))
+        );
+        assert_eq!(
+            validate_dependencies(&[first, task(2, at(3, 9), at(3, 10), &[(7, fs, 0)])]),
+            Err(DependencyError::UnknownPredecessor { task: 2, predecessor: 7 })
+        );
+    }
+}
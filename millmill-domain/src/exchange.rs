@@ -0,0 +1,393 @@
+//! What the import and export commands pass to the window. The files themselves are read
+//! and written by the backend.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::Calendar;
+use crate::dependency::{Dependency, DependencyKind};
+use crate::machine::Machine;
+use crate::task::{Task, TaskId};
+use crate::work_order::WorkOrder;
+
+/// A file format schedules can be imported from and exported to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExchangeFormat {
+    /// One row per task, with a header row naming the columns.
+    #[default]
+    Csv,
+    /// The XML interchange format of Microsoft Project (MSPDI).
+    MsProject,
+    /// One event per task.
+    ICalendar,
+}
+
+impl ExchangeFormat {
+    pub const ALL: [ExchangeFormat; 3] = [ExchangeFormat::Csv, ExchangeFormat::MsProject, ExchangeFormat::ICalendar];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            ExchangeFormat::Csv => "csv",
+            ExchangeFormat::MsProject => "ms_project",
+            ExchangeFormat::ICalendar => "ical",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        ExchangeFormat::ALL.into_iter().find(|format| format.code() == code)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExchangeFormat::Csv => "CSV",
+            ExchangeFormat::MsProject => "MS Project XML",
+            ExchangeFormat::ICalendar => "iCalendar",
+        }
+    }
+
+    /// The file extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExchangeFormat::Csv => "csv",
+            ExchangeFormat::MsProject => "xml",
+            ExchangeFormat::ICalendar => "ics",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ExchangeFormat::Csv => "text/csv",
+            ExchangeFormat::MsProject => "application/xml",
+            ExchangeFormat::ICalendar => "text/calendar",
+        }
+    }
+
+    /// The format of a file going by its name.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let (_, extension) = name.rsplit_once('.')?;
+        ExchangeFormat::ALL.into_iter().find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+}
+
+/// A task property a column of a table can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskField {
+    Id,
+    Name,
+    Description,
+    Start,
+    End,
+    Kind,
+    Status,
+    /// The machine name.
+    Machine,
+    /// The work order number.
+    WorkOrder,
+    /// The id of the summary task.
+    Parent,
+    /// Links in the form "3FS+30m, 4SS".
+    Predecessors,
+    PercentComplete,
+    ActualStart,
+    ActualEnd,
+    Color,
+    PartFamily,
+    Material,
+    Quantity,
+    /// The id of the first segment of a split job.
+    SegmentOf,
+}
+
+impl TaskField {
+    pub const ALL: [TaskField; 19] = [
+        TaskField::Id,
+        TaskField::Name,
+        TaskField::Description,
+        TaskField::Start,
+        TaskField::End,
+        TaskField::Kind,
+        TaskField::Status,
+        TaskField::Machine,
+        TaskField::WorkOrder,
+        TaskField::Parent,
+        TaskField::Predecessors,
+        TaskField::PercentComplete,
+        TaskField::ActualStart,
+        TaskField::ActualEnd,
+        TaskField::Color,
+        TaskField::PartFamily,
+        TaskField::Material,
+        TaskField::Quantity,
+        TaskField::SegmentOf,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            TaskField::Id => "id",
+            TaskField::Name => "name",
+            TaskField::Description => "description",
+            TaskField::Start => "start",
+            TaskField::End => "end",
+            TaskField::Kind => "kind",
+            TaskField::Status => "status",
+            TaskField::Machine => "machine",
+            TaskField::WorkOrder => "work_order",
+            TaskField::Parent => "parent",
+            TaskField::Predecessors => "predecessors",
+            TaskField::PercentComplete => "percent_complete",
+            TaskField::ActualStart => "actual_start",
+            TaskField::ActualEnd => "actual_end",
+            TaskField::Color => "color",
+            TaskField::PartFamily => "part_family",
+            TaskField::Material => "material",
+            TaskField::Quantity => "quantity",
+            TaskField::SegmentOf => "segment_of",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        TaskField::ALL.into_iter().find(|field| field.code() == code)
+    }
+
+    /// The column header used on export.
+    pub fn label(self) -> &'static str {
+        match self {
+            TaskField::Id => "ID",
+            TaskField::Name => "Name",
+            TaskField::Description => "Description",
+            TaskField::Start => "Start",
+            TaskField::End => "End",
+            TaskField::Kind => "Kind",
+            TaskField::Status => "Status",
+            TaskField::Machine => "Machine",
+            TaskField::WorkOrder => "Work Order",
+            TaskField::Parent => "Parent",
+            TaskField::Predecessors => "Predecessors",
+            TaskField::PercentComplete => "% Complete",
+            TaskField::ActualStart => "Actual Start",
+            TaskField::ActualEnd => "Actual End",
+            TaskField::Color => "Color",
+            TaskField::PartFamily => "Part Family",
+            TaskField::Material => "Material",
+            TaskField::Quantity => "Quantity",
+            TaskField::SegmentOf => "Segment Of",
+        }
+    }
+
+    /// The field a column header most likely means: its label or code, or a name other
+    /// planning tools use for it.
+    pub fn from_header(header: &str) -> Option<Self> {
+        let key: String = header.trim().to_lowercase().chars().filter(|c| c.is_alphanumeric() || *c == '%').collect();
+        let field = TaskField::ALL.into_iter().find(|field| {
+            let label: String = field.label().to_lowercase().chars().filter(|c| c.is_alphanumeric() || *c == '%').collect();
+            key == label || key == field.code().replace('_', "")
+        });
+        field.or(match key.as_str() {
+            "uid" | "taskid" | "no" => Some(TaskField::Id),
+            "task" | "taskname" | "title" | "operation" => Some(TaskField::Name),
+            "notes" | "note" | "comment" | "comments" => Some(TaskField::Description),
+            "startdate" | "begin" | "from" => Some(TaskField::Start),
+            "finish" | "finishdate" | "enddate" | "to" | "due" => Some(TaskField::End),
+            "milestone" | "type" => Some(TaskField::Kind),
+            "state" => Some(TaskField::Status),
+            "resource" | "resources" | "resourcenames" | "workcenter" => Some(TaskField::Machine),
+            "order" | "ordernumber" | "wo" => Some(TaskField::WorkOrder),
+            "parentid" | "summary" => Some(TaskField::Parent),
+            "predecessor" | "dependencies" | "dependson" => Some(TaskField::Predecessors),
+            "%" | "progress" | "percent" | "complete" => Some(TaskField::PercentComplete),
+            "actualfinish" => Some(TaskField::ActualEnd),
+            "family" | "jaws" => Some(TaskField::PartFamily),
+            "qty" | "pieces" | "pcs" => Some(TaskField::Quantity),
+            _ => None,
+        })
+    }
+}
+
+/// Which task property a column of an imported table goes to. Columns are matched by
+/// header, so a mapping still fits when the columns are reordered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub header: String,
+    /// `None` leaves the column out.
+    pub field: Option<TaskField>,
+}
+
+/// Guesses the field of every header from its name. Each field goes to the first column
+/// that names it.
+pub fn guess_mapping(headers: &[String]) -> Vec<ColumnMapping> {
+    let mut used = Vec::new();
+    headers
+        .iter()
+        .map(|header| {
+            let field = TaskField::from_header(header).filter(|field| !used.contains(field));
+            used.extend(field);
+            ColumnMapping { header: header.clone(), field }
+        })
+        .collect()
+}
+
+/// A problem found while reading a file. The record it is about is left out, or the value
+/// is ignored, so the rest can still be imported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportIssue {
+    /// The line of the file, when the problem is with one record.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// What an import would add, shown for review before anything changes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub tasks: Vec<Task>,
+    /// Machines named in the file that do not exist yet, with fresh ids.
+    pub machines: Vec<Machine>,
+    /// Calendars defined in the file, with fresh ids.
+    pub calendars: Vec<Calendar>,
+    /// Work orders read from an order list that do not exist yet, with fresh ids.
+    #[serde(default)]
+    pub work_orders: Vec<WorkOrder>,
+    /// How the columns were read; empty for formats without columns.
+    pub mapping: Vec<ColumnMapping>,
+    /// Columns, elements or properties in the file that no task property takes.
+    pub unmapped: Vec<String>,
+    pub issues: Vec<ImportIssue>,
+}
+
+/// Adds imported tasks after `tasks`, renumbering them from the next free id and keeping the
+/// links, grouping and splits among them.
+pub fn append_tasks(tasks: &mut Vec<Task>, imported: Vec<Task>) {
+    let first = tasks.iter().map(|task| task.id).max().unwrap_or(0) + 1;
+    let ids: HashMap<TaskId, TaskId> = imported.iter().enumerate().map(|(i, task)| (task.id, first + i)).collect();
+    tasks.extend(imported.into_iter().map(|mut task| {
+        task.id = ids[&task.id];
+        task.parent_id = task.parent_id.and_then(|id| ids.get(&id).copied());
+        task.split_of = task.split_of.and_then(|id| ids.get(&id).copied());
+        task.dependencies.retain_mut(|dependency| match ids.get(&dependency.predecessor_id) {
+            Some(id) => {
+                dependency.predecessor_id = *id;
+                true
+            }
+            None => false,
+        });
+        task
+    }));
+}
+
+/// Writes links the way planning tools show them: "3FS+30m, 4SS".
+pub fn format_predecessors(dependencies: &[Dependency]) -> String {
+    dependencies
+        .iter()
+        .map(|dependency| {
+            let lag = match dependency.lag_minutes {
+                0 => String::new(),
+                minutes => format!("{:+}m", minutes),
+            };
+            format!("{}{}{}", dependency.predecessor_id, dependency.kind.code(), lag)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Reads links written by `format_predecessors`. The kind defaults to FS, and a lag may be
+/// given in minutes (`m`, also without a unit), hours (`h`) or days (`d`).
+pub fn parse_predecessors(text: &str) -> Result<Vec<Dependency>, String> {
+    text.split([',', ';'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let digits = part.find(|c: char| !c.is_ascii_digit()).unwrap_or(part.len());
+            let predecessor_id = part[..digits].parse().map_err(|_| format!("\"{}\" does not start with a task id", part))?;
+            let rest = part[digits..].trim();
+            let (kind, lag) = match rest.get(..2).and_then(|code| DependencyKind::from_code(&code.to_uppercase())) {
+                Some(kind) => (kind, rest[2..].trim()),
+                None => (DependencyKind::FinishToStart, rest),
+            };
+            let lag_minutes = if lag.is_empty() {
+                0
+            } else {
+                let (number, unit) = match lag.char_indices().last() {
+                    Some((i, unit)) if unit.is_ascii_alphabetic() => (&lag[..i], unit.to_ascii_lowercase()),
+                    _ => (lag, 'm'),
+                };
+                let number: f64 = number.replace(' ', "").parse().map_err(|_| format!("\"{}\" has an unreadable lag", part))?;
+                let minutes = match unit {
+                    'm' => 1.0,
+                    'h' => 60.0,
+                    'd' => 24.0 * 60.0,
+                    _ => return Err(format!("\"{}\" has an unknown lag unit", part)),
+                };
+                (number * minutes).round() as i64
+            };
+            Ok(Dependency { predecessor_id, kind, lag_minutes })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_back_written_links_and_headers() {
+        let dependencies = vec![
+            Dependency { predecessor_id: 3, kind: DependencyKind::FinishToStart, lag_minutes: 30 },
+            Dependency { predecessor_id: 12, kind: DependencyKind::StartToStart, lag_minutes: -60 },
+        ];
+        let text = format_predecessors(&dependencies);
+        assert_eq!(text, "3FS+30m, 12SS-60m");
+        assert_eq!(parse_predecessors(&text), Ok(dependencies));
+        assert_eq!(parse_predecessors("4; 5ff+1d").unwrap()[1].lag_minutes, 24 * 60);
+        assert!(parse_predecessors("after 4").is_err());
+
+        let headers: Vec<String> = ["Task Name", "Finish", "Resource Names", "% Complete", "Cost", "Name"]
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+        let fields: Vec<_> = guess_mapping(&headers).into_iter().map(|mapping| mapping.field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                Some(TaskField::Name),
+                Some(TaskField::End),
+                Some(TaskField::Machine),
+                Some(TaskField::PercentComplete),
+                None,
+                // already taken by "Task Name"
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn appends_imported_tasks_with_fresh_ids() {
+        let task = |id: TaskId| Task { id, name: format!("Task {}", id), ..Default::default() };
+        let mut tasks = vec![task(1), task(2)];
+        let mut imported = vec![task(1), task(5), task(7)];
+        imported[1].parent_id = Some(1);
+        imported[2].split_of = Some(5);
+        imported[2].dependencies = vec![
+            Dependency { predecessor_id: 1, kind: DependencyKind::FinishToStart, lag_minutes: 0 },
+            // not part of the import
+            Dependency { predecessor_id: 9, kind: DependencyKind::FinishToStart, lag_minutes: 0 },
+        ];
+        append_tasks(&mut tasks, imported);
+        let ids: Vec<_> = tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(tasks[3].parent_id, Some(3));
+        assert_eq!(tasks[4].split_of, Some(4));
+        assert_eq!(tasks[4].dependencies.iter().map(|d| d.predecessor_id).collect::<Vec<_>>(), vec![3]);
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::machine::MachineId;
+use crate::tool::ToolId;
+use crate::validation::{require_text, Validate, ValidationError};
+use crate::work_order::WorkOrderId;
+
+pub type InstructionId = usize;
+
+/// A work instruction sheet telling the operator how to run one step of a work order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Instruction {
+    pub id: InstructionId,
+    pub work_order_id: WorkOrderId,
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub machine_id: Option<MachineId>,
+    #[serde(default)]
+    pub tool_ids: Vec<ToolId>,
+}
+
+impl Validate for Instruction {
+    fn validate(&self) -> Result<(), ValidationError> {
+        require_text("instruction title", &self.title)
+    }
+}
@@ -0,0 +1,206 @@
+//! How the Gantt chart is laid out: the tiers of the time scale and the rows of the machine
+//! lanes. Shared by the chart on screen and the exported one, so a printout looks like the
+//! window. Sizes in pixels stay with each of them.
+
+use std::collections::HashSet;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::machine::{Machine, MachineId};
+use crate::task::{Task, TaskId};
+
+/// Start of the first shift of the day and the length of a shift.
+const SHIFT_START_HOUR: i64 = 6;
+pub const SHIFT_HOURS: i64 = 8;
+/// Tiers with cells narrower than this are left out of the header (px).
+const MIN_CELL_WIDTH: f64 = 24.0;
+
+/// A tier of the time scale.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tier {
+    Quarter,
+    Month,
+    Week,
+    Day,
+    Shift,
+    Hour,
+}
+
+impl Tier {
+    pub const ALL: [Tier; 6] = [Tier::Quarter, Tier::Month, Tier::Week, Tier::Day, Tier::Shift, Tier::Hour];
+
+    /// The tiers whose cells are wide enough to label at `day_width` pixels a day, coarsest first.
+    pub fn shown(day_width: f64) -> Vec<Tier> {
+        Tier::ALL
+            .into_iter()
+            .filter(|tier| tier.approx_length().num_minutes() as f64 * day_width / (24.0 * 60.0) >= MIN_CELL_WIDTH)
+            .collect()
+    }
+
+    /// Roughly how long one cell lasts, to decide whether the tier is shown.
+    fn approx_length(self) -> Duration {
+        match self {
+            Tier::Quarter => Duration::days(91),
+            Tier::Month => Duration::days(30),
+            Tier::Week => Duration::days(7),
+            Tier::Day => Duration::days(1),
+            Tier::Shift => Duration::hours(SHIFT_HOURS),
+            Tier::Hour => Duration::hours(1),
+        }
+    }
+
+    /// The start of the cell containing `time`.
+    pub fn floor(self, time: NaiveDateTime) -> NaiveDateTime {
+        let midnight = time.date().and_time(NaiveTime::MIN);
+        match self {
+            Tier::Quarter => {
+                let month = (time.month() - 1) / 3 * 3 + 1;
+                NaiveDate::from_ymd_opt(time.year(), month, 1).unwrap_or(time.date()).and_time(NaiveTime::MIN)
+            }
+            Tier::Month => time.date().with_day(1).unwrap_or(time.date()).and_time(NaiveTime::MIN),
+            Tier::Week => midnight - Duration::days(time.weekday().num_days_from_monday() as i64),
+            Tier::Day => midnight,
+            Tier::Shift => {
+                let hours = (time.hour() as i64 - SHIFT_START_HOUR).rem_euclid(SHIFT_HOURS);
+                time.date().and_hms_opt(time.hour(), 0, 0).unwrap_or(midnight) - Duration::hours(hours)
+            }
+            Tier::Hour => time.date().and_hms_opt(time.hour(), 0, 0).unwrap_or(midnight),
+        }
+    }
+
+    /// The start of the cell after the one starting at `start`.
+    pub fn next(self, start: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Tier::Quarter => (0..3).fold(start, |time, _| Tier::Month.next(time)),
+            Tier::Month => {
+                let (year, month) = if start.month() == 12 { (start.year() + 1, 1) } else { (start.year(), start.month() + 1) };
+                NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(start.date()).and_time(NaiveTime::MIN)
+            }
+            Tier::Week => start + Duration::days(7),
+            Tier::Day => start + Duration::days(1),
+            Tier::Shift => start + Duration::hours(SHIFT_HOURS),
+            Tier::Hour => start + Duration::hours(1),
+        }
+    }
+
+    /// The text of the cell starting at `start`.
+    pub fn label(self, start: NaiveDateTime) -> String {
+        match self {
+            Tier::Quarter => format!("{} Q{}", start.year(), (start.month() - 1) / 3 + 1),
+            Tier::Month => start.format("%Y-%m").to_string(),
+            Tier::Week => format!("W{}", start.iso_week().week()),
+            Tier::Day => start.format("%d %a").to_string(),
+            Tier::Shift => {
+                let shift = (start.hour() as i64 - SHIFT_START_HOUR).rem_euclid(24) / SHIFT_HOURS + 1;
+                format!("S{}", shift)
+            }
+            Tier::Hour => start.format("%H").to_string(),
+        }
+    }
+}
+
+/// The lane of one machine, or of the tasks without a machine, with the row of each bar in it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct StackedLane {
+    /// `None` for the lane of tasks without a machine.
+    pub machine_id: Option<MachineId>,
+    /// Rows the lane needs; at least one, so an idle machine still gets a lane.
+    pub rows: usize,
+    /// The bars of the lane in start order, each with its row counted from the top of the lane.
+    pub bars: Vec<(TaskId, usize)>,
+}
+
+/// A lane per machine in machine order, then one for tasks without a machine or with one that
+/// does not exist. Bars that overlap in a lane are stacked on rows of their own. Only tasks that
+/// pass `shown` are placed, and summary tasks never are.
+pub fn stack_lanes(tasks: &[Task], machines: &[Machine], shown: impl Fn(&Task) -> bool) -> Vec<StackedLane> {
+    let summaries: HashSet<TaskId> = tasks.iter().filter_map(|task| task.parent_id).collect();
+    let tasks: Vec<&Task> = tasks.iter().filter(|task| !summaries.contains(&task.id) && shown(task)).collect();
+    let lane_of = |task: &Task| task.machine_id.filter(|id| machines.iter().any(|machine| machine.id == *id));
+    machines
+        .iter()
+        .map(|machine| Some(machine.id))
+        .chain(std::iter::once(None))
+        .map(|machine_id| {
+            let mut lane_tasks: Vec<&Task> = tasks.iter().copied().filter(|task| lane_of(task) == machine_id).collect();
+            lane_tasks.sort_by_key(|task| (task.start_date, task.id));
+            // when the last bar on each row ends
+            let mut row_ends: Vec<NaiveDateTime> = Vec::new();
+            let mut bars = Vec::new();
+            for task in lane_tasks {
+                let row = match row_ends.iter().position(|end| *end <= task.start_date) {
+                    Some(row) => {
+                        row_ends[row] = task.end_date;
+                        row
+                    }
+                    None => {
+                        row_ends.push(task.end_date);
+                        row_ends.len() - 1
+                    }
+                };
+                bars.push((task.id, row));
+            }
+            StackedLane { machine_id, rows: row_ends.len().max(1), bars }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn task(id: TaskId, machine_id: Option<MachineId>, start: NaiveDateTime, end: NaiveDateTime) -> Task {
+        Task {
+            id,
+            name: format!("Task {}", id),
+            start_date: start,
+            end_date: end,
+            color: "#4CAF50".to_string(),
+            machine_id,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tiers_follow_the_zoom() {
+        assert_eq!(Tier::shown(100.0), vec![Tier::Quarter, Tier::Month, Tier::Week, Tier::Day, Tier::Shift]);
+        assert_eq!(Tier::shown(1.5), vec![Tier::Quarter, Tier::Month]);
+        // the night shift of Sunday runs into Monday
+        assert_eq!(Tier::Shift.floor(at(3, 3)), at(2, 22));
+        assert_eq!(Tier::Shift.label(at(2, 22)), "S3");
+        let april = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap().and_time(NaiveTime::MIN);
+        assert_eq!(Tier::Quarter.next(Tier::Quarter.floor(at(3, 3))), april);
+    }
+
+    #[test]
+    fn overlapping_bars_stack_within_their_lane() {
+        let lathe = Machine { id: 1, name: "Lathe".to_string(), ..Default::default() };
+        let mill = Machine { id: 2, name: "Mill".to_string(), ..Default::default() };
+        let mut summary = task(5, Some(1), at(3, 8), at(3, 20));
+        summary.name = "Order".to_string();
+        let mut child = task(1, Some(1), at(3, 8), at(3, 12));
+        child.parent_id = Some(5);
+        let tasks = vec![
+            summary,
+            child,
+            task(2, Some(1), at(3, 10), at(3, 14)),
+            // back to back with the first: no overlap
+            task(3, Some(1), at(3, 12), at(3, 16)),
+            task(4, Some(9), at(3, 8), at(3, 9)),
+            task(6, None, at(4, 8), at(4, 9)),
+        ];
+        let lanes = stack_lanes(&tasks, &[lathe, mill], |task| task.id != 6);
+        assert_eq!(
+            lanes,
+            vec![
+                StackedLane { machine_id: Some(1), rows: 2, bars: vec![(1, 0), (2, 1), (3, 0)] },
+                StackedLane { machine_id: Some(2), rows: 1, bars: vec![] },
+                StackedLane { machine_id: None, rows: 1, bars: vec![(4, 0)] },
+            ]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Domain types shared by the Yew frontend (`millmill-ui`) and the Tauri backend (`millmill`).
+//!
+//! Everything in this crate is plain serde-serializable data plus the validation rules for it.
+//! View state such as drag positions does not belong here.
+
+mod baseline;
+mod calendar;
+mod chart;
+mod conflict;
+mod critical_path;
+mod deadline;
+mod dependency;
+mod exchange;
+mod instruction;
+mod layout;
+mod machine;
+mod scheduler;
+mod setup;
+mod split;
+mod task;
+mod tool;
+mod validation;
+mod wbs;
+mod work_order;
+mod workbook;
+
+pub use baseline::{variance, Baseline, BaselineId, BaselineTask, TaskVariance};
+pub use calendar::{
+    holidays_from_ical, parse_ical_date_time, unescape_ical, unfold_ical, Calendar, CalendarException, CalendarId, Shift,
+};
+pub use chart::{ChartExport, ChartFormat, Orientation, PaperSize};
+pub use conflict::{find_conflicts, Conflict, ConflictKind};
+pub use critical_path::{analyze_schedule, ScheduleAnalysis, TaskTiming};
+pub use deadline::{linked_work_orders, missed_deadlines, MissedDeadline};
+pub use dependency::{
+    reschedule_successors, topological_order, validate_dependencies, Dependency, DependencyError,
+    DependencyKind,
+};
+pub use exchange::{
+    append_tasks, format_predecessors, guess_mapping, parse_predecessors, ColumnMapping, ExchangeFormat, ImportIssue,
+    ImportPreview, TaskField,
+};
+pub use instruction::{Instruction, InstructionId};
+pub use layout::{stack_lanes, StackedLane, Tier, SHIFT_HOURS};
+pub use machine::{Machine, MachineId, MaintenanceWindow};
+pub use scheduler::{
+    auto_schedule, PriorityRule, ScheduleDirection, ScheduleOutcome, SchedulerOptions, SkipReason, SkippedTask,
+};
+pub use setup::{setup_blocks, SetupBlock, SetupKey, SetupMatrix, SetupTime};
+pub use split::{
+    lot_id, merge_segments, rejoin_segments, segments, split_task, validate_segments, SplitError, SplitPoint,
+};
+pub use task::{Task, TaskId, TaskKind, TaskStatus};
+pub use tool::{Tool, ToolId};
+pub use validation::{Validate, ValidationError};
+pub use wbs::{is_summary, lift_orphans, outline, roll_up_summaries, subtree, validate_hierarchy, HierarchyError, OutlineRow};
+pub use work_order::{WorkOrder, WorkOrderId};
+pub use workbook::{
+    guess_order_mapping, ImportProfile, ImportProfileId, OrderColumnMapping, WorkbookPreview, WorkOrderField,
+    XLSX_EXTENSION, XLSX_MIME_TYPE,
+};
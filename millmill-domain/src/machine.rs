@@ -0,0 +1,75 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::CalendarId;
+use crate::setup::SetupMatrix;
+use crate::validation::{require_span, require_text, Validate, ValidationError};
+
+pub type MachineId = usize;
+
+/// A machine tool on the shop floor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Machine {
+    pub id: MachineId,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// How many jobs the machine can run at once, e.g. the number of pallets.
+    #[serde(default = "default_capacity")]
+    pub capacity: u32,
+    /// Periods when the machine is down and must not run jobs.
+    #[serde(default)]
+    pub maintenance: Vec<MaintenanceWindow>,
+    /// The working time of the machine; without one it runs around the clock.
+    #[serde(default)]
+    pub calendar_id: Option<CalendarId>,
+    /// Changeover times between consecutive jobs.
+    #[serde(default)]
+    pub setup: SetupMatrix,
+}
+
+fn default_capacity() -> u32 {
+    1
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Machine {
+            id: 0,
+            name: String::new(),
+            description: String::new(),
+            capacity: default_capacity(),
+            maintenance: Vec::new(),
+            calendar_id: None,
+            setup: SetupMatrix::default(),
+        }
+    }
+}
+
+/// A planned downtime of a machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+    #[serde(default)]
+    pub reason: String,
+}
+
+impl MaintenanceWindow {
+    pub fn overlaps(&self, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+        start < self.end_date && self.start_date < end
+    }
+}
+
+impl Validate for Machine {
+    fn validate(&self) -> Result<(), ValidationError> {
+        require_text("machine name", &self.name)?;
+        if self.capacity == 0 {
+            return Err(ValidationError::NotPositive("machine capacity"));
+        }
+        for window in &self.maintenance {
+            require_span(window.start_date, window.end_date)?;
+        }
+        self.setup.validate()
+    }
+}
@@ -0,0 +1,625 @@
+//! Finite-capacity scheduling: places the open operations of work orders onto their machines
+//! one at a time, in priority order, so that no machine runs more jobs than it can hold.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::Calendar;
+use crate::deadline::linked_work_orders;
+use crate::dependency::{topological_order, DependencyError};
+use crate::machine::{Machine, MachineId};
+use crate::setup::{SetupKey, SetupMatrix};
+use crate::task::{Task, TaskId, TaskStatus};
+use crate::work_order::{WorkOrder, WorkOrderId};
+
+type Span = (NaiveDateTime, NaiveDateTime);
+
+/// Which way operations are packed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleDirection {
+    /// As early as possible from the start of the horizon.
+    #[default]
+    Forward,
+    /// As late as possible, finishing by the due date of the work order.
+    Backward,
+}
+
+impl ScheduleDirection {
+    pub const ALL: [ScheduleDirection; 2] = [ScheduleDirection::Forward, ScheduleDirection::Backward];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            ScheduleDirection::Forward => "forward",
+            ScheduleDirection::Backward => "backward",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        ScheduleDirection::ALL.into_iter().find(|direction| direction.code() == code)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ScheduleDirection::Forward => "Forward",
+            ScheduleDirection::Backward => "Backward from due date",
+        }
+    }
+}
+
+/// Which of the operations ready to be placed goes first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityRule {
+    /// Earliest due date of the work order.
+    #[default]
+    EarliestDueDate,
+    /// Shortest processing time of the operation.
+    ShortestProcessingTime,
+    /// Smallest ratio of time left until the due date to work left on the order.
+    CriticalRatio,
+}
+
+impl PriorityRule {
+    pub const ALL: [PriorityRule; 3] = [
+        PriorityRule::EarliestDueDate,
+        PriorityRule::ShortestProcessingTime,
+        PriorityRule::CriticalRatio,
+    ];
+
+    /// Short label used in the UI ("EDD", "SPT", "CR").
+    pub fn code(self) -> &'static str {
+        match self {
+            PriorityRule::EarliestDueDate => "EDD",
+            PriorityRule::ShortestProcessingTime => "SPT",
+            PriorityRule::CriticalRatio => "CR",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        PriorityRule::ALL.into_iter().find(|rule| rule.code() == code)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PriorityRule::EarliestDueDate => "Earliest due date",
+            PriorityRule::ShortestProcessingTime => "Shortest processing time",
+            PriorityRule::CriticalRatio => "Critical ratio",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchedulerOptions {
+    pub direction: ScheduleDirection,
+    pub rule: PriorityRule,
+    /// Nothing is placed before this time, usually now.
+    pub start: NaiveDateTime,
+    /// Leave operations that are still only planned where they are and place the released ones.
+    #[serde(default)]
+    pub released_only: bool,
+}
+
+/// Why an open operation was left where it was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The operation takes time but is not assigned to a machine.
+    NoMachine,
+    /// Backward scheduling needs the work order to have a due date.
+    NoDueDate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedTask {
+    pub task_id: TaskId,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleOutcome {
+    /// All tasks, with the placed operations at their new dates. Summary dates are not rolled up.
+    pub tasks: Vec<Task>,
+    /// Operations whose dates changed, in the order they were placed.
+    pub moved: Vec<TaskId>,
+    pub skipped: Vec<SkippedTask>,
+    /// Backward mode only: operations that would have to start before `SchedulerOptions::start`
+    /// to be done by their due date.
+    pub infeasible: Vec<TaskId>,
+}
+
+/// An operation to place.
+struct Operation {
+    id: TaskId,
+    machine_id: Option<MachineId>,
+    /// Working minutes on the machine's calendar; zero for milestones, which need no machine.
+    work: i64,
+    work_order_id: WorkOrderId,
+    due_date: Option<NaiveDateTime>,
+}
+
+/// The periods a machine is taken, one list per unit of capacity, each with what the machine
+/// was set up for.
+struct MachineLoad<'a> {
+    calendar: Option<&'a Calendar>,
+    setup: &'a SetupMatrix,
+    slots: Vec<Vec<(Span, SetupKey)>>,
+    maintenance: Vec<Span>,
+}
+
+impl<'a> MachineLoad<'a> {
+    fn new(machine: &'a Machine, calendar: Option<&'a Calendar>) -> Self {
+        MachineLoad {
+            calendar,
+            setup: &machine.setup,
+            slots: vec![Vec::new(); machine.capacity.max(1) as usize],
+            maintenance: machine.maintenance.iter().map(|window| (window.start_date, window.end_date)).collect(),
+        }
+    }
+
+    /// Books `span` on the first unit that is free for it, or on the first unit if none is.
+    fn occupy(&mut self, span: Span, key: SetupKey) {
+        let slot = (0..self.slots.len())
+            .find(|slot| self.clashes(*slot, span, &key).next().is_none())
+            .unwrap_or(0);
+        self.slots[slot].push((span, key));
+    }
+
+    /// The periods on one unit that keep a `key` job from running in `span`: booked time and
+    /// maintenance overlapping it, and the neighbouring jobs with the changeover between them
+    /// and this job when it does not fit in the gap.
+    fn clashes(&self, slot: usize, (start, end): Span, key: &SetupKey) -> impl Iterator<Item = Span> + '_ {
+        let booked = &self.slots[slot];
+        let previous = booked.iter().filter(|((_, busy_end), _)| *busy_end <= start).max_by_key(|(span, _)| span.1);
+        let next = booked.iter().filter(|((busy_start, _), _)| *busy_start >= end).min_by_key(|(span, _)| span.0);
+        let setup_before = previous
+            .map(|(span, from)| (span.0, span.1 + Duration::minutes(self.setup.minutes(from, key))))
+            .filter(|(_, set_up)| *set_up > start);
+        let setup_after = next
+            .map(|(span, to)| (span.0 - Duration::minutes(self.setup.minutes(key, to)), span.1))
+            .filter(|(setup_start, _)| *setup_start < end);
+        booked
+            .iter()
+            .map(|(span, _)| span)
+            .chain(&self.maintenance)
+            .filter(move |(busy_start, busy_end)| *busy_start < end && start < *busy_end)
+            .copied()
+            .chain(setup_before)
+            .chain(setup_after)
+    }
+
+    fn finish(&self, start: NaiveDateTime, work: i64) -> NaiveDateTime {
+        match self.calendar {
+            Some(calendar) => calendar.add_working_minutes(calendar.next_working_time(start), work),
+            None => start + Duration::minutes(work),
+        }
+    }
+
+    fn begin(&self, end: NaiveDateTime, work: i64) -> NaiveDateTime {
+        match self.calendar {
+            Some(calendar) => calendar.subtract_working_minutes(calendar.previous_working_time(end), work),
+            None => end - Duration::minutes(work),
+        }
+    }
+
+    /// The earliest span of `work` starting at or after `not_before` and ending at or after
+    /// `end_not_before`, on whichever unit finishes first.
+    fn place_forward(
+        &self,
+        work: i64,
+        key: &SetupKey,
+        not_before: NaiveDateTime,
+        end_not_before: NaiveDateTime,
+    ) -> (usize, Span) {
+        let first_start = not_before.max(self.begin(end_not_before, work));
+        (0..self.slots.len())
+            .map(|slot| {
+                let mut start = first_start;
+                loop {
+                    start = self.calendar.map_or(start, |calendar| calendar.next_working_time(start));
+                    let end = self.finish(start, work);
+                    match self.clashes(slot, (start, end), key).map(|(_, busy_end)| busy_end).max() {
+                        Some(busy_end) => start = busy_end,
+                        None => return (slot, (start, end)),
+                    }
+                }
+            })
+            .min_by_key(|(slot, (_, end))| (*end, *slot))
+            .expect("a machine has at least one unit of capacity")
+    }
+
+    /// The latest span of `work` ending at or before `not_after` and starting at or before
+    /// `start_not_after`, on whichever unit starts last.
+    fn place_backward(
+        &self,
+        work: i64,
+        key: &SetupKey,
+        not_after: NaiveDateTime,
+        start_not_after: Option<NaiveDateTime>,
+    ) -> (usize, Span) {
+        let last_end = start_not_after.map_or(not_after, |start| not_after.min(self.finish(start, work)));
+        (0..self.slots.len())
+            .map(|slot| {
+                let mut end = last_end;
+                loop {
+                    end = self.calendar.map_or(end, |calendar| calendar.previous_working_time(end));
+                    let start = self.begin(end, work);
+                    match self.clashes(slot, (start, end), key).map(|(busy_start, _)| busy_start).min() {
+                        Some(busy_start) => end = busy_start,
+                        None => return (slot, (start, end)),
+                    }
+                }
+            })
+            .max_by_key(|(slot, (start, _))| (*start, std::cmp::Reverse(*slot)))
+            .expect("a machine has at least one unit of capacity")
+    }
+}
+
+/// Places the open operations of work orders: tasks linked to an order (directly or through a
+/// summary) that have not started yet. Every other task keeps its dates and its machine time.
+///
+/// Operations are taken one at a time from those whose predecessors (forward) or successors
+/// (backward) are already placed, in the order of `options.rule`, and put on the unit of their
+/// machine where they finish earliest (forward) or start latest (backward), skipping booked
+/// time, maintenance and the non-working time of the machine's calendar. The changeover from
+/// the job before on the same unit, and to the job after, is kept free as the machine's setup
+/// matrix asks, so operations and their successors move to make room for setup.
+pub fn auto_schedule(
+    tasks: &[Task],
+    machines: &[Machine],
+    calendars: &[Calendar],
+    work_orders: &[WorkOrder],
+    options: &SchedulerOptions,
+) -> Result<ScheduleOutcome, DependencyError> {
+    topological_order(tasks)?;
+    let backward = options.direction == ScheduleDirection::Backward;
+    let calendar_of = |machine: &Machine| {
+        calendars
+            .iter()
+            .find(|calendar| Some(calendar.id) == machine.calendar_id)
+            .filter(|calendar| calendar.has_working_time())
+    };
+    let due_dates: HashMap<WorkOrderId, Option<NaiveDateTime>> =
+        work_orders.iter().map(|order| (order.id, order.due_date)).collect();
+    let summaries: HashSet<TaskId> = tasks.iter().filter_map(|task| task.parent_id).collect();
+    let links = linked_work_orders(tasks);
+
+    let mut operations: Vec<Operation> = Vec::new();
+    let mut skipped = Vec::new();
+    for task in tasks {
+        let open = match task.status {
+            TaskStatus::Released => true,
+            TaskStatus::Planned => !options.released_only,
+            _ => false,
+        };
+        let Some(&work_order_id) = links.get(&task.id).filter(|_| open && !summaries.contains(&task.id)) else {
+            continue;
+        };
+        let due_date = due_dates.get(&work_order_id).copied().flatten();
+        let machine = machines.iter().find(|machine| Some(machine.id) == task.machine_id);
+        let work = match (task.is_milestone(), machine) {
+            (true, _) => 0,
+            (false, Some(machine)) => match calendar_of(machine) {
+                Some(calendar) => calendar.working_minutes(task.start_date, task.end_date),
+                None => (task.end_date - task.start_date).num_minutes(),
+            },
+            (false, None) => {
+                skipped.push(SkippedTask { task_id: task.id, reason: SkipReason::NoMachine });
+                continue;
+            }
+        };
+        if backward && due_date.is_none() {
+            skipped.push(SkippedTask { task_id: task.id, reason: SkipReason::NoDueDate });
+            continue;
+        }
+        operations.push(Operation { id: task.id, machine_id: task.machine_id, work, work_order_id, due_date });
+    }
+
+    // Everything that is not placed here keeps its machine time
+    let placing: HashSet<TaskId> = operations.iter().map(|operation| operation.id).collect();
+    let mut loads: HashMap<MachineId, MachineLoad> =
+        machines.iter().map(|machine| (machine.id, MachineLoad::new(machine, calendar_of(machine)))).collect();
+    for task in tasks {
+        if placing.contains(&task.id) || summaries.contains(&task.id) || task.status == TaskStatus::Cancelled {
+            continue;
+        }
+        if let Some(load) = task.machine_id.and_then(|id| loads.get_mut(&id)) {
+            if task.start_date < task.end_date {
+                load.occupy((task.start_date, task.end_date), task.setup_key());
+            }
+        }
+    }
+
+    let by_id: HashMap<TaskId, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+    // For each task, the tasks that name it as predecessor
+    let mut successors: HashMap<TaskId, Vec<TaskId>> = HashMap::new();
+    for task in tasks {
+        for dependency in &task.dependencies {
+            successors.entry(dependency.predecessor_id).or_default().push(task.id);
+        }
+    }
+    let mut spans: HashMap<TaskId, Span> = tasks
+        .iter()
+        .filter(|task| !placing.contains(&task.id))
+        .map(|task| (task.id, (task.start_date, task.end_date)))
+        .collect();
+
+    let mut remaining: Vec<Operation> = operations;
+    let mut placed_order = Vec::new();
+    let mut infeasible = Vec::new();
+    while !remaining.is_empty() {
+        // Operations whose neighbours on the near side are all placed
+        let ready: Vec<usize> = (0..remaining.len())
+            .filter(|index| {
+                let task = by_id[&remaining[*index].id];
+                if backward {
+                    successors.get(&task.id).into_iter().flatten().all(|id| spans.contains_key(id))
+                } else {
+                    task.dependencies.iter().all(|dependency| {
+                        spans.contains_key(&dependency.predecessor_id) || !by_id.contains_key(&dependency.predecessor_id)
+                    })
+                }
+            })
+            .collect();
+        let work_left: HashMap<WorkOrderId, i64> = remaining.iter().fold(HashMap::new(), |mut work, operation| {
+            *work.entry(operation.work_order_id).or_default() += operation.work;
+            work
+        });
+        let priority = |operation: &Operation| -> f64 {
+            let until_due = operation.due_date.map(|due| (due - options.start).num_minutes() as f64);
+            match options.rule {
+                PriorityRule::EarliestDueDate => until_due.unwrap_or(f64::INFINITY),
+                PriorityRule::ShortestProcessingTime => operation.work as f64,
+                PriorityRule::CriticalRatio => match until_due {
+                    Some(until_due) => until_due / work_left[&operation.work_order_id].max(1) as f64,
+                    None => f64::INFINITY,
+                },
+            }
+        };
+        let Some(next) = ready.into_iter().min_by(|a, b| {
+            let (a, b) = (&remaining[*a], &remaining[*b]);
+            priority(a).total_cmp(&priority(b)).then(a.due_date.cmp(&b.due_date)).then(a.id.cmp(&b.id))
+        }) else {
+            // only possible with links through tasks that are missing from the list
+            break;
+        };
+        let operation = remaining.swap_remove(next);
+        let task = by_id[&operation.id];
+        let key = task.setup_key();
+        let load = operation.machine_id.filter(|_| operation.work > 0).and_then(|id| loads.get_mut(&id));
+
+        let span = if backward {
+            let (mut not_after, mut start_not_after) = (operation.due_date.unwrap_or(task.end_date), None);
+            for successor in successors.get(&task.id).into_iter().flatten().map(|id| by_id[id]) {
+                for dependency in successor.dependencies.iter().filter(|dependency| dependency.predecessor_id == task.id) {
+                    let (successor_start, successor_end) = spans[&successor.id];
+                    let anchor = if dependency.kind.to_finish() { successor_end } else { successor_start } - dependency.lag();
+                    if dependency.kind.from_finish() {
+                        not_after = not_after.min(anchor);
+                    } else {
+                        start_not_after = Some(start_not_after.map_or(anchor, |start: NaiveDateTime| start.min(anchor)));
+                    }
+                }
+            }
+            let span = match load {
+                Some(load) => {
+                    let (slot, span) = load.place_backward(operation.work, &key, not_after, start_not_after);
+                    load.slots[slot].push((span, key));
+                    span
+                }
+                None => {
+                    let end = start_not_after.map_or(not_after, |start| not_after.min(start));
+                    (end, end)
+                }
+            };
+            if span.0 < options.start {
+                infeasible.push(task.id);
+            }
+            span
+        } else {
+            let (mut not_before, mut end_not_before) = (options.start, options.start);
+            for dependency in &task.dependencies {
+                let Some(&(predecessor_start, predecessor_end)) = spans.get(&dependency.predecessor_id) else {
+                    continue;
+                };
+                let anchor = if dependency.kind.from_finish() { predecessor_end } else { predecessor_start } + dependency.lag();
+                if dependency.kind.to_finish() {
+                    end_not_before = end_not_before.max(anchor);
+                } else {
+                    not_before = not_before.max(anchor);
+                }
+            }
+            match load {
+                Some(load) => {
+                    let (slot, span) = load.place_forward(operation.work, &key, not_before, end_not_before);
+                    load.slots[slot].push((span, key));
+                    span
+                }
+                None => {
+                    let start = not_before.max(end_not_before);
+                    (start, start)
+                }
+            }
+        };
+        spans.insert(task.id, span);
+        placed_order.push(task.id);
+    }
+
+    let mut scheduled = tasks.to_vec();
+    let mut moved = Vec::new();
+    for id in placed_order {
+        let task = scheduled.iter_mut().find(|task| task.id == id).expect("placed tasks come from the list");
+        let (start, end) = spans[&id];
+        if (task.start_date, task.end_date) != (start, end) {
+            (task.start_date, task.end_date) = (start, end);
+            moved.push(id);
+        }
+    }
+    Ok(ScheduleOutcome { tasks: scheduled, moved, skipped, infeasible })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::Shift;
+    use crate::dependency::{Dependency, DependencyKind};
+    use crate::setup::setup_blocks;
+    use chrono::NaiveDate;
+
+    fn at(hour: i64) -> NaiveDateTime {
+        // 2025-03-03 is a Monday
+        NaiveDate::from_ymd_opt(2025, 3, 3).unwrap().and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn operation(id: TaskId, work_order_id: WorkOrderId, machine_id: MachineId, hours: i64, after: Option<TaskId>) -> Task {
+        Task {
+            id,
+            name: format!("Op {}", id),
+            start_date: at(0),
+            end_date: at(hours),
+            color: "#4CAF50".to_string(),
+            work_order_id: Some(work_order_id),
+            machine_id: Some(machine_id),
+            dependencies: after
+                .map(|predecessor_id| Dependency { predecessor_id, kind: DependencyKind::FinishToStart, lag_minutes: 0 })
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn order(id: WorkOrderId, due_hour: i64) -> WorkOrder {
+        WorkOrder {
+            id,
+            number: format!("WO-{}", id),
+            customer: String::new(),
+            part_name: "Bracket".to_string(),
+            quantity: 1,
+            due_date: Some(at(due_hour)),
+        }
+    }
+
+    fn spans(outcome: &ScheduleOutcome) -> Vec<(TaskId, i64, i64)> {
+        outcome
+            .tasks
+            .iter()
+            .map(|task| (task.id, (task.start_date - at(0)).num_hours(), (task.end_date - at(0)).num_hours()))
+            .collect()
+    }
+
+    #[test]
+    fn forward_packs_operations_by_rule_around_fixed_work() {
+        // order 1 (due 30h): lathe 4h then mill 2h; order 2 (due 10h): lathe 3h then mill 3h
+        let mut fixed = operation(5, 3, 2, 2, None);
+        fixed.work_order_id = None;
+        fixed.start_date = at(3);
+        fixed.end_date = at(5);
+        let tasks = vec![
+            operation(1, 1, 1, 4, None),
+            operation(2, 1, 2, 2, Some(1)),
+            operation(3, 2, 1, 3, None),
+            operation(4, 2, 2, 3, Some(3)),
+            fixed,
+        ];
+        let machines = vec![
+            Machine { id: 1, name: "Lathe".to_string(), ..Default::default() },
+            Machine { id: 2, name: "Mill".to_string(), ..Default::default() },
+        ];
+        let orders = vec![order(1, 30), order(2, 10)];
+        let mut options = SchedulerOptions {
+            direction: ScheduleDirection::Forward,
+            rule: PriorityRule::EarliestDueDate,
+            start: at(0),
+            released_only: false,
+        };
+
+        // order 2 goes first; its mill operation waits for the fixed job on the mill
+        let outcome = auto_schedule(&tasks, &machines, &[], &orders, &options).unwrap();
+        assert_eq!(spans(&outcome), vec![(1, 3, 7), (2, 8, 10), (3, 0, 3), (4, 5, 8), (5, 3, 5)]);
+        // the first lathe job already sits at the start of the horizon
+        assert_eq!(outcome.moved, vec![4, 1, 2]);
+
+        // shortest first also takes the 3h jobs of order 2 ahead of the 4h lathe job
+        options.rule = PriorityRule::ShortestProcessingTime;
+        let outcome = auto_schedule(&tasks, &machines, &[], &orders, &options).unwrap();
+        assert_eq!(spans(&outcome)[..4], [(1, 3, 7), (2, 8, 10), (3, 0, 3), (4, 5, 8)]);
+
+        // only released operations move
+        options.released_only = true;
+        let outcome = auto_schedule(&tasks, &machines, &[], &orders, &options).unwrap();
+        assert!(outcome.moved.is_empty());
+    }
+
+    #[test]
+    fn changeovers_push_operations_and_their_successors_back() {
+        // an hour to change the lathe over between any two jobs
+        let lathe = Machine {
+            id: 1,
+            name: "Lathe".to_string(),
+            setup: SetupMatrix { default_minutes: 60, entries: Vec::new() },
+            ..Default::default()
+        };
+        let machines = vec![lathe, Machine { id: 2, name: "Mill".to_string(), ..Default::default() }];
+        let family = |task: Task, part_family: &str| Task { part_family: part_family.to_string(), ..task };
+        let mut fixed = family(operation(4, 3, 1, 3, None), "shaft");
+        fixed.work_order_id = None;
+        fixed.start_date = at(5);
+        fixed.end_date = at(8);
+        let tasks = vec![
+            family(operation(1, 1, 1, 2, None), "shaft"),
+            family(operation(2, 2, 1, 2, None), "flange"),
+            operation(3, 2, 2, 1, Some(2)),
+            fixed,
+        ];
+        let options = SchedulerOptions {
+            direction: ScheduleDirection::Forward,
+            rule: PriorityRule::EarliestDueDate,
+            start: at(0),
+            released_only: false,
+        };
+
+        // the flange job would fit from 03:00 after its setup, but then the lathe could not be
+        // set back up for the fixed shaft job at 05:00, so it follows that job and its setup
+        let outcome = auto_schedule(&tasks, &machines, &[], &[order(1, 10), order(2, 20)], &options).unwrap();
+        assert_eq!(spans(&outcome), vec![(1, 0, 2), (2, 9, 11), (3, 11, 12), (4, 5, 8)]);
+        assert!(setup_blocks(&outcome.tasks, &machines).iter().all(|block| block.shortfall_minutes() == 0));
+
+        // backward, the shaft job ends an hour before the flange job to leave time for setup
+        let tasks = vec![family(operation(1, 1, 1, 2, None), "shaft"), family(operation(2, 1, 1, 2, Some(1)), "flange")];
+        let backward = SchedulerOptions { direction: ScheduleDirection::Backward, ..options };
+        let outcome = auto_schedule(&tasks, &machines, &[], &[order(1, 12)], &backward).unwrap();
+        assert_eq!(spans(&outcome), vec![(1, 7, 9), (2, 10, 12)]);
+    }
+
+    #[test]
+    fn backward_finishes_by_the_due_date_within_working_time() {
+        // the lathe works 06:00-14:00 on weekdays; the order is due Tuesday 12:00
+        let mut week: [Vec<Shift>; 7] = Default::default();
+        week[..5].fill(vec![Shift::parse("06:00-14:00").unwrap()]);
+        let calendar = Calendar { id: 1, name: "Day shift".to_string(), week, exceptions: Vec::new() };
+        let machines = vec![Machine { id: 1, name: "Lathe".to_string(), calendar_id: Some(1), ..Default::default() }];
+        let mut first = operation(1, 1, 1, 8, None);
+        first.start_date = at(6);
+        first.end_date = at(14);
+        let mut second = operation(2, 1, 1, 4, Some(1));
+        second.start_date = at(6);
+        second.end_date = at(10);
+        let options = SchedulerOptions {
+            direction: ScheduleDirection::Backward,
+            rule: PriorityRule::CriticalRatio,
+            start: at(0),
+            released_only: false,
+        };
+
+        let outcome = auto_schedule(&[first, second], &machines, &[calendar], &[order(1, 36)], &options).unwrap();
+        // 4h on Tuesday up to 12:00, then 8h of work ending at 08:00 Tuesday: 2h Tuesday + 6h Monday
+        assert_eq!(spans(&outcome), vec![(1, 8, 32), (2, 32, 36)]);
+        assert!(outcome.infeasible.is_empty());
+
+        let late = SchedulerOptions { start: at(12), ..options };
+        let outcome = auto_schedule(&outcome.tasks, &machines, &[], &[order(1, 36)], &late).unwrap();
+        assert_eq!(outcome.infeasible, vec![1]);
+    }
+}
@@ -0,0 +1,221 @@
+//! Sequence-dependent changeover time between consecutive jobs on a machine.
+
+use std::collections::HashSet;
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::machine::{Machine, MachineId};
+use crate::task::{Task, TaskId, TaskStatus};
+use crate::validation::{require_range, ValidationError};
+
+/// The longest changeover a matrix may hold, one week.
+const MAX_SETUP_MINUTES: i64 = 7 * 24 * 60;
+
+/// What a machine has to be set up for to run a job. In a matrix entry an empty field matches
+/// any value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SetupKey {
+    #[serde(default)]
+    pub part_family: String,
+    #[serde(default)]
+    pub material: String,
+}
+
+impl SetupKey {
+    fn matches(&self, key: &SetupKey) -> bool {
+        (self.part_family.is_empty() || self.part_family == key.part_family)
+            && (self.material.is_empty() || self.material == key.material)
+    }
+
+    /// How many fields are filled in; more specific entries win over wildcards.
+    fn specificity(&self) -> usize {
+        [&self.part_family, &self.material].iter().filter(|field| !field.is_empty()).count()
+    }
+}
+
+/// One cell of a setup matrix: the changeover from a job like `from` to a job like `to`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SetupTime {
+    pub from: SetupKey,
+    pub to: SetupKey,
+    pub minutes: i64,
+}
+
+/// The changeover times of one machine, keyed by the part family and material of the job
+/// before and the job after.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SetupMatrix {
+    /// Used when no entry matches.
+    #[serde(default)]
+    pub default_minutes: i64,
+    #[serde(default)]
+    pub entries: Vec<SetupTime>,
+}
+
+impl SetupMatrix {
+    /// A machine without setup times never gets setup blocks.
+    pub fn is_empty(&self) -> bool {
+        self.default_minutes == 0 && self.entries.is_empty()
+    }
+
+    /// Minutes to change over from a `from` job to a `to` job: the matching entry with the most
+    /// fields filled in (the first one on a tie), or `default_minutes` when none matches.
+    pub fn minutes(&self, from: &SetupKey, to: &SetupKey) -> i64 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.from.matches(from) && entry.to.matches(to))
+            // `max_by_key` keeps the last of equals, so go backwards to prefer the first entry
+            .rev()
+            .max_by_key(|entry| entry.from.specificity() + entry.to.specificity())
+            .map_or(self.default_minutes, |entry| entry.minutes)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ValidationError> {
+        for minutes in std::iter::once(self.default_minutes).chain(self.entries.iter().map(|entry| entry.minutes)) {
+            require_range("setup minutes", minutes as f64, 0.0, MAX_SETUP_MINUTES as f64)?;
+        }
+        Ok(())
+    }
+}
+
+/// The changeover before a job, ending when the job starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetupBlock {
+    pub machine_id: MachineId,
+    /// The job the machine is set up for.
+    pub task_id: TaskId,
+    /// The job that ran before on the same unit of the machine.
+    pub previous_id: TaskId,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// When the previous job ends; the setup does not fit when it starts before this.
+    pub previous_end: NaiveDateTime,
+}
+
+impl SetupBlock {
+    pub fn minutes(&self) -> i64 {
+        (self.end - self.start).num_minutes()
+    }
+
+    /// Minutes the setup overlaps the previous job, zero when it fits in the gap.
+    pub fn shortfall_minutes(&self) -> i64 {
+        (self.previous_end - self.start).num_minutes().max(0)
+    }
+}
+
+/// Places a setup block before every job that follows another on the same machine, using the
+/// machine's setup matrix. Jobs are taken in start order and follow the job that ended last on
+/// the first free unit of the machine, so each unit of a multi-pallet machine has its own
+/// sequence. Milestones, summaries and cancelled jobs are left out.
+pub fn setup_blocks(tasks: &[Task], machines: &[Machine]) -> Vec<SetupBlock> {
+    let summaries: HashSet<TaskId> = tasks.iter().filter_map(|task| task.parent_id).collect();
+    let mut blocks = Vec::new();
+    for machine in machines.iter().filter(|machine| !machine.setup.is_empty()) {
+        let mut jobs: Vec<&Task> = tasks
+            .iter()
+            .filter(|task| {
+                task.machine_id == Some(machine.id)
+                    && !task.is_milestone()
+                    && task.status != TaskStatus::Cancelled
+                    && !summaries.contains(&task.id)
+            })
+            .collect();
+        jobs.sort_by_key(|task| (task.start_date, task.id));
+        // The last job on each unit
+        let mut units: Vec<Option<&Task>> = vec![None; machine.capacity.max(1) as usize];
+        for job in jobs {
+            let unit = units
+                .iter()
+                .position(|last| last.is_none_or(|last| last.end_date <= job.start_date))
+                .unwrap_or_else(|| {
+                    // every unit is still busy; the job follows the one that ends first
+                    (0..units.len()).min_by_key(|unit| units[*unit].map(|last| last.end_date)).unwrap_or(0)
+                });
+            if let Some(previous) = units[unit] {
+                let minutes = machine.setup.minutes(&previous.setup_key(), &job.setup_key());
+                if minutes > 0 {
+                    blocks.push(SetupBlock {
+                        machine_id: machine.id,
+                        task_id: job.id,
+                        previous_id: previous.id,
+                        start: job.start_date - Duration::minutes(minutes),
+                        end: job.start_date,
+                        previous_end: previous.end_date,
+                    });
+                }
+            }
+            units[unit] = Some(job);
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn key(part_family: &str, material: &str) -> SetupKey {
+        SetupKey { part_family: part_family.to_string(), material: material.to_string() }
+    }
+
+    fn at(hour: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 3).unwrap().and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn job(id: TaskId, start: i64, end: i64, part_family: &str, material: &str) -> Task {
+        Task {
+            id,
+            name: format!("Job {}", id),
+            start_date: at(start),
+            end_date: at(end),
+            color: "#4CAF50".to_string(),
+            machine_id: Some(1),
+            part_family: part_family.to_string(),
+            material: material.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn picks_the_most_specific_entry_for_each_changeover() {
+        let matrix = SetupMatrix {
+            default_minutes: 90,
+            entries: vec![
+                // same jaws and material: only a quick check
+                SetupTime { from: key("shaft", "S45C"), to: key("shaft", "S45C"), minutes: 10 },
+                SetupTime { from: key("shaft", ""), to: key("shaft", ""), minutes: 30 },
+            ],
+        };
+        assert_eq!(matrix.minutes(&key("shaft", "S45C"), &key("shaft", "S45C")), 10);
+        assert_eq!(matrix.minutes(&key("shaft", "S45C"), &key("shaft", "SUS304")), 30);
+        assert_eq!(matrix.minutes(&key("shaft", "S45C"), &key("flange", "S45C")), 90);
+    }
+
+    #[test]
+    fn follows_the_order_of_jobs_on_the_machine() {
+        let machines = vec![Machine {
+            id: 1,
+            name: "Lathe".to_string(),
+            setup: SetupMatrix {
+                default_minutes: 90,
+                entries: vec![SetupTime { from: key("shaft", ""), to: key("shaft", ""), minutes: 10 }],
+            },
+            ..Default::default()
+        }];
+        let mut tasks = vec![job(1, 0, 4, "shaft", "S45C"), job(2, 5, 8, "flange", "S45C"), job(3, 10, 12, "shaft", "S45C")];
+        let spans = |blocks: Vec<SetupBlock>| {
+            blocks.iter().map(|block| (block.task_id, block.previous_id, block.minutes(), block.shortfall_minutes())).collect::<Vec<_>>()
+        };
+        // the flange needs new jaws and 90 minutes, half an hour more than the gap after the first shaft
+        assert_eq!(spans(setup_blocks(&tasks, &machines)), vec![(2, 1, 90, 30), (3, 2, 90, 0)]);
+
+        // moving the second shaft ahead of the flange groups the shafts together
+        tasks[2].start_date = at(5);
+        tasks[2].end_date = at(6);
+        tasks[1].start_date = at(8);
+        tasks[1].end_date = at(10);
+        assert_eq!(spans(setup_blocks(&tasks, &machines)), vec![(3, 1, 10, 0), (2, 3, 90, 0)]);
+    }
+}
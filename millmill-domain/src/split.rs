@@ -0,0 +1,373 @@
+//! Jobs split into segments that keep one identity, e.g. a lot divided between two machines or
+//! a job paused over a weekend. The first segment keeps the task id; the others point at it
+//! through `split_of`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::calendar::Calendar;
+use crate::dependency::{Dependency, DependencyKind};
+use crate::task::{Task, TaskId, TaskStatus};
+use crate::wbs::is_summary;
+
+/// Where to divide a job.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitPoint {
+    /// End the first segment at this time; the rest continues after it.
+    At(NaiveDateTime),
+    /// Put this many pieces in the first segment; the rest is a lot of its own that can run on
+    /// another machine.
+    Quantity(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SplitError {
+    UnknownTask(TaskId),
+    /// Milestones and summary tasks have no work of their own to divide.
+    NotSplittable(TaskId),
+    /// The split point leaves one of the segments empty.
+    EmptySegment,
+    /// A segment names a first segment that is missing or is itself a later segment.
+    UnknownLot { task: TaskId, lot: TaskId },
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitError::UnknownTask(id) => write!(f, "task {} does not exist", id),
+            SplitError::NotSplittable(id) => write!(f, "task {} is a milestone or a summary and cannot be split", id),
+            SplitError::EmptySegment => write!(f, "the split point must fall inside the task and leave work on both sides"),
+            SplitError::UnknownLot { task, lot } => {
+                write!(f, "task {} is a segment of task {}, which is not the first segment of a job", task, lot)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SplitError {}
+
+/// The id shared by all segments of the job `task` belongs to.
+pub fn lot_id(task: &Task) -> TaskId {
+    task.split_of.unwrap_or(task.id)
+}
+
+/// The segments of the job `id` belongs to in start order; just that task when it is not split.
+pub fn segments(tasks: &[Task], id: TaskId) -> Vec<&Task> {
+    let Some(lot) = tasks.iter().find(|task| task.id == id).map(lot_id) else {
+        return Vec::new();
+    };
+    let mut segments: Vec<&Task> = tasks.iter().filter(|task| lot_id(task) == lot).collect();
+    segments.sort_by_key(|task| (task.start_date, task.id));
+    segments
+}
+
+/// Checks that every segment points at a task that is the first segment of its job.
+pub fn validate_segments(tasks: &[Task]) -> Result<(), SplitError> {
+    let lots: HashMap<TaskId, Option<TaskId>> = tasks.iter().map(|task| (task.id, task.split_of)).collect();
+    for task in tasks {
+        if let Some(lot) = task.split_of {
+            if lot == task.id || lots.get(&lot) != Some(&None) {
+                return Err(SplitError::UnknownLot { task: task.id, lot });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The amount of work between `start` and `end` used to share out progress: working minutes on
+/// `calendar`, or wall-clock minutes without one.
+fn work_minutes(calendar: Option<&Calendar>, start: NaiveDateTime, end: NaiveDateTime) -> i64 {
+    calendar.map_or((end - start).num_minutes(), |calendar| calendar.working_minutes(start, end))
+}
+
+/// Divides task `id` in two at `point`; the second segment gets `new_id`. `calendar` is that of
+/// the task's machine and decides how the work is shared out.
+///
+/// Quantity is divided in proportion to the work, or the other way round for a quantity split,
+/// with at least one piece on each side, and the pieces already done are counted into the first segment first, so the job as a whole
+/// keeps its quantity and progress. Successors wait for the second segment as well; after a split
+/// in time the second segment also follows the first.
+pub fn split_task(
+    tasks: &mut Vec<Task>,
+    id: TaskId,
+    point: SplitPoint,
+    new_id: TaskId,
+    calendar: Option<&Calendar>,
+) -> Result<(), SplitError> {
+    let index = tasks.iter().position(|task| task.id == id).ok_or(SplitError::UnknownTask(id))?;
+    let task = tasks[index].clone();
+    if task.is_milestone() || is_summary(tasks, id) {
+        return Err(SplitError::NotSplittable(id));
+    }
+    // Spans that fall entirely outside the calendar's working time are measured on the clock
+    let calendar = calendar.filter(|calendar| calendar.working_minutes(task.start_date, task.end_date) > 0);
+    let total_minutes = work_minutes(calendar, task.start_date, task.end_date);
+
+    let (split_time, first_quantity) = match point {
+        SplitPoint::At(time) => {
+            if time <= task.start_date || time >= task.end_date {
+                return Err(SplitError::EmptySegment);
+            }
+            let share = work_minutes(calendar, task.start_date, time) as f64 / total_minutes.max(1) as f64;
+            let quantity = (task.quantity as f64 * share).round() as u32;
+            // A quantity of 0 means "not counted", so a counted job keeps a piece on each side
+            match task.quantity {
+                0 => (time, 0),
+                1 => return Err(SplitError::EmptySegment),
+                total => (time, quantity.clamp(1, total - 1)),
+            }
+        }
+        SplitPoint::Quantity(quantity) => {
+            if quantity == 0 || quantity >= task.quantity {
+                return Err(SplitError::EmptySegment);
+            }
+            let minutes = (total_minutes as f64 * quantity as f64 / task.quantity as f64).round() as i64;
+            let time = match calendar {
+                Some(calendar) => calendar.add_working_minutes(task.start_date, minutes),
+                None => task.start_date + Duration::minutes(minutes),
+            };
+            if time <= task.start_date || time >= task.end_date {
+                return Err(SplitError::EmptySegment);
+            }
+            (time, quantity)
+        }
+    };
+
+    // Progress is shared out in pieces when the job has a quantity, in minutes of work otherwise
+    let (first_units, total_units) = if task.quantity > 0 {
+        (first_quantity as f64, task.quantity as f64)
+    } else {
+        (work_minutes(calendar, task.start_date, split_time) as f64, total_minutes as f64)
+    };
+    let done = task.percent_complete / 100.0 * total_units;
+    let percent = |done: f64, units: f64| if units > 0.0 { (done / units * 100.0).clamp(0.0, 100.0) } else { task.percent_complete };
+    let first_done = done.min(first_units);
+
+    let mut second = Task {
+        id: new_id,
+        start_date: split_time,
+        quantity: task.quantity - first_quantity,
+        percent_complete: percent(done - first_done, total_units - first_units),
+        split_of: Some(lot_id(&task)),
+        ..task.clone()
+    };
+    match point {
+        SplitPoint::At(_) => {
+            second.dependencies =
+                vec![Dependency { predecessor_id: id, kind: DependencyKind::FinishToStart, lag_minutes: 0 }];
+        }
+        // a separate lot needs the same inputs as the first
+        SplitPoint::Quantity(_) => {}
+    }
+    // The rest of the work has not started unless part of it is already done
+    if !matches!(task.status, TaskStatus::Done | TaskStatus::Cancelled) && second.percent_complete == 0.0 {
+        if task.status.is_started() {
+            second.status = TaskStatus::Released;
+        }
+        second.actual_start = None;
+        second.actual_end = None;
+    }
+
+    let first = &mut tasks[index];
+    first.end_date = split_time;
+    first.quantity = first_quantity;
+    first.percent_complete = percent(first_done, first_units);
+
+    for successor in tasks.iter_mut() {
+        let links: Vec<Dependency> = successor
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.predecessor_id == id && dependency.kind.from_finish())
+            .cloned()
+            .collect();
+        for link in links {
+            if matches!(point, SplitPoint::At(_)) {
+                successor.dependencies.retain(|dependency| dependency.predecessor_id != id);
+            }
+            successor.dependencies.push(Dependency { predecessor_id: new_id, ..link });
+        }
+    }
+    tasks.insert(index + 1, second);
+    Ok(())
+}
+
+/// Joins all segments of the job `id` belongs to back into its first segment, which then runs
+/// from the earliest start to the latest end on its own machine. Quantities add up, progress is
+/// combined in proportion to pieces (or to the work on `calendar` without a quantity) and links
+/// to and from the other segments move to the joined task. Returns the id of the joined task.
+pub fn merge_segments(tasks: &mut Vec<Task>, id: TaskId, calendar: Option<&Calendar>) -> Result<TaskId, SplitError> {
+    let lot = tasks.iter().find(|task| task.id == id).map(lot_id).ok_or(SplitError::UnknownTask(id))?;
+    let parts: Vec<Task> = segments(tasks, id).into_iter().cloned().collect();
+    if parts.len() < 2 {
+        return Ok(lot);
+    }
+    let ids: Vec<TaskId> = parts.iter().map(|task| task.id).collect();
+    let quantity: u32 = parts.iter().map(|task| task.quantity).sum();
+    let weight = |task: &Task| {
+        if quantity > 0 {
+            task.quantity as f64
+        } else {
+            work_minutes(calendar, task.start_date, task.end_date).max(0) as f64
+        }
+    };
+    let total_weight: f64 = parts.iter().map(weight).sum();
+    let percent_complete = if total_weight > 0.0 {
+        parts.iter().map(|task| task.percent_complete * weight(task)).sum::<f64>() / total_weight
+    } else {
+        parts.iter().map(|task| task.percent_complete).sum::<f64>() / parts.len() as f64
+    };
+    let status = if parts.iter().all(|task| task.status == parts[0].status) {
+        parts[0].status
+    } else if parts.iter().any(|task| matches!(task.status, TaskStatus::InSetup | TaskStatus::Running)) {
+        TaskStatus::Running
+    } else if parts.iter().any(|task| task.status.is_started()) {
+        TaskStatus::Paused
+    } else {
+        parts.iter().find(|task| task.id == lot).map_or(TaskStatus::Planned, |task| task.status)
+    };
+    let mut dependencies: Vec<Dependency> = Vec::new();
+    for dependency in parts.iter().flat_map(|task| &task.dependencies) {
+        if !ids.contains(&dependency.predecessor_id)
+            && !dependencies.iter().any(|kept| kept.predecessor_id == dependency.predecessor_id)
+        {
+            dependencies.push(dependency.clone());
+        }
+    }
+
+    tasks.retain(|task| task.id == lot || !ids.contains(&task.id));
+    for task in tasks.iter_mut() {
+        if task.id == lot {
+            task.start_date = parts.iter().map(|part| part.start_date).min().unwrap_or(task.start_date);
+            task.end_date = parts.iter().map(|part| part.end_date).max().unwrap_or(task.end_date);
+            task.quantity = quantity;
+            task.percent_complete = percent_complete;
+            task.status = status;
+            task.actual_start = parts.iter().filter_map(|part| part.actual_start).min();
+            task.actual_end = (status == TaskStatus::Done)
+                .then(|| parts.iter().filter_map(|part| part.actual_end).max())
+                .flatten();
+            task.dependencies = dependencies.clone();
+            continue;
+        }
+        let mut kept: Vec<Dependency> = Vec::new();
+        for dependency in task.dependencies.drain(..) {
+            let dependency = if ids.contains(&dependency.predecessor_id) {
+                Dependency { predecessor_id: lot, ..dependency }
+            } else {
+                dependency
+            };
+            if !kept.iter().any(|other| other.predecessor_id == dependency.predecessor_id) {
+                kept.push(dependency);
+            }
+        }
+        task.dependencies = kept;
+    }
+    Ok(lot)
+}
+
+/// Keeps the remaining segments of jobs whose first segment is among `removed` together: the
+/// earliest of them becomes the first segment of the others.
+pub fn rejoin_segments(tasks: &mut [Task], removed: &[Task]) {
+    for gone in removed.iter().filter(|task| task.split_of.is_none()) {
+        let new_lot = tasks
+            .iter()
+            .filter(|task| task.split_of == Some(gone.id))
+            .min_by_key(|task| (task.start_date, task.id))
+            .map(|task| task.id);
+        for task in tasks.iter_mut().filter(|task| task.split_of == Some(gone.id)) {
+            task.split_of = new_lot.filter(|lot| *lot != task.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(hour: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 3).unwrap().and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn task(id: TaskId, start: i64, end: i64) -> Task {
+        Task {
+            id,
+            name: format!("Task {}", id),
+            start_date: at(start),
+            end_date: at(end),
+            color: "#4CAF50".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn link(predecessor_id: TaskId) -> Dependency {
+        Dependency { predecessor_id, kind: DependencyKind::FinishToStart, lag_minutes: 0 }
+    }
+
+    #[test]
+    fn splitting_at_a_time_keeps_quantity_progress_and_order() {
+        // 500 pieces over 10 hours, 40% (200 pieces) done
+        let mut job = task(1, 0, 10);
+        job.quantity = 500;
+        job.percent_complete = 40.0;
+        job.status = TaskStatus::Running;
+        let mut next = task(2, 10, 12);
+        next.dependencies = vec![link(1)];
+        let mut tasks = vec![job, next];
+
+        split_task(&mut tasks, 1, SplitPoint::At(at(6)), 3, None).unwrap();
+        let (first, second) = (&tasks[0], &tasks[1]);
+        assert_eq!((first.end_date, first.quantity, first.percent_complete), (at(6), 300, 200.0 / 300.0 * 100.0));
+        assert_eq!((second.start_date, second.end_date, second.quantity, second.percent_complete), (at(6), at(10), 200, 0.0));
+        assert_eq!((second.split_of, second.status), (Some(1), TaskStatus::Released));
+        assert_eq!(second.dependencies, vec![link(1)]);
+        assert_eq!(tasks[2].dependencies, vec![link(3)]);
+        assert_eq!(segments(&tasks, 3).iter().map(|task| task.id).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(split_task(&mut tasks.clone(), 1, SplitPoint::At(at(6)), 4, None), Err(SplitError::EmptySegment));
+    }
+
+    #[test]
+    fn a_lot_split_by_quantity_merges_back() {
+        let mut job = task(1, 0, 10);
+        job.quantity = 500;
+        job.percent_complete = 10.0;
+        let mut next = task(2, 10, 12);
+        next.dependencies = vec![link(1)];
+        let original = vec![job, next];
+        let mut tasks = original.clone();
+
+        split_task(&mut tasks, 1, SplitPoint::Quantity(200), 3, None).unwrap();
+        assert_eq!((tasks[0].end_date, tasks[0].quantity, tasks[0].percent_complete), (at(4), 200, 25.0));
+        assert_eq!((tasks[1].start_date, tasks[1].quantity, tasks[1].percent_complete), (at(4), 300, 0.0));
+        // the follow-up waits for both lots
+        assert_eq!(tasks[2].dependencies, vec![link(1), link(3)]);
+        validate_segments(&tasks).unwrap();
+
+        // the second lot moves to another machine and runs alongside
+        tasks[1].machine_id = Some(2);
+        tasks[1].start_date = at(0);
+        tasks[1].end_date = at(6);
+        assert_eq!(merge_segments(&mut tasks, 3, None), Ok(1));
+        assert_eq!(tasks.len(), 2);
+        assert_eq!((tasks[0].start_date, tasks[0].end_date, tasks[0].machine_id), (at(0), at(6), None));
+        assert_eq!((tasks[0].quantity, tasks[0].percent_complete), (500, 10.0));
+        assert_eq!(tasks[1].dependencies, vec![link(1)]);
+    }
+
+    #[test]
+    fn splitting_near_an_end_keeps_a_piece_on_each_side() {
+        let mut job = task(1, 0, 100);
+        job.quantity = 10;
+        let mut tasks = vec![job.clone()];
+        split_task(&mut tasks, 1, SplitPoint::At(at(1)), 2, None).unwrap();
+        assert_eq!((tasks[0].quantity, tasks[1].quantity), (1, 9));
+        let mut tasks = vec![job.clone()];
+        split_task(&mut tasks, 1, SplitPoint::At(at(99)), 2, None).unwrap();
+        assert_eq!((tasks[0].quantity, tasks[1].quantity), (9, 1));
+
+        job.quantity = 1;
+        assert_eq!(split_task(&mut vec![job], 1, SplitPoint::At(at(50)), 2, None), Err(SplitError::EmptySegment));
+    }
+}
@@ -0,0 +1,251 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::dependency::Dependency;
+use crate::machine::MachineId;
+use crate::setup::SetupKey;
+use crate::validation::{require_color, require_range, require_span, require_text, Validate, ValidationError};
+use crate::work_order::WorkOrderId;
+
+pub type TaskId = usize;
+
+/// Where a job is in its life on the shop floor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// Scheduled but not yet handed to the shop floor.
+    #[default]
+    Planned,
+    /// Handed to the shop floor; material and instructions are ready.
+    Released,
+    /// The machine is being set up for the job.
+    InSetup,
+    Running,
+    /// Started, but interrupted.
+    Paused,
+    Done,
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub const ALL: [TaskStatus; 7] = [
+        TaskStatus::Planned,
+        TaskStatus::Released,
+        TaskStatus::InSetup,
+        TaskStatus::Running,
+        TaskStatus::Paused,
+        TaskStatus::Done,
+        TaskStatus::Cancelled,
+    ];
+
+    /// The value used in stored data ("planned", "in_setup", ...).
+    pub fn code(self) -> &'static str {
+        match self {
+            TaskStatus::Planned => "planned",
+            TaskStatus::Released => "released",
+            TaskStatus::InSetup => "in_setup",
+            TaskStatus::Running => "running",
+            TaskStatus::Paused => "paused",
+            TaskStatus::Done => "done",
+            TaskStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        TaskStatus::ALL.into_iter().find(|status| status.code() == code)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TaskStatus::Planned => "Planned",
+            TaskStatus::Released => "Released",
+            TaskStatus::InSetup => "In setup",
+            TaskStatus::Running => "Running",
+            TaskStatus::Paused => "Paused",
+            TaskStatus::Done => "Done",
+            TaskStatus::Cancelled => "Cancelled",
+        }
+    }
+
+    /// Reads a status code, or the free text that earlier versions stored. Text that does not
+    /// name a status is treated as planned.
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim().to_lowercase().replace([' ', '-'], "_");
+        TaskStatus::from_code(&text).unwrap_or(match text.as_str() {
+            "in_progress" | "started" | "wip" => TaskStatus::Running,
+            "setup" => TaskStatus::InSetup,
+            "complete" | "completed" | "finished" => TaskStatus::Done,
+            "canceled" => TaskStatus::Cancelled,
+            _ => TaskStatus::Planned,
+        })
+    }
+
+    /// Whether work has begun, so the task has an actual start.
+    pub fn is_started(self) -> bool {
+        matches!(self, TaskStatus::InSetup | TaskStatus::Running | TaskStatus::Paused | TaskStatus::Done)
+    }
+}
+
+impl<'de> Deserialize<'de> for TaskStatus {
+    /// Accepts the free-text statuses of older files as well as status codes.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|text| TaskStatus::parse(&text))
+    }
+}
+
+/// Whether a task takes time or marks a point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    #[default]
+    Task,
+    /// An event such as a ship date or a material arrival. Starts and ends at the same time.
+    Milestone,
+}
+
+impl TaskKind {
+    pub const ALL: [TaskKind; 2] = [TaskKind::Task, TaskKind::Milestone];
+
+    /// The value used in stored data.
+    pub fn code(self) -> &'static str {
+        match self {
+            TaskKind::Task => "task",
+            TaskKind::Milestone => "milestone",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        TaskKind::ALL.into_iter().find(|kind| kind.code() == code)
+    }
+}
+
+/// One bar on the Gantt chart.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    pub id: TaskId,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+    pub color: String,
+    #[serde(default)]
+    pub kind: TaskKind,
+    #[serde(default)]
+    pub status: TaskStatus,
+    /// The work order this task is an operation of, if any.
+    #[serde(default)]
+    pub work_order_id: Option<WorkOrderId>,
+    /// The machine this task runs on; unassigned tasks get a lane of their own.
+    #[serde(default)]
+    pub machine_id: Option<MachineId>,
+    /// Links to the tasks that must come before this one.
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    /// The summary task this one is grouped under in the work breakdown structure.
+    #[serde(default)]
+    pub parent_id: Option<TaskId>,
+    /// How much of the work is done, from 0 to 100. Summary tasks roll this up from their children.
+    #[serde(default)]
+    pub percent_complete: f64,
+    /// When work really began, kept apart from the planned `start_date`.
+    #[serde(default)]
+    pub actual_start: Option<NaiveDateTime>,
+    /// When work really finished, kept apart from the planned `end_date`.
+    #[serde(default)]
+    pub actual_end: Option<NaiveDateTime>,
+    /// The kind of part, e.g. the jaw set it is held in. Together with `material` it decides
+    /// the setup time on the machine.
+    #[serde(default)]
+    pub part_family: String,
+    #[serde(default)]
+    pub material: String,
+    /// Pieces to make; 0 when the job is not counted in pieces.
+    #[serde(default)]
+    pub quantity: u32,
+    /// The first segment of the job this task is a later segment of, when the job is split.
+    #[serde(default)]
+    pub split_of: Option<TaskId>,
+}
+
+impl Task {
+    pub fn is_milestone(&self) -> bool {
+        self.kind == TaskKind::Milestone
+    }
+
+    pub fn setup_key(&self) -> SetupKey {
+        SetupKey { part_family: self.part_family.clone(), material: self.material.clone() }
+    }
+
+    /// Moves the task to `status` at time `now`, recording the actual start when work begins
+    /// and the actual end when it is done or abandoned after starting. Done tasks are 100% complete.
+    pub fn record_status(&mut self, status: TaskStatus, now: NaiveDateTime) {
+        if status.is_started() && self.actual_start.is_none() {
+            self.actual_start = Some(now);
+        }
+        match status {
+            TaskStatus::Done => {
+                self.actual_end.get_or_insert(now);
+                self.percent_complete = 100.0;
+            }
+            TaskStatus::Cancelled => {
+                if self.actual_start.is_some() {
+                    self.actual_end.get_or_insert(now);
+                }
+            }
+            // Reopened work has not finished yet
+            _ => self.actual_end = None,
+        }
+        self.status = status;
+    }
+}
+
+impl Validate for Task {
+    fn validate(&self) -> Result<(), ValidationError> {
+        require_text("task name", &self.name)?;
+        require_span(self.start_date, self.end_date)?;
+        if self.is_milestone() && self.end_date != self.start_date {
+            return Err(ValidationError::MilestoneDuration);
+        }
+        require_range("percent complete", self.percent_complete, 0.0, 100.0)?;
+        if let Some(end) = self.actual_end {
+            let start = self.actual_start.ok_or(ValidationError::EmptyField("actual start"))?;
+            require_span(start, end)?;
+        }
+        require_color(&self.color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::value::{Error, StrDeserializer};
+    use serde::de::IntoDeserializer;
+
+    use super::*;
+
+    #[test]
+    fn statuses_read_from_codes_and_free_text() {
+        for status in TaskStatus::ALL {
+            assert_eq!(TaskStatus::parse(status.code()), status);
+            assert_eq!(TaskStatus::parse(status.label()), status);
+        }
+        assert_eq!(TaskStatus::parse(" In-Progress "), TaskStatus::Running);
+        assert_eq!(TaskStatus::parse("WIP"), TaskStatus::Running);
+        assert_eq!(TaskStatus::parse("setup"), TaskStatus::InSetup);
+        assert_eq!(TaskStatus::parse("Completed"), TaskStatus::Done);
+        assert_eq!(TaskStatus::parse("canceled"), TaskStatus::Cancelled);
+        assert_eq!(TaskStatus::parse(""), TaskStatus::Planned);
+        assert_eq!(TaskStatus::parse("waiting for material"), TaskStatus::Planned);
+    }
+
+    #[test]
+    fn older_files_deserialize_with_their_free_text() {
+        let read = |text: &str| {
+            let deserializer: StrDeserializer<'_, Error> = text.into_deserializer();
+            TaskStatus::deserialize(deserializer).unwrap()
+        };
+        assert_eq!(read("in_setup"), TaskStatus::InSetup);
+        assert_eq!(read("in progress"), TaskStatus::Running);
+        assert_eq!(read("on hold"), TaskStatus::Planned);
+    }
+}
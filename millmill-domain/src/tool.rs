@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use crate::machine::MachineId;
+use crate::validation::{require_text, Validate, ValidationError};
+
+pub type ToolId = usize;
+
+/// A cutting tool, optionally mounted on a machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    pub id: ToolId,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub diameter_mm: Option<f64>,
+    #[serde(default)]
+    pub machine_id: Option<MachineId>,
+}
+
+impl Validate for Tool {
+    fn validate(&self) -> Result<(), ValidationError> {
+        require_text("tool name", &self.name)?;
+        match self.diameter_mm {
+            Some(diameter) if diameter <= 0.0 => Err(ValidationError::NotPositive("tool diameter")),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+use chrono::NaiveDateTime;
+
+/// A rule violated by a domain value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// A required text field is empty or whitespace only.
+    EmptyField(&'static str),
+    /// A time span ends before it starts.
+    EndBeforeStart {
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    },
+    /// A numeric field that must be greater than zero is not.
+    NotPositive(&'static str),
+    /// A numeric field outside its allowed range.
+    OutOfRange {
+        field: &'static str,
+        min: f64,
+        max: f64,
+    },
+    /// A color that is not in `#RRGGBB` form.
+    InvalidColor(String),
+    /// A milestone that does not start and end at the same time.
+    MilestoneDuration,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::EmptyField(field) => write!(f, "{} must not be empty", field),
+            ValidationError::EndBeforeStart { start, end } => {
+                write!(f, "end ({}) is before start ({})", end, start)
+            }
+            ValidationError::NotPositive(field) => write!(f, "{} must be greater than zero", field),
+            ValidationError::OutOfRange { field, min, max } => {
+                write!(f, "{} must be between {} and {}", field, min, max)
+            }
+            ValidationError::InvalidColor(color) => {
+                write!(f, "\"{}\" is not a #RRGGBB color", color)
+            }
+            ValidationError::MilestoneDuration => write!(f, "a milestone must end when it starts"),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Checks the invariants of a domain value before it is stored or scheduled.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationError>;
+}
+
+pub(crate) fn require_text(field: &'static str, value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::EmptyField(field));
+    }
+    Ok(())
+}
+
+pub(crate) fn require_span(start: NaiveDateTime, end: NaiveDateTime) -> Result<(), ValidationError> {
+    if end < start {
+        return Err(ValidationError::EndBeforeStart { start, end });
+    }
+    Ok(())
+}
+
+pub(crate) fn require_range(field: &'static str, value: f64, min: f64, max: f64) -> Result<(), ValidationError> {
+    if !(min..=max).contains(&value) {
+        return Err(ValidationError::OutOfRange { field, min, max });
+    }
+    Ok(())
+}
+
+pub(crate) fn require_color(color: &str) -> Result<(), ValidationError> {
+    let hex = color.strip_prefix('#').unwrap_or("");
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ValidationError::InvalidColor(color.to_string()));
+    }
+    Ok(())
+}
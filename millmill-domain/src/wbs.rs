@@ -0,0 +1,265 @@
+//! The work breakdown structure: tasks grouped under summary tasks through `parent_id`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::task::{Task, TaskId};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HierarchyError {
+    /// A task names a parent that is not in the list.
+    UnknownParent { task: TaskId, parent: TaskId },
+    /// The parent links form a loop; the ids are the tasks on it, child first.
+    Cycle(Vec<TaskId>),
+}
+
+impl fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HierarchyError::UnknownParent { task, parent } => {
+                write!(f, "task {} is grouped under missing task {}", task, parent)
+            }
+            HierarchyError::Cycle(ids) => match ids.first() {
+                Some(first) => {
+                    let path: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                    write!(f, "task grouping loops: {} → {}", path.join(" → "), first)
+                }
+                None => write!(f, "task grouping loops"),
+            },
+        }
+    }
+}
+
+impl std::error::Error for HierarchyError {}
+
+/// Checks that every parent exists and that no task is grouped under itself, directly or not.
+pub fn validate_hierarchy(tasks: &[Task]) -> Result<(), HierarchyError> {
+    let parents: HashMap<TaskId, Option<TaskId>> = tasks.iter().map(|task| (task.id, task.parent_id)).collect();
+    for task in tasks {
+        let mut path = vec![task.id];
+        let mut current = task.parent_id;
+        while let Some(id) = current {
+            let Some(parent) = parents.get(&id) else {
+                return Err(HierarchyError::UnknownParent { task: *path.last().unwrap(), parent: id });
+            };
+            if let Some(position) = path.iter().position(|seen| *seen == id) {
+                return Err(HierarchyError::Cycle(path.split_off(position)));
+            }
+            path.push(id);
+            current = *parent;
+        }
+    }
+    Ok(())
+}
+
+/// Whether any task is grouped under `id`.
+pub fn is_summary(tasks: &[Task], id: TaskId) -> bool {
+    tasks.iter().any(|task| task.parent_id == Some(id))
+}
+
+/// `id` followed by every task below it, parents before their children.
+pub fn subtree(tasks: &[Task], id: TaskId) -> Vec<TaskId> {
+    let mut ids = vec![id];
+    let mut seen = HashSet::from([id]);
+    let mut next = 0;
+    while next < ids.len() {
+        let parent = ids[next];
+        for task in tasks.iter().filter(|task| task.parent_id == Some(parent)) {
+            if seen.insert(task.id) {
+                ids.push(task.id);
+            }
+        }
+        next += 1;
+    }
+    ids
+}
+
+/// Regroups the children of `removed` tasks under their nearest ancestor that is still in
+/// `tasks`, or at the top level when there is none.
+pub fn lift_orphans(tasks: &mut [Task], removed: &[Task]) {
+    let parents: HashMap<TaskId, Option<TaskId>> = removed.iter().map(|task| (task.id, task.parent_id)).collect();
+    for task in tasks.iter_mut() {
+        let mut seen = HashSet::new();
+        while let Some(parent) = task.parent_id.and_then(|id| parents.get(&id)) {
+            if !seen.insert(task.parent_id) {
+                task.parent_id = None;
+                break;
+            }
+            task.parent_id = *parent;
+        }
+    }
+}
+
+/// One row of the outline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutlineRow {
+    pub task_id: TaskId,
+    /// 0 for top-level tasks.
+    pub depth: usize,
+    pub has_children: bool,
+}
+
+/// Tasks in outline order: each task followed by its children, siblings sorted by start and id.
+/// Tasks whose parent is missing are listed at the top level. The children of tasks in
+/// `collapsed` are left out, along with everything below them.
+pub fn outline(tasks: &[Task], collapsed: &HashSet<TaskId>) -> Vec<OutlineRow> {
+    let ids: HashSet<TaskId> = tasks.iter().map(|task| task.id).collect();
+    let mut children: HashMap<Option<TaskId>, Vec<&Task>> = HashMap::new();
+    for task in tasks {
+        let parent = task.parent_id.filter(|parent| ids.contains(parent));
+        children.entry(parent).or_default().push(task);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|task| (task.start_date, task.id));
+    }
+
+    let mut rows = Vec::with_capacity(tasks.len());
+    let mut seen = HashSet::new();
+    // Depth-first, with the stack holding siblings in reverse so they pop in order
+    let mut stack: Vec<(&Task, usize)> = children.get(&None).into_iter().flatten().rev().map(|task| (*task, 0)).collect();
+    while let Some((task, depth)) = stack.pop() {
+        if !seen.insert(task.id) {
+            continue;
+        }
+        let below = children.get(&Some(task.id));
+        rows.push(OutlineRow { task_id: task.id, depth, has_children: below.is_some() });
+        if !collapsed.contains(&task.id) {
+            stack.extend(below.into_iter().flatten().rev().map(|child| (*child, depth + 1)));
+        }
+    }
+    rows
+}
+
+/// Stretches every summary task over its children and sets its percent complete to the
+/// children's average weighted by duration. Deeper summaries are rolled up first so the
+/// values carry all the way to the top. Returns the ids of the summaries that changed.
+pub fn roll_up_summaries(tasks: &mut [Task]) -> Vec<TaskId> {
+    let depth: HashMap<TaskId, usize> = outline(tasks, &HashSet::new())
+        .into_iter()
+        .map(|row| (row.task_id, row.depth))
+        .collect();
+    let index: HashMap<TaskId, usize> = tasks.iter().enumerate().map(|(i, task)| (task.id, i)).collect();
+    let mut children: HashMap<TaskId, Vec<usize>> = HashMap::new();
+    for (i, task) in tasks.iter().enumerate() {
+        if let Some(parent) = task.parent_id.filter(|parent| index.contains_key(parent)) {
+            children.entry(parent).or_default().push(i);
+        }
+    }
+    let mut summaries: Vec<TaskId> = children.keys().copied().collect();
+    summaries.sort_by_key(|id| (std::cmp::Reverse(depth.get(id).copied().unwrap_or(0)), *id));
+
+    let mut changed = Vec::new();
+    for id in summaries {
+        let children: Vec<&Task> = children[&id].iter().map(|i| &tasks[*i]).collect();
+        let (Some(start), Some(end)) = (
+            children.iter().map(|task| task.start_date).min(),
+            children.iter().map(|task| task.end_date).max(),
+        ) else {
+            continue;
+        };
+        let weights: Vec<(f64, f64)> = children
+            .iter()
+            .map(|task| ((task.end_date - task.start_date).num_minutes() as f64, task.percent_complete))
+            .collect();
+        let total: f64 = weights.iter().map(|(weight, _)| weight).sum();
+        let percent = if total > 0.0 {
+            weights.iter().map(|(weight, percent)| weight * percent).sum::<f64>() / total
+        } else {
+            // Children without length (milestones) count equally
+            weights.iter().map(|(_, percent)| percent).sum::<f64>() / weights.len() as f64
+        };
+
+        let summary = &mut tasks[index[&id]];
+        if summary.start_date != start || summary.end_date != end || summary.percent_complete != percent {
+            summary.start_date = start;
+            summary.end_date = end;
+            summary.percent_complete = percent;
+            changed.push(id);
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+    fn at(hour: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 1).unwrap().and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour)
+    }
+
+    fn task(id: TaskId, parent_id: Option<TaskId>, start_hour: i64, hours: i64, percent_complete: f64) -> Task {
+        Task {
+            id,
+            name: format!("Task {}", id),
+            start_date: at(start_hour),
+            end_date: at(start_hour + hours),
+            color: "#4CAF50".to_string(),
+            parent_id,
+            percent_complete,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn summaries_span_children_and_weight_progress_by_duration() {
+        // order 1 → part 2 → operations 3 (2h, done) and 4 (6h, not started); operation 5 under the order
+        let mut tasks = vec![
+            task(1, None, 0, 1, 0.0),
+            task(2, Some(1), 0, 1, 0.0),
+            task(3, Some(2), 2, 2, 100.0),
+            task(4, Some(2), 4, 6, 0.0),
+            task(5, Some(1), 12, 8, 50.0),
+        ];
+        let mut changed = roll_up_summaries(&mut tasks);
+        changed.sort();
+        assert_eq!(changed, vec![1, 2]);
+
+        assert_eq!((tasks[1].start_date, tasks[1].end_date), (at(2), at(10)));
+        assert_eq!(tasks[1].percent_complete, 25.0);
+        // part 2 now lasts 8h at 25%, operation 5 lasts 8h at 50%
+        assert_eq!((tasks[0].start_date, tasks[0].end_date), (at(2), at(20)));
+        assert_eq!(tasks[0].percent_complete, 37.5);
+        assert!(roll_up_summaries(&mut tasks).is_empty());
+    }
+
+    #[test]
+    fn outline_nests_children_and_skips_collapsed_ones() {
+        let tasks = vec![
+            task(1, None, 0, 10, 0.0),
+            task(2, Some(1), 5, 1, 0.0),
+            task(3, Some(1), 0, 1, 0.0),
+            task(4, None, 2, 1, 0.0),
+            task(5, Some(3), 0, 1, 0.0),
+        ];
+        let order = |collapsed: &[TaskId]| -> Vec<(TaskId, usize)> {
+            outline(&tasks, &collapsed.iter().copied().collect())
+                .into_iter()
+                .map(|row| (row.task_id, row.depth))
+                .collect()
+        };
+        assert_eq!(order(&[]), vec![(1, 0), (3, 1), (5, 2), (2, 1), (4, 0)]);
+        assert_eq!(order(&[3]), vec![(1, 0), (3, 1), (2, 1), (4, 0)]);
+        assert_eq!(order(&[1]), vec![(1, 0), (4, 0)]);
+        assert_eq!(subtree(&tasks, 1), vec![1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn removing_a_summary_lifts_its_children() {
+        let mut tasks = vec![task(1, None, 0, 1, 0.0), task(4, Some(3), 0, 1, 0.0), task(5, Some(2), 0, 1, 0.0)];
+        lift_orphans(&mut tasks, &[task(2, Some(1), 0, 1, 0.0), task(3, Some(2), 0, 1, 0.0)]);
+        assert_eq!(tasks.iter().map(|task| task.parent_id).collect::<Vec<_>>(), vec![None, Some(1), Some(1)]);
+    }
+
+    #[test]
+    fn rejects_loops_and_missing_parents() {
+        let tasks = vec![task(1, Some(3), 0, 1, 0.0), task(2, Some(1), 0, 1, 0.0), task(3, Some(2), 0, 1, 0.0)];
+        assert_eq!(validate_hierarchy(&tasks), Err(HierarchyError::Cycle(vec![1, 3, 2])));
+        assert_eq!(HierarchyError::Cycle(vec![1, 3, 2]).to_string(), "task grouping loops: 1 → 3 → 2 → 1");
+        assert_eq!(HierarchyError::Cycle(Vec::new()).to_string(), "task grouping loops");
+        let tasks = vec![task(1, Some(9), 0, 1, 0.0)];
+        assert_eq!(validate_hierarchy(&tasks), Err(HierarchyError::UnknownParent { task: 1, parent: 9 }));
+        assert_eq!(validate_hierarchy(&[task(1, None, 0, 1, 0.0), task(2, Some(1), 0, 1, 0.0)]), Ok(()));
+    }
+}
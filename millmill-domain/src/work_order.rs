@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::validation::{require_text, Validate, ValidationError};
+
+pub type WorkOrderId = usize;
+
+/// A customer order for a quantity of one part.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkOrder {
+    pub id: WorkOrderId,
+    pub number: String,
+    #[serde(default)]
+    pub customer: String,
+    pub part_name: String,
+    pub quantity: u32,
+    #[serde(default)]
+    pub due_date: Option<NaiveDateTime>,
+}
+
+impl Validate for WorkOrder {
+    fn validate(&self) -> Result<(), ValidationError> {
+        require_text("work order number", &self.number)?;
+        require_text("part name", &self.part_name)?;
+        if self.quantity == 0 {
+            return Err(ValidationError::NotPositive("quantity"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Excel workbooks: order lists read into work orders and tasks, and the schedule written back.
+//! The workbooks themselves are read and written by the backend.
+
+use serde::{Deserialize, Serialize};
+
+use crate::exchange::{ColumnMapping, ImportPreview};
+use crate::validation::{require_text, Validate, ValidationError};
+
+pub type ImportProfileId = usize;
+
+pub const XLSX_EXTENSION: &str = "xlsx";
+pub const XLSX_MIME_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// A work order property a column of an order list can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkOrderField {
+    Number,
+    Customer,
+    PartName,
+    Quantity,
+    DueDate,
+}
+
+impl WorkOrderField {
+    pub const ALL: [WorkOrderField; 5] = [
+        WorkOrderField::Number,
+        WorkOrderField::Customer,
+        WorkOrderField::PartName,
+        WorkOrderField::Quantity,
+        WorkOrderField::DueDate,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            WorkOrderField::Number => "number",
+            WorkOrderField::Customer => "customer",
+            WorkOrderField::PartName => "part_name",
+            WorkOrderField::Quantity => "quantity",
+            WorkOrderField::DueDate => "due_date",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        WorkOrderField::ALL.into_iter().find(|field| field.code() == code)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WorkOrderField::Number => "Order Number",
+            WorkOrderField::Customer => "Customer",
+            WorkOrderField::PartName => "Part",
+            WorkOrderField::Quantity => "Quantity",
+            WorkOrderField::DueDate => "Due Date",
+        }
+    }
+
+    /// The field a column header most likely means: its label or code, or a name order lists
+    /// commonly use for it.
+    pub fn from_header(header: &str) -> Option<Self> {
+        let key: String = header.trim().to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect();
+        let field = WorkOrderField::ALL.into_iter().find(|field| {
+            let label: String = field.label().to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect();
+            key == label || key == field.code().replace('_', "")
+        });
+        field.or(match key.as_str() {
+            "order" | "orderno" | "workorder" | "wo" | "po" | "ponumber" | "no" => Some(WorkOrderField::Number),
+            "client" | "customername" | "account" => Some(WorkOrderField::Customer),
+            "partno" | "partnumber" | "item" | "article" | "product" | "drawing" => Some(WorkOrderField::PartName),
+            "qty" | "pieces" | "pcs" | "amount" => Some(WorkOrderField::Quantity),
+            "due" | "delivery" | "deliverydate" | "shipdate" | "requireddate" => Some(WorkOrderField::DueDate),
+            _ => None,
+        })
+    }
+}
+
+/// Which work order property a column of an order list goes to, matched by header.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderColumnMapping {
+    pub header: String,
+    /// `None` leaves the column out.
+    pub field: Option<WorkOrderField>,
+}
+
+/// Guesses the field of every header of an order list. Each field goes to the first column
+/// that names it.
+pub fn guess_order_mapping(headers: &[String]) -> Vec<OrderColumnMapping> {
+    let mut used = Vec::new();
+    headers
+        .iter()
+        .map(|header| {
+            let field = WorkOrderField::from_header(header).filter(|field| !used.contains(field));
+            used.extend(field);
+            OrderColumnMapping { header: header.clone(), field }
+        })
+        .collect()
+}
+
+/// How the sheets and columns of a workbook are read, saved under a name so the next list
+/// from the same sender reads the same way.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportProfile {
+    pub id: ImportProfileId,
+    pub name: String,
+    /// The sheet with one work order per row; `None` reads no work orders.
+    pub work_order_sheet: Option<String>,
+    pub work_order_columns: Vec<OrderColumnMapping>,
+    /// The sheet with one task per row; `None` reads no tasks.
+    pub task_sheet: Option<String>,
+    pub task_columns: Vec<ColumnMapping>,
+}
+
+impl Validate for ImportProfile {
+    fn validate(&self) -> Result<(), ValidationError> {
+        require_text("profile name", &self.name)
+    }
+}
+
+/// What a workbook import would add, shown for review before anything changes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkbookPreview {
+    /// All sheets of the workbook, in order.
+    pub sheets: Vec<String>,
+    /// The sheets and columns as they were read, ready to be saved under a name.
+    pub profile: ImportProfile,
+    pub import: ImportPreview,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guesses_order_list_columns() {
+        let headers: Vec<String> = ["PO Number", "Client", "Item", "Qty", "Delivery Date", "Remarks", "Order"]
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+        let fields: Vec<_> = guess_order_mapping(&headers).into_iter().map(|mapping| mapping.field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                Some(WorkOrderField::Number),
+                Some(WorkOrderField::Customer),
+                Some(WorkOrderField::PartName),
+                Some(WorkOrderField::Quantity),
+                Some(WorkOrderField::DueDate),
+                None,
+                // already taken by "PO Number"
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn profile_needs_a_name() {
+        let mut profile = ImportProfile { task_sheet: Some("Orders".to_string()), ..Default::default() };
+        assert_eq!(profile.validate(), Err(ValidationError::EmptyField("profile name")));
+        profile.name = "Sales weekly list".to_string();
+        assert_eq!(profile.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,196 @@
+//! Renders the chart to SVG, PNG and PDF in the backend, so it works without the window.
+//!
+//! The drawing follows the chart on screen: the time-scale header, the lanes with their bars,
+//! setups and due dates, and the dependency arrows, with a legend of the marks.
+
+mod layout;
+mod pdf;
+mod svg;
+
+use std::sync::OnceLock;
+
+use millmill_domain::{Calendar, ChartExport, ChartFormat, Machine, Task, Validate, WorkOrder};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{fontdb, PostProcessingSteps, Tree, TreeParsing, TreePostProc};
+
+use svg::Chart;
+
+/// Fonts for text, in order of preference; the first one installed is used.
+const SANS_SERIF: [&str; 5] = ["Arial", "Helvetica", "Noto Sans", "DejaVu Sans", "Liberation Sans"];
+/// PNG images are drawn at this many pixels per pixel of the chart, sharp enough to print.
+const PNG_SCALE: f32 = 2.0;
+/// Longest side of a PNG image (px), beyond which image viewers give up.
+const MAX_PNG_SIZE: f32 = 16384.0;
+
+/// What the chart shows.
+pub struct Plan<'a> {
+  pub tasks: &'a [Task],
+  pub machines: &'a [Machine],
+  pub calendars: &'a [Calendar],
+  pub work_orders: &'a [WorkOrder],
+}
+
+/// The file contents of the chart of `plan` as `options` asks for.
+pub fn render(plan: &Plan<'_>, options: &ChartExport) -> Result<Vec<u8>, String> {
+  options.validate().map_err(|e| format!("Invalid export: {}", e))?;
+  let chart = Chart::new(plan, options);
+  match options.format {
+    ChartFormat::Svg => Ok(chart.document().into_bytes()),
+    ChartFormat::Png => png(&chart.document()),
+    ChartFormat::Pdf => pdf::write(&chart, options),
+  }
+}
+
+fn png(svg: &str) -> Result<Vec<u8>, String> {
+  let tree = parse(svg)?;
+  let (width, height) = (tree.size.width() * PNG_SCALE, tree.size.height() * PNG_SCALE);
+  if width.max(height) > MAX_PNG_SIZE {
+    return Err(format!(
+      "The chart is too large for a PNG image ({:.0} × {:.0} px); choose fewer days or zoom out",
+      width, height
+    ));
+  }
+  let mut pixmap = Pixmap::new(width.ceil() as u32, height.ceil() as u32).ok_or("The chart is empty")?;
+  resvg::render(&tree, Transform::from_scale(PNG_SCALE, PNG_SCALE), &mut pixmap.as_mut());
+  pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+/// Reads a drawing and turns its text into outlines with the fonts of the system.
+fn parse(svg: &str) -> Result<Tree, String> {
+  let mut tree = Tree::from_str(svg, &resvg::usvg::Options::default()).map_err(|e| e.to_string())?;
+  tree.postprocess(PostProcessingSteps::default(), fonts());
+  Ok(tree)
+}
+
+/// Loading the system fonts takes a while, so it is done once. Glyphs missing from the chosen
+/// font, such as Japanese names, are taken from another installed font.
+fn fonts() -> &'static fontdb::Database {
+  static FONTS: OnceLock<fontdb::Database> = OnceLock::new();
+  FONTS.get_or_init(|| {
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+    let installed = |family: &&str| fonts.faces().any(|face| face.families.iter().any(|(name, _)| name == family));
+    if let Some(family) = SANS_SERIF.into_iter().find(installed) {
+      fonts.set_sans_serif_family(family);
+    }
+    fonts
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{NaiveDate, NaiveDateTime};
+  use millmill_domain::{Dependency, DependencyKind, Orientation, PaperSize, TaskId};
+
+  use super::*;
+
+  fn day(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 4, day).unwrap()
+  }
+
+  fn at(day_of_month: u32, hour: u32) -> NaiveDateTime {
+    day(day_of_month).and_hms_opt(hour, 0, 0).unwrap()
+  }
+
+  fn task(id: TaskId, name: &str, start: NaiveDateTime, end: NaiveDateTime) -> Task {
+    Task {
+      id,
+      name: name.to_string(),
+      start_date: start,
+      end_date: end,
+      color: "#3F51B5".to_string(),
+      machine_id: Some(1),
+      ..Default::default()
+    }
+  }
+
+  fn machines() -> Vec<Machine> {
+    vec![Machine { id: 1, name: "Lathe & Mill".to_string(), ..Default::default() }]
+  }
+
+  fn render_tasks(tasks: &[Task], options: &ChartExport) -> Result<Vec<u8>, String> {
+    let plan = Plan { tasks, machines: &machines(), calendars: &[], work_orders: &[] };
+    render(&plan, options)
+  }
+
+  /// A week at 100 px a day in the given format.
+  fn week(format: ChartFormat) -> ChartExport {
+    ChartExport { format, ..ChartExport::new(day(7), day(13)) }
+  }
+
+  #[test]
+  fn draws_the_plan_as_svg_with_names_escaped() {
+    let tasks = vec![
+      task(1, "Shaft <A> & \"B\"", at(7, 8), at(7, 17)),
+      Task {
+        dependencies: vec![Dependency { predecessor_id: 1, kind: DependencyKind::FinishToStart, lag_minutes: 0 }],
+        ..task(2, "Milling", at(8, 8), at(8, 12))
+      },
+    ];
+    let svg = String::from_utf8(render_tasks(&tasks, &week(ChartFormat::Svg)).unwrap()).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.ends_with("</svg>"));
+    assert!(svg.contains("Shaft &lt;A&gt; &amp; &quot;B&quot;"));
+    assert!(!svg.contains("<A>"));
+    assert!(svg.contains("Lathe &amp; Mill"));
+    assert!(svg.contains("Milling"));
+    assert!(svg.contains("Production Schedule"));
+    assert!(svg.contains("2025-04-07 – 2025-04-13"));
+    // the whole drawing is well formed
+    assert!(parse(&svg).is_ok());
+
+    let titled = ChartExport { title: " Line 2 ".to_string(), ..week(ChartFormat::Svg) };
+    let svg = String::from_utf8(render_tasks(&tasks, &titled).unwrap()).unwrap();
+    assert!(svg.contains(">Line 2<"));
+    assert!(!svg.contains("Production Schedule"));
+  }
+
+  #[test]
+  fn tiles_the_pdf_over_pages_with_a_title_block() {
+    // one row per task in the outline, so the 40 rows take two sheets of A4 down and the two
+    // weeks three sheets across
+    let tasks: Vec<Task> = (1..=40).map(|id| task(id, &format!("Job {}", id), at(7, 8), at(7, 17))).collect();
+    let options = ChartExport {
+      format: ChartFormat::Pdf,
+      outline: true,
+      title: "Line 2".to_string(),
+      paper: PaperSize::A4,
+      orientation: Orientation::Portrait,
+      ..ChartExport::new(day(7), day(20))
+    };
+    let pdf = render_tasks(&tasks, &options).unwrap();
+    assert!(pdf.starts_with(b"%PDF"));
+    let text = String::from_utf8_lossy(&pdf);
+    assert_eq!(text.matches("/Type /Page\n").count(), 6);
+    assert!(text.contains("/Count 6"));
+    assert!(text.contains("/Title (Line 2)"));
+  }
+
+  #[test]
+  fn refuses_charts_too_large_for_png_or_pdf() {
+    let tasks = vec![task(1, "Turning", at(7, 8), at(7, 17))];
+    let png = render_tasks(&tasks, &ChartExport { day_width: 2000.0, ..week(ChartFormat::Png) });
+    assert!(png.is_err_and(|e| e.contains("too large for a PNG image")));
+    // four months at the closest zoom, a sheet of A4 for every 558 px across
+    let pdf = render_tasks(
+      &tasks,
+      &ChartExport {
+        day_width: 2880.0,
+        paper: PaperSize::A4,
+        orientation: Orientation::Portrait,
+        ..ChartExport::new(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(), day(30))
+      },
+    );
+    assert!(pdf.is_err_and(|e| e.contains("would take") && e.contains("pages")));
+    // beyond the zoom the export allows, nothing is drawn at all
+    let zoomed = render_tasks(&tasks, &ChartExport { day_width: 1.0e6, ..week(ChartFormat::Svg) });
+    assert!(zoomed.is_err_and(|e| e.starts_with("Invalid export")));
+  }
+
+  #[test]
+  fn draws_a_png_within_the_limit() {
+    let tasks = vec![task(1, "Turning", at(7, 8), at(7, 17))];
+    let png = render_tasks(&tasks, &week(ChartFormat::Png)).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+  }
+}
@@ -0,0 +1,147 @@
+//! Where things go on the chart: the time scale along the top and the rows down the side.
+//! Follows the chart on screen so a printout looks like the window.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use millmill_domain::{outline, stack_lanes, ChartExport, Machine, MachineId, Task, TaskId, Tier};
+
+/// Height of one row of bars (px).
+pub const ROW_HEIGHT: f64 = 30.0;
+/// Height of one tier of the header (px).
+pub const HEADER_ROW_HEIGHT: f64 = 20.0;
+/// Width of the column of lane names (px).
+pub const LABEL_WIDTH: f64 = 160.0;
+/// Indentation of one outline level in the label column (px).
+const INDENT: f64 = 16.0;
+
+/// The time scale: the days of the export side by side, `day_width` pixels each.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Timeline {
+  pub origin: NaiveDateTime,
+  pub end: NaiveDateTime,
+  pub day_width: f64,
+}
+
+impl Timeline {
+  pub fn new(options: &ChartExport) -> Self {
+    Timeline {
+      origin: options.from.and_time(NaiveTime::MIN),
+      end: (options.to + Duration::days(1)).and_time(NaiveTime::MIN),
+      day_width: options.day_width,
+    }
+  }
+
+  pub fn x(&self, time: NaiveDateTime) -> f64 {
+    (time - self.origin).num_minutes() as f64 * self.day_width / (24.0 * 60.0)
+  }
+
+  pub fn width(&self) -> f64 {
+    self.x(self.end)
+  }
+
+  /// Whether anything between `start` and `end` falls on the chart.
+  pub fn shows(&self, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+    start <= self.end && end >= self.origin
+  }
+
+  /// The tiers whose cells are wide enough to label, coarsest first.
+  pub fn tiers(&self) -> Vec<Tier> {
+    Tier::shown(self.day_width)
+  }
+
+  pub fn header_height(&self) -> f64 {
+    self.tiers().len() as f64 * HEADER_ROW_HEIGHT
+  }
+
+  /// The starts of the cells of `tier` on the chart.
+  pub fn boundaries(&self, tier: Tier) -> Vec<NaiveDateTime> {
+    let mut time = tier.floor(self.origin);
+    let mut boundaries = Vec::new();
+    while time < self.end {
+      boundaries.push(time);
+      time = tier.next(time);
+    }
+    boundaries
+  }
+}
+
+/// A row of the label column: the lane of a machine, or one task of the outline.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Lane {
+  /// `None` for the lane of tasks without a machine.
+  pub machine_id: Option<MachineId>,
+  pub label: String,
+  /// Indentation of the label (px).
+  pub indent: f64,
+  pub top: f64,
+  pub height: f64,
+}
+
+/// The lanes and the row of each bar drawn.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Layout {
+  pub lanes: Vec<Lane>,
+  bar_tops: HashMap<TaskId, f64>,
+  pub outline: bool,
+}
+
+impl Layout {
+  /// The layout of `shown`, the tasks that fall on the chart.
+  pub fn new(tasks: &[Task], shown: &HashSet<TaskId>, machines: &[Machine], options: &ChartExport) -> Self {
+    if options.outline {
+      Layout::outline(tasks, shown, options)
+    } else {
+      Layout::machines(tasks, shown, machines)
+    }
+  }
+
+  /// The lanes of the chart on screen: a lane per machine, then one for tasks without a machine.
+  fn machines(tasks: &[Task], shown: &HashSet<TaskId>, machines: &[Machine]) -> Self {
+    let mut layout = Layout::default();
+    let mut top = 0.0;
+    for lane in stack_lanes(tasks, machines, |task| shown.contains(&task.id)) {
+      let label = match machines.iter().find(|machine| Some(machine.id) == lane.machine_id) {
+        Some(machine) if machine.capacity > 1 => format!("{} ×{}", machine.name, machine.capacity),
+        Some(machine) => machine.name.clone(),
+        None => "Unassigned".to_string(),
+      };
+      for (id, row) in lane.bars {
+        layout.bar_tops.insert(id, top + row as f64 * ROW_HEIGHT);
+      }
+      let height = lane.rows as f64 * ROW_HEIGHT;
+      layout.lanes.push(Lane { machine_id: lane.machine_id, label, indent: 0.0, top, height });
+      top += height;
+    }
+    layout
+  }
+
+  /// One row per task in outline order, leaving out the children of collapsed tasks.
+  fn outline(tasks: &[Task], shown: &HashSet<TaskId>, options: &ChartExport) -> Self {
+    let by_id: HashMap<TaskId, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+    let collapsed: HashSet<TaskId> = options.collapsed.iter().copied().collect();
+    let mut layout = Layout { outline: true, ..Layout::default() };
+    for row in outline(tasks, &collapsed).into_iter().filter(|row| shown.contains(&row.task_id)) {
+      let Some(task) = by_id.get(&row.task_id) else { continue };
+      let top = layout.height();
+      layout.bar_tops.insert(task.id, top);
+      layout.lanes.push(Lane {
+        machine_id: task.machine_id,
+        label: task.name.clone(),
+        indent: row.depth as f64 * INDENT,
+        top,
+        height: ROW_HEIGHT,
+      });
+    }
+    layout
+  }
+
+  pub fn height(&self) -> f64 {
+    self.lanes.last().map_or(0.0, |lane| lane.top + lane.height)
+  }
+
+  /// `None` for tasks not drawn.
+  pub fn bar_top(&self, id: TaskId) -> Option<f64> {
+    self.bar_tops.get(&id).copied()
+  }
+}
@@ -0,0 +1,201 @@
+//! Print-ready pages: the chart tiled over sheets of the chosen paper at the zoom of the export,
+//! with the header and lane names repeated on every sheet and a title block along the bottom.
+//!
+//! Each part of the chart is converted once and every page shows its tile of it through a clip,
+//! so a chart over many sheets does not grow the file with every sheet.
+
+use chrono::Local;
+use millmill_domain::ChartExport;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, TextStr};
+
+use super::layout::{LABEL_WIDTH, ROW_HEIGHT};
+use super::parse;
+use super::svg::{self, Chart};
+
+/// Points per millimetre.
+const PT_PER_MM: f64 = 72.0 / 25.4;
+/// Points per pixel of the chart, so it prints as large as it shows on a 96 dpi screen.
+const PT_PER_PX: f64 = 0.75;
+const MARGIN_MM: f64 = 10.0;
+/// Smallest height of the title block and the space between it and the chart (pt).
+const TITLE_BLOCK_HEIGHT: f64 = 48.0;
+const GAP: f64 = 6.0;
+/// Shares of the title block taken by the title and by the legend; the page number gets the rest.
+const TITLE_SHARE: f64 = 0.3;
+const LEGEND_SHARE: f64 = 0.5;
+/// More sheets than this come from a mistake in the range or the zoom.
+const MAX_PAGES: usize = 500;
+
+/// A part of the chart, converted once and placed on every page.
+struct Part {
+  id: Ref,
+  name: &'static [u8],
+  /// Size of the part (px).
+  width: f64,
+  height: f64,
+}
+
+impl Part {
+  /// Shows the rectangle of the part starting at `from` and `size` large, with its top left
+  /// corner at `at` on the page (pt, from the bottom left of the page).
+  fn place(&self, content: &mut Content, from: (f64, f64), size: (f64, f64), at: (f64, f64)) {
+    let (width, height) = (size.0 * PT_PER_PX, size.1 * PT_PER_PX);
+    content.save_state();
+    content.rect(at.0 as f32, (at.1 - height) as f32, width as f32, height as f32);
+    content.clip_nonzero();
+    content.end_path();
+    // the part is drawn into the unit square, its top left corner at (0, 1)
+    content.transform([
+      (self.width * PT_PER_PX) as f32,
+      0.0,
+      0.0,
+      (self.height * PT_PER_PX) as f32,
+      (at.0 - from.0 * PT_PER_PX) as f32,
+      (at.1 - (self.height - from.1) * PT_PER_PX) as f32,
+    ]);
+    content.x_object(Name(self.name));
+    content.restore_state();
+  }
+}
+
+pub(super) fn write(chart: &Chart, options: &ChartExport) -> Result<Vec<u8>, String> {
+  let (page_width, page_height) = options.paper.size_mm(options.orientation);
+  let (page_width, page_height) = (page_width * PT_PER_MM, page_height * PT_PER_MM);
+  let margin = MARGIN_MM * PT_PER_MM;
+  let inner_width = page_width - 2.0 * margin;
+  let (legend, legend_height) = svg::legend(inner_width * LEGEND_SHARE / PT_PER_PX);
+  let block_height = (legend_height * PT_PER_PX + 8.0).max(TITLE_BLOCK_HEIGHT);
+  let chart_height = page_height - 2.0 * margin - block_height - GAP;
+
+  // Tiles end on whole rows so no bar is cut between two sheets
+  let tile_width = inner_width / PT_PER_PX - LABEL_WIDTH;
+  let tile_height = ((chart_height / PT_PER_PX - chart.header_height) / ROW_HEIGHT).floor() * ROW_HEIGHT;
+  if tile_width < ROW_HEIGHT || tile_height < ROW_HEIGHT {
+    return Err(format!("The chart does not fit on {} paper at this zoom", options.paper.label()));
+  }
+  let columns = (chart.width / tile_width).ceil().max(1.0) as usize;
+  let rows = (chart.height / tile_height).ceil().max(1.0) as usize;
+  if columns * rows > MAX_PAGES {
+    return Err(format!(
+      "The chart would take {} pages; choose fewer days, zoom out or use larger paper",
+      columns * rows
+    ));
+  }
+
+  let mut pdf = Pdf::new();
+  let mut next = Ref::new(1);
+  let catalog_id = bump(&mut next);
+  let page_tree_id = bump(&mut next);
+  let info_id = bump(&mut next);
+  let mut part = |name: &'static [u8], width: f64, height: f64, content: &str| -> Result<Part, String> {
+    let id = bump(&mut next);
+    let document = format!("{}<defs>{}</defs>{}</svg>", svg::open(width, height), chart.defs, content);
+    next = svg2pdf::convert_tree_into(&parse(&document)?, svg2pdf::Options::default(), &mut pdf, id);
+    Ok(Part { id, name, width, height })
+  };
+  let parts = [
+    part(b"Corner", LABEL_WIDTH, chart.header_height, &chart.corner)?,
+    part(b"Header", chart.width, chart.header_height, &chart.header)?,
+    part(b"Labels", LABEL_WIDTH, chart.height, &chart.labels)?,
+    part(b"Body", chart.width, chart.height, &chart.body)?,
+  ];
+  let [corner, header, labels, body] = &parts;
+
+  let printed = Local::now().format("%Y-%m-%d %H:%M").to_string();
+  let mut page_ids = Vec::new();
+  for row in 0..rows {
+    for column in 0..columns {
+      let (page_id, content_id, block_id) = (bump(&mut next), bump(&mut next), bump(&mut next));
+      let number = page_ids.len() + 1;
+      let block = format!(
+        "{}<defs>{}</defs>{}</svg>",
+        svg::open(inner_width, block_height),
+        chart.defs,
+        title_block(chart, inner_width, block_height, &legend, &printed, (number, rows * columns), (row, column))
+      );
+      next = svg2pdf::convert_tree_into(&parse(&block)?, svg2pdf::Options::default(), &mut pdf, block_id);
+
+      let (from_x, from_y) = (column as f64 * tile_width, row as f64 * tile_height);
+      let (width, height) = ((chart.width - from_x).min(tile_width), (chart.height - from_y).min(tile_height));
+      let top = page_height - margin;
+      let (lanes_left, lanes_top) = (margin + LABEL_WIDTH * PT_PER_PX, top - chart.header_height * PT_PER_PX);
+      let mut content = Content::new();
+      corner.place(&mut content, (0.0, 0.0), (LABEL_WIDTH, chart.header_height), (margin, top));
+      header.place(&mut content, (from_x, 0.0), (width, chart.header_height), (lanes_left, top));
+      labels.place(&mut content, (0.0, from_y), (LABEL_WIDTH, height), (margin, lanes_top));
+      body.place(&mut content, (from_x, from_y), (width, height), (lanes_left, lanes_top));
+      let (frame_width, frame_height) = ((LABEL_WIDTH + width) * PT_PER_PX, (chart.header_height + height) * PT_PER_PX);
+      content.set_stroke_gray(0.6);
+      content.set_line_width(0.5);
+      content.rect(margin as f32, (top - frame_height) as f32, frame_width as f32, frame_height as f32);
+      content.stroke();
+      content.save_state();
+      content.transform([inner_width as f32, 0.0, 0.0, block_height as f32, margin as f32, margin as f32]);
+      content.x_object(Name(b"TitleBlock"));
+      content.restore_state();
+      pdf.stream(content_id, &content.finish());
+
+      let mut page = pdf.page(page_id);
+      page.media_box(Rect::new(0.0, 0.0, page_width as f32, page_height as f32));
+      page.parent(page_tree_id);
+      page.contents(content_id);
+      let mut resources = page.resources();
+      let mut x_objects = resources.x_objects();
+      for part in &parts {
+        x_objects.pair(Name(part.name), part.id);
+      }
+      x_objects.pair(Name(b"TitleBlock"), block_id);
+      x_objects.finish();
+      resources.finish();
+      page.finish();
+      page_ids.push(page_id);
+    }
+  }
+
+  pdf.catalog(catalog_id).pages(page_tree_id);
+  pdf.pages(page_tree_id).count(page_ids.len() as i32).kids(page_ids);
+  pdf.document_info(info_id).title(TextStr(&chart.title)).creator(TextStr("millmill"));
+  Ok(pdf.finish())
+}
+
+fn bump(next: &mut Ref) -> Ref {
+  let id = *next;
+  *next = Ref::new(id.get() + 1);
+  id
+}
+
+/// The title and period, the legend, and which sheet this is and where it goes on the board.
+fn title_block(
+  chart: &Chart,
+  width: f64,
+  height: f64,
+  legend: &str,
+  printed: &str,
+  (number, count): (usize, usize),
+  (row, column): (usize, usize),
+) -> String {
+  let (legend_left, info_left) = (width * TITLE_SHARE, width * (TITLE_SHARE + LEGEND_SHARE));
+  [
+    format!(
+      r##"<rect x="0.5" y="0.5" width="{}" height="{}" fill="white" stroke="#333333"/>"##,
+      width - 1.0,
+      height - 1.0
+    ),
+    format!(r##"<line x1="{0}" y1="0" x2="{0}" y2="{1}" stroke="#333333"/>"##, legend_left, height),
+    format!(r##"<line x1="{0}" y1="0" x2="{0}" y2="{1}" stroke="#333333"/>"##, info_left, height),
+    svg::viewport(
+      (0.0, 0.0, legend_left, height),
+      (0.0, 0.0),
+      &[
+        svg::text(8.0, 18.0, 14.0, "#333", &chart.title, r#"font-weight="bold""#),
+        svg::text(8.0, 34.0, 10.0, "#666", &chart.period, ""),
+      ]
+      .concat(),
+    ),
+    format!(r#"<g transform="translate({} 4) scale({})">{}</g>"#, legend_left, PT_PER_PX, legend),
+    svg::text(info_left + 8.0, 16.0, 11.0, "#333", &format!("Page {} of {}", number, count), r#"font-weight="bold""#),
+    svg::text(info_left + 8.0, 29.0, 9.0, "#666", &format!("Row {}, column {}", row + 1, column + 1), ""),
+    svg::text(info_left + 8.0, 41.0, 9.0, "#666", &format!("Printed {}", printed), ""),
+  ]
+  .concat()
+}
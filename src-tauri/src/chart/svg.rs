@@ -0,0 +1,570 @@
+//! The chart as SVG, drawn in parts (header, lane names, lanes) so the PDF pages can tile the
+//! lanes and repeat the header and names on every page.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Local, NaiveDateTime};
+use millmill_domain::{
+  analyze_schedule, find_conflicts, lot_id, missed_deadlines, setup_blocks, Calendar, ChartExport, Task, TaskId,
+  TaskStatus,
+};
+
+use super::layout::{Layout, Timeline, HEADER_ROW_HEIGHT, LABEL_WIDTH, ROW_HEIGHT};
+use super::Plan;
+
+/// Mapped to an installed font when the drawing is converted.
+const FONT_FAMILY: &str = "sans-serif";
+const DEFAULT_TITLE: &str = "Production Schedule";
+/// Colors as on screen.
+const SUMMARY_COLOR: &str = "#455a64";
+const CRITICAL_COLOR: &str = "#d32f2f";
+const CONFLICT_COLOR: &str = "#ff6f00";
+const ARROW_COLOR: &str = "#555555";
+const SETUP_COLOR: &str = "#795548";
+const SETUP_SHORT_COLOR: &str = "#d32f2f";
+const DUE_DATE_COLOR: &str = "#c62828";
+const LATE_COLOR: &str = "#d32f2f";
+const LEGEND_TASK_COLOR: &str = "#009688";
+/// Height of the band of actual work along the bottom of a bar (px).
+const ACTUAL_BAR_HEIGHT: f64 = 4.0;
+/// Side of the square a milestone diamond is turned from (px).
+const MILESTONE_SIZE: f64 = 16.0;
+/// How far dependency arrows run out of a bar before turning (px).
+const STUB: f64 = 10.0;
+/// Space above the chart for the title in the whole drawing (px).
+const TITLE_HEIGHT: f64 = 40.0;
+const LEGEND_ITEM_WIDTH: f64 = 120.0;
+const LEGEND_ROW_HEIGHT: f64 = 18.0;
+
+/// The parts of the chart, each in its own coordinates starting at 0, 0.
+pub struct Chart {
+  pub title: String,
+  /// The days shown, e.g. "2025-04-07 – 2025-04-13".
+  pub period: String,
+  /// Width of the time scale and height of the lanes (px).
+  pub width: f64,
+  pub height: f64,
+  pub header_height: f64,
+  /// Markers and patterns the parts refer to.
+  pub defs: String,
+  /// Above the lane names, `LABEL_WIDTH` × `header_height`.
+  pub corner: String,
+  /// The time scale, `width` × `header_height`.
+  pub header: String,
+  /// The lane names, `LABEL_WIDTH` × `height`.
+  pub labels: String,
+  /// Lanes, bars and arrows, `width` × `height`.
+  pub body: String,
+}
+
+impl Chart {
+  pub fn new(plan: &Plan<'_>, options: &ChartExport) -> Self {
+    let timeline = Timeline::new(options);
+    let shown: HashSet<TaskId> = plan
+      .tasks
+      .iter()
+      .filter(|task| timeline.shows(task.start_date, task.end_date))
+      .map(|task| task.id)
+      .collect();
+    let layout = Layout::new(plan.tasks, &shown, plan.machines, options);
+    let title = match options.title.trim() {
+      "" => DEFAULT_TITLE.to_string(),
+      title => title.to_string(),
+    };
+    Chart {
+      title,
+      period: format!("{} – {}", options.from.format("%Y-%m-%d"), options.to.format("%Y-%m-%d")),
+      width: timeline.width(),
+      // an empty chart still gets a row, so it has a size
+      height: layout.height().max(ROW_HEIGHT),
+      header_height: timeline.header_height(),
+      defs: defs(),
+      corner: corner(&layout, timeline.header_height()),
+      header: header(&timeline),
+      labels: labels(&layout),
+      body: body(plan, &timeline, &layout),
+    }
+  }
+
+  /// The whole chart in one drawing: the title, the chart and the legend below it.
+  pub fn document(&self) -> String {
+    let width = LABEL_WIDTH + self.width;
+    let (legend, legend_height) = legend(width);
+    let top = TITLE_HEIGHT + self.header_height;
+    let height = top + self.height + LEGEND_ROW_HEIGHT / 2.0 + legend_height;
+    let mut svg = open(width, height);
+    svg.push_str(&format!("<defs>{}</defs>", self.defs));
+    svg.push_str(&format!(r##"<rect width="{}" height="{}" fill="white"/>"##, width, height));
+    svg.push_str(&text(8.0, 26.0, 18.0, "#333", &self.title, r#"font-weight="bold""#));
+    svg.push_str(&text(width - 8.0, 26.0, 13.0, "#666", &self.period, r#"text-anchor="end""#));
+    svg.push_str(&viewport((0.0, TITLE_HEIGHT, LABEL_WIDTH, self.header_height), (0.0, 0.0), &self.corner));
+    svg.push_str(&viewport((LABEL_WIDTH, TITLE_HEIGHT, self.width, self.header_height), (0.0, 0.0), &self.header));
+    svg.push_str(&viewport((0.0, top, LABEL_WIDTH, self.height), (0.0, 0.0), &self.labels));
+    svg.push_str(&viewport((LABEL_WIDTH, top, self.width, self.height), (0.0, 0.0), &self.body));
+    svg.push_str(&format!(
+      r#"<g transform="translate(0 {})">{}</g>"#,
+      top + self.height + LEGEND_ROW_HEIGHT / 2.0,
+      legend
+    ));
+    svg.push_str("</svg>");
+    svg
+  }
+}
+
+/// The start of a standalone drawing `width` × `height` in user units.
+pub fn open(width: f64, height: f64) -> String {
+  format!(
+    r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="{2}">"#,
+    width, height, FONT_FAMILY
+  )
+}
+
+/// `content` seen through the rectangle `(x, y, width, height)`, showing it from `(from_x, from_y)`
+/// at the same scale. Whatever falls outside is cut off.
+pub fn viewport((x, y, width, height): (f64, f64, f64, f64), (from_x, from_y): (f64, f64), content: &str) -> String {
+  format!(
+    r#"<svg x="{}" y="{}" width="{}" height="{}" viewBox="{} {} {} {}" overflow="hidden">{}</svg>"#,
+    x, y, width, height, from_x, from_y, width, height, content
+  )
+}
+
+pub fn text(x: f64, y: f64, size: f64, color: &str, content: &str, attributes: &str) -> String {
+  format!(
+    r#"<text x="{}" y="{}" font-size="{}" fill="{}" {}>{}</text>"#,
+    x,
+    y,
+    size,
+    color,
+    attributes,
+    escape(content)
+  )
+}
+
+fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Hatching for maintenance and setups, and the arrowheads of dependencies.
+fn defs() -> String {
+  let hatch = |id: &str, angle: i32, color: &str, stripe: f64, gap: f64| {
+    format!(
+      r#"<pattern id="{}" patternUnits="userSpaceOnUse" width="{}" height="{}" patternTransform="rotate({})"><rect width="{}" height="{}" fill="{}"/></pattern>"#,
+      id,
+      stripe + gap,
+      stripe + gap,
+      angle,
+      stripe,
+      stripe + gap,
+      color
+    )
+  };
+  let arrowhead = |id: &str, color: &str| {
+    format!(
+      r#"<marker id="{}" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="6" markerHeight="6" orient="auto"><path d="M 0 0 L 10 5 L 0 10 z" fill="{}"/></marker>"#,
+      id, color
+    )
+  };
+  [
+    r##"<pattern id="maintenance" patternUnits="userSpaceOnUse" width="12" height="12" patternTransform="rotate(45)"><rect width="12" height="12" fill="#eeeeee"/><rect width="6" height="12" fill="#bdbdbd"/></pattern>"##
+      .to_string(),
+    hatch("setup", -45, SETUP_COLOR, 2.0, 4.0),
+    hatch("setup-short", -45, SETUP_SHORT_COLOR, 2.0, 4.0),
+    arrowhead("arrowhead", ARROW_COLOR),
+    arrowhead("arrowhead-critical", CRITICAL_COLOR),
+  ]
+  .concat()
+}
+
+fn corner(layout: &Layout, header_height: f64) -> String {
+  let heading = if layout.outline { "Task" } else { "Machine" };
+  format!(
+    r##"<rect width="{0}" height="{1}" fill="#fafafa"/>{2}<line x1="0" y1="{1}" x2="{0}" y2="{1}" stroke="#cccccc"/><line x1="{0}" y1="0" x2="{0}" y2="{1}" stroke="#cccccc"/>"##,
+    LABEL_WIDTH,
+    header_height,
+    text(8.0, header_height - 6.0, 11.0, "#666", heading, "")
+  )
+}
+
+/// The tiers of the time scale, coarsest on top.
+fn header(timeline: &Timeline) -> String {
+  let width = timeline.width();
+  let mut svg = format!(r##"<rect width="{}" height="{}" fill="#f5f5f5"/>"##, width, timeline.header_height());
+  for (row, tier) in timeline.tiers().into_iter().enumerate() {
+    let top = row as f64 * HEADER_ROW_HEIGHT;
+    svg.push_str(&format!(r##"<line x1="0" y1="{0}" x2="{1}" y2="{0}" stroke="#e0e0e0"/>"##, top, width));
+    for start in timeline.boundaries(tier) {
+      let left = timeline.x(start).max(0.0);
+      let cell_width = timeline.x(tier.next(start)).min(width) - left;
+      svg.push_str(&format!(
+        r##"<line x1="{0}" y1="{1}" x2="{0}" y2="{2}" stroke="#cccccc"/>"##,
+        left,
+        top,
+        top + HEADER_ROW_HEIGHT
+      ));
+      let label = text(3.0, 14.0, 12.0, "#333", &tier.label(start), "");
+      svg.push_str(&viewport((left, top, cell_width, HEADER_ROW_HEIGHT), (0.0, 0.0), &label));
+    }
+  }
+  let height = timeline.header_height();
+  svg.push_str(&format!(r##"<line x1="0" y1="{0}" x2="{1}" y2="{0}" stroke="#cccccc"/>"##, height, width));
+  svg
+}
+
+fn labels(layout: &Layout) -> String {
+  let mut svg = format!(
+    r##"<rect width="{0}" height="{1}" fill="#fafafa"/><line x1="{0}" y1="0" x2="{0}" y2="{1}" stroke="#cccccc"/>"##,
+    LABEL_WIDTH,
+    layout.height()
+  );
+  for lane in &layout.lanes {
+    let bottom = lane.top + lane.height;
+    svg.push_str(&format!(r##"<line x1="0" y1="{0}" x2="{1}" y2="{0}" stroke="#cccccc"/>"##, bottom, LABEL_WIDTH));
+    let label = text(8.0 + lane.indent, lane.height / 2.0 + 4.5, 13.0, "#333", &lane.label, "");
+    svg.push_str(&viewport((0.0, lane.top, LABEL_WIDTH - 8.0, lane.height), (0.0, 0.0), &label));
+  }
+  svg
+}
+
+/// Everything drawn on the lanes, back to front as on screen.
+fn body(plan: &Plan<'_>, timeline: &Timeline, layout: &Layout) -> String {
+  let width = timeline.width();
+  let mut svg = String::new();
+  for lane in &layout.lanes {
+    let bottom = lane.top + lane.height;
+    svg.push_str(&format!(r##"<line x1="0" y1="{0}" x2="{1}" y2="{0}" stroke="#cccccc"/>"##, bottom, width));
+  }
+
+  // Off-shift time and maintenance of each machine; a task row of the outline shows neither
+  if !layout.outline {
+    let calendars: HashMap<_, &Calendar> = plan.calendars.iter().map(|calendar| (calendar.id, calendar)).collect();
+    for lane in &layout.lanes {
+      let Some(machine) = plan.machines.iter().find(|machine| Some(machine.id) == lane.machine_id) else {
+        continue;
+      };
+      let calendar = machine.calendar_id.and_then(|id| calendars.get(&id)).filter(|c| c.has_working_time());
+      for (start, end) in calendar.map_or_else(Vec::new, |c| c.non_working_intervals(timeline.origin, timeline.end)) {
+        svg.push_str(&rect(timeline, start, end, lane.top, lane.height, r#"fill="black" fill-opacity="0.06""#));
+      }
+      for window in machine.maintenance.iter().filter(|w| timeline.shows(w.start_date, w.end_date)) {
+        let fill = r#"fill="url(#maintenance)" fill-opacity="0.7""#;
+        svg.push_str(&rect(timeline, window.start_date, window.end_date, lane.top, lane.height, fill));
+      }
+    }
+  }
+
+  for block in setup_blocks(plan.tasks, plan.machines) {
+    let Some(top) = layout.bar_top(block.task_id) else { continue };
+    let color = if block.shortfall_minutes() > 0 { "setup-short" } else { "setup" };
+    let stroke = if block.shortfall_minutes() > 0 { SETUP_SHORT_COLOR } else { SETUP_COLOR };
+    let left = timeline.x(block.start);
+    svg.push_str(&format!(
+      r#"<rect x="{}" y="{}" width="{}" height="{}" fill="url(#{})" stroke="{}"/>"#,
+      left,
+      top,
+      (timeline.x(block.end) - left).max(2.0),
+      ROW_HEIGHT,
+      color,
+      stroke
+    ));
+  }
+
+  if let Some(tier) = timeline.tiers().last() {
+    for time in timeline.boundaries(*tier) {
+      svg.push_str(&format!(
+        r##"<line x1="{0}" y1="0" x2="{0}" y2="{1}" stroke="#e0e0e0"/>"##,
+        timeline.x(time),
+        layout.height()
+      ));
+    }
+  }
+
+  let marks = Marks::new(plan);
+  for task in plan.tasks {
+    if let Some(top) = layout.bar_top(task.id) {
+      svg.push_str(&bar(task, top, timeline, &marks));
+    }
+  }
+
+  for order in plan.work_orders {
+    let Some(due_date) = order.due_date.filter(|due| timeline.shows(*due, *due)) else { continue };
+    let x = timeline.x(due_date);
+    svg.push_str(&format!(
+      r#"<line x1="{0}" y1="0" x2="{0}" y2="{1}" stroke="{2}" stroke-width="2" stroke-dasharray="6 4"/>"#,
+      x,
+      layout.height(),
+      DUE_DATE_COLOR
+    ));
+    let tag_width = order.number.chars().count() as f64 * 7.0 + 6.0;
+    svg.push_str(&format!(
+      r#"<rect x="{}" y="0" width="{}" height="14" rx="2" fill="{}"/>"#,
+      x + 2.0,
+      tag_width,
+      DUE_DATE_COLOR
+    ));
+    svg.push_str(&text(x + 5.0, 11.0, 11.0, "white", &order.number, ""));
+  }
+
+  svg.push_str(&arrows(plan.tasks, timeline, layout, &marks));
+  svg
+}
+
+/// A rectangle spanning `start`..`end` on a lane.
+fn rect(timeline: &Timeline, start: NaiveDateTime, end: NaiveDateTime, top: f64, height: f64, fill: &str) -> String {
+  let left = timeline.x(start);
+  format!(
+    r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+    left,
+    top,
+    timeline.x(end) - left,
+    height,
+    fill
+  )
+}
+
+/// What the bars are flagged with besides their own fields.
+struct Marks {
+  summaries: HashSet<TaskId>,
+  critical: HashSet<TaskId>,
+  /// (predecessor, successor) of the links on the critical path.
+  critical_links: HashSet<(TaskId, TaskId)>,
+  conflicting: HashSet<TaskId>,
+  missed: HashSet<TaskId>,
+  /// (number, count) of each segment of a split task.
+  segments: HashMap<TaskId, (usize, usize)>,
+  now: NaiveDateTime,
+}
+
+impl Marks {
+  fn new(plan: &Plan<'_>) -> Self {
+    let analysis = analyze_schedule(plan.tasks).ok();
+    let mut lots: HashMap<TaskId, Vec<&Task>> = HashMap::new();
+    for task in plan.tasks {
+      lots.entry(lot_id(task)).or_default().push(task);
+    }
+    let segments = lots
+      .into_values()
+      .filter(|lot| lot.len() > 1)
+      .flat_map(|mut lot| {
+        lot.sort_by_key(|task| (task.start_date, task.id));
+        let count = lot.len();
+        lot.into_iter().enumerate().map(move |(i, task)| (task.id, (i + 1, count))).collect::<Vec<_>>()
+      })
+      .collect();
+    Marks {
+      summaries: plan.tasks.iter().filter_map(|task| task.parent_id).collect(),
+      critical: plan
+        .tasks
+        .iter()
+        .map(|task| task.id)
+        .filter(|id| analysis.as_ref().is_some_and(|analysis| analysis.is_critical(*id)))
+        .collect(),
+      critical_links: plan
+        .tasks
+        .iter()
+        .flat_map(|task| task.dependencies.iter().map(|dependency| (dependency.predecessor_id, task.id)))
+        .filter(|&(predecessor, successor)| {
+          analysis.as_ref().is_some_and(|analysis| analysis.is_critical_link(predecessor, successor))
+        })
+        .collect(),
+      conflicting: find_conflicts(plan.tasks, plan.machines).into_iter().flat_map(|c| c.task_ids).collect(),
+      missed: missed_deadlines(plan.tasks, plan.work_orders).into_iter().map(|missed| missed.task_id).collect(),
+      segments,
+      now: Local::now().naive_local(),
+    }
+  }
+}
+
+fn status_icon(status: TaskStatus) -> &'static str {
+  match status {
+    TaskStatus::Done => "✓ ",
+    TaskStatus::Paused => "⏸ ",
+    TaskStatus::InSetup => "⚙ ",
+    _ => "",
+  }
+}
+
+fn bar(task: &Task, top: f64, timeline: &Timeline, marks: &Marks) -> String {
+  let left = timeline.x(task.start_date);
+  let width = timeline.x(task.end_date) - left;
+  let middle = top + ROW_HEIGHT / 2.0;
+  let critical = marks.critical.contains(&task.id);
+  let (stroke, stroke_width) = if critical { (CRITICAL_COLOR, 2.0) } else { ("black", 1.0) };
+  let missed = if marks.missed.contains(&task.id) { "⚑ " } else { "" };
+  // a cancelled task is drawn faintly
+  let mut svg = if task.status == TaskStatus::Cancelled { r#"<g opacity="0.4">"# } else { "<g>" }.to_string();
+
+  if task.is_milestone() {
+    let diamond = |half: f64| {
+      format!(
+        "M {} {} L {} {} L {} {} L {} {} Z",
+        left,
+        middle - half,
+        left + half,
+        middle,
+        left,
+        middle + half,
+        left - half,
+        middle
+      )
+    };
+    let half = MILESTONE_SIZE / std::f64::consts::SQRT_2;
+    if marks.conflicting.contains(&task.id) {
+      svg.push_str(&format!(r#"<path d="{}" fill="{}"/>"#, diamond(half + 2.0 * std::f64::consts::SQRT_2), CONFLICT_COLOR));
+    }
+    svg.push_str(&format!(
+      r#"<path d="{}" fill="{}" stroke="{}" stroke-width="{}"/>"#,
+      diamond(half),
+      task.color,
+      stroke,
+      stroke_width
+    ));
+    let label = format!("{}{}{}", missed, status_icon(task.status), task.name);
+    svg.push_str(&text(left + ROW_HEIGHT / 2.0, middle + 4.5, 13.0, "#333", &label, r#"font-weight="bold""#));
+    svg.push_str("</g>");
+    return svg;
+  }
+
+  if marks.conflicting.contains(&task.id) {
+    svg.push_str(&format!(
+      r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+      left - 2.0,
+      top - 2.0,
+      width + 4.0,
+      ROW_HEIGHT + 4.0,
+      CONFLICT_COLOR
+    ));
+  }
+  let is_summary = marks.summaries.contains(&task.id);
+  svg.push_str(&format!(
+    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+    left,
+    top,
+    width,
+    ROW_HEIGHT,
+    if is_summary { SUMMARY_COLOR } else { &task.color }
+  ));
+  svg.push_str(&format!(
+    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="black" fill-opacity="0.25"/>"#,
+    left,
+    top,
+    width * task.percent_complete / 100.0,
+    ROW_HEIGHT
+  ));
+  svg.push_str(&format!(
+    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+    left + 0.5,
+    top + 0.5,
+    (width - 1.0).max(0.0),
+    ROW_HEIGHT - 1.0,
+    stroke,
+    stroke_width
+  ));
+
+  let conflict = if marks.conflicting.contains(&task.id) { "⚠ " } else { "" };
+  let mut label = format!("{}{}{}{}", conflict, missed, status_icon(task.status), task.name);
+  if let Some((number, count)) = marks.segments.get(&task.id) {
+    label.push_str(&format!(" [{}/{}]", number, count));
+  }
+  if is_summary {
+    label.push_str(&format!(" ({:.0}%)", task.percent_complete));
+  }
+  // the name is cut off at the end of the bar
+  let label = text(10.0, ROW_HEIGHT / 2.0 + 4.5, 13.0, "white", &label, r#"font-weight="bold""#);
+  svg.push_str(&viewport((left, top, width.max(0.0), ROW_HEIGHT), (0.0, 0.0), &label));
+
+  // actual work runs until now while the task is in progress
+  if let Some(start) = task.actual_start {
+    let end = task.actual_end.unwrap_or(marks.now).max(start);
+    let actual_left = timeline.x(start);
+    svg.push_str(&format!(
+      r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+      actual_left,
+      top + ROW_HEIGHT - ACTUAL_BAR_HEIGHT,
+      (timeline.x(end) - actual_left).max(2.0),
+      ACTUAL_BAR_HEIGHT,
+      if end > task.end_date { LATE_COLOR } else { "#212121" }
+    ));
+  }
+  svg.push_str("</g>");
+  svg
+}
+
+/// Dependency arrows leave the end of the predecessor named by the link and run between the
+/// bars into the successor. Links on the critical path are red.
+fn arrows(tasks: &[Task], timeline: &Timeline, layout: &Layout, marks: &Marks) -> String {
+  let geometry = |task: &Task| {
+    let top = layout.bar_top(task.id)?;
+    let left = timeline.x(task.start_date);
+    Some((left, timeline.x(task.end_date) - left, top + ROW_HEIGHT / 2.0))
+  };
+  let by_id: HashMap<TaskId, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+  let mut svg = String::new();
+  for task in tasks {
+    for dependency in &task.dependencies {
+      let Some(predecessor) = by_id.get(&dependency.predecessor_id) else { continue };
+      let (Some((p_left, p_width, y1)), Some((s_left, s_width, y2))) = (geometry(predecessor), geometry(task)) else {
+        continue;
+      };
+      let x1 = if dependency.kind.from_finish() { p_left + p_width } else { p_left };
+      let x2 = if dependency.kind.to_finish() { s_left + s_width } else { s_left };
+      let exit = if dependency.kind.from_finish() { x1 + STUB } else { x1 - STUB };
+      let entry = if dependency.kind.to_finish() { x2 + STUB } else { x2 - STUB };
+      let mid_y = if y1 == y2 { y1 + ROW_HEIGHT / 2.0 } else { (y1 + y2) / 2.0 };
+      let critical = marks.critical_links.contains(&(predecessor.id, task.id));
+      let (color, width, marker) =
+        if critical { (CRITICAL_COLOR, 2.0, "arrowhead-critical") } else { (ARROW_COLOR, 1.5, "arrowhead") };
+      svg.push_str(&format!(
+        r#"<path d="M {} {} H {} V {} H {} V {} H {}" fill="none" stroke="{}" stroke-width="{}" marker-end="url(#{})"/>"#,
+        x1, y1, exit, mid_y, entry, y2, x2, color, width, marker
+      ));
+    }
+  }
+  svg
+}
+
+/// What the marks on the chart mean, in rows as wide as `width`. Returns the drawing and its height.
+pub fn legend(width: f64) -> (String, f64) {
+  let bar = |fill: &str, stroke: &str, stroke_width: f64| {
+    format!(
+      r#"<rect x="0" y="3" width="24" height="12" fill="{}" stroke="{}" stroke-width="{}"/>"#,
+      fill, stroke, stroke_width
+    )
+  };
+  let band = |color: &str| format!(r#"<rect x="0" y="11" width="24" height="{}" fill="{}"/>"#, ACTUAL_BAR_HEIGHT, color);
+  let items: Vec<(String, &str)> = vec![
+    (bar(LEGEND_TASK_COLOR, "black", 1.0), "Task"),
+    (bar(SUMMARY_COLOR, "black", 1.0), "Summary"),
+    (format!(r#"<path d="M 12 2 L 19 9 L 12 16 L 5 9 Z" fill="{}" stroke="black"/>"#, LEGEND_TASK_COLOR), "Milestone"),
+    (
+      bar(LEGEND_TASK_COLOR, "black", 1.0) + r#"<rect x="0" y="3" width="12" height="12" fill="black" fill-opacity="0.25"/>"#,
+      "Progress",
+    ),
+    (bar(LEGEND_TASK_COLOR, CRITICAL_COLOR, 2.0), "Critical path"),
+    (format!(r#"<rect x="-2" y="1" width="28" height="16" fill="{}"/>"#, CONFLICT_COLOR) + &bar(LEGEND_TASK_COLOR, "black", 1.0), "Conflict"),
+    (band("#212121"), "Actual"),
+    (band(LATE_COLOR), "Actual, late"),
+    (format!(r#"<rect x="0" y="3" width="24" height="12" fill="url(#setup)" stroke="{}"/>"#, SETUP_COLOR), "Setup"),
+    (
+      format!(r#"<rect x="0" y="3" width="24" height="12" fill="url(#setup-short)" stroke="{}"/>"#, SETUP_SHORT_COLOR),
+      "Setup too short",
+    ),
+    (r#"<rect x="0" y="3" width="24" height="12" fill="url(#maintenance)" fill-opacity="0.7"/>"#.to_string(), "Maintenance"),
+    (r#"<rect x="0" y="3" width="24" height="12" fill="black" fill-opacity="0.06"/>"#.to_string(), "Off shift"),
+    (
+      format!(r#"<line x1="12" y1="0" x2="12" y2="18" stroke="{}" stroke-width="2" stroke-dasharray="4 3"/>"#, DUE_DATE_COLOR),
+      "Due date",
+    ),
+    (
+      format!(r#"<path d="M 0 9 H 22" stroke="{}" stroke-width="1.5" marker-end="url(#arrowhead)"/>"#, ARROW_COLOR),
+      "Dependency",
+    ),
+  ];
+  let columns = ((width - 8.0) / LEGEND_ITEM_WIDTH).floor().max(1.0) as usize;
+  let mut svg = String::new();
+  for (index, (swatch, label)) in items.iter().enumerate() {
+    let x = 8.0 + (index % columns) as f64 * LEGEND_ITEM_WIDTH;
+    let y = (index / columns) as f64 * LEGEND_ROW_HEIGHT;
+    svg.push_str(&format!(r#"<g transform="translate({} {})">{}{}</g>"#, x, y, swatch, text(30.0, 13.0, 11.0, "#333", label, "")));
+  }
+  (svg, items.len().div_ceil(columns) as f64 * LEGEND_ROW_HEIGHT)
+}
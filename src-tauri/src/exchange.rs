@@ -0,0 +1,611 @@
+//! Schedules in the formats of other planning tools: CSV tables, MS Project XML, iCalendar and
+//! Excel workbooks.
+//!
+//! Imports never fail as a whole. Records that cannot be read are left out and reported in
+//! the preview, so the planner can decide whether the rest is worth taking over.
+
+mod csv;
+mod ical;
+mod ms_project;
+mod workbook;
+
+use std::collections::{BTreeSet, HashSet};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use millmill_domain::{
+  format_predecessors, guess_mapping, guess_order_mapping, parse_predecessors, validate_dependencies,
+  validate_hierarchy, validate_segments, Calendar, CalendarId, ColumnMapping, ExchangeFormat, ImportIssue,
+  ImportPreview, ImportProfile, Machine, MachineId, OrderColumnMapping, Task, TaskField, TaskId, TaskKind, TaskStatus,
+  Validate, WorkOrder, WorkOrderField, WorkOrderId, WorkbookPreview,
+};
+
+/// Color of imported tasks; none of the formats carries one, except our own columns.
+const DEFAULT_COLOR: &str = "#009688";
+/// How dates are written to tables.
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// The records of the open plan that a file refers to by name.
+pub struct Existing<'a> {
+  pub machines: &'a [Machine],
+  pub calendars: &'a [Calendar],
+  pub work_orders: &'a [WorkOrder],
+}
+
+/// Reads `text` without changing anything. `mapping` assigns CSV columns to task fields;
+/// without one it is guessed from the headers.
+pub fn import(
+  format: ExchangeFormat,
+  text: &str,
+  mapping: Option<&[ColumnMapping]>,
+  existing: &Existing<'_>,
+) -> ImportPreview {
+  let mut importer = Importer::new(existing);
+  // Excel likes to start UTF-8 files with a byte order mark
+  let text = text.trim_start_matches('\u{feff}');
+  let mapping = match format {
+    ExchangeFormat::Csv => csv::read(text, mapping, &mut importer),
+    ExchangeFormat::MsProject => {
+      ms_project::read(text, &mut importer);
+      Vec::new()
+    }
+    ExchangeFormat::ICalendar => {
+      ical::read(text, &mut importer);
+      Vec::new()
+    }
+  };
+  importer.finish(mapping)
+}
+
+pub fn export(format: ExchangeFormat, tasks: &[Task], existing: &Existing<'_>) -> Result<String, String> {
+  match format {
+    ExchangeFormat::Csv => csv::write(tasks, existing),
+    ExchangeFormat::MsProject => Ok(ms_project::write(tasks, existing)),
+    ExchangeFormat::ICalendar => Ok(ical::write(tasks, existing)),
+  }
+}
+
+/// Reads an Excel workbook without changing anything: work orders from one sheet and tasks
+/// from another, as `profile` says or as guessed from the headers. Fails only when the file
+/// is not a workbook.
+pub fn import_workbook(
+  bytes: &[u8],
+  profile: Option<&ImportProfile>,
+  existing: &Existing<'_>,
+) -> Result<WorkbookPreview, String> {
+  let mut importer = Importer::new(existing);
+  let (sheets, profile) = workbook::read(bytes, profile, &mut importer)?;
+  let mapping = profile.task_columns.clone();
+  Ok(WorkbookPreview { sheets, profile, import: importer.finish(mapping) })
+}
+
+/// The schedule as a table, and as bars of filled cells with a column per day.
+pub fn export_workbook(tasks: &[Task], existing: &Existing<'_>) -> Result<Vec<u8>, String> {
+  workbook::write(tasks, existing)
+}
+
+/// A task read from a file, with the line it started on.
+struct Record {
+  line: Option<usize>,
+  /// `None` when the file gives no id; one is handed out after all records are read.
+  id: Option<TaskId>,
+  task: Task,
+  has_start: bool,
+  has_end: bool,
+}
+
+impl Record {
+  /// A task with the defaults of the app, to be filled in from the file.
+  fn new(line: Option<usize>) -> Self {
+    Record {
+      line,
+      id: None,
+      task: Task { color: DEFAULT_COLOR.to_string(), ..Default::default() },
+      has_start: false,
+      has_end: false,
+    }
+  }
+
+  fn set_start(&mut self, start: NaiveDateTime) {
+    self.task.start_date = start;
+    self.has_start = true;
+  }
+
+  fn set_end(&mut self, end: NaiveDateTime) {
+    self.task.end_date = end;
+    self.has_end = true;
+  }
+}
+
+/// Collects what a file holds while it is read.
+struct Importer<'a> {
+  existing: &'a Existing<'a>,
+  records: Vec<Record>,
+  machines: Vec<Machine>,
+  calendars: Vec<Calendar>,
+  work_orders: Vec<WorkOrder>,
+  unmapped: BTreeSet<String>,
+  issues: Vec<ImportIssue>,
+}
+
+impl<'a> Importer<'a> {
+  fn new(existing: &'a Existing<'a>) -> Self {
+    Importer {
+      existing,
+      records: Vec::new(),
+      machines: Vec::new(),
+      calendars: Vec::new(),
+      work_orders: Vec::new(),
+      unmapped: BTreeSet::new(),
+      issues: Vec::new(),
+    }
+  }
+
+  fn issue(&mut self, line: Option<usize>, message: impl Into<String>) {
+    self.issues.push(ImportIssue { line, message: message.into() });
+  }
+
+  fn unmapped(&mut self, name: impl Into<String>) {
+    self.unmapped.insert(name.into());
+  }
+
+  /// The machine called `name`: an existing one, or a new one added to the import.
+  fn machine_id(&mut self, name: &str) -> MachineId {
+    let name = name.trim();
+    let same = |machine: &&Machine| machine.name.trim().eq_ignore_ascii_case(name);
+    if let Some(machine) = self.existing.machines.iter().chain(&self.machines).find(same) {
+      return machine.id;
+    }
+    let id = self.existing.machines.iter().chain(&self.machines).map(|machine| machine.id).max().unwrap_or(0) + 1;
+    self.machines.push(Machine { id, name: name.to_string(), ..Default::default() });
+    id
+  }
+
+  /// Adds `calendar` to the import under a fresh id, unless one with the same name exists.
+  fn add_calendar(&mut self, calendar: Calendar) -> CalendarId {
+    let same = |existing: &&Calendar| existing.name.trim().eq_ignore_ascii_case(calendar.name.trim());
+    if let Some(existing) = self.existing.calendars.iter().chain(&self.calendars).find(same) {
+      return existing.id;
+    }
+    let id = self.existing.calendars.iter().chain(&self.calendars).map(|calendar| calendar.id).max().unwrap_or(0) + 1;
+    self.calendars.push(Calendar { id, ..calendar });
+    id
+  }
+
+  /// Work orders are only created from an order list; an unknown number is reported and
+  /// left out.
+  fn work_order_id(&self, number: &str) -> Result<WorkOrderId, String> {
+    self
+      .existing
+      .work_orders
+      .iter()
+      .chain(&self.work_orders)
+      .find(|order| order.number.trim().eq_ignore_ascii_case(number.trim()))
+      .map(|order| order.id)
+      .ok_or_else(|| format!("there is no work order {}", number.trim()))
+  }
+
+  /// Adds a work order read from an order list under a fresh id. Orders already in the plan
+  /// or in the list are reported and left out, so reading the same list twice adds nothing.
+  fn add_work_order(&mut self, line: Option<usize>, order: WorkOrder) {
+    if self.work_order_id(&order.number).is_ok() {
+      return self.issue(line, format!("Work order {} already exists and is left out", order.number.trim()));
+    }
+    if let Err(e) = order.validate() {
+      let number = if order.number.trim().is_empty() { "A work order" } else { order.number.trim() };
+      return self.issue(line, format!("{} is left out: {}", number, e));
+    }
+    let id = self.existing.work_orders.iter().chain(&self.work_orders).map(|order| order.id).max().unwrap_or(0) + 1;
+    self.work_orders.push(WorkOrder { id, ..order });
+  }
+
+  /// Sets `field` of the record from the text of a cell. Empty cells keep the default; values
+  /// that cannot be read are reported and skipped.
+  fn set_field(&mut self, record: &mut Record, field: TaskField, value: &str) {
+    let value = value.trim();
+    if value.is_empty() {
+      return;
+    }
+    if let Err(message) = self.apply_field(record, field, value) {
+      self.issue(record.line, format!("{}: {}", field.label(), message));
+    }
+  }
+
+  fn apply_field(&mut self, record: &mut Record, field: TaskField, value: &str) -> Result<(), String> {
+    let task = &mut record.task;
+    match field {
+      TaskField::Id => record.id = Some(parse_id(value)?),
+      TaskField::Name => task.name = value.to_string(),
+      TaskField::Description => task.description = value.to_string(),
+      TaskField::Start => record.set_start(parse_date_time(value)?),
+      TaskField::End => record.set_end(parse_date_time(value)?),
+      TaskField::Kind => task.kind = parse_kind(value)?,
+      TaskField::Status => task.status = TaskStatus::parse(value),
+      TaskField::Machine => task.machine_id = Some(self.machine_id(value)),
+      TaskField::WorkOrder => task.work_order_id = Some(self.work_order_id(value)?),
+      TaskField::Parent => task.parent_id = Some(parse_id(value)?),
+      TaskField::Predecessors => task.dependencies = parse_predecessors(value)?,
+      TaskField::PercentComplete => {
+        task.percent_complete = value
+          .trim_end_matches('%')
+          .trim()
+          .parse()
+          .map_err(|_| format!("\"{}\" is not a percentage", value))?
+      }
+      TaskField::ActualStart => task.actual_start = Some(parse_date_time(value)?),
+      TaskField::ActualEnd => task.actual_end = Some(parse_date_time(value)?),
+      TaskField::Color => task.color = value.to_string(),
+      TaskField::PartFamily => task.part_family = value.to_string(),
+      TaskField::Material => task.material = value.to_string(),
+      TaskField::Quantity => task.quantity = value.parse().map_err(|_| format!("\"{}\" is not a number of pieces", value))?,
+      TaskField::SegmentOf => task.split_of = Some(parse_id(value)?),
+    }
+    Ok(())
+  }
+
+  /// Adds a record once all of its fields are read. Records without a start date or that break
+  /// a task rule are reported and left out.
+  fn push(&mut self, mut record: Record) {
+    let task = &mut record.task;
+    let name = if task.name.is_empty() { "A task".to_string() } else { task.name.clone() };
+    if !record.has_start {
+      return self.issue(record.line, format!("{} has no start date and is left out", name));
+    }
+    // Without an end the task is read as a point in time; milestones take no time anyway
+    if !record.has_end || task.is_milestone() {
+      task.end_date = task.start_date;
+    }
+    if let Err(e) = task.validate() {
+      return self.issue(record.line, format!("{} is left out: {}", name, e));
+    }
+    self.records.push(record);
+  }
+
+  /// Hands out missing ids and drops links the imported tasks cannot keep.
+  fn finish(mut self, mapping: Vec<ColumnMapping>) -> ImportPreview {
+    let mut ids = HashSet::new();
+    let mut next_id = self.records.iter().filter_map(|record| record.id).max().unwrap_or(0) + 1;
+    let mut tasks = Vec::new();
+    for record in std::mem::take(&mut self.records) {
+      // 0 is no task's id; such rows get a new one like rows without an id
+      let id = match record.id.filter(|&id| id > 0) {
+        Some(id) if !ids.contains(&id) => id,
+        Some(id) => {
+          self.issue(record.line, format!("id {} is used twice; the second task is left out", id));
+          continue;
+        }
+        None => {
+          next_id += 1;
+          next_id - 1
+        }
+      };
+      ids.insert(id);
+      tasks.push((record.line, Task { id, ..record.task }));
+    }
+
+    for (line, task) in &mut tasks {
+      let (line, name) = (*line, task.name.clone());
+      let mut dropped = Vec::new();
+      task.dependencies.retain(|dependency| ids.contains(&dependency.predecessor_id) || {
+        dropped.push(dependency.predecessor_id);
+        false
+      });
+      for predecessor in dropped {
+        self.issue(line, format!("{}: predecessor {} is not in the file", name, predecessor));
+      }
+      if let Some(parent) = task.parent_id.filter(|parent| !ids.contains(parent)) {
+        self.issue(line, format!("{}: summary task {} is not in the file", name, parent));
+        task.parent_id = None;
+      }
+      if let Some(first) = task.split_of.filter(|first| !ids.contains(first)) {
+        self.issue(line, format!("{}: first segment {} is not in the file", name, first));
+        task.split_of = None;
+      }
+    }
+    let mut tasks: Vec<Task> = tasks.into_iter().map(|(_, task)| task).collect();
+
+    // The plan could not be saved with these; keep the tasks and drop what is broken
+    if let Err(e) = validate_dependencies(&tasks) {
+      self.issue(None, format!("All links are left out: {}", e));
+      for task in &mut tasks {
+        task.dependencies.clear();
+      }
+    }
+    if let Err(e) = validate_hierarchy(&tasks) {
+      self.issue(None, format!("The task grouping is left out: {}", e));
+      for task in &mut tasks {
+        task.parent_id = None;
+      }
+    }
+    if let Err(e) = validate_segments(&tasks) {
+      self.issue(None, format!("Split jobs are imported as separate tasks: {}", e));
+      for task in &mut tasks {
+        task.split_of = None;
+      }
+    }
+
+    ImportPreview {
+      tasks,
+      machines: self.machines,
+      calendars: self.calendars,
+      work_orders: self.work_orders,
+      mapping,
+      unmapped: self.unmapped.into_iter().collect(),
+      issues: self.issues,
+    }
+  }
+}
+
+/// The text of `field` for `task`, as written to a table cell.
+fn field_text(field: TaskField, task: &Task, existing: &Existing<'_>) -> String {
+  let date = |date: Option<NaiveDateTime>| date.map(|date| date.format(DATE_FORMAT).to_string()).unwrap_or_default();
+  let id = |id: Option<TaskId>| id.map(|id| id.to_string()).unwrap_or_default();
+  match field {
+    TaskField::Id => task.id.to_string(),
+    TaskField::Name => task.name.clone(),
+    TaskField::Description => task.description.clone(),
+    TaskField::Start => date(Some(task.start_date)),
+    TaskField::End => date(Some(task.end_date)),
+    TaskField::Kind => task.kind.code().to_string(),
+    TaskField::Status => task.status.label().to_string(),
+    TaskField::Machine => machine_name(task.machine_id, existing).unwrap_or_default(),
+    TaskField::WorkOrder => existing
+      .work_orders
+      .iter()
+      .find(|order| Some(order.id) == task.work_order_id)
+      .map(|order| order.number.clone())
+      .unwrap_or_default(),
+    TaskField::Parent => id(task.parent_id),
+    TaskField::Predecessors => format_predecessors(&task.dependencies),
+    TaskField::PercentComplete => format!("{}", task.percent_complete),
+    TaskField::ActualStart => date(task.actual_start),
+    TaskField::ActualEnd => date(task.actual_end),
+    TaskField::Color => task.color.clone(),
+    TaskField::PartFamily => task.part_family.clone(),
+    TaskField::Material => task.material.clone(),
+    TaskField::Quantity => match task.quantity {
+      0 => String::new(),
+      quantity => quantity.to_string(),
+    },
+    TaskField::SegmentOf => id(task.split_of),
+  }
+}
+
+/// A column of a table mapping: which field a header goes to.
+trait Column: Sized {
+  type Field: Copy;
+
+  fn new(header: String, field: Option<Self::Field>) -> Self;
+  fn header(&self) -> &str;
+  fn field(&self) -> Option<Self::Field>;
+  /// The mapping guessed from the headers alone.
+  fn guess(headers: &[String]) -> Vec<Self>;
+}
+
+impl Column for ColumnMapping {
+  type Field = TaskField;
+
+  fn new(header: String, field: Option<TaskField>) -> Self {
+    ColumnMapping { header, field }
+  }
+
+  fn header(&self) -> &str {
+    &self.header
+  }
+
+  fn field(&self) -> Option<TaskField> {
+    self.field
+  }
+
+  fn guess(headers: &[String]) -> Vec<Self> {
+    guess_mapping(headers)
+  }
+}
+
+impl Column for OrderColumnMapping {
+  type Field = WorkOrderField;
+
+  fn new(header: String, field: Option<WorkOrderField>) -> Self {
+    OrderColumnMapping { header, field }
+  }
+
+  fn header(&self) -> &str {
+    &self.header
+  }
+
+  fn field(&self) -> Option<WorkOrderField> {
+    self.field
+  }
+
+  fn guess(headers: &[String]) -> Vec<Self> {
+    guess_order_mapping(headers)
+  }
+}
+
+/// The mapping of `headers` through a saved one, or guessed when there is none. A saved
+/// mapping applies by header; columns it does not know are left out.
+fn apply_mapping<C: Column>(headers: &[String], saved: Option<&[C]>) -> Vec<C> {
+  match saved {
+    Some(saved) => headers
+      .iter()
+      .map(|header| {
+        let field = saved.iter().find(|column| column.header() == header).and_then(|column| column.field());
+        C::new(header.clone(), field)
+      })
+      .collect(),
+    None => C::guess(headers),
+  }
+}
+
+fn machine_name(id: Option<MachineId>, existing: &Existing<'_>) -> Option<String> {
+  existing.machines.iter().find(|machine| Some(machine.id) == id).map(|machine| machine.name.clone())
+}
+
+fn parse_id(value: &str) -> Result<TaskId, String> {
+  value.parse().map_err(|_| format!("\"{}\" is not a task id", value))
+}
+
+/// Reads the kind from our own codes or from a yes/no milestone flag.
+fn parse_kind(value: &str) -> Result<TaskKind, String> {
+  match value.to_lowercase().as_str() {
+    "yes" | "y" | "true" | "1" => Ok(TaskKind::Milestone),
+    "no" | "n" | "false" | "0" => Ok(TaskKind::Task),
+    code => TaskKind::from_code(code).ok_or_else(|| format!("\"{}\" is neither a task nor a milestone", value)),
+  }
+}
+
+/// Reads the date formats spreadsheets commonly write. A date without a time is midnight.
+fn parse_date_time(value: &str) -> Result<NaiveDateTime, String> {
+  const DATE_TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y/%m/%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+    "%m/%d/%Y %H:%M:%S",
+  ];
+  const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y"];
+  let value = value.trim();
+  DATE_TIME_FORMATS
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+      DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })
+    .ok_or_else(|| format!("\"{}\" is not a date", value))
+}
+
+
+#[cfg(test)]
+mod tests {
+  use chrono::NaiveDate;
+  use millmill_domain::{Dependency, DependencyKind};
+
+  use super::*;
+
+  fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2025, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+  }
+
+  fn task(id: TaskId, name: &str, start: NaiveDateTime, end: NaiveDateTime) -> Task {
+    Task {
+      id,
+      name: name.to_string(),
+      start_date: start,
+      end_date: end,
+      color: "#3F51B5".to_string(),
+      ..Default::default()
+    }
+  }
+
+  /// A summary over two operations and an inspection milestone, linked in every way a file can
+  /// carry, with progress that is not a whole percent.
+  fn plan() -> (Vec<Task>, Vec<Machine>, Vec<WorkOrder>) {
+    let machines = vec![Machine { id: 1, name: "Lathe".to_string(), capacity: 2, ..Default::default() }];
+    let work_orders = vec![WorkOrder {
+      id: 1,
+      number: "WO-1".to_string(),
+      customer: "ACME".to_string(),
+      part_name: "Shaft".to_string(),
+      quantity: 40,
+      due_date: None,
+    }];
+    let tasks = vec![
+      Task { description: "Order, \"rush\"; line 2\nend".to_string(), ..task(1, "Shaft", at(3, 8, 0), at(4, 17, 0)) },
+      Task {
+        parent_id: Some(1),
+        machine_id: Some(1),
+        work_order_id: Some(1),
+        status: TaskStatus::Running,
+        percent_complete: 200.0 / 3.0,
+        actual_start: Some(at(3, 8, 15)),
+        part_family: "shaft".to_string(),
+        material: "S45C".to_string(),
+        quantity: 40,
+        ..task(2, "Turning", at(3, 8, 0), at(3, 12, 30))
+      },
+      Task {
+        parent_id: Some(1),
+        dependencies: vec![
+          Dependency { predecessor_id: 2, kind: DependencyKind::FinishToStart, lag_minutes: 30 },
+        ],
+        ..task(3, "Milling", at(3, 13, 0), at(4, 17, 0))
+      },
+      Task {
+        kind: TaskKind::Milestone,
+        dependencies: vec![
+          Dependency { predecessor_id: 2, kind: DependencyKind::StartToStart, lag_minutes: 0 },
+          Dependency { predecessor_id: 3, kind: DependencyKind::FinishToFinish, lag_minutes: -60 },
+        ],
+        ..task(4, "Inspection", at(4, 17, 0), at(4, 17, 0))
+      },
+    ];
+    (tasks, machines, work_orders)
+  }
+
+  fn round_trip(format: ExchangeFormat) {
+    let (tasks, machines, work_orders) = plan();
+    let existing = Existing { machines: &machines, calendars: &[], work_orders: &work_orders };
+    let text = export(format, &tasks, &existing).unwrap();
+    let preview = import(format, &text, None, &existing);
+    assert_eq!(preview.issues, vec![], "{}", text);
+    assert_eq!(preview.machines, vec![]);
+    assert_eq!(preview.tasks, tasks, "{}", text);
+  }
+
+  #[test]
+  fn csv_reads_back_as_written() {
+    round_trip(ExchangeFormat::Csv);
+  }
+
+  #[test]
+  fn ms_project_reads_back_as_written() {
+    round_trip(ExchangeFormat::MsProject);
+  }
+
+  #[test]
+  fn icalendar_reads_back_as_written() {
+    round_trip(ExchangeFormat::ICalendar);
+  }
+
+  #[test]
+  fn overlong_icalendar_durations_are_reported() {
+    let text = "BEGIN:VCALENDAR\r\n\
+      BEGIN:VEVENT\r\nSUMMARY:Too many weeks\r\nDTSTART:20250303T080000\r\nDURATION:P99999999999999W\r\nEND:VEVENT\r\n\
+      BEGIN:VEVENT\r\nSUMMARY:Too many days\r\nDTSTART:20250303T080000\r\nDURATION:P9999999999D\r\nEND:VEVENT\r\n\
+      BEGIN:VEVENT\r\nSUMMARY:Setup\r\nDTSTART:20250303T080000\r\nDURATION:PT1H30M\r\nEND:VEVENT\r\n\
+      END:VCALENDAR\r\n";
+    let existing = Existing { machines: &[], calendars: &[], work_orders: &[] };
+    let preview = import(ExchangeFormat::ICalendar, text, None, &existing);
+    let messages: Vec<&str> = preview.issues.iter().map(|issue| issue.message.as_str()).collect();
+    assert_eq!(
+      messages,
+      vec![
+        "DURATION: \"P99999999999999W\" is not a duration",
+        "Too many days lasts beyond any date and is left out",
+      ]
+    );
+    // Without a duration the first event is read as a point in time
+    let spans: Vec<_> = preview.tasks.iter().map(|task| (task.name.as_str(), task.end_date - task.start_date)).collect();
+    assert_eq!(spans, vec![("Too many weeks", chrono::Duration::zero()), ("Setup", chrono::Duration::minutes(90))]);
+  }
+
+  #[test]
+  fn zero_ids_get_new_ids_and_only_repeated_ids_are_reported() {
+    let text = "ID,Name,Start,End\n\
+      0,Sawing,2025-03-03 08:00,2025-03-03 10:00\n\
+      0,Deburring,2025-03-03 10:00,2025-03-03 11:00\n\
+      2,Turning,2025-03-03 11:00,2025-03-03 15:00\n\
+      2,Turning again,2025-03-04 08:00,2025-03-04 12:00\n";
+    let existing = Existing { machines: &[], calendars: &[], work_orders: &[] };
+    let preview = import(ExchangeFormat::Csv, text, None, &existing);
+    let messages: Vec<&str> = preview.issues.iter().map(|issue| issue.message.as_str()).collect();
+    assert_eq!(messages, vec!["id 2 is used twice; the second task is left out"]);
+    let ids: Vec<_> = preview.tasks.iter().map(|task| (task.id, task.name.as_str())).collect();
+    assert_eq!(ids, vec![(3, "Sawing"), (4, "Deburring"), (2, "Turning")]);
+  }
+}
@@ -0,0 +1,78 @@
+//! Tables with a header row, as spreadsheets save them.
+
+use millmill_domain::{ColumnMapping, Task, TaskField};
+
+use super::{apply_mapping, field_text, Existing, Importer, Record};
+
+/// Reads the rows of `text` through `mapping`, guessed from the headers when not given.
+/// Returns the mapping used, with one entry per column of the file.
+pub(super) fn read(text: &str, mapping: Option<&[ColumnMapping]>, importer: &mut Importer<'_>) -> Vec<ColumnMapping> {
+  let mut reader = ::csv::ReaderBuilder::new()
+    .delimiter(delimiter(text))
+    .flexible(true)
+    .trim(::csv::Trim::All)
+    .from_reader(text.as_bytes());
+  let headers: Vec<String> = match reader.headers() {
+    Ok(headers) => headers.iter().map(str::to_string).collect(),
+    Err(e) => {
+      importer.issue(None, format!("Cannot read the header row: {}", e));
+      return Vec::new();
+    }
+  };
+  let mapping = apply_mapping(&headers, mapping);
+  for column in mapping.iter().filter(|column| column.field.is_none() && !column.header.is_empty()) {
+    importer.unmapped(column.header.clone());
+  }
+  if !mapping.iter().any(|column| column.field == Some(TaskField::Name)) {
+    importer.issue(None, "No column is read as the task name");
+  }
+
+  for row in reader.records() {
+    let row = match row {
+      Ok(row) => row,
+      Err(e) => {
+        let line = e.position().map(|position| position.line() as usize);
+        importer.issue(line, format!("Cannot read the row: {}", e));
+        continue;
+      }
+    };
+    if row.iter().all(str::is_empty) {
+      continue;
+    }
+    let mut record = Record::new(row.position().map(|position| position.line() as usize));
+    for (column, value) in mapping.iter().zip(row.iter()) {
+      if let Some(field) = column.field {
+        importer.set_field(&mut record, field, value);
+      }
+    }
+    importer.push(record);
+  }
+  mapping
+}
+
+/// One column per task field, headed by the field's label, so the file reads back as it was.
+pub(super) fn write(tasks: &[Task], existing: &Existing<'_>) -> Result<String, String> {
+  let mut writer = ::csv::Writer::from_writer(Vec::new());
+  writer
+    .write_record(TaskField::ALL.iter().map(|field| field.label()))
+    .map_err(|e| e.to_string())?;
+  for task in tasks {
+    writer
+      .write_record(TaskField::ALL.iter().map(|field| field_text(*field, task, existing)))
+      .map_err(|e| e.to_string())?;
+  }
+  let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+  String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// Spreadsheets with a comma as the decimal mark save with semicolons, and some tools use tabs;
+/// whichever appears most in the header row wins.
+fn delimiter(text: &str) -> u8 {
+  let header = text.lines().next().unwrap_or_default();
+  // `max_by_key` keeps the last of equals, so a tie goes to the comma
+  [b',', b';', b'\t']
+    .into_iter()
+    .rev()
+    .max_by_key(|delimiter| header.bytes().filter(|byte| byte == delimiter).count())
+    .unwrap_or(b',')
+}
@@ -0,0 +1,204 @@
+//! iCalendar files with one event per task, for calendar apps and shared shop-floor calendars.
+//!
+//! Task properties without an iCalendar equivalent are written as `X-MILLMILL-` properties
+//! named after the task field, so our own files read back completely.
+
+use chrono::{Duration, Utc};
+use millmill_domain::{parse_ical_date_time, unescape_ical, unfold_ical, Task, TaskField, TaskStatus};
+
+use super::{field_text, machine_name, Existing, Importer, Record};
+
+const PREFIX: &str = "X-MILLMILL-";
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+/// Lines longer than this many bytes are folded.
+const LINE_LIMIT: usize = 75;
+
+/// Fields written as properties of their own; the rest go to `X-MILLMILL-` properties.
+const NATIVE_FIELDS: [TaskField; 5] =
+  [TaskField::Id, TaskField::Name, TaskField::Description, TaskField::Start, TaskField::End];
+/// Event properties that carry nothing a task would keep.
+const IGNORED: &[&str] = &["DTSTAMP", "CREATED", "LAST-MODIFIED", "SEQUENCE", "TRANSP", "CLASS", "PRIORITY"];
+
+pub(super) fn read(text: &str, importer: &mut Importer<'_>) {
+  let mut event: Option<Event> = None;
+  let mut events = 0;
+  for (line, content) in unfold_ical(text) {
+    let Some((key, value)) = content.split_once(':') else { continue };
+    let mut parameters = key.split(';');
+    let name = parameters.next().unwrap_or_default().to_ascii_uppercase();
+    let is_date = parameters.any(|parameter| parameter.eq_ignore_ascii_case("VALUE=DATE"));
+    let Some(current) = event.as_mut() else {
+      if name == "BEGIN" && value.eq_ignore_ascii_case("VEVENT") {
+        event = Some(Event { record: Record::new(Some(line)), duration: None, is_date: false });
+        events += 1;
+      }
+      continue;
+    };
+    let record = &mut current.record;
+    match name.as_str() {
+      "END" if value.eq_ignore_ascii_case("VEVENT") => {
+        if let Some(event) = event.take() {
+          event.finish(importer);
+        }
+      }
+      "UID" => {
+        // our own events are "task-<id>@millmill"; other ids are not task ids
+        let id = value.strip_prefix("task-").and_then(|rest| rest.split('@').next());
+        if let Some(id) = id.filter(|id| id.parse::<usize>().is_ok()) {
+          importer.set_field(record, TaskField::Id, id);
+        }
+      }
+      "SUMMARY" => importer.set_field(record, TaskField::Name, &unescape_ical(value)),
+      "DESCRIPTION" => importer.set_field(record, TaskField::Description, &unescape_ical(value)),
+      "LOCATION" => importer.set_field(record, TaskField::Machine, &unescape_ical(value)),
+      "DTSTART" | "DTEND" => match parse_ical_date_time(value) {
+        Some(time) => {
+          current.is_date |= is_date || !value.contains('T');
+          if name == "DTSTART" {
+            record.set_start(time);
+          } else {
+            record.set_end(time);
+          }
+        }
+        None => importer.issue(Some(line), format!("{}: \"{}\" is not a date", name, value)),
+      },
+      "DURATION" => match parse_duration(value) {
+        Some(duration) => current.duration = Some(duration),
+        None => importer.issue(Some(line), format!("DURATION: \"{}\" is not a duration", value)),
+      },
+      "STATUS" if value.eq_ignore_ascii_case("CANCELLED") => record.task.status = TaskStatus::Cancelled,
+      _ => {
+        let field = name.strip_prefix(PREFIX).and_then(|code| TaskField::from_code(&code.to_lowercase().replace('-', "_")));
+        match field {
+          Some(field) => importer.set_field(record, field, &unescape_ical(value)),
+          // only a cancelled status means something for a task
+          None if !IGNORED.contains(&name.as_str()) && name != "STATUS" => importer.unmapped(format!("VEVENT/{}", name)),
+          None => {}
+        }
+      }
+    }
+  }
+  if events == 0 {
+    importer.issue(None, "The file has no events");
+  }
+}
+
+/// An event being read.
+struct Event {
+  record: Record,
+  duration: Option<Duration>,
+  /// Whether the event is all-day, with dates but no times.
+  is_date: bool,
+}
+
+impl Event {
+  fn finish(mut self, importer: &mut Importer<'_>) {
+    let record = &mut self.record;
+    if !record.has_end && record.has_start {
+      // An all-day event without an end lasts the day
+      let duration = self.duration.or(self.is_date.then(|| Duration::days(1)));
+      if let Some(duration) = duration {
+        match record.task.start_date.checked_add_signed(duration) {
+          Some(end) => record.set_end(end),
+          None => {
+            let name = if record.task.name.is_empty() { "An event" } else { record.task.name.as_str() };
+            let message = format!("{} lasts beyond any date and is left out", name);
+            return importer.issue(record.line, message);
+          }
+        }
+      }
+    }
+    importer.push(self.record)
+  }
+}
+
+/// A DURATION value such as "PT1H30M" or "P2D". `None` when it cannot be read or does not fit
+/// in a `Duration`.
+fn parse_duration(value: &str) -> Option<Duration> {
+  let value = value.trim();
+  let (negative, value) = match value.strip_prefix('-') {
+    Some(rest) => (true, rest),
+    None => (false, value.strip_prefix('+').unwrap_or(value)),
+  };
+  let mut rest = value.strip_prefix('P')?;
+  let mut total = Duration::zero();
+  while !rest.is_empty() {
+    rest = rest.strip_prefix('T').unwrap_or(rest);
+    let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+    let number: i64 = rest[..digits].parse().ok()?;
+    let part = match rest[digits..].chars().next()? {
+      'W' => Duration::try_weeks(number),
+      'D' => Duration::try_days(number),
+      'H' => Duration::try_hours(number),
+      'M' => Duration::try_minutes(number),
+      'S' => Duration::try_seconds(number),
+      _ => return None,
+    };
+    total = total.checked_add(&part?)?;
+    rest = &rest[digits + 1..];
+  }
+  Some(if negative { -total } else { total })
+}
+
+fn escape(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace(';', "\\;")
+    .replace(',', "\\,")
+    .replace("\r\n", "\\n")
+    .replace('\n', "\\n")
+}
+
+pub(super) fn write(tasks: &[Task], existing: &Existing<'_>) -> String {
+  let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+  let mut lines = vec![
+    "BEGIN:VCALENDAR".to_string(),
+    "VERSION:2.0".to_string(),
+    "PRODID:-//millmill//Production Schedule//EN".to_string(),
+    "CALSCALE:GREGORIAN".to_string(),
+  ];
+  for task in tasks {
+    lines.push("BEGIN:VEVENT".to_string());
+    lines.push(format!("UID:task-{}@millmill", task.id));
+    lines.push(format!("DTSTAMP:{}", stamp));
+    // floating times: the plan is kept in the plant's local time
+    lines.push(format!("DTSTART:{}", task.start_date.format(DATE_TIME_FORMAT)));
+    lines.push(format!("DTEND:{}", task.end_date.format(DATE_TIME_FORMAT)));
+    lines.push(format!("SUMMARY:{}", escape(&task.name)));
+    if !task.description.is_empty() {
+      lines.push(format!("DESCRIPTION:{}", escape(&task.description)));
+    }
+    if let Some(machine) = machine_name(task.machine_id, existing) {
+      lines.push(format!("LOCATION:{}", escape(&machine)));
+    }
+    if task.status == TaskStatus::Cancelled {
+      lines.push("STATUS:CANCELLED".to_string());
+    }
+    for field in TaskField::ALL.into_iter().filter(|field| !NATIVE_FIELDS.contains(field) && *field != TaskField::Machine) {
+      let text = field_text(field, task, existing);
+      if !text.is_empty() {
+        lines.push(format!("{}{}:{}", PREFIX, field.code().to_uppercase().replace('_', "-"), escape(&text)));
+      }
+    }
+    lines.push("END:VEVENT".to_string());
+  }
+  lines.push("END:VCALENDAR".to_string());
+  lines.iter().map(|line| fold(line)).collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+/// Splits a content line into lines of at most `LINE_LIMIT` bytes, continued with a space.
+fn fold(line: &str) -> String {
+  let mut folded = String::with_capacity(line.len() + line.len() / LINE_LIMIT * 3);
+  let mut width = 0;
+  for c in line.chars() {
+    if width + c.len_utf8() > LINE_LIMIT {
+      folded.push_str("\r\n ");
+      // the space counts towards the continued line
+      width = 1;
+    }
+    folded.push(c);
+    width += c.len_utf8();
+  }
+  folded
+}
+
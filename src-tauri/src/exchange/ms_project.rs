@@ -0,0 +1,496 @@
+//! Microsoft Project's XML interchange format (MSPDI): tasks with their outline and links,
+//! work resources as machines, and the calendars those work to.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDate, NaiveTime};
+use millmill_domain::{
+  outline, Calendar, CalendarException, CalendarId, Dependency, DependencyKind, Shift, Task, TaskField, TaskKind,
+  TaskStatus,
+};
+use roxmltree::{Document, Node};
+
+use super::{field_text, Existing, Importer, Record};
+
+const NAMESPACE: &str = "http://schemas.microsoft.com/project";
+const DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+const TIME_FORMAT: &str = "%H:%M:%S";
+/// Exceptions longer than this are most likely "forever" placeholders and are not expanded.
+const MAX_EXCEPTION_DAYS: i64 = 366;
+
+/// Task elements that are read, or that follow from what is read.
+const TASK_ELEMENTS: &[&str] = &[
+  "UID",
+  "ID",
+  "Name",
+  "Notes",
+  "Start",
+  "Finish",
+  "Duration",
+  "DurationFormat",
+  "Milestone",
+  "Summary",
+  "OutlineLevel",
+  "OutlineNumber",
+  "WBS",
+  "PercentComplete",
+  "ActualStart",
+  "ActualFinish",
+  "PredecessorLink",
+  "ExtendedAttribute",
+  "IsNull",
+];
+const RESOURCE_ELEMENTS: &[&str] = &["UID", "ID", "Name", "Type", "MaxUnits", "CalendarUID", "IsNull"];
+
+/// Task fields MS Project has no element for, kept in its custom text fields Text1 to Text8.
+/// `PercentComplete` only holds whole percents, so the exact progress goes to a text field as
+/// well. Custom fields are read by their alias, so files from other tools with matching names
+/// work too.
+const CUSTOM_FIELDS: [(TaskField, &str); 8] = [
+  (TaskField::Status, "188743731"),
+  (TaskField::WorkOrder, "188743734"),
+  (TaskField::Color, "188743737"),
+  (TaskField::PartFamily, "188743740"),
+  (TaskField::Material, "188743743"),
+  (TaskField::Quantity, "188743746"),
+  (TaskField::SegmentOf, "188743747"),
+  (TaskField::PercentComplete, "188743748"),
+];
+
+/// The link types of MSPDI, by their number.
+const LINK_TYPES: [DependencyKind; 4] = [
+  DependencyKind::FinishToFinish,
+  DependencyKind::FinishToStart,
+  DependencyKind::StartToFinish,
+  DependencyKind::StartToStart,
+];
+
+pub(super) fn read(text: &str, importer: &mut Importer<'_>) {
+  let document = match Document::parse(text) {
+    Ok(document) => document,
+    Err(e) => return importer.issue(Some(e.pos().row as usize), format!("Not an XML file: {}", e)),
+  };
+  let project = document.root_element();
+  if project.tag_name().name() != "Project" {
+    return importer.issue(None, "Not an MS Project file: it does not start with <Project>");
+  }
+  let line = |node: Node<'_, '_>| Some(document.text_pos_at(node.range().start).row as usize);
+
+  // Custom fields whose alias names a task field
+  let custom_fields: HashMap<&str, TaskField> = elements(project, &["ExtendedAttributes", "ExtendedAttribute"])
+    .filter_map(|node| {
+      let alias = value(node, "Alias").or(value(node, "FieldName"))?;
+      Some((value(node, "FieldID")?, TaskField::from_header(alias)?))
+    })
+    .collect();
+
+  let calendar_nodes: HashMap<&str, Node<'_, '_>> = elements(project, &["Calendars", "Calendar"])
+    .filter_map(|node| Some((value(node, "UID")?, node)))
+    .collect();
+  let mut calendars: HashMap<&str, Option<CalendarId>> = HashMap::new();
+
+  // Resources become machines, looked up by name
+  let mut machines = HashMap::new();
+  for resource in elements(project, &["Resources", "Resource"]) {
+    let Some(uid) = value(resource, "UID") else { continue };
+    // UID 0 stands for unassigned work
+    if uid == "0" || value(resource, "IsNull") == Some("1") {
+      continue;
+    }
+    let Some(name) = value(resource, "Name").filter(|name| !name.trim().is_empty()) else {
+      importer.issue(line(resource), "A resource without a name is left out");
+      continue;
+    };
+    // 0 is a material resource and 2 a cost resource
+    if matches!(value(resource, "Type"), Some("0") | Some("2")) {
+      importer.issue(line(resource), format!("{} is not a work resource and is left out", name));
+      continue;
+    }
+    report_unmapped(importer, resource, "Resource", RESOURCE_ELEMENTS);
+    let known = importer
+      .existing
+      .machines
+      .iter()
+      .chain(&importer.machines)
+      .any(|machine| machine.name.trim().eq_ignore_ascii_case(name.trim()));
+    let id = importer.machine_id(name);
+    if !known {
+      let calendar_id = match value(resource, "CalendarUID") {
+        Some(uid) => *calendars
+          .entry(uid)
+          .or_insert_with(|| read_calendar(&calendar_nodes, uid, 0).map(|calendar| importer.add_calendar(calendar))),
+        None => None,
+      };
+      let capacity = value(resource, "MaxUnits")
+        .and_then(|units| units.parse::<f64>().ok())
+        .map_or(1, |units| units.round().max(1.0) as u32);
+      if let Some(machine) = importer.machines.iter_mut().find(|machine| machine.id == id) {
+        machine.capacity = capacity;
+        machine.calendar_id = calendar_id;
+      }
+    }
+    machines.insert(uid, id);
+  }
+
+  let mut assignments: HashMap<&str, Vec<&str>> = HashMap::new();
+  for assignment in elements(project, &["Assignments", "Assignment"]) {
+    if let (Some(task), Some(resource)) = (value(assignment, "TaskUID"), value(assignment, "ResourceUID")) {
+      if machines.contains_key(resource) {
+        assignments.entry(task).or_default().push(resource);
+      }
+    }
+  }
+
+  // Summary tasks come before their children, one outline level up
+  let mut outline_parents: Vec<(u32, &str)> = Vec::new();
+  for node in elements(project, &["Tasks", "Task"]) {
+    let Some(uid) = value(node, "UID") else { continue };
+    // UID 0 is the project summary task
+    if uid == "0" || value(node, "IsNull") == Some("1") {
+      continue;
+    }
+    report_unmapped(importer, node, "Task", TASK_ELEMENTS);
+    let mut record = Record::new(line(node));
+    for (element, field) in [
+      ("UID", TaskField::Id),
+      ("Name", TaskField::Name),
+      ("Notes", TaskField::Description),
+      ("Start", TaskField::Start),
+      ("Finish", TaskField::End),
+      ("PercentComplete", TaskField::PercentComplete),
+      ("ActualStart", TaskField::ActualStart),
+      ("ActualFinish", TaskField::ActualEnd),
+    ] {
+      if let Some(text) = value(node, element) {
+        importer.set_field(&mut record, field, text);
+      }
+    }
+    let mut has_status = false;
+    for attribute in node.children().filter(|child| child.has_tag_name("ExtendedAttribute")) {
+      let field = value(attribute, "FieldID").and_then(|id| custom_fields.get(id));
+      match (field, value(attribute, "Value")) {
+        (Some(field), Some(text)) => {
+          has_status |= *field == TaskField::Status;
+          importer.set_field(&mut record, *field, text);
+        }
+        _ => importer.unmapped("Task/ExtendedAttribute"),
+      }
+    }
+    let task = &mut record.task;
+    if value(node, "Milestone") == Some("1") {
+      task.kind = TaskKind::Milestone;
+    }
+    // Without a status field the status follows from the progress
+    if !has_status {
+      task.status = if task.percent_complete >= 100.0 {
+        TaskStatus::Done
+      } else if task.percent_complete > 0.0 || task.actual_start.is_some() {
+        TaskStatus::Running
+      } else {
+        TaskStatus::Planned
+      };
+    }
+    if task.actual_start.is_none() {
+      task.actual_end = None;
+    }
+
+    let level = value(node, "OutlineLevel").and_then(|level| level.parse().ok()).unwrap_or(1);
+    while outline_parents.last().is_some_and(|(parent_level, _)| *parent_level >= level) {
+      outline_parents.pop();
+    }
+    task.parent_id = outline_parents.last().and_then(|(_, parent)| parent.parse().ok());
+    outline_parents.push((level, uid));
+
+    for link in node.children().filter(|child| child.has_tag_name("PredecessorLink")) {
+      let Some(predecessor_id) = value(link, "PredecessorUID").and_then(|uid| uid.parse().ok()) else { continue };
+      let kind = value(link, "Type").and_then(|code| code.parse::<usize>().ok()).and_then(|code| LINK_TYPES.get(code));
+      // LinkLag counts tenths of a minute
+      let lag = value(link, "LinkLag").and_then(|lag| lag.parse::<i64>().ok()).unwrap_or(0);
+      task.dependencies.push(Dependency {
+        predecessor_id,
+        kind: kind.copied().unwrap_or(DependencyKind::FinishToStart),
+        lag_minutes: lag / 10,
+      });
+    }
+
+    if let Some([resource, rest @ ..]) = assignments.get(uid).map(Vec::as_slice) {
+      task.machine_id = machines.get(resource).copied();
+      if !rest.is_empty() {
+        let message = format!("{} has {} resources; only the first is kept", task.name, rest.len() + 1);
+        importer.issue(record.line, message);
+      }
+    }
+    importer.push(record);
+  }
+}
+
+/// Reads the calendar `uid` with what it inherits from its base calendar.
+fn read_calendar(nodes: &HashMap<&str, Node<'_, '_>>, uid: &str, depth: usize) -> Option<Calendar> {
+  let node = nodes.get(uid)?;
+  let base = value(*node, "BaseCalendarUID").filter(|base| *base != uid && depth < nodes.len());
+  let mut calendar = base.and_then(|base| read_calendar(nodes, base, depth + 1)).unwrap_or_default();
+  calendar.name = value(*node, "Name").unwrap_or(uid).to_string();
+
+  for day in elements(*node, &["WeekDays", "WeekDay"]) {
+    let shifts = working_times(day);
+    match value(day, "DayType").and_then(|day_type| day_type.parse::<usize>().ok()) {
+      // 1 is Sunday; the week of a calendar starts on Monday
+      Some(day_type @ 1..=7) => calendar.week[(day_type + 5) % 7] = shifts,
+      // Older files list exceptions as day type 0 with a time period
+      Some(0) => add_exceptions(&mut calendar, day, String::new(), shifts),
+      _ => {}
+    }
+  }
+  for exception in elements(*node, &["Exceptions", "Exception"]) {
+    let name = value(exception, "Name").unwrap_or_default().to_string();
+    add_exceptions(&mut calendar, exception, name, working_times(exception));
+  }
+  Some(calendar)
+}
+
+/// The shifts of a week day or exception. A working day without times gets the standard day.
+fn working_times(node: Node<'_, '_>) -> Vec<Shift> {
+  if value(node, "DayWorking") == Some("0") {
+    return Vec::new();
+  }
+  let time = |node: Node<'_, '_>, name: &str| value(node, name).and_then(|time| NaiveTime::parse_from_str(time, TIME_FORMAT).ok());
+  let shifts: Vec<Shift> = elements(node, &["WorkingTimes", "WorkingTime"])
+    .filter_map(|working_time| Some(Shift::new(time(working_time, "FromTime")?, time(working_time, "ToTime")?)))
+    .collect();
+  if shifts.is_empty() {
+    let at = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or_default();
+    return vec![Shift::new(at(8), at(12)), Shift::new(at(13), at(17))];
+  }
+  shifts
+}
+
+fn add_exceptions(calendar: &mut Calendar, node: Node<'_, '_>, name: String, shifts: Vec<Shift>) {
+  let Some(period) = node.children().find(|child| child.has_tag_name("TimePeriod")) else { return };
+  let date = |name: &str| value(period, name).and_then(|date| NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok());
+  let (Some(from), Some(to)) = (date("FromDate"), date("ToDate")) else { return };
+  if (to - from).num_days() > MAX_EXCEPTION_DAYS {
+    return;
+  }
+  let mut date = from;
+  while date <= to {
+    calendar.set_exception(CalendarException { date, name: name.clone(), shifts: shifts.clone() });
+    date += Duration::days(1);
+  }
+}
+
+fn report_unmapped(importer: &mut Importer<'_>, node: Node<'_, '_>, entity: &str, known: &[&str]) {
+  for child in node.children().filter(Node::is_element) {
+    let name = child.tag_name().name();
+    let is_set = child.has_children();
+    if is_set && !known.contains(&name) {
+      importer.unmapped(format!("{}/{}", entity, name));
+    }
+  }
+}
+
+/// The elements at `path` below `node`, ignoring namespaces.
+fn elements<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> impl Iterator<Item = Node<'a, 'input>> {
+  let mut nodes = vec![node];
+  for name in path {
+    nodes = nodes
+      .into_iter()
+      .flat_map(|node| node.children().filter(|child| child.tag_name().name() == *name))
+      .collect();
+  }
+  nodes.into_iter()
+}
+
+/// The text of the child element `name`.
+fn value<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+  node
+    .children()
+    .find(|child| child.tag_name().name() == name)
+    .and_then(|child| child.text())
+    .map(str::trim)
+}
+
+pub(super) fn write(tasks: &[Task], existing: &Existing<'_>) -> String {
+  let mut xml = Xml::default();
+  xml.line("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>");
+  xml.open(&format!("Project xmlns=\"{}\"", NAMESPACE));
+  xml.element("Name", "millmill");
+
+  xml.open("ExtendedAttributes");
+  for (index, (field, id)) in CUSTOM_FIELDS.iter().enumerate() {
+    xml.open("ExtendedAttribute");
+    xml.element("FieldID", id);
+    xml.element("FieldName", format!("Text{}", index + 1));
+    xml.element("Alias", field.label());
+    xml.close("ExtendedAttribute");
+  }
+  xml.close("ExtendedAttributes");
+
+  xml.open("Calendars");
+  for calendar in existing.calendars {
+    xml.open("Calendar");
+    xml.element("UID", calendar.id);
+    xml.element("Name", &calendar.name);
+    xml.element("IsBaseCalendar", 1);
+    xml.element("BaseCalendarUID", -1);
+    xml.open("WeekDays");
+    for day_type in 1..=7 {
+      xml.open("WeekDay");
+      xml.element("DayType", day_type);
+      write_working_times(&mut xml, &calendar.week[(day_type + 5) % 7]);
+      xml.close("WeekDay");
+    }
+    xml.close("WeekDays");
+    xml.open("Exceptions");
+    for exception in &calendar.exceptions {
+      xml.open("Exception");
+      xml.open("TimePeriod");
+      xml.element("FromDate", exception.date.and_hms_opt(0, 0, 0).unwrap_or_default().format(DATE_TIME_FORMAT));
+      xml.element("ToDate", exception.date.and_hms_opt(23, 59, 0).unwrap_or_default().format(DATE_TIME_FORMAT));
+      xml.close("TimePeriod");
+      xml.element("Occurrences", 1);
+      xml.element("Name", &exception.name);
+      // 1 is a daily exception
+      xml.element("Type", 1);
+      write_working_times(&mut xml, &exception.shifts);
+      xml.close("Exception");
+    }
+    xml.close("Exceptions");
+    xml.close("Calendar");
+  }
+  xml.close("Calendars");
+
+  // MS Project expects every summary task right before its children
+  let rows = outline(tasks, &HashSet::new());
+  let tasks: HashMap<_, _> = tasks.iter().map(|task| (task.id, task)).collect();
+  xml.open("Tasks");
+  for (index, row) in rows.iter().enumerate() {
+    let task = tasks[&row.task_id];
+    xml.open("Task");
+    xml.element("UID", task.id);
+    xml.element("ID", index + 1);
+    xml.element("Name", &task.name);
+    if !task.description.is_empty() {
+      xml.element("Notes", &task.description);
+    }
+    xml.element("Start", task.start_date.format(DATE_TIME_FORMAT));
+    xml.element("Finish", task.end_date.format(DATE_TIME_FORMAT));
+    let minutes = (task.end_date - task.start_date).num_minutes();
+    xml.element("Duration", format!("PT{}H{}M0S", minutes / 60, minutes % 60));
+    xml.element("Milestone", u8::from(task.is_milestone()));
+    xml.element("Summary", u8::from(row.has_children));
+    xml.element("OutlineLevel", row.depth + 1);
+    xml.element("PercentComplete", task.percent_complete.round());
+    if let Some(actual_start) = task.actual_start {
+      xml.element("ActualStart", actual_start.format(DATE_TIME_FORMAT));
+    }
+    if let Some(actual_end) = task.actual_end {
+      xml.element("ActualFinish", actual_end.format(DATE_TIME_FORMAT));
+    }
+    for (field, id) in CUSTOM_FIELDS {
+      let text = field_text(field, task, existing);
+      if !text.is_empty() {
+        xml.open("ExtendedAttribute");
+        xml.element("FieldID", id);
+        xml.element("Value", text);
+        xml.close("ExtendedAttribute");
+      }
+    }
+    for dependency in &task.dependencies {
+      let code = LINK_TYPES.iter().position(|kind| *kind == dependency.kind).unwrap_or(1);
+      xml.open("PredecessorLink");
+      xml.element("PredecessorUID", dependency.predecessor_id);
+      xml.element("Type", code);
+      xml.element("LinkLag", dependency.lag_minutes * 10);
+      // shown in hours
+      xml.element("LagFormat", 5);
+      xml.close("PredecessorLink");
+    }
+    xml.close("Task");
+  }
+  xml.close("Tasks");
+
+  xml.open("Resources");
+  for (index, machine) in existing.machines.iter().enumerate() {
+    xml.open("Resource");
+    xml.element("UID", machine.id);
+    xml.element("ID", index + 1);
+    xml.element("Name", &machine.name);
+    xml.element("Type", 1);
+    xml.element("MaxUnits", format!("{:.2}", machine.capacity));
+    if let Some(calendar_id) = machine.calendar_id {
+      xml.element("CalendarUID", calendar_id);
+    }
+    xml.close("Resource");
+  }
+  xml.close("Resources");
+
+  xml.open("Assignments");
+  for (index, (task, machine_id)) in rows
+    .iter()
+    .filter_map(|row| {
+      let task = tasks[&row.task_id];
+      Some((task, task.machine_id?))
+    })
+    .enumerate()
+  {
+    xml.open("Assignment");
+    xml.element("UID", index + 1);
+    xml.element("TaskUID", task.id);
+    xml.element("ResourceUID", machine_id);
+    xml.element("Units", 1);
+    xml.close("Assignment");
+  }
+  xml.close("Assignments");
+  xml.close("Project");
+  xml.text
+}
+
+fn write_working_times(xml: &mut Xml, shifts: &[Shift]) {
+  xml.element("DayWorking", u8::from(!shifts.is_empty()));
+  if shifts.is_empty() {
+    return;
+  }
+  xml.open("WorkingTimes");
+  for shift in shifts {
+    xml.open("WorkingTime");
+    xml.element("FromTime", shift.start.format(TIME_FORMAT));
+    xml.element("ToTime", shift.end.format(TIME_FORMAT));
+    xml.close("WorkingTime");
+  }
+  xml.close("WorkingTimes");
+}
+
+/// Indented XML text, built one element per line.
+#[derive(Default)]
+struct Xml {
+  text: String,
+  depth: usize,
+}
+
+impl Xml {
+  fn line(&mut self, line: &str) {
+    self.text.push_str(&"  ".repeat(self.depth));
+    self.text.push_str(line);
+    self.text.push('\n');
+  }
+
+  fn open(&mut self, tag: &str) {
+    self.line(&format!("<{}>", tag));
+    self.depth += 1;
+  }
+
+  fn close(&mut self, name: &str) {
+    self.depth -= 1;
+    self.line(&format!("</{}>", name));
+  }
+
+  fn element(&mut self, name: &str, value: impl std::fmt::Display) {
+    let value = value
+      .to_string()
+      .replace('&', "&amp;")
+      .replace('<', "&lt;")
+      .replace('>', "&gt;")
+      .replace('"', "&quot;");
+    self.line(&format!("<{0}>{1}</{0}>", name, value));
+  }
+}
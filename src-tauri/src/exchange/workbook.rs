@@ -0,0 +1,452 @@
+//! Excel workbooks: order lists as sales sends them, and the schedule for the office as a
+//! table and as bars of filled cells.
+
+use std::io::Cursor;
+
+use calamine::{open_workbook_auto_from_rs, Data, Range, Reader};
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+use millmill_domain::{
+  guess_mapping, guess_order_mapping, ColumnMapping, ImportProfile, OrderColumnMapping, Task, TaskField, WorkOrder,
+  WorkOrderField,
+};
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook, Worksheet, XlsxError};
+
+use super::{apply_mapping, field_text, machine_name, parse_date_time, Existing, Importer, Record, DATE_FORMAT};
+
+/// How dates are shown in the table.
+const DATE_TIME_FORMAT: &str = "yyyy-mm-dd hh:mm";
+const HEADER_COLOR: &str = "#EEEEEE";
+const WEEKEND_COLOR: &str = "#DDDDDD";
+/// Columns left of the days on the bar sheet: id, name and machine.
+const BAR_LABEL_COLUMNS: u16 = 3;
+/// Excel has 16384 columns; longer schedules are better cut into several exports.
+const MAX_BAR_DAYS: i64 = 16000;
+
+/// A sheet as its rows of text, the header row first.
+struct Sheet {
+  name: String,
+  headers: Vec<String>,
+  /// The rows below the header with their row number in Excel. Empty rows are left out.
+  rows: Vec<(usize, Vec<String>)>,
+}
+
+impl Sheet {
+  fn new(name: String, range: &Range<Data>) -> Self {
+    let first_row = range.start().map_or(0, |(row, _)| row as usize);
+    let mut rows: Vec<(usize, Vec<String>)> = range
+      .rows()
+      .enumerate()
+      .map(|(index, row)| (first_row + index + 1, row.iter().map(cell_text).collect::<Vec<_>>()))
+      .filter(|(_, row)| row.iter().any(|cell| !cell.is_empty()))
+      .collect();
+    // Lists often start with a title in a single cell; the headers are the first row naming
+    // at least two columns
+    let filled = |row: &[String]| row.iter().filter(|cell| !cell.is_empty()).count();
+    let header_row = rows.iter().position(|(_, row)| filled(row) >= 2).unwrap_or(0);
+    let mut rows = rows.split_off(header_row.min(rows.len())).into_iter();
+    let headers = rows.next().map(|(_, row)| row).unwrap_or_default();
+    Sheet { name, headers, rows: rows.collect() }
+  }
+
+  fn holds_tasks(&self) -> bool {
+    let fields: Vec<_> = guess_mapping(&self.headers).into_iter().filter_map(|column| column.field).collect();
+    fields.contains(&TaskField::Name) && fields.contains(&TaskField::Start)
+  }
+
+  fn holds_work_orders(&self) -> bool {
+    let fields: Vec<_> = guess_order_mapping(&self.headers).into_iter().filter_map(|column| column.field).collect();
+    fields.contains(&WorkOrderField::Number)
+      && (fields.contains(&WorkOrderField::PartName) || fields.contains(&WorkOrderField::Quantity))
+  }
+}
+
+/// Reads the work orders and tasks of a workbook through `profile`, or from the sheets and
+/// columns guessed from their headers. A profile without columns for a sheet has them guessed,
+/// so another sheet can be picked. Returns the names of all sheets and the profile as used.
+pub(super) fn read(
+  bytes: &[u8],
+  profile: Option<&ImportProfile>,
+  importer: &mut Importer<'_>,
+) -> Result<(Vec<String>, ImportProfile), String> {
+  let mut workbook = open_workbook_auto_from_rs(Cursor::new(bytes)).map_err(|e| format!("Cannot open the workbook: {}", e))?;
+  let mut sheets = Vec::new();
+  for name in workbook.sheet_names() {
+    match workbook.worksheet_range(&name) {
+      Ok(range) => sheets.push(Sheet::new(name, &range)),
+      Err(e) => importer.issue(None, format!("Cannot read sheet {}: {}", name, e)),
+    }
+  }
+  let names = sheets.iter().map(|sheet| sheet.name.clone()).collect();
+
+  let mut used = match profile {
+    Some(profile) => profile.clone(),
+    None => {
+      let task_sheet = sheets.iter().find(|sheet| sheet.holds_tasks()).map(|sheet| sheet.name.clone());
+      let work_order_sheet = sheets
+        .iter()
+        .filter(|sheet| Some(&sheet.name) != task_sheet.as_ref())
+        .find(|sheet| sheet.holds_work_orders())
+        .map(|sheet| sheet.name.clone());
+      ImportProfile { work_order_sheet, task_sheet, ..ImportProfile::default() }
+    }
+  };
+  let find = |name: &str| sheets.iter().find(|sheet| sheet.name == name);
+
+  // Orders first, so the tasks can refer to the new ones
+  if let Some(name) = &used.work_order_sheet {
+    match find(name) {
+      Some(sheet) => {
+        let saved = Some(used.work_order_columns.as_slice()).filter(|columns| !columns.is_empty());
+        used.work_order_columns = read_work_orders(sheet, saved, importer);
+      }
+      None => importer.issue(None, format!("The workbook has no sheet {}", name)),
+    }
+  }
+  if let Some(name) = &used.task_sheet {
+    match find(name) {
+      Some(sheet) => {
+        let saved = Some(used.task_columns.as_slice()).filter(|columns| !columns.is_empty());
+        used.task_columns = read_tasks(sheet, saved, importer);
+      }
+      None => importer.issue(None, format!("The workbook has no sheet {}", name)),
+    }
+  }
+  if used.work_order_sheet.is_none() && used.task_sheet.is_none() {
+    importer.issue(None, "No sheet looks like a list of work orders or tasks; choose the sheets to read");
+  }
+  Ok((names, used))
+}
+
+fn read_work_orders(
+  sheet: &Sheet,
+  saved: Option<&[OrderColumnMapping]>,
+  importer: &mut Importer<'_>,
+) -> Vec<OrderColumnMapping> {
+  let mapping = apply_mapping(&sheet.headers, saved);
+  for column in mapping.iter().filter(|column| column.field.is_none() && !column.header.is_empty()) {
+    importer.unmapped(format!("{}: {}", sheet.name, column.header));
+  }
+  if !mapping.iter().any(|column| column.field == Some(WorkOrderField::Number)) {
+    importer.issue(None, format!("No column of {} is read as the order number", sheet.name));
+    return mapping;
+  }
+
+  for (line, row) in &sheet.rows {
+    let mut order =
+      WorkOrder { id: 0, number: String::new(), customer: String::new(), part_name: String::new(), quantity: 0, due_date: None };
+    for (column, value) in mapping.iter().zip(row) {
+      let value = value.trim();
+      let Some(field) = column.field.filter(|_| !value.is_empty()) else { continue };
+      if let Err(message) = set_order_field(&mut order, field, value) {
+        importer.issue(Some(*line), format!("{}: {}", field.label(), message));
+      }
+    }
+    importer.add_work_order(Some(*line), order);
+  }
+  mapping
+}
+
+fn set_order_field(order: &mut WorkOrder, field: WorkOrderField, value: &str) -> Result<(), String> {
+  match field {
+    WorkOrderField::Number => order.number = value.to_string(),
+    WorkOrderField::Customer => order.customer = value.to_string(),
+    WorkOrderField::PartName => order.part_name = value.to_string(),
+    WorkOrderField::Quantity => {
+      order.quantity = value.parse().map_err(|_| format!("\"{}\" is not a number of pieces", value))?
+    }
+    WorkOrderField::DueDate => order.due_date = Some(parse_date_time(value)?),
+  }
+  Ok(())
+}
+
+fn read_tasks(sheet: &Sheet, saved: Option<&[ColumnMapping]>, importer: &mut Importer<'_>) -> Vec<ColumnMapping> {
+  let mapping = apply_mapping(&sheet.headers, saved);
+  for column in mapping.iter().filter(|column| column.field.is_none() && !column.header.is_empty()) {
+    importer.unmapped(format!("{}: {}", sheet.name, column.header));
+  }
+  if !mapping.iter().any(|column| column.field == Some(TaskField::Name)) {
+    importer.issue(None, format!("No column of {} is read as the task name", sheet.name));
+  }
+
+  for (line, row) in &sheet.rows {
+    let mut record = Record::new(Some(*line));
+    for (column, value) in mapping.iter().zip(row) {
+      if let Some(field) = column.field {
+        importer.set_field(&mut record, field, value);
+      }
+    }
+    importer.push(record);
+  }
+  mapping
+}
+
+/// The text of a cell the way the table readers take it. Excel keeps every number as a float,
+/// and dates as numbers shown with a date format.
+fn cell_text(cell: &Data) -> String {
+  match cell {
+    Data::Empty | Data::Error(_) => String::new(),
+    Data::Float(value) if value.fract() == 0.0 && value.abs() < 1e15 => format!("{}", *value as i64),
+    Data::DateTime(date) => date.as_datetime().map(|date| date.format(DATE_FORMAT).to_string()).unwrap_or_default(),
+    cell => cell.to_string().trim().to_string(),
+  }
+}
+
+/// A sheet with the task table, which reads back as it was, and a sheet of bars.
+pub(super) fn write(tasks: &[Task], existing: &Existing<'_>) -> Result<Vec<u8>, String> {
+  let mut workbook = Workbook::new();
+  write_table(workbook.add_worksheet(), tasks, existing).map_err(|e| e.to_string())?;
+  write_bars(workbook.add_worksheet(), tasks, existing)?;
+  workbook.save_to_buffer().map_err(|e| e.to_string())
+}
+
+/// One column per task field, like the CSV export, with dates and numbers as Excel values so
+/// they can be sorted and calculated with.
+fn write_table(sheet: &mut Worksheet, tasks: &[Task], existing: &Existing<'_>) -> Result<(), XlsxError> {
+  sheet.set_name("Schedule")?;
+  let header = Format::new().set_bold().set_background_color(HEADER_COLOR).set_border_bottom(FormatBorder::Thin);
+  let date = Format::new().set_num_format(DATE_TIME_FORMAT);
+  for (column, field) in TaskField::ALL.into_iter().enumerate() {
+    let column = column as u16;
+    sheet.write_string_with_format(0, column, field.label(), &header)?;
+    let width = match field {
+      TaskField::Name | TaskField::Description => 28.0,
+      TaskField::Start | TaskField::End | TaskField::ActualStart | TaskField::ActualEnd => 17.0,
+      _ => 12.0,
+    };
+    sheet.set_column_width(column, width)?;
+  }
+
+  for (index, task) in tasks.iter().enumerate() {
+    let row = index as u32 + 1;
+    for (column, field) in TaskField::ALL.into_iter().enumerate() {
+      let column = column as u16;
+      let dated = match field {
+        TaskField::Start => Some(task.start_date),
+        TaskField::End => Some(task.end_date),
+        TaskField::ActualStart => task.actual_start,
+        TaskField::ActualEnd => task.actual_end,
+        _ => None,
+      };
+      if let Some(dated) = dated {
+        sheet.write_datetime_with_format(row, column, dated, &date)?;
+        continue;
+      }
+      let text = field_text(field, task, existing);
+      let numeric = matches!(
+        field,
+        TaskField::Id | TaskField::Parent | TaskField::PercentComplete | TaskField::Quantity | TaskField::SegmentOf
+      );
+      match text.parse::<f64>() {
+        Ok(number) if numeric => sheet.write_number(row, column, number)?,
+        _ if text.is_empty() => continue,
+        _ => sheet.write_string(row, column, &text)?,
+      };
+    }
+  }
+  sheet.set_freeze_panes(1, 0)?;
+  sheet.autofilter(0, 0, tasks.len() as u32, TaskField::ALL.len() as u16 - 1)?;
+  Ok(())
+}
+
+/// A row per task and a column per day, the days a task runs on filled with its color, for
+/// those who would rather print a spreadsheet than the chart.
+fn write_bars(sheet: &mut Worksheet, tasks: &[Task], existing: &Existing<'_>) -> Result<(), String> {
+  sheet.set_name("Bars").map_err(|e| e.to_string())?;
+  let first = tasks.iter().map(|task| task.start_date.date()).min();
+  let last = tasks.iter().map(last_day).max();
+  let days = match (first, last) {
+    (Some(first), Some(last)) => (last - first).num_days() + 1,
+    _ => 0,
+  };
+  if days > MAX_BAR_DAYS {
+    return Err(format!("The schedule spans {} days, too many for a sheet of bars", days));
+  }
+  let first = first.unwrap_or_default();
+  write_bar_rows(sheet, tasks, existing, first, days).map_err(|e| e.to_string())
+}
+
+fn write_bar_rows(
+  sheet: &mut Worksheet,
+  tasks: &[Task],
+  existing: &Existing<'_>,
+  first: NaiveDate,
+  days: i64,
+) -> Result<(), XlsxError> {
+  let header = Format::new().set_bold().set_background_color(HEADER_COLOR);
+  let day_header = Format::new().set_align(FormatAlign::Center).set_background_color(HEADER_COLOR);
+  let weekend_header = Format::new().set_align(FormatAlign::Center).set_background_color(WEEKEND_COLOR);
+  let milestone = Format::new().set_align(FormatAlign::Center);
+
+  // Two header rows: the month where it starts, and the day of the month
+  for (column, label) in ["ID", "Task", "Machine"].into_iter().enumerate() {
+    sheet.write_string_with_format(1, column as u16, label, &header)?;
+  }
+  sheet.set_column_width(0, 6.0)?;
+  sheet.set_column_width(1, 28.0)?;
+  sheet.set_column_width(2, 14.0)?;
+  for offset in 0..days {
+    let day = first + Duration::days(offset);
+    let column = BAR_LABEL_COLUMNS + offset as u16;
+    if offset == 0 || day.day() == 1 {
+      sheet.write_string_with_format(0, column, day.format("%Y-%m").to_string(), &header)?;
+    }
+    let format = if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) { &weekend_header } else { &day_header };
+    sheet.write_number_with_format(1, column, day.day() as f64, format)?;
+    sheet.set_column_width(column, 3.0)?;
+  }
+
+  for (index, task) in tasks.iter().enumerate() {
+    let row = index as u32 + 2;
+    sheet.write_number(row, 0, task.id as f64)?;
+    sheet.write_string(row, 1, &task.name)?;
+    if let Some(machine) = machine_name(task.machine_id, existing) {
+      sheet.write_string(row, 2, machine)?;
+    }
+    let column = |day: NaiveDate| BAR_LABEL_COLUMNS + (day - first).num_days() as u16;
+    if task.is_milestone() {
+      sheet.write_string_with_format(row, column(task.start_date.date()), "◆", &milestone)?;
+      continue;
+    }
+    let fill = Format::new().set_background_color(task.color.as_str());
+    for day in task.start_date.date().iter_days().take_while(|day| *day <= last_day(task)) {
+      sheet.write_blank(row, column(day), &fill)?;
+    }
+  }
+  sheet.set_freeze_panes(2, BAR_LABEL_COLUMNS)?;
+  Ok(())
+}
+
+/// The last day a task runs on. A task ending at midnight does not run on the day that starts.
+fn last_day(task: &Task) -> NaiveDate {
+  let end = task.end_date;
+  if end > task.start_date && end.time() == NaiveTime::MIN {
+    (end - Duration::days(1)).date()
+  } else {
+    end.date()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::NaiveDateTime;
+  use millmill_domain::Machine;
+
+  use super::*;
+
+  fn at(day: u32, hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2025, 3, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+  }
+
+  fn order(id: usize, number: &str) -> WorkOrder {
+    WorkOrder {
+      id,
+      number: number.to_string(),
+      customer: "ACME".to_string(),
+      part_name: "Shaft".to_string(),
+      quantity: 40,
+      due_date: Some(at(20, 0)),
+    }
+  }
+
+  /// An order list the way sales sends it: a title above the headers and a blank row.
+  fn order_list() -> Vec<u8> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet().set_name("Orders").unwrap();
+    sheet.write_string(0, 0, "Orders for March").unwrap();
+    for (column, header) in ["Order No", "Customer", "Part No", "Qty", "Due"].into_iter().enumerate() {
+      sheet.write_string(2, column as u16, header).unwrap();
+    }
+    let date = Format::new().set_num_format(DATE_TIME_FORMAT);
+    for (row, number) in [(3, "WO-1"), (4, "WO-2"), (6, "wo-2")] {
+      sheet.write_string(row, 0, number).unwrap();
+      sheet.write_string(row, 1, " ACME ").unwrap();
+      sheet.write_string(row, 2, "Shaft").unwrap();
+      sheet.write_number(row, 3, 40.0).unwrap();
+      sheet.write_datetime_with_format(row, 4, at(20, 0), &date).unwrap();
+    }
+    workbook.save_to_buffer().unwrap()
+  }
+
+  #[test]
+  fn cells_read_as_the_table_readers_take_them() {
+    assert_eq!(cell_text(&Data::Empty), "");
+    assert_eq!(cell_text(&Data::Float(40.0)), "40");
+    assert_eq!(cell_text(&Data::Float(2.5)), "2.5");
+    assert_eq!(cell_text(&Data::Int(7)), "7");
+    assert_eq!(cell_text(&Data::String(" Lathe ".to_string())), "Lathe");
+    assert_eq!(cell_text(&Data::Bool(true)), "true");
+  }
+
+  #[test]
+  fn headers_are_found_below_a_title() {
+    let mut range = Range::new((0, 0), (4, 2));
+    range.set_value((0, 0), Data::String("Orders for March".to_string()));
+    range.set_value((2, 0), Data::String("Order No".to_string()));
+    range.set_value((2, 2), Data::String("Qty".to_string()));
+    range.set_value((3, 0), Data::String("WO-1".to_string()));
+    range.set_value((3, 2), Data::Float(40.0));
+    range.set_value((4, 0), Data::String("WO-2".to_string()));
+    let sheet = Sheet::new("Orders".to_string(), &range);
+    assert_eq!(sheet.headers, vec!["Order No", "", "Qty"]);
+    // Rows keep the numbers Excel shows
+    let texts = |cells: [&str; 3]| cells.map(str::to_string).to_vec();
+    assert_eq!(sheet.rows, vec![(4, texts(["WO-1", "", "40"])), (5, texts(["WO-2", "", ""]))]);
+  }
+
+  #[test]
+  fn orders_already_known_are_left_out() {
+    let work_orders = vec![order(3, "WO-1")];
+    let existing = Existing { machines: &[], calendars: &[], work_orders: &work_orders };
+    let mut importer = Importer::new(&existing);
+    let (sheets, profile) = read(&order_list(), None, &mut importer).unwrap();
+    assert_eq!(sheets, vec!["Orders"]);
+    assert_eq!(profile.work_order_sheet.as_deref(), Some("Orders"));
+    assert_eq!(profile.task_sheet, None);
+    assert_eq!(importer.work_orders, vec![order(4, "WO-2")]);
+    let messages: Vec<(Option<usize>, &str)> =
+      importer.issues.iter().map(|issue| (issue.line, issue.message.as_str())).collect();
+    assert_eq!(
+      messages,
+      vec![
+        (Some(4), "Work order WO-1 already exists and is left out"),
+        (Some(7), "Work order wo-2 already exists and is left out"),
+      ]
+    );
+  }
+
+  #[test]
+  fn the_table_reads_back_as_written() {
+    let machines = vec![Machine { id: 1, name: "Lathe".to_string(), ..Default::default() }];
+    let existing = Existing { machines: &machines, calendars: &[], work_orders: &[] };
+    let tasks = vec![
+      Task {
+        id: 1,
+        name: "Turning".to_string(),
+        start_date: at(3, 8),
+        end_date: at(4, 12),
+        color: "#3F51B5".to_string(),
+        machine_id: Some(1),
+        percent_complete: 12.5,
+        quantity: 40,
+        ..Default::default()
+      },
+      Task {
+        id: 2,
+        name: "Milling".to_string(),
+        start_date: at(4, 13),
+        end_date: at(4, 17),
+        color: "#4CAF50".to_string(),
+        ..Default::default()
+      },
+    ];
+    let bytes = write(&tasks, &existing).unwrap();
+    let mut importer = Importer::new(&existing);
+    let (sheets, profile) = read(&bytes, None, &mut importer).unwrap();
+    assert_eq!(sheets, vec!["Schedule", "Bars"]);
+    assert_eq!(profile.task_sheet.as_deref(), Some("Schedule"));
+    assert_eq!(profile.work_order_sheet, None);
+    let preview = importer.finish(profile.task_columns);
+    assert_eq!(preview.issues, vec![]);
+    assert_eq!(preview.tasks, tasks);
+  }
+}
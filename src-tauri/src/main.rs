@@ -4,70 +4,356 @@
   windows_subsystem = "windows"
 )]
 
-use serde::{Deserialize, Serialize};
-use tauri::State;
+mod chart;
+mod exchange;
+mod storage;
+
+use millmill_domain::{
+  Baseline, BaselineId, Calendar, CalendarId, ChartExport, ChartFormat, ColumnMapping, ExchangeFormat, ImportPreview,
+  ImportProfile, ImportProfileId, Machine, MachineId, Orientation, PaperSize, ScheduleOutcome, SchedulerOptions, Task,
+  TaskId, WorkOrder, WorkOrderId, WorkbookPreview,
+};
+use tauri::ipc::Response;
+use tauri::{Manager, State};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::fs::File;
-use std::io::{Read, Write};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Task {
-  name: String,
-  description: String,
-  due_date: String,
-  status: String,
-}
+use chart::Plan;
+use exchange::Existing;
+use storage::{Storage, DATABASE_FILE, LEGACY_TASKS_FILE};
 
-#[derive(Debug, Default)]
 struct AppState {
-  tasks: Mutex<Vec<Task>>,
-}
-
-#[tauri::command]
-fn add_task(
-  state: State<'_, AppState>,
-  name: String,
-  description: String,
-  due_date: String,
-  status: String,
-) -> Result<(), String> {
-  let mut tasks = state.tasks.lock().unwrap();
-  let new_task = Task {
-    name,
-    description,
-    due_date,
-    status,
-  };
-  tasks.push(new_task);
-  println!("Tasks: {:?}", tasks);
-  Ok(())
+  storage: Mutex<Storage>,
+}
+
+/// Returns `None` when no tasks have been saved yet.
+#[tauri::command]
+fn load_tasks(state: State<'_, AppState>) -> Result<Option<Vec<Task>>, String> {
+  let storage = state.storage.lock().unwrap();
+  storage.load_tasks().map_err(|e| e.to_string())
+}
+
+/// Replaces all stored tasks in a single transaction.
+#[tauri::command]
+fn save_tasks(state: State<'_, AppState>, tasks: Vec<Task>) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.replace_tasks(&tasks).map_err(|e| e.to_string())
+}
+
+/// Reads an Excel workbook the window has opened, without changing anything, so the result
+/// can be reviewed first. Without a profile the sheets and columns are guessed.
+#[tauri::command(rename_all = "snake_case")]
+fn preview_workbook(
+  bytes: Vec<u8>,
+  profile: Option<ImportProfile>,
+  machines: Vec<Machine>,
+  calendars: Vec<Calendar>,
+  work_orders: Vec<WorkOrder>,
+) -> Result<WorkbookPreview, String> {
+  let existing = Existing { machines: &machines, calendars: &calendars, work_orders: &work_orders };
+  exchange::import_workbook(&bytes, profile.as_ref(), &existing)
+}
+
+/// Writes the plan as the window holds it to an Excel workbook.
+#[tauri::command(rename_all = "snake_case")]
+fn export_workbook(
+  tasks: Vec<Task>,
+  machines: Vec<Machine>,
+  calendars: Vec<Calendar>,
+  work_orders: Vec<WorkOrder>,
+) -> Result<Response, String> {
+  let existing = Existing { machines: &machines, calendars: &calendars, work_orders: &work_orders };
+  exchange::export_workbook(&tasks, &existing).map(Response::new)
+}
+
+#[tauri::command]
+fn load_import_profiles(state: State<'_, AppState>) -> Result<Vec<ImportProfile>, String> {
+  let storage = state.storage.lock().unwrap();
+  storage.list_import_profiles().map_err(|e| e.to_string())
+}
+
+/// Replaces a profile of the same name. Returns the profile as stored.
+#[tauri::command]
+fn save_import_profile(state: State<'_, AppState>, profile: ImportProfile) -> Result<ImportProfile, String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.save_import_profile(&profile).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_import_profile(state: State<'_, AppState>, id: ImportProfileId) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.delete_import_profile(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn create_task(state: State<'_, AppState>, task: Task) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.create_task(&task).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn update_task(state: State<'_, AppState>, task: Task) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.update_task(&task).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_task(state: State<'_, AppState>, id: TaskId) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.delete_task(id).map_err(|e| e.to_string())
+}
+
+/// Returns `None` when no machines have been saved yet.
+#[tauri::command]
+fn load_machines(state: State<'_, AppState>) -> Result<Option<Vec<Machine>>, String> {
+  let storage = state.storage.lock().unwrap();
+  storage.load_machines().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn create_machine(state: State<'_, AppState>, machine: Machine) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.create_machine(&machine).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn update_machine(state: State<'_, AppState>, machine: Machine) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.update_machine(&machine).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_machine(state: State<'_, AppState>, id: MachineId) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.delete_machine(id).map_err(|e| e.to_string())
 }
 
+/// Returns `None` when no calendars have been saved yet.
 #[tauri::command]
-fn save_tasks(state: State<'_, AppState>) -> Result<(), String> {
-  let tasks = state.tasks.lock().unwrap();
-  let json = serde_json::to_string(&*tasks).map_err(|e| e.to_string())?;
-  let mut file = File::create("tasks.json").map_err(|e| e.to_string())?;
-  file.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
-  Ok(())
+fn load_calendars(state: State<'_, AppState>) -> Result<Option<Vec<Calendar>>, String> {
+  let storage = state.storage.lock().unwrap();
+  storage.load_calendars().map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn load_tasks(state: State<'_, AppState>) -> Result<Vec<Task>, String> {
-  let mut file = File::open("tasks.json").map_err(|e| e.to_string())?;
-  let mut json = String::new();
-  file.read_to_string(&mut json).map_err(|e| e.to_string())?;
-  let tasks: Vec<Task> = serde_json::from_str(&json).map_err(|e| e.to_string())?;
-  let mut app_state_tasks = state.tasks.lock().unwrap();
-  *app_state_tasks = tasks.clone();
-  Ok(tasks)
+fn create_calendar(state: State<'_, AppState>, calendar: Calendar) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.create_calendar(&calendar).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn update_calendar(state: State<'_, AppState>, calendar: Calendar) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.update_calendar(&calendar).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_calendar(state: State<'_, AppState>, id: CalendarId) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.delete_calendar(id).map_err(|e| e.to_string())
+}
+
+/// Returns `None` when no work orders have been saved yet.
+#[tauri::command]
+fn load_work_orders(state: State<'_, AppState>) -> Result<Option<Vec<WorkOrder>>, String> {
+  let storage = state.storage.lock().unwrap();
+  storage.load_work_orders().map_err(|e| e.to_string())
+}
+
+// The frontend names the argument after the command suffix, `work_order`
+#[tauri::command(rename_all = "snake_case")]
+fn create_work_order(state: State<'_, AppState>, work_order: WorkOrder) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.create_work_order(&work_order).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn update_work_order(state: State<'_, AppState>, work_order: WorkOrder) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.update_work_order(&work_order).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_work_order(state: State<'_, AppState>, id: WorkOrderId) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.delete_work_order(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn load_baselines(state: State<'_, AppState>) -> Result<Vec<Baseline>, String> {
+  let storage = state.storage.lock().unwrap();
+  storage.list_baselines().map_err(|e| e.to_string())
+}
+
+/// Freezes the dates of `tasks` under `name`. The window sends its plan, so an edit still being
+/// saved is part of the baseline.
+#[tauri::command]
+fn create_baseline(state: State<'_, AppState>, name: String, tasks: Vec<Task>) -> Result<Baseline, String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage
+    .create_baseline(&name, chrono::Local::now().naive_local(), &tasks)
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_baseline(state: State<'_, AppState>, id: BaselineId) -> Result<(), String> {
+  let mut storage = state.storage.lock().unwrap();
+  storage.delete_baseline(id).map_err(|e| e.to_string())
+}
+
+/// Places the open work order operations on their machines. Works on the plan as the window
+/// holds it, unsaved edits included, and leaves saving the result to the window.
+#[tauri::command(rename_all = "snake_case")]
+fn auto_schedule(
+  tasks: Vec<Task>,
+  machines: Vec<Machine>,
+  calendars: Vec<Calendar>,
+  work_orders: Vec<WorkOrder>,
+  options: SchedulerOptions,
+) -> Result<ScheduleOutcome, String> {
+  millmill_domain::auto_schedule(&tasks, &machines, &calendars, &work_orders, &options).map_err(|e| e.to_string())
+}
+
+/// Reads a file the window has opened, without changing anything, so the result can be
+/// reviewed first. Machines, calendars and work orders in the file are matched by name
+/// against those of the plan.
+#[tauri::command(rename_all = "snake_case")]
+fn preview_import(
+  format: ExchangeFormat,
+  text: String,
+  mapping: Option<Vec<ColumnMapping>>,
+  machines: Vec<Machine>,
+  calendars: Vec<Calendar>,
+  work_orders: Vec<WorkOrder>,
+) -> ImportPreview {
+  let existing = Existing { machines: &machines, calendars: &calendars, work_orders: &work_orders };
+  exchange::import(format, &text, mapping.as_deref(), &existing)
+}
+
+/// Writes the plan as the window holds it; the window saves the text where the user wants it.
+#[tauri::command(rename_all = "snake_case")]
+fn export_schedule(
+  format: ExchangeFormat,
+  tasks: Vec<Task>,
+  machines: Vec<Machine>,
+  calendars: Vec<Calendar>,
+  work_orders: Vec<WorkOrder>,
+) -> Result<String, String> {
+  let existing = Existing { machines: &machines, calendars: &calendars, work_orders: &work_orders };
+  exchange::export(format, &tasks, &existing)
+}
+
+/// Draws the chart of the plan as the window holds it; the window saves the file where the
+/// user wants it.
+#[tauri::command(rename_all = "snake_case")]
+fn export_chart(
+  tasks: Vec<Task>,
+  machines: Vec<Machine>,
+  calendars: Vec<Calendar>,
+  work_orders: Vec<WorkOrder>,
+  options: ChartExport,
+) -> Result<Response, String> {
+  let plan = Plan { tasks: &tasks, machines: &machines, calendars: &calendars, work_orders: &work_orders };
+  chart::render(&plan, &options).map(Response::new)
+}
+
+/// `millmill export-chart <database> <output> <from> <to> [a4] [portrait] [outline]` draws the
+/// chart of a saved plan without opening the window, e.g. to print the board from a scheduled
+/// job. The format follows the extension of `output`.
+fn export_chart_headless(args: &[String]) -> Result<(), String> {
+  let [database, output, from, to, flags @ ..] = args else {
+    return Err("usage: millmill export-chart <database> <output> <from> <to> [a4] [portrait] [outline]".into());
+  };
+  let date = |text: &str| text.parse().map_err(|_| format!("Not a date (YYYY-MM-DD): {}", text));
+  let mut options = ChartExport::new(date(from)?, date(to)?);
+  options.format = ChartFormat::from_file_name(output).ok_or_else(|| format!("Unknown chart format: {}", output))?;
+  for flag in flags {
+    match flag.as_str() {
+      "a4" => options.paper = PaperSize::A4,
+      "portrait" => options.orientation = Orientation::Portrait,
+      "outline" => options.outline = true,
+      _ => return Err(format!("Unknown option: {}", flag)),
+    }
+  }
+
+  // A mistyped path must not leave an empty database behind
+  let storage = Storage::open_read_only(Path::new(database)).map_err(|e| e.to_string())?;
+  let tasks = storage.load_tasks().map_err(|e| e.to_string())?.unwrap_or_default();
+  let machines = storage.load_machines().map_err(|e| e.to_string())?.unwrap_or_default();
+  let calendars = storage.load_calendars().map_err(|e| e.to_string())?.unwrap_or_default();
+  let work_orders = storage.load_work_orders().map_err(|e| e.to_string())?.unwrap_or_default();
+  let plan = Plan { tasks: &tasks, machines: &machines, calendars: &calendars, work_orders: &work_orders };
+  let bytes = chart::render(&plan, &options)?;
+  std::fs::write(output, bytes).map_err(|e| format!("Failed to write {}: {}", output, e))
+}
+
+/// Places where earlier versions may have written `tasks.json`: the working directory the
+/// binary was launched from, and the app data directory.
+fn legacy_tasks_files(data_dir: &Path) -> Vec<PathBuf> {
+  let mut candidates = vec![data_dir.join(LEGACY_TASKS_FILE)];
+  if let Ok(current_dir) = std::env::current_dir() {
+    candidates.push(current_dir.join(LEGACY_TASKS_FILE));
+  }
+  candidates
+}
 
 fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.first().map(String::as_str) == Some("export-chart") {
+    if let Err(e) = export_chart_headless(&args[1..]) {
+      eprintln!("{}", e);
+      std::process::exit(1);
+    }
+    return;
+  }
+
   tauri::Builder::default()
-    .manage(AppState::default())
-    .invoke_handler(tauri::generate_handler![add_task, save_tasks, load_tasks])
+    .setup(|app| {
+      let data_dir = app.path().app_data_dir()?;
+      std::fs::create_dir_all(&data_dir)?;
+      let mut storage = Storage::open(&data_dir.join(DATABASE_FILE))?;
+      for path in legacy_tasks_files(&data_dir) {
+        match storage.import_legacy_tasks(&path) {
+          Ok(0) => {}
+          Ok(count) => println!("Imported {} tasks from {}", count, path.display()),
+          // Leave the file in place so nothing is lost; the import is retried on next launch
+          Err(e) => eprintln!("Failed to import {}: {}", path.display(), e),
+        }
+      }
+      app.manage(AppState { storage: Mutex::new(storage) });
+      Ok(())
+    })
+    .invoke_handler(tauri::generate_handler![
+      load_tasks,
+      save_tasks,
+      preview_workbook,
+      export_workbook,
+      load_import_profiles,
+      save_import_profile,
+      delete_import_profile,
+      create_task,
+      update_task,
+      delete_task,
+      load_machines,
+      create_machine,
+      update_machine,
+      delete_machine,
+      load_calendars,
+      create_calendar,
+      update_calendar,
+      delete_calendar,
+      load_work_orders,
+      create_work_order,
+      update_work_order,
+      delete_work_order,
+      load_baselines,
+      create_baseline,
+      delete_baseline,
+      auto_schedule,
+      preview_import,
+      export_schedule,
+      export_chart
+    ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
@@ -0,0 +1,349 @@
+//! Embedded SQLite store for everything the app persists.
+
+mod baselines;
+mod calendars;
+mod import_profiles;
+mod legacy;
+mod machines;
+mod migrations;
+mod tasks;
+mod work_orders;
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags};
+
+pub const DATABASE_FILE: &str = "millmill.sqlite3";
+pub const LEGACY_TASKS_FILE: &str = "tasks.json";
+
+#[derive(Debug)]
+pub enum StorageError {
+  Sqlite(rusqlite::Error),
+  Io(std::io::Error),
+  Json(serde_json::Error),
+  Invalid(String),
+  NotFound { entity: &'static str, id: usize },
+  Legacy(String),
+}
+
+impl fmt::Display for StorageError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      StorageError::Sqlite(e) => write!(f, "database error: {}", e),
+      StorageError::Io(e) => write!(f, "file error: {}", e),
+      StorageError::Json(e) => write!(f, "JSON error: {}", e),
+      StorageError::Invalid(message) => write!(f, "{}", message),
+      StorageError::NotFound { entity, id } => write!(f, "{} {} does not exist", entity, id),
+      StorageError::Legacy(message) => write!(f, "cannot import legacy tasks: {}", message),
+    }
+  }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<rusqlite::Error> for StorageError {
+  fn from(e: rusqlite::Error) -> Self {
+    StorageError::Sqlite(e)
+  }
+}
+
+impl From<std::io::Error> for StorageError {
+  fn from(e: std::io::Error) -> Self {
+    StorageError::Io(e)
+  }
+}
+
+impl From<serde_json::Error> for StorageError {
+  fn from(e: serde_json::Error) -> Self {
+    StorageError::Json(e)
+  }
+}
+
+/// Entity operations live next to their tables in the submodules (`tasks`, `machines`, ...).
+pub struct Storage {
+  conn: Connection,
+  /// Whether the database file was created by this `open` call.
+  is_new: bool,
+}
+
+impl Storage {
+  /// Opens (or creates) the database at `path` and brings its schema up to date.
+  pub fn open(path: &Path) -> Result<Self, StorageError> {
+    let is_new = !path.exists();
+    let conn = Connection::open(path)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    Storage::migrated(conn, is_new)
+  }
+
+  /// Opens an existing database without writing to it, e.g. to print a plan from a script.
+  /// Fails when there is no database at `path` or when its schema is older than this version,
+  /// since upgrading it is left to the app.
+  pub fn open_read_only(path: &Path) -> Result<Self, StorageError> {
+    if !path.exists() {
+      return Err(StorageError::Invalid(format!("There is no database at {}", path.display())));
+    }
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)?;
+    if migrations::schema_version(&conn)? < migrations::MIGRATIONS.len() {
+      let message = format!("The database at {} is from an older version; open it in the app once to upgrade it", path.display());
+      return Err(StorageError::Invalid(message));
+    }
+    Ok(Storage { conn, is_new: false })
+  }
+
+  /// A fresh database that only lives as long as the returned store.
+  #[cfg(test)]
+  pub fn open_in_memory() -> Result<Self, StorageError> {
+    Storage::migrated(Connection::open_in_memory()?, true)
+  }
+
+  fn migrated(mut conn: Connection, is_new: bool) -> Result<Self, StorageError> {
+    conn.pragma_update(None, "foreign_keys", true)?;
+    migrations::migrate(&mut conn)?;
+    Ok(Storage { conn, is_new })
+  }
+
+  /// Imports a `tasks.json` written by earlier versions, if one exists and the store is
+  /// still empty. The file is renamed afterwards so the import only ever runs once.
+  /// Returns the number of imported tasks.
+  pub fn import_legacy_tasks(&mut self, path: &Path) -> Result<usize, StorageError> {
+    if !path.exists() || self.count_tasks()? > 0 {
+      return Ok(0);
+    }
+    let tasks = legacy::read_tasks(path)?;
+    self.replace_tasks(&tasks)?;
+    fs::rename(path, path.with_extension("json.migrated"))?;
+    Ok(tasks.len())
+  }
+
+  /// `None` when the database was created on this launch and `records` is still empty,
+  /// so the frontend can show its samples instead.
+  fn unless_fresh<T>(&self, records: Vec<T>) -> Option<Vec<T>> {
+    if self.is_new && records.is_empty() {
+      return None;
+    }
+    Some(records)
+  }
+}
+
+fn not_found(entity: &'static str, id: usize) -> StorageError {
+  StorageError::NotFound { entity, id }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{Duration, NaiveDate, NaiveDateTime};
+  use millmill_domain::{
+    ColumnMapping, Dependency, DependencyKind, ImportProfile, Machine, OrderColumnMapping, Task, TaskField, TaskKind,
+    TaskStatus, WorkOrder, WorkOrderField,
+  };
+
+  use super::*;
+
+  fn at(day: u32, hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2025, 3, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+  }
+
+  fn task(id: usize, start: NaiveDateTime, end: NaiveDateTime) -> Task {
+    Task {
+      id,
+      name: format!("Op {}", id),
+      start_date: start,
+      end_date: end,
+      color: "#4CAF50".to_string(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn migrates_an_empty_database_to_the_latest_schema() {
+    let storage = Storage::open_in_memory().unwrap();
+    assert_eq!(migrations::schema_version(&storage.conn).unwrap(), migrations::MIGRATIONS.len());
+    // Nothing written yet: the frontend shows its samples
+    assert_eq!(storage.load_tasks().unwrap(), None);
+    assert_eq!(storage.load_machines().unwrap(), None);
+    assert_eq!(storage.load_work_orders().unwrap(), None);
+    assert_eq!(storage.load_calendars().unwrap(), None);
+  }
+
+  #[test]
+  fn reads_only_existing_databases_without_changing_them() {
+    let path = std::env::temp_dir().join(format!("millmill-read-only-{}.sqlite3", std::process::id()));
+    let _ = fs::remove_file(&path);
+    assert!(Storage::open_read_only(&path).is_err());
+    assert!(!path.exists());
+
+    Storage::open(&path).unwrap().create_task(&task(1, at(3, 8), at(3, 12))).unwrap();
+    let mut storage = Storage::open_read_only(&path).unwrap();
+    assert_eq!(storage.load_tasks().unwrap().map(|tasks| tasks.len()), Some(1));
+    assert!(storage.delete_task(1).is_err());
+    drop(storage);
+    for suffix in ["", "-wal", "-shm"] {
+      let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+  }
+
+  #[test]
+  fn stores_updates_and_deletes_records() {
+    let mut storage = Storage::open_in_memory().unwrap();
+    let machine = Machine { id: 1, name: "Lathe".to_string(), capacity: 2, ..Default::default() };
+    storage.create_machine(&machine).unwrap();
+    let order = WorkOrder {
+      id: 1,
+      number: "WO-1".to_string(),
+      customer: "ACME".to_string(),
+      part_name: "Shaft".to_string(),
+      quantity: 40,
+      due_date: Some(at(7, 17)),
+    };
+    storage.create_work_order(&order).unwrap();
+    let first = Task { machine_id: Some(1), work_order_id: Some(1), ..task(1, at(3, 8), at(3, 12)) };
+    let second = Task {
+      dependencies: vec![Dependency { predecessor_id: 1, kind: DependencyKind::FinishToStart, lag_minutes: 30 }],
+      ..task(2, at(3, 13), at(3, 17))
+    };
+    storage.create_task(&first).unwrap();
+    storage.create_task(&second).unwrap();
+    assert_eq!(storage.list_tasks().unwrap(), vec![first.clone(), second.clone()]);
+
+    let moved = Task { start_date: at(4, 8), end_date: at(4, 12), percent_complete: 50.0, ..first.clone() };
+    storage.update_task(&moved).unwrap();
+    let renamed = Machine { name: "CNC lathe".to_string(), ..machine };
+    storage.update_machine(&renamed).unwrap();
+    assert_eq!(storage.list_tasks().unwrap(), vec![moved, second.clone()]);
+    assert_eq!(storage.list_machines().unwrap(), vec![renamed]);
+
+    // Deleting the order and the machine unlinks their tasks; deleting a task drops the links to it
+    storage.delete_work_order(1).unwrap();
+    storage.delete_machine(1).unwrap();
+    storage.delete_task(1).unwrap();
+    assert_eq!(storage.list_tasks().unwrap(), vec![Task { dependencies: Vec::new(), ..second }]);
+    assert_eq!(storage.list_work_orders().unwrap(), Vec::new());
+    assert!(matches!(storage.delete_task(1), Err(StorageError::NotFound { entity: "task", id: 1 })));
+  }
+
+  #[test]
+  fn import_profiles_of_the_same_name_are_replaced() {
+    let mut storage = Storage::open_in_memory().unwrap();
+    let column = |header: &str, field| OrderColumnMapping { header: header.to_string(), field };
+    let profile = ImportProfile {
+      id: 0,
+      name: " ACME ".to_string(),
+      work_order_sheet: Some("Orders".to_string()),
+      work_order_columns: vec![column("Order No", Some(WorkOrderField::Number)), column("Note", None)],
+      task_sheet: None,
+      task_columns: Vec::new(),
+    };
+    let saved = storage.save_import_profile(&profile).unwrap();
+    assert_eq!(saved, ImportProfile { id: 1, name: "ACME".to_string(), ..profile.clone() });
+
+    let changed = ImportProfile {
+      work_order_columns: vec![column("Order No", Some(WorkOrderField::Number))],
+      task_sheet: Some("Plan".to_string()),
+      task_columns: vec![ColumnMapping { header: "Op".to_string(), field: Some(TaskField::Name) }],
+      ..profile
+    };
+    let replaced = storage.save_import_profile(&changed).unwrap();
+    assert_eq!(replaced.id, 1);
+    assert_eq!(storage.list_import_profiles().unwrap(), vec![replaced]);
+
+    storage.delete_import_profile(1).unwrap();
+    assert_eq!(storage.list_import_profiles().unwrap(), Vec::new());
+    assert!(storage.save_import_profile(&ImportProfile::default()).is_err());
+  }
+
+  /// A fresh directory for the files of one test.
+  fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("millmill-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn legacy_tasks_of_both_shapes_are_imported_once() {
+    let dir = scratch_dir("legacy-import");
+    let path = dir.join(LEGACY_TASKS_FILE);
+    let current = Task { description: "Rough cut".to_string(), ..task(4, at(3, 8), at(3, 12)) };
+    let json = format!(
+      r#"[{}, {{"name": "Ship", "description": "To ACME", "due_date": "2025-03-07", "status": "Done"}},
+        {{"name": "Inspect", "due_date": "2025-03-06T15:30"}}]"#,
+      serde_json::to_string(&current).unwrap()
+    );
+    fs::write(&path, json).unwrap();
+
+    let mut storage = Storage::open_in_memory().unwrap();
+    assert_eq!(storage.import_legacy_tasks(&path).unwrap(), 3);
+    let milestone = |id, name: &str, due: NaiveDateTime| Task {
+      id,
+      name: name.to_string(),
+      start_date: due,
+      end_date: due,
+      color: "#009688".to_string(),
+      kind: TaskKind::Milestone,
+      ..Default::default()
+    };
+    assert_eq!(
+      storage.list_tasks().unwrap(),
+      vec![
+        current,
+        Task {
+          description: "To ACME".to_string(),
+          status: TaskStatus::Done,
+          ..milestone(5, "Ship", at(7, 0))
+        },
+        milestone(6, "Inspect", at(6, 15) + Duration::minutes(30)),
+      ]
+    );
+    // The file is kept under another name, so the next launch does not import it again
+    assert!(!path.exists());
+    assert!(path.with_extension("json.migrated").exists());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn legacy_tasks_are_not_imported_over_stored_ones() {
+    let dir = scratch_dir("legacy-skip");
+    let path = dir.join(LEGACY_TASKS_FILE);
+    fs::write(&path, r#"[{"name": "Ship", "due_date": "2025-03-07"}]"#).unwrap();
+    let mut storage = Storage::open_in_memory().unwrap();
+    let stored = task(1, at(3, 8), at(3, 12));
+    storage.create_task(&stored).unwrap();
+
+    assert_eq!(storage.import_legacy_tasks(&path).unwrap(), 0);
+    assert_eq!(storage.list_tasks().unwrap(), vec![stored]);
+    assert!(path.exists());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn unreadable_legacy_files_leave_the_store_and_the_file_alone() {
+    let dir = scratch_dir("legacy-malformed");
+    let path = dir.join(LEGACY_TASKS_FILE);
+    let mut storage = Storage::open_in_memory().unwrap();
+
+    fs::write(&path, r#"[{"name": "Ship", "due_date": "#).unwrap();
+    assert!(matches!(storage.import_legacy_tasks(&path), Err(StorageError::Json(_))));
+    // One bad record stops the whole file rather than importing part of it
+    fs::write(&path, r#"[{"name": "Ship", "due_date": "2025-03-07"}, {"name": "Inspect", "due_date": "soon"}]"#).unwrap();
+    assert!(matches!(storage.import_legacy_tasks(&path), Err(StorageError::Legacy(_))));
+
+    assert_eq!(storage.list_tasks().unwrap(), Vec::new());
+    assert!(path.exists());
+    assert!(!path.with_extension("json.migrated").exists());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn baselines_freeze_the_given_tasks_rather_than_the_stored_ones() {
+    let mut storage = Storage::open_in_memory().unwrap();
+    storage.create_task(&task(1, at(3, 8), at(3, 12))).unwrap();
+    // the move to the afternoon is not saved yet
+    let shown = vec![task(1, at(3, 13), at(3, 17)), task(2, at(4, 8), at(4, 12))];
+    let baseline = storage.create_baseline(" Plan A ", at(1, 9), &shown).unwrap();
+    assert_eq!(baseline.name, "Plan A");
+    assert_eq!(baseline.task(1).map(|task| task.start_date), Some(at(3, 13)));
+    assert_eq!(storage.list_baselines().unwrap(), vec![baseline]);
+  }
+}
@@ -0,0 +1,83 @@
+use chrono::NaiveDateTime;
+use millmill_domain::{Baseline, BaselineId, BaselineTask, Task, TaskId, Validate};
+use rusqlite::params;
+
+use super::{not_found, Storage, StorageError};
+
+impl Storage {
+  /// Lists the baselines, oldest first, each with its task dates.
+  pub fn list_baselines(&self) -> Result<Vec<Baseline>, StorageError> {
+    let mut statement = self
+      .conn
+      .prepare("SELECT id, name, created_at FROM baselines ORDER BY created_at, id")?;
+    let mut baselines = statement
+      .query_map([], |row| {
+        Ok(Baseline {
+          id: row.get::<_, i64>(0)? as BaselineId,
+          name: row.get(1)?,
+          created_at: row.get(2)?,
+          tasks: Vec::new(),
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = self.conn.prepare(
+      "SELECT baseline_id, task_id, name, start_date, end_date
+       FROM baseline_tasks ORDER BY baseline_id, task_id",
+    )?;
+    let tasks = statement.query_map([], |row| {
+      Ok((
+        row.get::<_, i64>(0)? as BaselineId,
+        BaselineTask {
+          task_id: row.get::<_, i64>(1)? as TaskId,
+          name: row.get(2)?,
+          start_date: row.get(3)?,
+          end_date: row.get(4)?,
+        },
+      ))
+    })?;
+    for task in tasks {
+      let (baseline_id, task) = task?;
+      if let Some(baseline) = baselines.iter_mut().find(|baseline| baseline.id == baseline_id) {
+        baseline.tasks.push(task);
+      }
+    }
+    Ok(baselines)
+  }
+
+  /// Freezes the dates of `tasks` under `name` and returns the new baseline.
+  pub fn create_baseline(
+    &mut self,
+    name: &str,
+    created_at: NaiveDateTime,
+    tasks: &[Task],
+  ) -> Result<Baseline, StorageError> {
+    let tx = self.conn.transaction()?;
+    let id: i64 = tx.query_row("SELECT COALESCE(MAX(id), 0) + 1 FROM baselines", [], |row| row.get(0))?;
+    let baseline = Baseline::capture(id as BaselineId, name, created_at, tasks);
+    baseline
+      .validate()
+      .map_err(|e| StorageError::Invalid(format!("baseline: {}", e)))?;
+    tx.execute(
+      "INSERT INTO baselines (id, name, created_at) VALUES (?1, ?2, ?3)",
+      params![id, baseline.name, baseline.created_at],
+    )?;
+    for task in &baseline.tasks {
+      tx.execute(
+        "INSERT INTO baseline_tasks (baseline_id, task_id, name, start_date, end_date)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, task.task_id as i64, task.name, task.start_date, task.end_date],
+      )?;
+    }
+    tx.commit()?;
+    Ok(baseline)
+  }
+
+  pub fn delete_baseline(&mut self, id: BaselineId) -> Result<(), StorageError> {
+    let changed = self.conn.execute("DELETE FROM baselines WHERE id = ?1", params![id as i64])?;
+    if changed == 0 {
+      return Err(not_found("baseline", id));
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,154 @@
+use chrono::NaiveDate;
+use millmill_domain::{Calendar, CalendarException, CalendarId, Shift, Validate};
+use rusqlite::{params, Transaction};
+
+use super::{not_found, Storage, StorageError};
+
+impl Storage {
+  /// Returns `None` when the database was created on this launch and no calendar has been
+  /// written to it yet.
+  pub fn load_calendars(&self) -> Result<Option<Vec<Calendar>>, StorageError> {
+    Ok(self.unless_fresh(self.list_calendars()?))
+  }
+
+  /// Lists the calendars with their weekly shifts and exceptions.
+  pub fn list_calendars(&self) -> Result<Vec<Calendar>, StorageError> {
+    let mut statement = self.conn.prepare("SELECT id, name FROM calendars ORDER BY id")?;
+    let mut calendars = statement
+      .query_map([], |row| {
+        Ok(Calendar {
+          id: row.get::<_, i64>(0)? as CalendarId,
+          name: row.get(1)?,
+          ..Calendar::default()
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = self
+      .conn
+      .prepare("SELECT calendar_id, date, name FROM calendar_exceptions ORDER BY calendar_id, date")?;
+    let exceptions = statement.query_map([], |row| {
+      Ok((
+        row.get::<_, i64>(0)? as CalendarId,
+        CalendarException {
+          date: row.get(1)?,
+          name: row.get(2)?,
+          shifts: Vec::new(),
+        },
+      ))
+    })?;
+    for exception in exceptions {
+      let (calendar_id, exception) = exception?;
+      if let Some(calendar) = calendars.iter_mut().find(|calendar| calendar.id == calendar_id) {
+        calendar.exceptions.push(exception);
+      }
+    }
+
+    let mut statement = self.conn.prepare(
+      "SELECT calendar_id, weekday, date, start_time, end_time
+       FROM calendar_shifts ORDER BY calendar_id, weekday, date, start_time",
+    )?;
+    let shifts = statement.query_map([], |row| {
+      Ok((
+        row.get::<_, i64>(0)? as CalendarId,
+        row.get::<_, Option<i64>>(1)?,
+        row.get::<_, Option<NaiveDate>>(2)?,
+        Shift {
+          start: row.get(3)?,
+          end: row.get(4)?,
+        },
+      ))
+    })?;
+    for shift in shifts {
+      let (calendar_id, weekday, date, shift) = shift?;
+      let Some(calendar) = calendars.iter_mut().find(|calendar| calendar.id == calendar_id) else {
+        continue;
+      };
+      match (weekday, date) {
+        (Some(weekday), _) => calendar.week[weekday as usize].push(shift),
+        (None, Some(date)) => {
+          if let Some(exception) = calendar.exceptions.iter_mut().find(|exception| exception.date == date) {
+            exception.shifts.push(shift);
+          }
+        }
+        (None, None) => {}
+      }
+    }
+    Ok(calendars)
+  }
+
+  pub fn create_calendar(&mut self, calendar: &Calendar) -> Result<(), StorageError> {
+    validate(calendar)?;
+    let tx = self.conn.transaction()?;
+    tx.execute(
+      "INSERT INTO calendars (id, name) VALUES (?1, ?2)",
+      params![calendar.id as i64, calendar.name],
+    )?;
+    insert_working_time(&tx, calendar)?;
+    tx.commit()?;
+    Ok(())
+  }
+
+  pub fn update_calendar(&mut self, calendar: &Calendar) -> Result<(), StorageError> {
+    validate(calendar)?;
+    let tx = self.conn.transaction()?;
+    let changed = tx.execute(
+      "UPDATE calendars SET name = ?2 WHERE id = ?1",
+      params![calendar.id as i64, calendar.name],
+    )?;
+    if changed == 0 {
+      return Err(not_found("calendar", calendar.id));
+    }
+    insert_working_time(&tx, calendar)?;
+    tx.commit()?;
+    Ok(())
+  }
+
+  /// Deletes a calendar; its machines run around the clock again.
+  pub fn delete_calendar(&mut self, id: CalendarId) -> Result<(), StorageError> {
+    let changed = self.conn.execute("DELETE FROM calendars WHERE id = ?1", params![id as i64])?;
+    if changed == 0 {
+      return Err(not_found("calendar", id));
+    }
+    Ok(())
+  }
+}
+
+fn validate(calendar: &Calendar) -> Result<(), StorageError> {
+  calendar
+    .validate()
+    .map_err(|e| StorageError::Invalid(format!("calendar {}: {}", calendar.id, e)))
+}
+
+/// Replaces the stored shifts and exceptions of `calendar`.
+fn insert_working_time(tx: &Transaction<'_>, calendar: &Calendar) -> Result<(), StorageError> {
+  let id = calendar.id as i64;
+  tx.execute("DELETE FROM calendar_shifts WHERE calendar_id = ?1", params![id])?;
+  tx.execute("DELETE FROM calendar_exceptions WHERE calendar_id = ?1", params![id])?;
+  for (weekday, shifts) in calendar.week.iter().enumerate() {
+    for shift in shifts {
+      tx.execute(
+        "INSERT INTO calendar_shifts (calendar_id, weekday, start_time, end_time) VALUES (?1, ?2, ?3, ?4)",
+        params![id, weekday as i64, shift.start, shift.end],
+      )?;
+    }
+  }
+  for exception in &calendar.exceptions {
+    // a repeated date replaces the earlier entry, as `Calendar::set_exception` does
+    tx.execute(
+      "INSERT OR REPLACE INTO calendar_exceptions (calendar_id, date, name) VALUES (?1, ?2, ?3)",
+      params![id, exception.date, exception.name],
+    )?;
+    tx.execute(
+      "DELETE FROM calendar_shifts WHERE calendar_id = ?1 AND date = ?2",
+      params![id, exception.date],
+    )?;
+    for shift in &exception.shifts {
+      tx.execute(
+        "INSERT INTO calendar_shifts (calendar_id, date, start_time, end_time) VALUES (?1, ?2, ?3, ?4)",
+        params![id, exception.date, shift.start, shift.end],
+      )?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,122 @@
+use millmill_domain::{
+  ColumnMapping, ImportProfile, ImportProfileId, OrderColumnMapping, TaskField, Validate, WorkOrderField,
+};
+use rusqlite::{params, OptionalExtension, Transaction};
+
+use super::{not_found, Storage, StorageError};
+
+/// Values of `import_profile_columns.sheet`.
+const WORK_ORDER_SHEET: &str = "work_orders";
+const TASK_SHEET: &str = "tasks";
+
+impl Storage {
+  /// Lists the saved workbook mappings by name, each with its columns in order.
+  pub fn list_import_profiles(&self) -> Result<Vec<ImportProfile>, StorageError> {
+    let mut statement = self
+      .conn
+      .prepare("SELECT id, name, work_order_sheet, task_sheet FROM import_profiles ORDER BY name, id")?;
+    let mut profiles = statement
+      .query_map([], |row| {
+        Ok(ImportProfile {
+          id: row.get::<_, i64>(0)? as ImportProfileId,
+          name: row.get(1)?,
+          work_order_sheet: row.get(2)?,
+          work_order_columns: Vec::new(),
+          task_sheet: row.get(3)?,
+          task_columns: Vec::new(),
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = self.conn.prepare(
+      "SELECT profile_id, sheet, header, field
+       FROM import_profile_columns ORDER BY profile_id, sheet, position",
+    )?;
+    let columns = statement.query_map([], |row| {
+      Ok((
+        row.get::<_, i64>(0)? as ImportProfileId,
+        row.get::<_, String>(1)?,
+        row.get::<_, String>(2)?,
+        row.get::<_, Option<String>>(3)?,
+      ))
+    })?;
+    for column in columns {
+      let (profile_id, sheet, header, field) = column?;
+      let Some(profile) = profiles.iter_mut().find(|profile| profile.id == profile_id) else { continue };
+      // Fields this version does not know are read as left out
+      let field = field.as_deref();
+      if sheet == WORK_ORDER_SHEET {
+        let field = field.and_then(WorkOrderField::from_code);
+        profile.work_order_columns.push(OrderColumnMapping { header, field });
+      } else {
+        profile.task_columns.push(ColumnMapping { header, field: field.and_then(TaskField::from_code) });
+      }
+    }
+    Ok(profiles)
+  }
+
+  /// Saves `profile` under its name, replacing a profile of the same name, and returns it
+  /// with the id it is stored under.
+  pub fn save_import_profile(&mut self, profile: &ImportProfile) -> Result<ImportProfile, StorageError> {
+    profile
+      .validate()
+      .map_err(|e| StorageError::Invalid(format!("import profile: {}", e)))?;
+    let name = profile.name.trim();
+    let tx = self.conn.transaction()?;
+    let existing: Option<i64> = tx
+      .query_row("SELECT id FROM import_profiles WHERE name = ?1", params![name], |row| row.get(0))
+      .optional()?;
+    let id = match existing {
+      Some(id) => {
+        tx.execute(
+          "UPDATE import_profiles SET work_order_sheet = ?2, task_sheet = ?3 WHERE id = ?1",
+          params![id, profile.work_order_sheet, profile.task_sheet],
+        )?;
+        id
+      }
+      None => {
+        let id: i64 = tx.query_row("SELECT COALESCE(MAX(id), 0) + 1 FROM import_profiles", [], |row| row.get(0))?;
+        tx.execute(
+          "INSERT INTO import_profiles (id, name, work_order_sheet, task_sheet) VALUES (?1, ?2, ?3, ?4)",
+          params![id, name, profile.work_order_sheet, profile.task_sheet],
+        )?;
+        id
+      }
+    };
+    let work_order_columns = profile.work_order_columns.iter().map(|c| (c.header.as_str(), c.field.map(|f| f.code())));
+    let task_columns = profile.task_columns.iter().map(|c| (c.header.as_str(), c.field.map(|f| f.code())));
+    insert_columns(&tx, id, WORK_ORDER_SHEET, work_order_columns)?;
+    insert_columns(&tx, id, TASK_SHEET, task_columns)?;
+    tx.commit()?;
+    Ok(ImportProfile { id: id as ImportProfileId, name: name.to_string(), ..profile.clone() })
+  }
+
+  pub fn delete_import_profile(&mut self, id: ImportProfileId) -> Result<(), StorageError> {
+    let changed = self.conn.execute("DELETE FROM import_profiles WHERE id = ?1", params![id as i64])?;
+    if changed == 0 {
+      return Err(not_found("import profile", id));
+    }
+    Ok(())
+  }
+}
+
+/// Replaces the stored columns of one sheet of a profile, keeping their order.
+fn insert_columns<'a>(
+  tx: &Transaction<'_>,
+  profile_id: i64,
+  sheet: &str,
+  columns: impl Iterator<Item = (&'a str, Option<&'static str>)>,
+) -> Result<(), StorageError> {
+  tx.execute(
+    "DELETE FROM import_profile_columns WHERE profile_id = ?1 AND sheet = ?2",
+    params![profile_id, sheet],
+  )?;
+  for (position, (header, field)) in columns.enumerate() {
+    tx.execute(
+      "INSERT INTO import_profile_columns (profile_id, sheet, position, header, field)
+       VALUES (?1, ?2, ?3, ?4, ?5)",
+      params![profile_id, sheet, position as i64, header, field],
+    )?;
+  }
+  Ok(())
+}
@@ -0,0 +1,84 @@
+//! Import of the `tasks.json` files written before tasks were stored in SQLite.
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use millmill_domain::{Task, TaskKind, TaskStatus};
+use serde::Deserialize;
+
+use super::StorageError;
+
+const DEFAULT_COLOR: &str = "#009688";
+
+/// The original backend shape, which only had a free-text due date.
+#[derive(Deserialize)]
+struct OriginalTask {
+  name: String,
+  #[serde(default)]
+  description: String,
+  due_date: String,
+  #[serde(default)]
+  status: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LegacyRecord {
+  Current(Task),
+  Original(OriginalTask),
+}
+
+/// Reads a legacy `tasks.json`, accepting both the original `due_date` shape and the
+/// shared `Task` shape. Original records become milestones at their due date.
+pub fn read_tasks(path: &Path) -> Result<Vec<Task>, StorageError> {
+  let json = fs::read_to_string(path)?;
+  let records: Vec<LegacyRecord> = serde_json::from_str(&json)?;
+  let mut next_id = records
+    .iter()
+    .filter_map(|record| match record {
+      LegacyRecord::Current(task) => Some(task.id),
+      LegacyRecord::Original(_) => None,
+    })
+    .max()
+    .unwrap_or(0)
+    + 1;
+
+  records
+    .into_iter()
+    .map(|record| match record {
+      LegacyRecord::Current(task) => Ok(task),
+      LegacyRecord::Original(original) => {
+        let due_date = parse_due_date(&original.due_date).ok_or_else(|| {
+          StorageError::Legacy(format!("task \"{}\" has an unreadable due date \"{}\"", original.name, original.due_date))
+        })?;
+        let id = next_id;
+        next_id += 1;
+        Ok(Task {
+          id,
+          name: original.name,
+          description: original.description,
+          start_date: due_date,
+          end_date: due_date,
+          color: DEFAULT_COLOR.to_string(),
+          kind: TaskKind::Milestone,
+          status: TaskStatus::parse(&original.status),
+          ..Default::default()
+        })
+      }
+    })
+    .collect()
+}
+
+fn parse_due_date(value: &str) -> Option<NaiveDateTime> {
+  const FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+  let value = value.trim();
+  FORMATS
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+      NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })
+}
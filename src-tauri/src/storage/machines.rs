@@ -0,0 +1,182 @@
+use millmill_domain::{Machine, MachineId, MaintenanceWindow, SetupKey, SetupMatrix, SetupTime, Validate};
+use rusqlite::{params, Row, Transaction};
+
+use super::{not_found, Storage, StorageError};
+
+const MACHINE_COLUMNS: &str = "id, name, description, capacity, calendar_id, setup_default_minutes";
+
+impl Storage {
+  /// Returns `None` when the database was created on this launch and no machine has been
+  /// written to it yet.
+  pub fn load_machines(&self) -> Result<Option<Vec<Machine>>, StorageError> {
+    Ok(self.unless_fresh(self.list_machines()?))
+  }
+
+  pub fn list_machines(&self) -> Result<Vec<Machine>, StorageError> {
+    let mut statement = self
+      .conn
+      .prepare(&format!("SELECT {} FROM machines ORDER BY id", MACHINE_COLUMNS))?;
+    let mut machines = statement
+      .query_map([], machine_from_row)?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = self.conn.prepare(
+      "SELECT machine_id, start_date, end_date, reason
+       FROM machine_maintenance ORDER BY machine_id, start_date",
+    )?;
+    let windows = statement.query_map([], |row| {
+      Ok((
+        row.get::<_, i64>(0)? as MachineId,
+        MaintenanceWindow {
+          start_date: row.get(1)?,
+          end_date: row.get(2)?,
+          reason: row.get(3)?,
+        },
+      ))
+    })?;
+    for window in windows {
+      let (machine_id, window) = window?;
+      if let Some(machine) = machines.iter_mut().find(|machine| machine.id == machine_id) {
+        machine.maintenance.push(window);
+      }
+    }
+
+    let mut statement = self.conn.prepare(
+      "SELECT machine_id, from_family, from_material, to_family, to_material, minutes
+       FROM machine_setup_times ORDER BY machine_id, position",
+    )?;
+    let entries = statement.query_map([], |row| {
+      Ok((
+        row.get::<_, i64>(0)? as MachineId,
+        SetupTime {
+          from: SetupKey {
+            part_family: row.get(1)?,
+            material: row.get(2)?,
+          },
+          to: SetupKey {
+            part_family: row.get(3)?,
+            material: row.get(4)?,
+          },
+          minutes: row.get(5)?,
+        },
+      ))
+    })?;
+    for entry in entries {
+      let (machine_id, entry) = entry?;
+      if let Some(machine) = machines.iter_mut().find(|machine| machine.id == machine_id) {
+        machine.setup.entries.push(entry);
+      }
+    }
+    Ok(machines)
+  }
+
+  pub fn create_machine(&mut self, machine: &Machine) -> Result<(), StorageError> {
+    validate(machine)?;
+    let tx = self.conn.transaction()?;
+    tx.execute(
+      &format!("INSERT INTO machines ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", MACHINE_COLUMNS),
+      params![
+        machine.id as i64,
+        machine.name,
+        machine.description,
+        machine.capacity,
+        machine.calendar_id.map(|id| id as i64),
+        machine.setup.default_minutes,
+      ],
+    )?;
+    insert_maintenance(&tx, machine)?;
+    insert_setup_times(&tx, machine)?;
+    tx.commit()?;
+    Ok(())
+  }
+
+  pub fn update_machine(&mut self, machine: &Machine) -> Result<(), StorageError> {
+    validate(machine)?;
+    let tx = self.conn.transaction()?;
+    let changed = tx.execute(
+      "UPDATE machines
+       SET name = ?2, description = ?3, capacity = ?4, calendar_id = ?5, setup_default_minutes = ?6
+       WHERE id = ?1",
+      params![
+        machine.id as i64,
+        machine.name,
+        machine.description,
+        machine.capacity,
+        machine.calendar_id.map(|id| id as i64),
+        machine.setup.default_minutes,
+      ],
+    )?;
+    if changed == 0 {
+      return Err(not_found("machine", machine.id));
+    }
+    insert_maintenance(&tx, machine)?;
+    insert_setup_times(&tx, machine)?;
+    tx.commit()?;
+    Ok(())
+  }
+
+  /// Deletes a machine; its tasks become unassigned.
+  pub fn delete_machine(&mut self, id: MachineId) -> Result<(), StorageError> {
+    let changed = self.conn.execute("DELETE FROM machines WHERE id = ?1", params![id as i64])?;
+    if changed == 0 {
+      return Err(not_found("machine", id));
+    }
+    Ok(())
+  }
+}
+
+fn validate(machine: &Machine) -> Result<(), StorageError> {
+  machine
+    .validate()
+    .map_err(|e| StorageError::Invalid(format!("machine {}: {}", machine.id, e)))
+}
+
+/// Replaces the stored maintenance windows of `machine`.
+fn insert_maintenance(tx: &Transaction<'_>, machine: &Machine) -> Result<(), StorageError> {
+  tx.execute("DELETE FROM machine_maintenance WHERE machine_id = ?1", params![machine.id as i64])?;
+  for window in &machine.maintenance {
+    tx.execute(
+      "INSERT INTO machine_maintenance (machine_id, start_date, end_date, reason)
+       VALUES (?1, ?2, ?3, ?4)",
+      params![machine.id as i64, window.start_date, window.end_date, window.reason],
+    )?;
+  }
+  Ok(())
+}
+
+/// Replaces the stored setup matrix entries of `machine`, keeping their order.
+fn insert_setup_times(tx: &Transaction<'_>, machine: &Machine) -> Result<(), StorageError> {
+  tx.execute("DELETE FROM machine_setup_times WHERE machine_id = ?1", params![machine.id as i64])?;
+  for (position, entry) in machine.setup.entries.iter().enumerate() {
+    tx.execute(
+      "INSERT INTO machine_setup_times
+         (machine_id, position, from_family, from_material, to_family, to_material, minutes)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+      params![
+        machine.id as i64,
+        position as i64,
+        entry.from.part_family,
+        entry.from.material,
+        entry.to.part_family,
+        entry.to.material,
+        entry.minutes,
+      ],
+    )?;
+  }
+  Ok(())
+}
+
+fn machine_from_row(row: &Row<'_>) -> rusqlite::Result<Machine> {
+  Ok(Machine {
+    id: row.get::<_, i64>(0)? as MachineId,
+    name: row.get(1)?,
+    description: row.get(2)?,
+    capacity: row.get(3)?,
+    maintenance: Vec::new(),
+    calendar_id: row.get::<_, Option<i64>>(4)?.map(|id| id as usize),
+    setup: SetupMatrix {
+      default_minutes: row.get(5)?,
+      entries: Vec::new(),
+    },
+  })
+}
@@ -0,0 +1,200 @@
+//! Versioned schema migrations.
+//!
+//! The schema version is kept in SQLite's `user_version` pragma. Migration `n` in
+//! [`MIGRATIONS`] upgrades the schema from version `n` to `n + 1`, so new migrations are
+//! only ever appended to the end of the list.
+
+use rusqlite::Connection;
+
+pub const MIGRATIONS: &[&str] = &[
+  // 1: tasks
+  "CREATE TABLE tasks (
+    id            INTEGER PRIMARY KEY,
+    name          TEXT NOT NULL,
+    description   TEXT NOT NULL DEFAULT '',
+    start_date    TEXT NOT NULL,
+    end_date      TEXT NOT NULL,
+    color         TEXT NOT NULL,
+    status        TEXT NOT NULL DEFAULT '',
+    work_order_id INTEGER
+  );",
+  // 2: task dependencies. Deferred so whole task lists can be inserted in any order.
+  "CREATE TABLE task_dependencies (
+    successor_id   INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    predecessor_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    kind           TEXT NOT NULL,
+    lag_minutes    INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (successor_id, predecessor_id)
+  );",
+  // 3: machines and the machine each task runs on
+  "CREATE TABLE machines (
+    id          INTEGER PRIMARY KEY,
+    name        TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT ''
+  );
+  ALTER TABLE tasks ADD COLUMN machine_id INTEGER REFERENCES machines(id) ON DELETE SET NULL;",
+  // 4: machine capacity and maintenance windows
+  "ALTER TABLE machines ADD COLUMN capacity INTEGER NOT NULL DEFAULT 1;
+  CREATE TABLE machine_maintenance (
+    machine_id INTEGER NOT NULL REFERENCES machines(id) ON DELETE CASCADE,
+    start_date TEXT NOT NULL,
+    end_date   TEXT NOT NULL,
+    reason     TEXT NOT NULL DEFAULT ''
+  );",
+  // 5: work breakdown structure and progress. Children outlive a deleted parent as top-level tasks.
+  "ALTER TABLE tasks ADD COLUMN parent_id INTEGER
+    REFERENCES tasks(id) ON DELETE SET NULL DEFERRABLE INITIALLY DEFERRED;
+  ALTER TABLE tasks ADD COLUMN percent_complete REAL NOT NULL DEFAULT 0;",
+  // 6: status codes in place of free text, and actual start and end times. Reads the text the
+  // way TaskStatus::parse does.
+  "UPDATE tasks SET status = CASE replace(replace(lower(trim(status)), ' ', '_'), '-', '_')
+      WHEN 'released' THEN 'released'
+      WHEN 'in_setup' THEN 'in_setup'
+      WHEN 'setup' THEN 'in_setup'
+      WHEN 'running' THEN 'running'
+      WHEN 'in_progress' THEN 'running'
+      WHEN 'started' THEN 'running'
+      WHEN 'wip' THEN 'running'
+      WHEN 'paused' THEN 'paused'
+      WHEN 'done' THEN 'done'
+      WHEN 'complete' THEN 'done'
+      WHEN 'completed' THEN 'done'
+      WHEN 'finished' THEN 'done'
+      WHEN 'cancelled' THEN 'cancelled'
+      WHEN 'canceled' THEN 'cancelled'
+      ELSE 'planned'
+    END;
+  ALTER TABLE tasks ADD COLUMN actual_start TEXT;
+  ALTER TABLE tasks ADD COLUMN actual_end TEXT;",
+  // 7: baselines. Task ids are not foreign keys so a baseline still reports tasks deleted since.
+  "CREATE TABLE baselines (
+    id         INTEGER PRIMARY KEY,
+    name       TEXT NOT NULL,
+    created_at TEXT NOT NULL
+  );
+  CREATE TABLE baseline_tasks (
+    baseline_id INTEGER NOT NULL REFERENCES baselines(id) ON DELETE CASCADE,
+    task_id     INTEGER NOT NULL,
+    name        TEXT NOT NULL,
+    start_date  TEXT NOT NULL,
+    end_date    TEXT NOT NULL,
+    PRIMARY KEY (baseline_id, task_id)
+  );",
+  // 8: work orders with their due dates, and milestones. Zero-length tasks were always meant as milestones.
+  "CREATE TABLE work_orders (
+    id        INTEGER PRIMARY KEY,
+    number    TEXT NOT NULL,
+    customer  TEXT NOT NULL DEFAULT '',
+    part_name TEXT NOT NULL,
+    quantity  INTEGER NOT NULL,
+    due_date  TEXT
+  );
+  ALTER TABLE tasks ADD COLUMN kind TEXT NOT NULL DEFAULT 'task';
+  UPDATE tasks SET kind = 'milestone' WHERE start_date = end_date;",
+  // 9: working calendars. A shift belongs either to a weekday (0 = Monday) or to an exception date.
+  "CREATE TABLE calendars (
+    id   INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+  );
+  CREATE TABLE calendar_exceptions (
+    calendar_id INTEGER NOT NULL REFERENCES calendars(id) ON DELETE CASCADE,
+    date        TEXT NOT NULL,
+    name        TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (calendar_id, date)
+  );
+  CREATE TABLE calendar_shifts (
+    calendar_id INTEGER NOT NULL REFERENCES calendars(id) ON DELETE CASCADE,
+    weekday     INTEGER CHECK (weekday BETWEEN 0 AND 6),
+    date        TEXT,
+    start_time  TEXT NOT NULL,
+    end_time    TEXT NOT NULL,
+    CHECK ((weekday IS NULL) <> (date IS NULL))
+  );
+  ALTER TABLE machines ADD COLUMN calendar_id INTEGER REFERENCES calendars(id) ON DELETE SET NULL;",
+  // 10: sequence-dependent setup times. An empty family or material in a matrix entry matches any job.
+  "ALTER TABLE tasks ADD COLUMN part_family TEXT NOT NULL DEFAULT '';
+  ALTER TABLE tasks ADD COLUMN material TEXT NOT NULL DEFAULT '';
+  ALTER TABLE machines ADD COLUMN setup_default_minutes INTEGER NOT NULL DEFAULT 0;
+  CREATE TABLE machine_setup_times (
+    machine_id    INTEGER NOT NULL REFERENCES machines(id) ON DELETE CASCADE,
+    position      INTEGER NOT NULL,
+    from_family   TEXT NOT NULL DEFAULT '',
+    from_material TEXT NOT NULL DEFAULT '',
+    to_family     TEXT NOT NULL DEFAULT '',
+    to_material   TEXT NOT NULL DEFAULT '',
+    minutes       INTEGER NOT NULL,
+    PRIMARY KEY (machine_id, position)
+  );",
+  // 11: piece counts and split jobs. Later segments point at the first segment of their job.
+  "ALTER TABLE tasks ADD COLUMN quantity INTEGER NOT NULL DEFAULT 0;
+  ALTER TABLE tasks ADD COLUMN split_of INTEGER
+    REFERENCES tasks(id) ON DELETE SET NULL DEFERRABLE INITIALLY DEFERRED;",
+  // 12: saved mappings of workbook sheets and columns. `field` is NULL for columns left out.
+  "CREATE TABLE import_profiles (
+    id               INTEGER PRIMARY KEY,
+    name             TEXT NOT NULL UNIQUE,
+    work_order_sheet TEXT,
+    task_sheet       TEXT
+  );
+  CREATE TABLE import_profile_columns (
+    profile_id INTEGER NOT NULL REFERENCES import_profiles(id) ON DELETE CASCADE,
+    sheet      TEXT NOT NULL CHECK (sheet IN ('work_orders', 'tasks')),
+    position   INTEGER NOT NULL,
+    header     TEXT NOT NULL,
+    field      TEXT,
+    PRIMARY KEY (profile_id, sheet, position)
+  );",
+];
+
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<usize> {
+  conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+    .map(|version| version as usize)
+}
+
+/// Applies every migration newer than the current schema version, each in its own transaction.
+pub fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+  let current = schema_version(conn)?;
+  for (index, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+    let tx = conn.transaction()?;
+    tx.execute_batch(sql)?;
+    tx.pragma_update(None, "user_version", (index + 1) as i64)?;
+    tx.commit()?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use millmill_domain::TaskStatus;
+  use rusqlite::params;
+
+  use super::*;
+
+  #[test]
+  fn free_text_statuses_become_the_codes_the_app_reads_them_as() {
+    let spellings = [
+      "", "Released", "in setup", "In-Setup", "setup", "Running", "in progress", "in_progress", "In-Progress", "WIP",
+      "started", "paused", " Done ", "complete", "Completed", "finished", "cancelled", "Canceled", "on hold",
+    ];
+    let mut conn = Connection::open_in_memory().unwrap();
+    for sql in &MIGRATIONS[..5] {
+      conn.execute_batch(sql).unwrap();
+    }
+    conn.pragma_update(None, "user_version", 5).unwrap();
+    for (id, status) in spellings.iter().enumerate() {
+      conn
+        .execute(
+          "INSERT INTO tasks (id, name, start_date, end_date, color, status)
+           VALUES (?1, 'Op', '2025-03-03T08:00:00', '2025-03-03T12:00:00', '#4CAF50', ?2)",
+          params![id as i64 + 1, status],
+        )
+        .unwrap();
+    }
+    migrate(&mut conn).unwrap();
+
+    let mut statement = conn.prepare("SELECT status FROM tasks ORDER BY id").unwrap();
+    let codes: Vec<String> = statement.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap();
+    let expected: Vec<&str> = spellings.iter().map(|status| TaskStatus::parse(status).code()).collect();
+    assert_eq!(codes, expected);
+  }
+}
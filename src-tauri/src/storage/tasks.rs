@@ -0,0 +1,234 @@
+use millmill_domain::{
+  validate_dependencies, validate_hierarchy, validate_segments, Dependency, DependencyKind, Task, TaskId,
+  TaskKind, TaskStatus, Validate,
+};
+use rusqlite::{params, Row, Transaction};
+
+use super::{not_found, Storage, StorageError};
+
+const TASK_COLUMNS: &str =
+  "id, name, description, start_date, end_date, color, status, work_order_id, machine_id, parent_id, percent_complete,
+   actual_start, actual_end, kind, part_family, material, quantity, split_of";
+
+impl Storage {
+  /// Returns `None` when the database was created on this launch and nothing has been
+  /// written to it yet, so the frontend can show its sample tasks.
+  pub fn load_tasks(&self) -> Result<Option<Vec<Task>>, StorageError> {
+    Ok(self.unless_fresh(self.list_tasks()?))
+  }
+
+  pub fn list_tasks(&self) -> Result<Vec<Task>, StorageError> {
+    let mut statement = self
+      .conn
+      .prepare(&format!("SELECT {} FROM tasks ORDER BY id", TASK_COLUMNS))?;
+    let mut tasks = statement
+      .query_map([], task_from_row)?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = self.conn.prepare(
+      "SELECT successor_id, predecessor_id, kind, lag_minutes
+       FROM task_dependencies ORDER BY successor_id, predecessor_id",
+    )?;
+    let links = statement.query_map([], |row| {
+      let kind: String = row.get(2)?;
+      let kind = DependencyKind::from_code(&kind).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, format!("unknown dependency kind {}", kind).into())
+      })?;
+      Ok((
+        row.get::<_, i64>(0)? as TaskId,
+        Dependency {
+          predecessor_id: row.get::<_, i64>(1)? as TaskId,
+          kind,
+          lag_minutes: row.get(3)?,
+        },
+      ))
+    })?;
+    for link in links {
+      let (successor_id, dependency) = link?;
+      if let Some(task) = tasks.iter_mut().find(|task| task.id == successor_id) {
+        task.dependencies.push(dependency);
+      }
+    }
+    Ok(tasks)
+  }
+
+  pub fn create_task(&mut self, task: &Task) -> Result<(), StorageError> {
+    validate(task)?;
+    self.check_links_with(task)?;
+    let tx = self.conn.transaction()?;
+    insert_task(&tx, task)?;
+    tx.commit()?;
+    Ok(())
+  }
+
+  pub fn update_task(&mut self, task: &Task) -> Result<(), StorageError> {
+    validate(task)?;
+    self.check_links_with(task)?;
+    let tx = self.conn.transaction()?;
+    let changed = tx.execute(
+      "UPDATE tasks
+       SET name = ?2, description = ?3, start_date = ?4, end_date = ?5, color = ?6, status = ?7,
+           work_order_id = ?8, machine_id = ?9, parent_id = ?10, percent_complete = ?11,
+           actual_start = ?12, actual_end = ?13, kind = ?14, part_family = ?15, material = ?16,
+           quantity = ?17, split_of = ?18
+       WHERE id = ?1",
+      params![
+        task.id as i64,
+        task.name,
+        task.description,
+        task.start_date,
+        task.end_date,
+        task.color,
+        task.status.code(),
+        task.work_order_id.map(|id| id as i64),
+        task.machine_id.map(|id| id as i64),
+        task.parent_id.map(|id| id as i64),
+        task.percent_complete,
+        task.actual_start,
+        task.actual_end,
+        task.kind.code(),
+        task.part_family,
+        task.material,
+        task.quantity,
+        task.split_of.map(|id| id as i64),
+      ],
+    )?;
+    if changed == 0 {
+      return Err(not_found("task", task.id));
+    }
+    insert_dependencies(&tx, task)?;
+    tx.commit()?;
+    Ok(())
+  }
+
+  pub fn delete_task(&mut self, id: TaskId) -> Result<(), StorageError> {
+    let tx = self.conn.transaction()?;
+    let changed = tx.execute("DELETE FROM tasks WHERE id = ?1", params![id as i64])?;
+    if changed == 0 {
+      return Err(not_found("task", id));
+    }
+    tx.commit()?;
+    Ok(())
+  }
+
+  /// Replaces every task in one transaction; either all of `tasks` is stored or nothing changes.
+  pub fn replace_tasks(&mut self, tasks: &[Task]) -> Result<(), StorageError> {
+    for task in tasks {
+      validate(task)?;
+    }
+    validate_dependencies(tasks).map_err(|e| StorageError::Invalid(e.to_string()))?;
+    validate_hierarchy(tasks).map_err(|e| StorageError::Invalid(e.to_string()))?;
+    validate_segments(tasks).map_err(|e| StorageError::Invalid(e.to_string()))?;
+    let tx = self.conn.transaction()?;
+    tx.execute("DELETE FROM tasks", [])?;
+    for task in tasks {
+      insert_task(&tx, task)?;
+    }
+    tx.commit()?;
+    Ok(())
+  }
+
+  /// Checks that storing `task` would keep the dependency graph and the task grouping free of
+  /// cycles and dangling links, and split jobs attached to their first segment.
+  fn check_links_with(&self, task: &Task) -> Result<(), StorageError> {
+    let mut tasks = self.list_tasks()?;
+    match tasks.iter_mut().find(|stored| stored.id == task.id) {
+      Some(stored) => *stored = task.clone(),
+      None => tasks.push(task.clone()),
+    }
+    validate_dependencies(&tasks).map_err(|e| StorageError::Invalid(e.to_string()))?;
+    validate_hierarchy(&tasks).map_err(|e| StorageError::Invalid(e.to_string()))?;
+    validate_segments(&tasks).map_err(|e| StorageError::Invalid(e.to_string()))
+  }
+
+  pub(super) fn count_tasks(&self) -> Result<usize, StorageError> {
+    let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))?;
+    Ok(count as usize)
+  }
+}
+
+fn validate(task: &Task) -> Result<(), StorageError> {
+  task
+    .validate()
+    .map_err(|e| StorageError::Invalid(format!("task {}: {}", task.id, e)))
+}
+
+fn insert_task(tx: &Transaction<'_>, task: &Task) -> Result<(), StorageError> {
+  tx.execute(
+    &format!(
+      "INSERT INTO tasks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+      TASK_COLUMNS
+    ),
+    params![
+      task.id as i64,
+      task.name,
+      task.description,
+      task.start_date,
+      task.end_date,
+      task.color,
+      task.status.code(),
+      task.work_order_id.map(|id| id as i64),
+      task.machine_id.map(|id| id as i64),
+      task.parent_id.map(|id| id as i64),
+      task.percent_complete,
+      task.actual_start,
+      task.actual_end,
+      task.kind.code(),
+      task.part_family,
+      task.material,
+      task.quantity,
+      task.split_of.map(|id| id as i64),
+    ],
+  )?;
+  insert_dependencies(tx, task)
+}
+
+/// Writes the predecessor links of `task`, replacing any stored ones.
+fn insert_dependencies(tx: &Transaction<'_>, task: &Task) -> Result<(), StorageError> {
+  tx.execute("DELETE FROM task_dependencies WHERE successor_id = ?1", params![task.id as i64])?;
+  for dependency in &task.dependencies {
+    tx.execute(
+      "INSERT INTO task_dependencies (successor_id, predecessor_id, kind, lag_minutes)
+       VALUES (?1, ?2, ?3, ?4)",
+      params![
+        task.id as i64,
+        dependency.predecessor_id as i64,
+        dependency.kind.code(),
+        dependency.lag_minutes,
+      ],
+    )?;
+  }
+  Ok(())
+}
+
+fn task_from_row(row: &Row<'_>) -> rusqlite::Result<Task> {
+  let status: String = row.get(6)?;
+  let status = TaskStatus::from_code(&status).ok_or_else(|| {
+    rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, format!("unknown task status {}", status).into())
+  })?;
+  let kind: String = row.get(13)?;
+  let kind = TaskKind::from_code(&kind).ok_or_else(|| {
+    rusqlite::Error::FromSqlConversionFailure(13, rusqlite::types::Type::Text, format!("unknown task kind {}", kind).into())
+  })?;
+  Ok(Task {
+    id: row.get::<_, i64>(0)? as TaskId,
+    name: row.get(1)?,
+    description: row.get(2)?,
+    start_date: row.get(3)?,
+    end_date: row.get(4)?,
+    color: row.get(5)?,
+    kind,
+    status,
+    work_order_id: row.get::<_, Option<i64>>(7)?.map(|id| id as usize),
+    machine_id: row.get::<_, Option<i64>>(8)?.map(|id| id as usize),
+    dependencies: Vec::new(),
+    parent_id: row.get::<_, Option<i64>>(9)?.map(|id| id as usize),
+    percent_complete: row.get(10)?,
+    actual_start: row.get(11)?,
+    actual_end: row.get(12)?,
+    part_family: row.get(14)?,
+    material: row.get(15)?,
+    quantity: row.get(16)?,
+    split_of: row.get::<_, Option<i64>>(17)?.map(|id| id as usize),
+  })
+}
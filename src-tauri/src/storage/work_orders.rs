@@ -0,0 +1,89 @@
+use millmill_domain::{Validate, WorkOrder, WorkOrderId};
+use rusqlite::{params, Row};
+
+use super::{not_found, Storage, StorageError};
+
+const WORK_ORDER_COLUMNS: &str = "id, number, customer, part_name, quantity, due_date";
+
+impl Storage {
+  /// Returns `None` when the database was created on this launch and no work order has been
+  /// written to it yet.
+  pub fn load_work_orders(&self) -> Result<Option<Vec<WorkOrder>>, StorageError> {
+    Ok(self.unless_fresh(self.list_work_orders()?))
+  }
+
+  pub fn list_work_orders(&self) -> Result<Vec<WorkOrder>, StorageError> {
+    let mut statement = self
+      .conn
+      .prepare(&format!("SELECT {} FROM work_orders ORDER BY id", WORK_ORDER_COLUMNS))?;
+    let work_orders = statement
+      .query_map([], work_order_from_row)?
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(work_orders)
+  }
+
+  pub fn create_work_order(&mut self, work_order: &WorkOrder) -> Result<(), StorageError> {
+    validate(work_order)?;
+    self.conn.execute(
+      &format!("INSERT INTO work_orders ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", WORK_ORDER_COLUMNS),
+      params![
+        work_order.id as i64,
+        work_order.number,
+        work_order.customer,
+        work_order.part_name,
+        work_order.quantity,
+        work_order.due_date,
+      ],
+    )?;
+    Ok(())
+  }
+
+  pub fn update_work_order(&mut self, work_order: &WorkOrder) -> Result<(), StorageError> {
+    validate(work_order)?;
+    let changed = self.conn.execute(
+      "UPDATE work_orders SET number = ?2, customer = ?3, part_name = ?4, quantity = ?5, due_date = ?6
+       WHERE id = ?1",
+      params![
+        work_order.id as i64,
+        work_order.number,
+        work_order.customer,
+        work_order.part_name,
+        work_order.quantity,
+        work_order.due_date,
+      ],
+    )?;
+    if changed == 0 {
+      return Err(not_found("work order", work_order.id));
+    }
+    Ok(())
+  }
+
+  /// Deletes a work order; its tasks stay but are no longer linked to an order.
+  pub fn delete_work_order(&mut self, id: WorkOrderId) -> Result<(), StorageError> {
+    let tx = self.conn.transaction()?;
+    let changed = tx.execute("DELETE FROM work_orders WHERE id = ?1", params![id as i64])?;
+    if changed == 0 {
+      return Err(not_found("work order", id));
+    }
+    tx.execute("UPDATE tasks SET work_order_id = NULL WHERE work_order_id = ?1", params![id as i64])?;
+    tx.commit()?;
+    Ok(())
+  }
+}
+
+fn validate(work_order: &WorkOrder) -> Result<(), StorageError> {
+  work_order
+    .validate()
+    .map_err(|e| StorageError::Invalid(format!("work order {}: {}", work_order.id, e)))
+}
+
+fn work_order_from_row(row: &Row<'_>) -> rusqlite::Result<WorkOrder> {
+  Ok(WorkOrder {
+    id: row.get::<_, i64>(0)? as WorkOrderId,
+    number: row.get(1)?,
+    customer: row.get(2)?,
+    part_name: row.get(3)?,
+    quantity: row.get(4)?,
+    due_date: row.get(5)?,
+  })
+}
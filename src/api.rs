@@ -0,0 +1,293 @@
+use std::cell::RefCell;
+
+use millmill_domain::{
+    Baseline, BaselineId, Calendar, ChartExport, ColumnMapping, ExchangeFormat, ImportPreview, ImportProfile,
+    ImportProfileId, Machine, ScheduleOutcome, SchedulerOptions, Task, WorkOrder, WorkbookPreview,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_wasm_bindgen::Serializer;
+use wasm_bindgen::JsValue;
+
+use crate::app::invoke;
+
+/// Calls a Tauri command and converts both the arguments and the result with serde.
+/// The backend reports failures as plain strings, which are passed through as-is.
+pub async fn call<A, R>(cmd: &str, args: &A) -> Result<R, String>
+where
+    A: Serialize + ?Sized,
+    R: DeserializeOwned,
+{
+    // Tauri はプレーンなオブジェクトの引数しか受け付けないので Map もオブジェクトにする
+    let args = args.serialize(&Serializer::json_compatible()).map_err(|e| e.to_string())?;
+    let result = invoke(cmd, args).await.map_err(js_error_to_string)?;
+    serde_wasm_bindgen::from_value(result).map_err(|e| e.to_string())
+}
+
+/// Calls a Tauri command that answers with raw bytes rather than JSON, e.g. a file to save.
+pub async fn call_bytes<A>(cmd: &str, args: &A) -> Result<Vec<u8>, String>
+where
+    A: Serialize + ?Sized,
+{
+    let args = args.serialize(&Serializer::json_compatible()).map_err(|e| e.to_string())?;
+    let result = invoke(cmd, args).await.map_err(js_error_to_string)?;
+    Ok(js_sys::Uint8Array::new(&result).to_vec())
+}
+
+fn js_error_to_string(error: JsValue) -> String {
+    error.as_string().unwrap_or_else(|| format!("{:?}", error))
+}
+
+/// An entity stored through `load_*`, `create_*`, `update_*` and `delete_*` commands.
+pub trait Record: Clone + PartialEq + Serialize + DeserializeOwned {
+    /// Command suffix and argument name, e.g. `"task"` for `create_task(task)`.
+    const NAME: &'static str;
+    /// Plural used by the load command, e.g. `"tasks"` for `load_tasks`.
+    const PLURAL: &'static str;
+
+    fn id(&self) -> usize;
+}
+
+impl Record for Task {
+    const NAME: &'static str = "task";
+    const PLURAL: &'static str = "tasks";
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Record for Machine {
+    const NAME: &'static str = "machine";
+    const PLURAL: &'static str = "machines";
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Record for Calendar {
+    const NAME: &'static str = "calendar";
+    const PLURAL: &'static str = "calendars";
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Record for WorkOrder {
+    const NAME: &'static str = "work_order";
+    const PLURAL: &'static str = "work_orders";
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+/// Returns `None` when nothing has been saved yet.
+pub async fn load<T: Record>() -> Result<Option<Vec<T>>, String> {
+    call(&format!("load_{}", T::PLURAL), &()).await
+}
+
+/// A single change to a stored list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<T> {
+    Create(T),
+    Update(T),
+    Delete(usize),
+}
+
+impl<T: Record> Change<T> {
+    /// The id of the changed record.
+    pub fn id(&self) -> usize {
+        match self {
+            Change::Create(record) | Change::Update(record) => record.id(),
+            Change::Delete(id) => *id,
+        }
+    }
+}
+
+/// Lists the per-record commands that turn `previous` into `current`.
+pub fn diff<T: Record>(previous: &[T], current: &[T]) -> Vec<Change<T>> {
+    let mut changes = Vec::new();
+    for record in current {
+        match previous.iter().find(|r| r.id() == record.id()) {
+            None => changes.push(Change::Create(record.clone())),
+            Some(old) if old != record => changes.push(Change::Update(record.clone())),
+            Some(_) => {}
+        }
+    }
+    for record in previous {
+        if !current.iter().any(|r| r.id() == record.id()) {
+            changes.push(Change::Delete(record.id()));
+        }
+    }
+    changes
+}
+
+pub async fn apply_change<T: Record>(change: &Change<T>) -> Result<(), String> {
+    let mut args = serde_json::Map::new();
+    let (cmd, name, value) = match change {
+        Change::Create(record) => ("create", T::NAME, serde_json::to_value(record)),
+        Change::Update(record) => ("update", T::NAME, serde_json::to_value(record)),
+        Change::Delete(id) => ("delete", "id", serde_json::to_value(id)),
+    };
+    args.insert(name.to_string(), value.map_err(|e| e.to_string())?);
+    call(&format!("{}_{}", cmd, T::NAME), &args).await
+}
+
+/// Applies `changes` in order and returns the first failure. `persisted` already holds the
+/// records as if every change had been saved; a change that fails is put back to its state in
+/// `saved` there, so the next diff sends it again.
+pub async fn apply_changes<T: Record>(
+    changes: &[Change<T>],
+    persisted: &RefCell<Vec<T>>,
+    saved: &[T],
+) -> Result<(), String> {
+    let mut result = Ok(());
+    for change in changes {
+        if let Err(e) = apply_change(change).await {
+            let id = change.id();
+            let mut persisted = persisted.borrow_mut();
+            persisted.retain(|record| record.id() != id);
+            persisted.extend(saved.iter().find(|record| record.id() == id).cloned());
+            result = result.and(Err(e));
+        }
+    }
+    result
+}
+
+/// Reads an Excel workbook without changing anything. Without a profile the sheets and
+/// columns are guessed from the headers.
+pub async fn preview_workbook(
+    bytes: &[u8],
+    profile: Option<&ImportProfile>,
+    machines: &[Machine],
+    calendars: &[Calendar],
+    work_orders: &[WorkOrder],
+) -> Result<WorkbookPreview, String> {
+    let args = serde_json::json!({
+        "bytes": bytes,
+        "profile": profile,
+        "machines": machines,
+        "calendars": calendars,
+        "work_orders": work_orders,
+    });
+    call("preview_workbook", &args).await
+}
+
+/// The plan as an Excel workbook: a table of the tasks and a sheet of bars.
+pub async fn export_workbook(
+    tasks: &[Task],
+    machines: &[Machine],
+    calendars: &[Calendar],
+    work_orders: &[WorkOrder],
+) -> Result<Vec<u8>, String> {
+    let args = serde_json::json!({
+        "tasks": tasks,
+        "machines": machines,
+        "calendars": calendars,
+        "work_orders": work_orders,
+    });
+    call_bytes("export_workbook", &args).await
+}
+
+pub async fn load_import_profiles() -> Result<Vec<ImportProfile>, String> {
+    call("load_import_profiles", &()).await
+}
+
+/// Saves the profile under its name, replacing one of the same name. Returns it as stored.
+pub async fn save_import_profile(profile: &ImportProfile) -> Result<ImportProfile, String> {
+    call("save_import_profile", &serde_json::json!({ "profile": profile })).await
+}
+
+pub async fn delete_import_profile(id: ImportProfileId) -> Result<(), String> {
+    call("delete_import_profile", &serde_json::json!({ "id": id })).await
+}
+
+pub async fn load_baselines() -> Result<Vec<Baseline>, String> {
+    call("load_baselines", &()).await
+}
+
+/// Freezes the dates of the given tasks, as shown in the window. Returns the new baseline.
+pub async fn create_baseline(name: &str, tasks: &[Task]) -> Result<Baseline, String> {
+    call("create_baseline", &serde_json::json!({ "name": name, "tasks": tasks })).await
+}
+
+pub async fn delete_baseline(id: BaselineId) -> Result<(), String> {
+    call("delete_baseline", &serde_json::json!({ "id": id })).await
+}
+
+/// Places the open work order operations of the given plan. The result is not saved.
+pub async fn auto_schedule(
+    tasks: &[Task],
+    machines: &[Machine],
+    calendars: &[Calendar],
+    work_orders: &[WorkOrder],
+    options: &SchedulerOptions,
+) -> Result<ScheduleOutcome, String> {
+    let args = serde_json::json!({
+        "tasks": tasks,
+        "machines": machines,
+        "calendars": calendars,
+        "work_orders": work_orders,
+        "options": options,
+    });
+    call("auto_schedule", &args).await
+}
+
+/// Reads a CSV, MS Project XML or iCalendar file without changing anything, so the result
+/// can be checked first. Without a mapping the CSV columns are matched by their headers.
+pub async fn preview_import(
+    format: ExchangeFormat,
+    text: &str,
+    mapping: Option<&[ColumnMapping]>,
+    machines: &[Machine],
+    calendars: &[Calendar],
+    work_orders: &[WorkOrder],
+) -> Result<ImportPreview, String> {
+    let args = serde_json::json!({
+        "format": format,
+        "text": text,
+        "mapping": mapping,
+        "machines": machines,
+        "calendars": calendars,
+        "work_orders": work_orders,
+    });
+    call("preview_import", &args).await
+}
+
+pub async fn export_schedule(
+    format: ExchangeFormat,
+    tasks: &[Task],
+    machines: &[Machine],
+    calendars: &[Calendar],
+    work_orders: &[WorkOrder],
+) -> Result<String, String> {
+    let args = serde_json::json!({
+        "format": format,
+        "tasks": tasks,
+        "machines": machines,
+        "calendars": calendars,
+        "work_orders": work_orders,
+    });
+    call("export_schedule", &args).await
+}
+
+/// The chart as the file `options` asks for, drawn by the backend.
+pub async fn export_chart(
+    tasks: &[Task],
+    machines: &[Machine],
+    calendars: &[Calendar],
+    work_orders: &[WorkOrder],
+    options: &ChartExport,
+) -> Result<Vec<u8>, String> {
+    let args = serde_json::json!({
+        "tasks": tasks,
+        "machines": machines,
+        "calendars": calendars,
+        "work_orders": work_orders,
+        "options": options,
+    });
+    call_bytes("export_chart", &args).await
+}
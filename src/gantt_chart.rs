@@ -1,27 +1,227 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
-use chrono::{Duration, NaiveDateTime};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
 use stylist::yew::styled_component;
 use wasm_bindgen::JsCast;
-use web_sys::{WheelEvent, MouseEvent};
+use web_sys::{KeyboardEvent, WheelEvent, MouseEvent};
+use gloo::render::{request_animation_frame, AnimationFrame};
+use gloo_events::EventListener;
 use implicit_clone::ImplicitClone;
-use serde::{Serialize, Deserialize};
-use serde_json;
+use millmill_domain::{
+    analyze_schedule, find_conflicts, lift_orphans, lot_id, merge_segments, outline, rejoin_segments,
+    reschedule_successors, segments, setup_blocks, split_task, subtree, topological_order,
+    missed_deadlines, validate_dependencies, validate_hierarchy, variance, Baseline, BaselineId, Calendar,
+    CalendarId, ChartExport, ColumnMapping, Dependency, DependencyKind, ExchangeFormat, ImportProfile, ImportProfileId, Machine, MachineId, MissedDeadline, SchedulerOptions, SetupKey, SetupMatrix,
+    SetupTime, Shift, SkipReason, SplitPoint, Task, TaskId, TaskKind, TaskStatus, Validate, WorkOrder, WorkOrderId,
+    WorkbookPreview, XLSX_EXTENSION, XLSX_MIME_TYPE,
+};
 
+use crate::api;
 use crate::styles::*;
 use yew::prelude::*;
-// use tauri::invoke;
 use yew::platform::spawn_local;
 
-#[derive(Clone, PartialEq, Debug, ImplicitClone, Serialize, Deserialize)]
-struct Task {
-    id: usize,
-    name: String,
-    start_date: NaiveDateTime,
-    end_date: NaiveDateTime,
-    color: String,
-    is_dragging: bool,
-    drag_offset: i64,
-    drag_start_x: f64,
+mod auto_schedule;
+mod baselines;
+mod calendars;
+mod canvas;
+mod conflicts;
+mod dependencies;
+mod exchange;
+mod history;
+mod lanes;
+mod machine_editor;
+mod print;
+mod progress;
+mod selection;
+mod split;
+mod timeline;
+mod wbs_tree;
+mod work_orders;
+mod workbook;
+
+use auto_schedule::SchedulerToolbar;
+use baselines::{BaselineToolbar, VarianceReport};
+use calendars::{CalendarPanel, MachineCalendars};
+use canvas::{CanvasBars, Renderer};
+use conflicts::ConflictPanel;
+use dependencies::{DependencyArrows, DependencyEditor};
+use exchange::{download, import_action, ExchangeToolbar, ImportDialog, PendingImport};
+use history::{HistoryPanel, Schedule, ScheduleAction};
+use lanes::{Grouping, LaneLayout};
+use machine_editor::MachineEditor;
+use print::ChartExportToolbar;
+use progress::ProgressEditor;
+use selection::{add_working_days, RubberBand, SelectionToolbar};
+use split::SplitEditor;
+use timeline::{now, today, SnapGrid, Timeline, TimelineHeader, DEFAULT_DAY_WIDTH, MAX_DAY_WIDTH, MIN_DAY_WIDTH, ZOOM_PRESETS, ZOOM_STEP};
+use wbs_tree::{ParentSelect, WbsTree};
+use work_orders::{WorkOrderPanel, WorkOrderSelect};
+use workbook::{PendingWorkbook, WorkbookDialog};
+
+const ROW_HEIGHT: i64 = 30;
+/// サマリーのバーの色
+const SUMMARY_COLOR: &str = "#455a64";
+/// 進捗率の分だけバーに重ねる色
+const PROGRESS_FILL: &str = "rgba(0, 0, 0, 0.25)";
+/// 実績の帯の高さ (px)。バーの下端に重ねる
+const ACTUAL_BAR_HEIGHT: i64 = 4;
+/// マイルストーンのひし形の幅と高さ (px)
+const MILESTONE_SIZE: i64 = 16;
+/// 段取りの斜線の色。前のジョブと重なる段取りは赤
+const SETUP_COLOR: &str = "#795548";
+const SETUP_SHORT_COLOR: &str = "#d32f2f";
+/// 受注の納期の縦線の色
+const DUE_DATE_COLOR: &str = "#c62828";
+/// ベースラインの影の塗りと枠
+const BASELINE_FILL: &str = "rgba(0, 0, 0, 0.08)";
+const BASELINE_STROKE: &str = "#757575";
+/// 機械のカレンダーで稼働しない時間の塗り
+const NON_WORKING_FILL: &str = "rgba(0, 0, 0, 0.06)";
+/// 端のつまみで縮められる最短の長さ (分)
+const MIN_DURATION_MINUTES: i64 = 15;
+
+/// バー全体を動かすか、端をつまんで長さを変えるか
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum DragMode {
+    Move,
+    ResizeStart,
+    ResizeEnd,
+}
+
+impl DragMode {
+    /// 履歴に表示する操作名
+    fn label(self) -> &'static str {
+        match self {
+            DragMode::Move => "Move task",
+            DragMode::ResizeStart | DragMode::ResizeEnd => "Resize task",
+        }
+    }
+}
+
+/// ドラッグ中のタスクの表示用の状態 (保存はされない)
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct DragState {
+    task_id: TaskId,
+    mode: DragMode,
+    start_x: f64,
+    start_y: f64,
+    /// 横方向の移動量 (分)。刻みに丸めた値
+    offset_minutes: i64,
+    /// 縦方向の移動量 (px)。離した位置のレーンの機械に割り当て直す
+    offset_y: f64,
+    /// 選択中のタスクやサマリーの配下をまとめて同じ量だけ動かす (機械は変えない)
+    group: bool,
+}
+
+impl DragState {
+    /// ドラッグを離したときの開始と終了。`calendar` があれば、移動しても稼働時間が変わらないよう
+    /// 休止時間をまたいだ分だけ終了を延ばす
+    fn span(&self, task: &Task, calendar: Option<&Calendar>) -> (NaiveDateTime, NaiveDateTime) {
+        let offset = Duration::minutes(self.offset_minutes);
+        // 最短より短いタスクは今の長さまでしか縮めない。クリックしただけで延びないように
+        let min_duration = Duration::minutes(MIN_DURATION_MINUTES).min(task.end_date - task.start_date);
+        match self.mode {
+            DragMode::Move => {
+                let start = task.start_date + offset;
+                // 動かしていないときや休止時間にしかかからないタスクは長さを保ってずらす
+                let working = calendar
+                    .filter(|_| self.offset_minutes != 0 && !task.is_milestone())
+                    .map(|calendar| (calendar, calendar.working_minutes(task.start_date, task.end_date)))
+                    .filter(|(_, minutes)| *minutes > 0);
+                match working {
+                    Some((calendar, minutes)) => (start, calendar.add_working_minutes(start, minutes)),
+                    None => (start, task.end_date + offset),
+                }
+            }
+            DragMode::ResizeStart => ((task.start_date + offset).min(task.end_date - min_duration), task.end_date),
+            DragMode::ResizeEnd => (task.start_date, (task.end_date + offset).max(task.start_date + min_duration)),
+        }
+    }
+}
+
+/// ホイールでの横スクロール量をためておき、描画フレームごとに 1 回だけ反映する
+#[derive(Default)]
+struct PendingPan {
+    delta: f64,
+    /// 直近の描画時のスクロール位置
+    scroll_offset: i32,
+    scheduled: bool,
+    frame: Option<AnimationFrame>,
+}
+
+/// `task_id` のバーに適用するドラッグ。`moving` はドラッグと一緒に動くタスク
+fn drag_for(drag: Option<DragState>, moving: &HashSet<TaskId>, task_id: TaskId) -> Option<DragState> {
+    drag.filter(|drag| drag.task_id == task_id || moving.contains(&task_id))
+        .map(|drag| DragState { task_id, ..drag })
+}
+
+/// ドラッグと一緒に動くタスク。まとめて動かすときは選択中のタスク (選択外ならドラッグしたタスク) とその配下
+fn moving_tasks(tasks: &[Task], drag: Option<DragState>, selected: &HashSet<TaskId>) -> HashSet<TaskId> {
+    match drag {
+        Some(drag) if drag.group => {
+            let roots: Vec<TaskId> = if selected.contains(&drag.task_id) {
+                selected.iter().copied().collect()
+            } else {
+                vec![drag.task_id]
+            };
+            roots.into_iter().flat_map(|id| subtree(tasks, id)).collect()
+        }
+        Some(drag) => HashSet::from([drag.task_id]),
+        None => HashSet::new(),
+    }
+}
+
+/// バーの左端と幅 (px)。`drag` がこのタスクのものなら移動後の位置を返す。`calendar` はタスクの機械のもの
+fn bar_span(task: &Task, timeline: &Timeline, drag: Option<DragState>, calendar: Option<&Calendar>) -> (i64, i64) {
+    let (start, end) = drag
+        .filter(|drag| drag.task_id == task.id)
+        .map_or((task.start_date, task.end_date), |drag| drag.span(task, calendar));
+    let left = timeline.x(start);
+    (left, timeline.x(end) - left)
+}
+
+/// 実績の帯の左端と幅 (px)、および予定の終了より遅れているか。作業中なら今の時刻までを描く。
+/// 作業を始めていなければ None
+fn actual_span(task: &Task, timeline: &Timeline, now: NaiveDateTime) -> Option<(i64, i64, bool)> {
+    let start = task.actual_start?;
+    let end = task.actual_end.unwrap_or(now).max(start);
+    let left = timeline.x(start);
+    Some((left, (timeline.x(end) - left).max(2), end > task.end_date))
+}
+
+/// 余裕時間や所要時間を "1d 2h 30m" の形式で表示する
+fn format_duration(minutes: i64) -> String {
+    if minutes <= 0 {
+        return "0m".to_string();
+    }
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    let mut parts = Vec::new();
+    if days > 0 {
+        parts.push(format!("{}d", days));
+    }
+    if hours > 0 {
+        parts.push(format!("{}h", hours));
+    }
+    if minutes > 0 {
+        parts.push(format!("{}m", minutes));
+    }
+    parts.join(" ")
+}
+
+/// 期間の所要時間。カレンダーがあれば稼働時間を "16h work" のように時間単位で表示する
+fn format_span_duration(start: NaiveDateTime, end: NaiveDateTime, calendar: Option<&Calendar>) -> String {
+    match calendar {
+        Some(calendar) => {
+            let minutes = calendar.working_minutes(start, end);
+            match minutes % 60 {
+                0 => format!("{}h work", minutes / 60),
+                rest => format!("{}h {}m work", minutes / 60, rest),
+            }
+        }
+        None => format_duration((end - start).num_minutes()),
+    }
 }
 
 #[derive(Clone, PartialEq, ImplicitClone)]
@@ -29,13 +229,132 @@ struct TaskFormData {
     name: String,
     start_date: String,
     end_date: String,
+    /// 終了日時は使わず、開始日時の時点のマイルストーンを作る
+    milestone: bool,
 }
 
-fn initial_tasks() -> Vec<Task> {
-    let base_date = NaiveDateTime::parse_from_str("2025-03-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap_or_else(|e| {
-        log::error!("Failed to parse base date: {}", e);
-        NaiveDateTime::default()
+/// 保存する順に並べる。後ろの区切りは保存済みの先頭しか、後続作業は保存済みの先行作業しか、
+/// 子は保存済みの親しか指せないので、分割の先頭と先行作業と親を先にする
+fn sort_for_saving(upserts: &mut [api::Change<Task>]) {
+    upserts.sort_by_key(|change| {
+        matches!(change, api::Change::Create(task) | api::Change::Update(task) if task.split_of.is_some())
     });
+    // 分割の先頭と親も先行作業の 1 つとして並べる
+    let tasks: Vec<Task> = upserts
+        .iter()
+        .filter_map(|change| match change {
+            api::Change::Create(task) | api::Change::Update(task) => Some(task),
+            api::Change::Delete(_) => None,
+        })
+        .map(|task| {
+            let before = task.split_of.into_iter().chain(task.parent_id).map(|predecessor_id| Dependency {
+                predecessor_id,
+                kind: DependencyKind::FinishToStart,
+                lag_minutes: 0,
+            });
+            Task { dependencies: task.dependencies.iter().cloned().chain(before).collect(), ..task.clone() }
+        })
+        .collect();
+    // 循環していれば保存側で拒まれるので、先頭を先にした順のまま送る
+    if let Ok(order) = topological_order(&tasks) {
+        let rank: HashMap<TaskId, usize> = order.into_iter().enumerate().map(|(rank, id)| (id, rank)).collect();
+        upserts.sort_by_key(|change| rank.get(&change.id()).copied());
+    }
+}
+
+/// 保存したい計画
+struct PlanSnapshot {
+    tasks: Vec<Task>,
+    machines: Vec<Machine>,
+    work_orders: Vec<WorkOrder>,
+    calendars: Vec<Calendar>,
+}
+
+/// 保存の順番待ち。保存中に何度変更されても、待たせるのは最新の計画 1 つだけ
+#[derive(Default)]
+struct SaveQueue {
+    saving: bool,
+    pending: Option<PlanSnapshot>,
+}
+
+/// 保存済みの状態との差分を 1 件ずつ保存する。参照される側 (カレンダー、機械、受注) を先に作り、
+/// それらの削除は最後に行う。失敗したものは保存済みの状態を元に戻し、次の保存で送り直す
+async fn save_plan(
+    plan: PlanSnapshot,
+    persisted_tasks: &RefCell<Vec<Task>>,
+    persisted_machines: &RefCell<Vec<Machine>>,
+    persisted_work_orders: &RefCell<Vec<WorkOrder>>,
+    persisted_calendars: &RefCell<Vec<Calendar>>,
+) -> Result<(), String> {
+    let calendar_changes = api::diff(&persisted_calendars.borrow(), &plan.calendars);
+    let machine_changes = api::diff(&persisted_machines.borrow(), &plan.machines);
+    let work_order_changes = api::diff(&persisted_work_orders.borrow(), &plan.work_orders);
+    let task_changes = api::diff(&persisted_tasks.borrow(), &plan.tasks);
+    let saved_calendars = persisted_calendars.replace(plan.calendars);
+    let saved_machines = persisted_machines.replace(plan.machines);
+    let saved_work_orders = persisted_work_orders.replace(plan.work_orders);
+    let saved_tasks = persisted_tasks.replace(plan.tasks);
+    let (calendar_deletes, calendar_upserts): (Vec<_>, Vec<_>) =
+        calendar_changes.into_iter().partition(|change| matches!(change, api::Change::Delete(_)));
+    let (machine_deletes, machine_upserts): (Vec<_>, Vec<_>) =
+        machine_changes.into_iter().partition(|change| matches!(change, api::Change::Delete(_)));
+    let (work_order_deletes, work_order_upserts): (Vec<_>, Vec<_>) =
+        work_order_changes.into_iter().partition(|change| matches!(change, api::Change::Delete(_)));
+    let (task_deletes, mut task_upserts): (Vec<_>, Vec<_>) =
+        task_changes.into_iter().partition(|change| matches!(change, api::Change::Delete(_)));
+    sort_for_saving(&mut task_upserts);
+    api::apply_changes(&calendar_upserts, persisted_calendars, &saved_calendars)
+        .await
+        .and(api::apply_changes(&machine_upserts, persisted_machines, &saved_machines).await)
+        .and(api::apply_changes(&work_order_upserts, persisted_work_orders, &saved_work_orders).await)
+        .and(api::apply_changes(&task_upserts, persisted_tasks, &saved_tasks).await)
+        .and(api::apply_changes(&task_deletes, persisted_tasks, &saved_tasks).await)
+        .and(api::apply_changes(&work_order_deletes, persisted_work_orders, &saved_work_orders).await)
+        .and(api::apply_changes(&machine_deletes, persisted_machines, &saved_machines).await)
+        .and(api::apply_changes(&calendar_deletes, persisted_calendars, &saved_calendars).await)
+}
+
+fn initial_machines() -> Vec<Machine> {
+    vec![
+        Machine {
+            id: 1,
+            name: "Lathe 1".to_string(),
+            // 同じ品種なら爪の交換が要らない
+            setup: SetupMatrix {
+                default_minutes: 90,
+                entries: vec![SetupTime {
+                    from: SetupKey { part_family: "Shaft".to_string(), material: String::new() },
+                    to: SetupKey { part_family: "Shaft".to_string(), material: String::new() },
+                    minutes: 10,
+                }],
+            },
+            ..Default::default()
+        },
+        Machine { id: 2, name: "Machining Center 1".to_string(), calendar_id: Some(1), ..Default::default() },
+    ]
+}
+
+/// 平日と土曜の 2 交代、日曜休み
+fn initial_calendars() -> Vec<Calendar> {
+    let shifts = vec![Shift::parse("06:00-14:00").unwrap(), Shift::parse("14:00-22:00").unwrap()];
+    let mut week: [Vec<Shift>; 7] = Default::default();
+    week[..6].fill(shifts);
+    vec![Calendar { id: 1, name: "Two shifts".to_string(), week, exceptions: Vec::new() }]
+}
+
+fn initial_work_orders() -> Vec<WorkOrder> {
+    vec![WorkOrder {
+        id: 1,
+        number: "WO-0001".to_string(),
+        customer: String::new(),
+        part_name: "Sample part".to_string(),
+        quantity: 10,
+        due_date: Some(today() + Duration::days(15)),
+    }]
+}
+
+fn initial_tasks() -> Vec<Task> {
+    let base_date = today();
     vec![
         Task { 
             id: 1, 
@@ -43,9 +362,20 @@ fn initial_tasks() -> Vec<Task> {
             start_date: base_date, 
             end_date: base_date + Duration::days(5), 
             color: "#4CAF50".to_string(),
-            is_dragging: false,
-            drag_offset: 0,
-            drag_start_x: 0.0
+            description: String::new(),
+            kind: TaskKind::Task,
+            status: TaskStatus::Planned,
+            work_order_id: Some(1),
+            machine_id: Some(1),
+            dependencies: Vec::new(),
+            parent_id: None,
+            percent_complete: 0.0,
+            actual_start: None,
+            actual_end: None,
+            part_family: "Shaft".to_string(),
+            material: "S45C".to_string(),
+            quantity: 10,
+            split_of: None,
         },
         Task { 
             id: 2, 
@@ -53,9 +383,24 @@ fn initial_tasks() -> Vec<Task> {
             start_date: base_date + Duration::days(6), 
             end_date: base_date + Duration::days(9), 
             color: "#FF9800".to_string(),
-            is_dragging: false,
-            drag_offset: 0,
-            drag_start_x: 0.0
+            description: String::new(),
+            kind: TaskKind::Task,
+            status: TaskStatus::Planned,
+            work_order_id: Some(1),
+            machine_id: Some(2),
+            dependencies: vec![Dependency {
+                predecessor_id: 1,
+                kind: DependencyKind::FinishToStart,
+                lag_minutes: 0,
+            }],
+            parent_id: None,
+            percent_complete: 0.0,
+            actual_start: None,
+            actual_end: None,
+            part_family: "Shaft".to_string(),
+            material: "S45C".to_string(),
+            quantity: 10,
+            split_of: None,
         },
         Task { 
             id: 3, 
@@ -63,52 +408,761 @@ fn initial_tasks() -> Vec<Task> {
             start_date: base_date + Duration::days(10), 
             end_date: base_date + Duration::days(14), 
             color: "#673AB7".to_string(),
-            is_dragging: false,
-            drag_offset: 0,
-            drag_start_x: 0.0
+            description: String::new(),
+            kind: TaskKind::Task,
+            status: TaskStatus::Planned,
+            work_order_id: Some(1),
+            machine_id: Some(1),
+            dependencies: vec![Dependency {
+                predecessor_id: 2,
+                kind: DependencyKind::FinishToStart,
+                lag_minutes: 0,
+            }],
+            parent_id: None,
+            percent_complete: 0.0,
+            actual_start: None,
+            actual_end: None,
+            part_family: "Shaft".to_string(),
+            material: "S45C".to_string(),
+            quantity: 10,
+            split_of: None,
+        },
+        Task {
+            id: 4,
+            name: "Ship".to_string(),
+            start_date: base_date + Duration::days(14),
+            end_date: base_date + Duration::days(14),
+            color: "#E91E63".to_string(),
+            kind: TaskKind::Milestone,
+            work_order_id: Some(1),
+            dependencies: vec![Dependency {
+                predecessor_id: 3,
+                kind: DependencyKind::FinishToStart,
+                lag_minutes: 0,
+            }],
+            ..Default::default()
         },
     ]
 }
 
 #[styled_component(GanttChart)]
 pub fn gantt_chart() -> Html {
-    let tasks = use_state(|| {
-        initial_tasks()
+    // タスクと機械と受注とカレンダーは履歴付きのリデューサーで管理し、変更はすべて ScheduleAction を通す
+    let schedule = use_reducer(|| {
+        Schedule::new(initial_tasks(), initial_machines(), initial_work_orders(), initial_calendars())
     });
+    let is_loaded = use_state(|| false);
+    let error_message = use_state(|| None::<String>);
+    // バックエンドに保存済みのデータ (差分の計算に使う)
+    let persisted_tasks = use_mut_ref(Vec::<Task>::new);
+    let persisted_machines = use_mut_ref(Vec::<Machine>::new);
+    let persisted_work_orders = use_mut_ref(Vec::<WorkOrder>::new);
+    let persisted_calendars = use_mut_ref(Vec::<Calendar>::new);
+    let save_queue = use_mut_ref(SaveQueue::default);
 
-    // タスクの変更を保存する
-    let save_tasks = {
-        let tasks = tasks.clone();
-        Callback::from(move |_| {
-            let tasks = (*tasks).clone();
+    // マウント時に保存済みのカレンダーと機械と受注とタスクを読み込む
+    {
+        let schedule = schedule.clone();
+        let is_loaded = is_loaded.clone();
+        let error_message = error_message.clone();
+        let persisted_tasks = persisted_tasks.clone();
+        let persisted_machines = persisted_machines.clone();
+        let persisted_work_orders = persisted_work_orders.clone();
+        let persisted_calendars = persisted_calendars.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                let loaded = async {
+                    Ok::<_, String>((
+                        api::load::<Calendar>().await?,
+                        api::load::<Machine>().await?,
+                        api::load::<WorkOrder>().await?,
+                        api::load::<Task>().await?,
+                    ))
+                };
+                match loaded.await {
+                    Ok((stored_calendars, stored_machines, stored_work_orders, stored_tasks)) => {
+                        if let Some(stored) = &stored_calendars {
+                            *persisted_calendars.borrow_mut() = stored.clone();
+                        }
+                        if let Some(stored) = &stored_machines {
+                            *persisted_machines.borrow_mut() = stored.clone();
+                        }
+                        if let Some(stored) = &stored_work_orders {
+                            *persisted_work_orders.borrow_mut() = stored.clone();
+                        }
+                        if let Some(stored) = &stored_tasks {
+                            *persisted_tasks.borrow_mut() = stored.clone();
+                        }
+                        schedule.dispatch(ScheduleAction::Load {
+                            tasks: stored_tasks,
+                            machines: stored_machines,
+                            work_orders: stored_work_orders,
+                            calendars: stored_calendars,
+                        });
+                        is_loaded.set(true);
+                    }
+                    // 読み込みに失敗した場合は保存済みデータを上書きしないよう保存を無効のままにする
+                    Err(e) => error_message.set(Some(format!("Failed to load schedule: {}", e))),
+                }
+            });
+            || ()
+        });
+    }
+
+    // 保存済みのベースライン。スケジュールとは別に読み込み、履歴にも含めない
+    let baselines = use_state(|| Rc::new(Vec::<Baseline>::new()));
+    let shown_baseline = use_state(|| None::<BaselineId>);
+    {
+        let baselines = baselines.clone();
+        let error_message = error_message.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                match api::load_baselines().await {
+                    Ok(loaded) => baselines.set(Rc::new(loaded)),
+                    Err(e) => error_message.set(Some(format!("Failed to load baselines: {}", e))),
+                }
+            });
+            || ()
+        });
+    }
+
+    // Ctrl+Z で取り消し、Ctrl+Shift+Z でやり直す。入力欄の中ではブラウザの取り消しに任せる
+    {
+        let dispatcher = schedule.dispatcher();
+        use_effect_with((), move |_| {
+            let window = web_sys::window().unwrap();
+            let listener = EventListener::new(&window, "keydown", move |event| {
+                let Some(event) = event.dyn_ref::<KeyboardEvent>() else {
+                    return;
+                };
+                if !(event.ctrl_key() || event.meta_key()) || !event.key().eq_ignore_ascii_case("z") {
+                    return;
+                }
+                let in_input = event
+                    .target()
+                    .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+                    .is_some_and(|element| matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT"));
+                if in_input {
+                    return;
+                }
+                event.prevent_default();
+                dispatcher.dispatch(if event.shift_key() { ScheduleAction::Redo } else { ScheduleAction::Undo });
+            });
+            move || drop(listener)
+        });
+    }
+
+    // 保存は 1 つずつ順に行う。保存中に届いた変更は待たせ、前の保存が終わってから最新の保存済みの状態と比べる。
+    // 並行して送ると、作成より先に更新が届いたり、古い書き込みが新しいものを上書きしたりする
+    let save_changes = {
+        let error_message = error_message.clone();
+        let save_queue = save_queue.clone();
+        let persisted_tasks = persisted_tasks.clone();
+        let persisted_machines = persisted_machines.clone();
+        let persisted_work_orders = persisted_work_orders.clone();
+        let persisted_calendars = persisted_calendars.clone();
+        Callback::from(move |schedule: UseReducerHandle<Schedule>| {
+            let mut queue = save_queue.borrow_mut();
+            queue.pending = Some(PlanSnapshot {
+                tasks: schedule.tasks.clone(),
+                machines: schedule.machines.clone(),
+                work_orders: schedule.work_orders.clone(),
+                calendars: schedule.calendars.clone(),
+            });
+            if queue.saving {
+                return;
+            }
+            queue.saving = true;
+            drop(queue);
+            let error_message = error_message.clone();
+            let save_queue = save_queue.clone();
+            let persisted_tasks = persisted_tasks.clone();
+            let persisted_machines = persisted_machines.clone();
+            let persisted_work_orders = persisted_work_orders.clone();
+            let persisted_calendars = persisted_calendars.clone();
             spawn_local(async move {
-                // let _ = invoke("save_tasks", tasks).await;
+                loop {
+                    let Some(plan) = save_queue.borrow_mut().pending.take() else {
+                        break;
+                    };
+                    let result =
+                        save_plan(plan, &persisted_tasks, &persisted_machines, &persisted_work_orders, &persisted_calendars)
+                            .await;
+                    if let Err(e) = result {
+                        error_message.set(Some(format!("Failed to save schedule: {}", e)));
+                    }
+                }
+                save_queue.borrow_mut().saving = false;
             });
         })
     };
 
-    // タスクの更新時に保存を実行
-    use_effect_with(
-        (*tasks).clone(),
-        move |tasks| {
-            save_tasks.emit(());
+    // タスクや機械の一覧は大きくなりうるので、依存値には一覧の代わりに変更番号を使う
+    let revision = schedule.revision();
+
+    // スケジュールの更新時に保存を実行
+    {
+        let schedule = schedule.clone();
+        use_effect_with((revision, *is_loaded), move |(_, is_loaded)| {
+            if *is_loaded {
+                save_changes.emit(schedule.clone());
+            }
             || ()
+        });
+    }
+
+    // バーの描画に渡す一覧 (変更があったときだけ複製する)
+    let tasks = use_memo(revision, |_| schedule.tasks.clone());
+
+    // クリティカルパスと余裕時間
+    let analysis = use_memo(revision, |_| match analyze_schedule(&schedule.tasks) {
+        Ok(analysis) => Some(analysis),
+        Err(e) => {
+            log::error!("Failed to analyze schedule: {}", e);
+            None
+        }
+    });
+
+    // 行の並べ方と、折りたたんだサマリー
+    let grouping = use_state(|| Grouping::Machines);
+    let collapsed = use_state(HashSet::<TaskId>::new);
+    let outline_rows = use_memo((revision, (*collapsed).clone()), |(_, collapsed)| outline(&schedule.tasks, collapsed));
+    // 機械ごとのレーン配置、または親子関係の順に 1 タスク 1 行
+    let layout = use_memo((revision, *grouping, outline_rows.clone()), |(_, grouping, rows)| match grouping {
+        Grouping::Machines => LaneLayout::new(&schedule.tasks, &schedule.machines),
+        Grouping::Outline => LaneLayout::outline(&schedule.tasks, rows),
+    });
+    // 子を持つタスク
+    let summary_tasks = use_memo(revision, |_| schedule.tasks.iter().filter_map(|t| t.parent_id).collect::<HashSet<_>>());
+    let milestones = use_memo(revision, |_| schedule.tasks.iter().filter(|t| t.is_milestone()).map(|t| t.id).collect::<HashSet<_>>());
+
+    // 時間軸の原点。None のときはタスクの日付から決める
+    let timeline_origin = use_state(|| None::<NaiveDate>);
+    // ズーム (1 日あたりの幅 px)
+    let day_width = use_state(|| DEFAULT_DAY_WIDTH);
+    let viewport_width = web_sys::window()
+        .and_then(|window| window.inner_width().ok())
+        .and_then(|width| width.as_f64())
+        .unwrap_or(1920.0) as i64;
+    let timeline = use_memo(
+        (revision, *timeline_origin, *day_width, viewport_width),
+        |(_, origin, day_width, viewport_width)| {
+            Timeline::new(&schedule.tasks, *origin, *day_width).extended_to(*viewport_width)
         },
     );
 
-    let zoom_level = use_state(|| 50);
+    // 機械ごとの稼働カレンダー。ドラッグ時の長さと休止時間の網掛けに使う
+    let machine_calendars = use_memo(revision, |_| MachineCalendars::new(&schedule.machines, &schedule.calendars));
+
+    // 機械ごとの負荷超過とメンテナンス中の割り当て
+    let conflicts = use_memo(revision, |_| find_conflicts(&schedule.tasks, &schedule.machines));
+    let critical_tasks = use_memo(revision, |_| {
+        schedule.tasks.iter().map(|t| t.id).filter(|id| analysis.as_ref().as_ref().is_some_and(|a| a.is_critical(*id))).collect::<HashSet<_>>()
+    });
+    let conflicting_tasks = use_memo(revision, |_| {
+        conflicts.iter().flat_map(|c| c.task_ids.iter().copied()).collect::<HashSet<_>>()
+    });
+
+    // 受注の納期より後に終わるタスク
+    let missed = use_memo(revision, |_| {
+        missed_deadlines(&schedule.tasks, &schedule.work_orders)
+            .into_iter()
+            .map(|missed| (missed.task_id, missed))
+            .collect::<HashMap<_, _>>()
+    });
+
+    // 分割したタスクの何番目か (番号, 分割数)。バーに [1/2] のように表示する
+    let segment_numbers = use_memo(revision, |_| {
+        let mut lots: HashMap<TaskId, Vec<&Task>> = HashMap::new();
+        for task in schedule.tasks.iter() {
+            lots.entry(lot_id(task)).or_default().push(task);
+        }
+        lots.into_values()
+            .filter(|lot| lot.len() > 1)
+            .flat_map(|mut lot| {
+                lot.sort_by_key(|task| (task.start_date, task.id));
+                let count = lot.len();
+                lot.into_iter().enumerate().map(move |(i, task)| (task.id, (i + 1, count))).collect::<Vec<_>>()
+            })
+            .collect::<HashMap<_, _>>()
+    });
+
+    // 表示中のベースラインの予定と、そこからのずれ
+    let shown = baselines.iter().find(|b| Some(b.id) == *shown_baseline).map(|b| b.name.clone());
+    let baseline_spans = use_memo(((*baselines).clone(), *shown_baseline), |(baselines, shown)| {
+        baselines
+            .iter()
+            .filter(|baseline| Some(baseline.id) == *shown)
+            .flat_map(|baseline| baseline.tasks.iter().map(|task| (task.task_id, (task.start_date, task.end_date))))
+            .collect::<HashMap<_, _>>()
+    });
+    let baseline_variance = use_memo((revision, (*baselines).clone(), *shown_baseline), |(_, baselines, shown)| {
+        Rc::new(
+            baselines
+                .iter()
+                .find(|baseline| Some(baseline.id) == *shown)
+                .map(|baseline| variance(baseline, &schedule.tasks))
+                .unwrap_or_default(),
+        )
+    });
+
     let scroll_offset = use_state(|| 0);
-    let selected_task = use_state(|| None::<Task>);
+    let pending_pan = use_mut_ref(PendingPan::default);
+    pending_pan.borrow_mut().scroll_offset = *scroll_offset;
+    let renderer = use_state(|| Renderer::Auto);
     let show_task_form = use_state(|| false);
     let show_floating_window = use_state(|| false);
     let floating_window_position = use_state(|| (0.0, 0.0));
     let editing_task = use_state(|| None::<Task>);
-    let dragging_task = use_state(|| None::<usize>);
+    let dragging_task = use_state(|| None::<DragState>);
+    let snap = use_state(|| SnapGrid::Hour);
+    let selected_tasks = use_state(HashSet::<TaskId>::new);
+    let rubber_band = use_state(|| None::<RubberBand>);
+    // 直前のマウス操作でバーを動かしたか。動かした後のクリックでは編集ウィンドウを開かない
+    let drag_moved = use_mut_ref(|| false);
+    let moving = use_memo(
+        (revision, dragging_task.map(|drag| (drag.task_id, drag.group)), (*selected_tasks).clone()),
+        |(_, _, selected)| moving_tasks(&schedule.tasks, *dragging_task, selected),
+    );
+    // 段取りは同じ機械で前後するジョブから決まるので、ドラッグ中は動かした先の順で並べ直す
+    let setups = use_memo((revision, *dragging_task, moving.clone()), |(_, dragging, moving)| {
+        let tasks: Vec<Task> = schedule
+            .tasks
+            .iter()
+            .map(|task| match drag_for(*dragging, moving, task.id) {
+                Some(drag) => {
+                    let (start_date, end_date) = drag.span(task, machine_calendars.of(task));
+                    Task { start_date, end_date, ..task.clone() }
+                }
+                None => task.clone(),
+            })
+            .collect();
+        setup_blocks(&tasks, &schedule.machines)
+    });
     let task_form_data = use_state(|| TaskFormData {
         name: String::new(),
         start_date: String::new(),
         end_date: String::new(),
+        milestone: false,
     });
+    let new_machine_name = use_state(String::new);
+    let editing_machine = use_state(|| None::<MachineId>);
+    let selected_conflict = use_state(|| None::<usize>);
+
+    // 画面のタスクの日時をベースラインとして固定し、そのまま影として表示する。
+    // 保存済みのものを使うと、保存中の直前の編集が入らない
+    let create_baseline = {
+        let schedule = schedule.clone();
+        let baselines = baselines.clone();
+        let shown_baseline = shown_baseline.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |name: String| {
+            let tasks = schedule.tasks.clone();
+            let baselines = baselines.clone();
+            let shown_baseline = shown_baseline.clone();
+            let error_message = error_message.clone();
+            spawn_local(async move {
+                match api::create_baseline(&name, &tasks).await {
+                    Ok(baseline) => {
+                        shown_baseline.set(Some(baseline.id));
+                        let mut next = (**baselines).clone();
+                        next.push(baseline);
+                        baselines.set(Rc::new(next));
+                    }
+                    Err(e) => error_message.set(Some(format!("Failed to save baseline: {}", e))),
+                }
+            });
+        })
+    };
+    let delete_baseline = {
+        let baselines = baselines.clone();
+        let shown_baseline = shown_baseline.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |id: BaselineId| {
+            let baselines = baselines.clone();
+            let shown_baseline = shown_baseline.clone();
+            let error_message = error_message.clone();
+            spawn_local(async move {
+                match api::delete_baseline(id).await {
+                    Ok(()) => {
+                        shown_baseline.set(None);
+                        baselines.set(Rc::new(baselines.iter().filter(|b| b.id != id).cloned().collect()));
+                    }
+                    Err(e) => error_message.set(Some(format!("Failed to delete baseline: {}", e))),
+                }
+            });
+        })
+    };
+
+    // 受注の工程を機械の空きに並べ直す。結果は 1 回の編集として履歴に積むので元に戻せる
+    let scheduling = use_state(|| false);
+    let last_schedule_run = use_state(|| None::<String>);
+    let run_scheduler = {
+        let schedule = schedule.clone();
+        let scheduling = scheduling.clone();
+        let last_schedule_run = last_schedule_run.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |options: SchedulerOptions| {
+            let schedule = schedule.clone();
+            let scheduling = scheduling.clone();
+            let last_schedule_run = last_schedule_run.clone();
+            let error_message = error_message.clone();
+            scheduling.set(true);
+            spawn_local(async move {
+                let result = api::auto_schedule(
+                    &schedule.tasks,
+                    &schedule.machines,
+                    &schedule.calendars,
+                    &schedule.work_orders,
+                    &options,
+                )
+                .await;
+                scheduling.set(false);
+                let outcome = match result {
+                    Ok(outcome) => outcome,
+                    Err(e) => return error_message.set(Some(format!("Failed to schedule: {}", e))),
+                };
+                let name = |id: TaskId| {
+                    outcome.tasks.iter().find(|task| task.id == id).map_or_else(|| id.to_string(), |task| task.name.clone())
+                };
+                let mut warnings = Vec::new();
+                for skipped in &outcome.skipped {
+                    let reason = match skipped.reason {
+                        SkipReason::NoMachine => "it has no machine",
+                        SkipReason::NoDueDate => "its work order has no due date",
+                    };
+                    warnings.push(format!("{} was not scheduled because {}", name(skipped.task_id), reason));
+                }
+                if !outcome.infeasible.is_empty() {
+                    let names = outcome.infeasible.iter().map(|id| name(*id)).collect::<Vec<_>>().join(", ");
+                    warnings.push(format!("{} would have to start before now to meet the due date", names));
+                }
+                error_message.set((!warnings.is_empty()).then(|| warnings.join("; ")));
+                last_schedule_run.set(Some(format!(
+                    "{} moved ({}, {})",
+                    outcome.moved.len(),
+                    options.rule.code(),
+                    options.direction.code()
+                )));
+                if !outcome.moved.is_empty() {
+                    let label = format!("Auto schedule ({}, {})", options.rule.code(), options.direction.code());
+                    schedule.dispatch(ScheduleAction::edit_tasks(&label, outcome.tasks));
+                }
+            });
+        })
+    };
+
+    // CSV・MS Project XML・iCalendar の受け渡し。読み込みは確認してから 1 回の編集として取り込む
+    let pending_import = use_state(|| None::<PendingImport>);
+    let export_schedule = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |format: ExchangeFormat| {
+            let schedule = schedule.clone();
+            let error_message = error_message.clone();
+            spawn_local(async move {
+                let result = api::export_schedule(
+                    format,
+                    &schedule.tasks,
+                    &schedule.machines,
+                    &schedule.calendars,
+                    &schedule.work_orders,
+                )
+                .await
+                .and_then(|text| download(&format!("schedule.{}", format.extension()), format.mime_type(), text.as_str()));
+                if let Err(e) = result {
+                    error_message.set(Some(format!("Failed to export: {}", e)));
+                }
+            });
+        })
+    };
+    let preview_import = {
+        let schedule = schedule.clone();
+        let pending_import = pending_import.clone();
+        let error_message = error_message.clone();
+        move |file_name: String, format: ExchangeFormat, text: String, mapping: Option<Vec<ColumnMapping>>| {
+            let schedule = schedule.clone();
+            let pending_import = pending_import.clone();
+            let error_message = error_message.clone();
+            spawn_local(async move {
+                let result = api::preview_import(
+                    format,
+                    &text,
+                    mapping.as_deref(),
+                    &schedule.machines,
+                    &schedule.calendars,
+                    &schedule.work_orders,
+                )
+                .await;
+                match result {
+                    Ok(preview) => pending_import.set(Some(PendingImport { file_name, format, text, preview })),
+                    Err(e) => error_message.set(Some(format!("Failed to read {}: {}", file_name, e))),
+                }
+            });
+        }
+    };
+    let open_import = {
+        let preview_import = preview_import.clone();
+        Callback::from(move |(file_name, format, text): (String, ExchangeFormat, String)| {
+            preview_import(file_name, format, text, None)
+        })
+    };
+    let remap_import = {
+        let pending_import = pending_import.clone();
+        Callback::from(move |mapping: Vec<ColumnMapping>| {
+            if let Some(pending) = (*pending_import).clone() {
+                preview_import(pending.file_name, pending.format, pending.text, Some(mapping));
+            }
+        })
+    };
+    let apply_import = {
+        let schedule = schedule.clone();
+        let pending_import = pending_import.clone();
+        Callback::from(move |replace: bool| {
+            let Some(pending) = (*pending_import).clone() else {
+                return;
+            };
+            schedule.dispatch(import_action(&schedule, &pending.file_name, pending.preview, replace));
+            pending_import.set(None);
+        })
+    };
+    let cancel_import = {
+        let pending_import = pending_import.clone();
+        Callback::from(move |_| pending_import.set(None))
+    };
+    let show_error = {
+        let error_message = error_message.clone();
+        Callback::from(move |message: String| error_message.set(Some(message)))
+    };
+
+    // Excel の受注リストと予定表。シートと列の割り当ては名前を付けて保存しておける
+    let pending_workbook = use_state(|| None::<PendingWorkbook>);
+    let import_profiles = use_state(Vec::<ImportProfile>::new);
+    let export_workbook = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |_| {
+            let schedule = schedule.clone();
+            let error_message = error_message.clone();
+            spawn_local(async move {
+                let result = api::export_workbook(
+                    &schedule.tasks,
+                    &schedule.machines,
+                    &schedule.calendars,
+                    &schedule.work_orders,
+                )
+                .await
+                .and_then(|bytes| download(&format!("schedule.{}", XLSX_EXTENSION), XLSX_MIME_TYPE, bytes.as_slice()));
+                if let Err(e) = result {
+                    error_message.set(Some(format!("Failed to export: {}", e)));
+                }
+            });
+        })
+    };
+    let preview_workbook = {
+        let schedule = schedule.clone();
+        let pending_workbook = pending_workbook.clone();
+        let error_message = error_message.clone();
+        move |file_name: String, bytes: Rc<Vec<u8>>, profile: Option<ImportProfile>| {
+            let schedule = schedule.clone();
+            let pending_workbook = pending_workbook.clone();
+            let error_message = error_message.clone();
+            spawn_local(async move {
+                let result = api::preview_workbook(
+                    &bytes,
+                    profile.as_ref(),
+                    &schedule.machines,
+                    &schedule.calendars,
+                    &schedule.work_orders,
+                )
+                .await;
+                match result {
+                    Ok(preview) => pending_workbook.set(Some(PendingWorkbook { file_name, bytes, preview })),
+                    Err(e) => error_message.set(Some(format!("Failed to read {}: {}", file_name, e))),
+                }
+            });
+        }
+    };
+    let open_workbook = {
+        let preview_workbook = preview_workbook.clone();
+        let import_profiles = import_profiles.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |(file_name, bytes): (String, Vec<u8>)| {
+            let import_profiles = import_profiles.clone();
+            let error_message = error_message.clone();
+            spawn_local(async move {
+                match api::load_import_profiles().await {
+                    Ok(profiles) => import_profiles.set(profiles),
+                    Err(e) => error_message.set(Some(format!("Failed to load mappings: {}", e))),
+                }
+            });
+            preview_workbook(file_name, Rc::new(bytes), None)
+        })
+    };
+    let reread_workbook = {
+        let pending_workbook = pending_workbook.clone();
+        let preview_workbook = preview_workbook.clone();
+        Callback::from(move |profile: Option<ImportProfile>| {
+            if let Some(pending) = (*pending_workbook).clone() {
+                preview_workbook(pending.file_name, pending.bytes, profile);
+            }
+        })
+    };
+    let save_import_profile = {
+        let pending_workbook = pending_workbook.clone();
+        let import_profiles = import_profiles.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |name: String| {
+            let Some(pending) = (*pending_workbook).clone() else {
+                return;
+            };
+            let pending_workbook = pending_workbook.clone();
+            let import_profiles = import_profiles.clone();
+            let error_message = error_message.clone();
+            spawn_local(async move {
+                let profile = ImportProfile { name, ..pending.preview.profile.clone() };
+                match api::save_import_profile(&profile).await {
+                    Ok(saved) => {
+                        let mut profiles: Vec<_> =
+                            import_profiles.iter().filter(|profile| profile.id != saved.id).cloned().collect();
+                        profiles.push(saved.clone());
+                        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+                        import_profiles.set(profiles);
+                        let preview = WorkbookPreview { profile: saved, ..pending.preview };
+                        pending_workbook.set(Some(PendingWorkbook { preview, ..pending }));
+                    }
+                    Err(e) => error_message.set(Some(format!("Failed to save the mapping: {}", e))),
+                }
+            });
+        })
+    };
+    let delete_import_profile = {
+        let pending_workbook = pending_workbook.clone();
+        let import_profiles = import_profiles.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |id: ImportProfileId| {
+            let pending_workbook = pending_workbook.clone();
+            let import_profiles = import_profiles.clone();
+            let error_message = error_message.clone();
+            spawn_local(async move {
+                match api::delete_import_profile(id).await {
+                    Ok(()) => {
+                        import_profiles.set(import_profiles.iter().filter(|profile| profile.id != id).cloned().collect());
+                        // 割り当てはそのまま、保存されていないものとして扱う
+                        if let Some(mut pending) = (*pending_workbook).clone() {
+                            pending.preview.profile.id = 0;
+                            pending_workbook.set(Some(pending));
+                        }
+                    }
+                    Err(e) => error_message.set(Some(format!("Failed to delete the mapping: {}", e))),
+                }
+            });
+        })
+    };
+    let apply_workbook = {
+        let schedule = schedule.clone();
+        let pending_workbook = pending_workbook.clone();
+        Callback::from(move |replace: bool| {
+            let Some(pending) = (*pending_workbook).clone() else {
+                return;
+            };
+            schedule.dispatch(import_action(&schedule, &pending.file_name, pending.preview.import, replace));
+            pending_workbook.set(None);
+        })
+    };
+    let cancel_workbook = {
+        let pending_workbook = pending_workbook.clone();
+        Callback::from(move |_| pending_workbook.set(None))
+    };
+
+    // チャートの書き出し。描画はバックエンドで行い、今のズームと表示の切り替えをそのまま使う
+    let exporting_chart = use_state(|| false);
+    let export_chart = {
+        let schedule = schedule.clone();
+        let exporting_chart = exporting_chart.clone();
+        let error_message = error_message.clone();
+        let (day_width, outline) = (*day_width, *grouping == Grouping::Outline);
+        let collapsed = collapsed.clone();
+        Callback::from(move |options: ChartExport| {
+            let options = ChartExport { day_width, outline, collapsed: collapsed.iter().copied().collect(), ..options };
+            let schedule = schedule.clone();
+            let exporting_chart = exporting_chart.clone();
+            let error_message = error_message.clone();
+            exporting_chart.set(true);
+            spawn_local(async move {
+                let result = api::export_chart(
+                    &schedule.tasks,
+                    &schedule.machines,
+                    &schedule.calendars,
+                    &schedule.work_orders,
+                    &options,
+                )
+                .await
+                .and_then(|bytes| {
+                    let format = options.format;
+                    download(&format!("chart.{}", format.extension()), format.mime_type(), bytes.as_slice())
+                });
+                if let Err(e) = result {
+                    error_message.set(Some(format!("Failed to export the chart: {}", e)));
+                }
+                exporting_chart.set(false);
+            });
+        })
+    };
+
+    let add_machine = {
+        let schedule = schedule.clone();
+        let new_machine_name = new_machine_name.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |_| {
+            let mut new_machines = schedule.machines.clone();
+            let id = new_machines.iter().map(|m| m.id).max().unwrap_or(0) + 1;
+            let machine = Machine { id, name: (*new_machine_name).clone(), ..Default::default() };
+            if let Err(e) = machine.validate() {
+                error_message.set(Some(format!("Invalid machine: {}", e)));
+                return;
+            }
+            new_machines.push(machine);
+            schedule.dispatch(ScheduleAction::edit_machines("Add machine", new_machines));
+            new_machine_name.set(String::new());
+        })
+    };
+
+    // 機械を削除すると、その機械のタスクは未割り当てに戻る
+    let remove_machine = {
+        let schedule = schedule.clone();
+        Callback::from(move |id: MachineId| {
+            let mut new_tasks = schedule.tasks.clone();
+            for task in new_tasks.iter_mut().filter(|t| t.machine_id == Some(id)) {
+                task.machine_id = None;
+            }
+            schedule.dispatch(ScheduleAction::Edit {
+                label: "Remove machine".to_string(),
+                tasks: Some(new_tasks),
+                machines: Some(schedule.machines.iter().filter(|m| m.id != id).cloned().collect()),
+                work_orders: None,
+                calendars: None,
+            });
+        })
+    };
+
+    let update_machine = {
+        let schedule = schedule.clone();
+        let editing_machine = editing_machine.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |machine: Machine| {
+            if let Err(e) = machine.validate() {
+                error_message.set(Some(format!("Invalid machine: {}", e)));
+                return;
+            }
+            let new_machines = schedule.machines
+                .iter()
+                .map(|m| if m.id == machine.id { machine.clone() } else { m.clone() })
+                .collect();
+            schedule.dispatch(ScheduleAction::edit_machines("Edit machine", new_machines));
+            editing_machine.set(None);
+        })
+    };
 
     let add_task = {
         let show_task_form = show_task_form.clone();
@@ -118,172 +1172,567 @@ pub fn gantt_chart() -> Html {
     };
 
     let submit_task = {
-        let tasks = tasks.clone();
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
         let show_task_form = show_task_form.clone();
         let task_form_data = task_form_data.clone();
         Callback::from(move |_| {
-            let base_date = NaiveDateTime::parse_from_str("2025-03-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap_or_else(|e| {
-                log::error!("Failed to parse base date: {}", e);
-                NaiveDateTime::default()
-            });
+            let base_date = today();
             let start_date = NaiveDateTime::parse_from_str(&task_form_data.start_date, "%Y-%m-%dT%H:%M")
                 .unwrap_or_else(|e| {
                     log::error!("Failed to parse start date: {}", e);
                     base_date
                 });
-            let end_date = NaiveDateTime::parse_from_str(&task_form_data.end_date, "%Y-%m-%dT%H:%M")
-                .unwrap_or_else(|e| {
-                    log::error!("Failed to parse end date: {}", e);
-                    base_date + Duration::days(1)
-                });
+            let end_date = if task_form_data.milestone {
+                start_date
+            } else {
+                NaiveDateTime::parse_from_str(&task_form_data.end_date, "%Y-%m-%dT%H:%M")
+                    .unwrap_or_else(|e| {
+                        log::error!("Failed to parse end date: {}", e);
+                        base_date + Duration::days(1)
+                    })
+            };
 
-            let mut new_tasks = (*tasks).clone();
-            let id = new_tasks.len() + 1;
-            new_tasks.push(Task {
+            let mut new_tasks = schedule.tasks.clone();
+            // 削除後も ID が重複しないよう最大値から採番する
+            let id = new_tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+            let task = Task {
                 id,
                 name: task_form_data.name.clone(),
                 start_date,
                 end_date,
                 color: "#009688".to_string(),
-                is_dragging: false,
-                drag_offset: 0,
-                drag_start_x: 0.0
-            });
-            tasks.set(new_tasks);
+                description: String::new(),
+                kind: if task_form_data.milestone { TaskKind::Milestone } else { TaskKind::Task },
+                status: TaskStatus::Planned,
+                work_order_id: None,
+                machine_id: None,
+                dependencies: Vec::new(),
+                parent_id: None,
+                percent_complete: 0.0,
+                actual_start: None,
+                actual_end: None,
+                part_family: String::new(),
+                material: String::new(),
+                quantity: 0,
+                split_of: None,
+            };
+            if let Err(e) = task.validate() {
+                error_message.set(Some(format!("Invalid task: {}", e)));
+                return;
+            }
+            new_tasks.push(task);
+            schedule.dispatch(ScheduleAction::edit_tasks("Add task", new_tasks));
             show_task_form.set(false);
             task_form_data.set(TaskFormData {
                 name: String::new(),
                 start_date: String::new(),
                 end_date: String::new(),
+                milestone: false,
             });
         })
     };
 
     let remove_task = {
-        let tasks = tasks.clone();
-        Callback::from(move |id: usize| {
-            let new_tasks = (*tasks).clone().into_iter().filter(|task| task.id != id).collect();
-            tasks.set(new_tasks);
+        let schedule = schedule.clone();
+        Callback::from(move |id: TaskId| {
+            let (removed, mut new_tasks): (Vec<Task>, Vec<Task>) = schedule.tasks.iter().cloned().partition(|task| task.id == id);
+            // 削除したタスクへのリンクも外し、子は一つ上の親に付け替える。残った分割はまとまったまま
+            for task in &mut new_tasks {
+                task.dependencies.retain(|dependency| dependency.predecessor_id != id);
+            }
+            lift_orphans(&mut new_tasks, &removed);
+            rejoin_segments(&mut new_tasks, &removed);
+            schedule.dispatch(ScheduleAction::edit_tasks("Remove task", new_tasks));
         })
     };
 
-    let edit_task = {
-        let tasks = tasks.clone();
-        let selected_task = selected_task.clone();
-        Callback::from(move |(id, name, start, end): (usize, String, NaiveDateTime, NaiveDateTime)| {
-            let tasks = tasks.clone();
-            let name = name.clone();
-            let mut new_tasks = (*tasks).clone();
-            for task in &mut new_tasks {
-                if task.id == id {
-                    task.name = name.clone();
-                    task.start_date = start;
-                    task.end_date = end;
-                    break;
+    let add_dependency = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |(successor_id, dependency): (TaskId, Dependency)| {
+            let mut new_tasks = schedule.tasks.clone();
+            let predecessor_id = dependency.predecessor_id;
+            if let Some(task) = new_tasks.iter_mut().find(|t| t.id == successor_id) {
+                task.dependencies.retain(|d| d.predecessor_id != predecessor_id);
+                task.dependencies.push(dependency);
+            }
+            // 循環するリンクは追加しない
+            if let Err(e) = validate_dependencies(&new_tasks) {
+                error_message.set(Some(format!("Cannot add dependency: {}", e)));
+                return;
+            }
+            if let Err(e) = reschedule_successors(&mut new_tasks, predecessor_id) {
+                error_message.set(Some(format!("Cannot reschedule: {}", e)));
+                return;
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks("Link tasks", new_tasks));
+        })
+    };
+
+    let remove_dependency = {
+        let schedule = schedule.clone();
+        Callback::from(move |(successor_id, predecessor_id): (TaskId, TaskId)| {
+            let mut new_tasks = schedule.tasks.clone();
+            if let Some(task) = new_tasks.iter_mut().find(|t| t.id == successor_id) {
+                task.dependencies.retain(|d| d.predecessor_id != predecessor_id);
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks("Unlink tasks", new_tasks));
+        })
+    };
+
+    let update_progress = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |updated: Task| {
+            if let Err(e) = updated.validate() {
+                error_message.set(Some(format!("Invalid progress: {}", e)));
+                return;
+            }
+            let mut new_tasks = schedule.tasks.clone();
+            if let Some(task) = new_tasks.iter_mut().find(|t| t.id == updated.id) {
+                *task = updated;
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks("Update progress", new_tasks));
+        })
+    };
+
+    // 分割と結合。編集中のタスクも新しい期間に合わせ、保存で古い期間に戻らないようにする
+    let split = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        let editing_task = editing_task.clone();
+        let machine_calendars = machine_calendars.clone();
+        Callback::from(move |(id, point): (TaskId, SplitPoint)| {
+            let mut new_tasks = schedule.tasks.clone();
+            let new_id = new_tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+            let calendar = new_tasks.iter().find(|t| t.id == id).and_then(|task| machine_calendars.of(task)).cloned();
+            if let Err(e) = split_task(&mut new_tasks, id, point, new_id, calendar.as_ref()) {
+                error_message.set(Some(format!("Cannot split: {}", e)));
+                return;
+            }
+            editing_task.set(new_tasks.iter().find(|t| t.id == id).cloned());
+            let label = match point {
+                SplitPoint::At(_) => "Split task",
+                SplitPoint::Quantity(_) => "Split lot",
+            };
+            schedule.dispatch(ScheduleAction::edit_tasks(label, new_tasks));
+        })
+    };
+    let merge = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        let editing_task = editing_task.clone();
+        let machine_calendars = machine_calendars.clone();
+        Callback::from(move |id: TaskId| {
+            let mut new_tasks = schedule.tasks.clone();
+            let lot = new_tasks.iter().find(|t| t.id == id).map(lot_id);
+            let calendar = new_tasks.iter().find(|t| Some(t.id) == lot).and_then(|task| machine_calendars.of(task)).cloned();
+            match merge_segments(&mut new_tasks, id, calendar.as_ref()) {
+                Ok(lot) => editing_task.set(new_tasks.iter().find(|t| t.id == lot).cloned()),
+                Err(e) => {
+                    error_message.set(Some(format!("Cannot merge: {}", e)));
+                    return;
                 }
             }
-            tasks.set(new_tasks);
-            selected_task.set(None);
+            schedule.dispatch(ScheduleAction::edit_tasks("Merge segments", new_tasks));
+        })
+    };
+    let set_quantity = {
+        let schedule = schedule.clone();
+        Callback::from(move |(id, quantity): (TaskId, u32)| {
+            let mut new_tasks = schedule.tasks.clone();
+            if let Some(task) = new_tasks.iter_mut().find(|t| t.id == id) {
+                task.quantity = quantity;
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks("Set quantity", new_tasks));
+        })
+    };
+
+    let set_parent = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        let editing_task = editing_task.clone();
+        Callback::from(move |(id, parent_id): (TaskId, Option<TaskId>)| {
+            let mut new_tasks = schedule.tasks.clone();
+            if let Some(task) = new_tasks.iter_mut().find(|t| t.id == id) {
+                task.parent_id = parent_id;
+            }
+            if let Err(e) = validate_hierarchy(&new_tasks) {
+                error_message.set(Some(format!("Cannot group task: {}", e)));
+                return;
+            }
+            if let Some(task) = (*editing_task).clone() {
+                editing_task.set(Some(Task { parent_id, ..task }));
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks("Group task", new_tasks));
+        })
+    };
+
+    // マイルストーンにすると開始の時点に縮め、タスクに戻すと 1 時間の長さを持たせる
+    let set_kind = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        let editing_task = editing_task.clone();
+        let summary_tasks = summary_tasks.clone();
+        Callback::from(move |(id, kind): (TaskId, TaskKind)| {
+            if kind == TaskKind::Milestone && summary_tasks.contains(&id) {
+                error_message.set(Some("A task with children cannot be a milestone".to_string()));
+                return;
+            }
+            let mut new_tasks = schedule.tasks.clone();
+            let Some(task) = new_tasks.iter_mut().find(|t| t.id == id) else {
+                return;
+            };
+            task.kind = kind;
+            task.end_date = match kind {
+                TaskKind::Milestone => task.start_date,
+                TaskKind::Task => task.end_date.max(task.start_date + Duration::hours(1)),
+            };
+            let end_date = task.end_date;
+            if let Err(e) = reschedule_successors(&mut new_tasks, id) {
+                error_message.set(Some(format!("Cannot reschedule: {}", e)));
+                return;
+            }
+            if let Some(task) = (*editing_task).clone() {
+                editing_task.set(Some(Task { kind, end_date, ..task }));
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks(
+                if kind == TaskKind::Milestone { "Make milestone" } else { "Make task" },
+                new_tasks,
+            ));
+        })
+    };
+
+    let set_work_order = {
+        let schedule = schedule.clone();
+        Callback::from(move |(id, work_order_id): (TaskId, Option<WorkOrderId>)| {
+            let mut new_tasks = schedule.tasks.clone();
+            if let Some(task) = new_tasks.iter_mut().find(|t| t.id == id) {
+                task.work_order_id = work_order_id;
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks("Link order", new_tasks));
+        })
+    };
+
+    // 品種と材質。機械の段取り表で前後のジョブとの段取り時間が決まる
+    let set_setup_key = {
+        let schedule = schedule.clone();
+        Callback::from(move |(id, key): (TaskId, SetupKey)| {
+            let mut new_tasks = schedule.tasks.clone();
+            let Some(task) = new_tasks.iter_mut().find(|t| t.id == id) else {
+                return;
+            };
+            if task.setup_key() == key {
+                return;
+            }
+            task.part_family = key.part_family;
+            task.material = key.material;
+            schedule.dispatch(ScheduleAction::edit_tasks("Set part family", new_tasks));
+        })
+    };
+
+    let add_work_order = {
+        let schedule = schedule.clone();
+        Callback::from(move |()| {
+            let mut new_work_orders = schedule.work_orders.clone();
+            let id: WorkOrderId = new_work_orders.iter().map(|o| o.id).max().unwrap_or(0) + 1;
+            new_work_orders.push(WorkOrder {
+                id,
+                number: format!("WO-{:04}", id),
+                customer: String::new(),
+                part_name: "New part".to_string(),
+                quantity: 1,
+                due_date: None,
+            });
+            schedule.dispatch(ScheduleAction::edit_work_orders("Add order", new_work_orders));
+        })
+    };
+
+    let update_work_order = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |work_order: WorkOrder| {
+            if let Err(e) = work_order.validate() {
+                error_message.set(Some(format!("Invalid work order: {}", e)));
+                return;
+            }
+            let new_work_orders = schedule.work_orders
+                .iter()
+                .map(|o| if o.id == work_order.id { work_order.clone() } else { o.clone() })
+                .collect();
+            schedule.dispatch(ScheduleAction::edit_work_orders("Edit order", new_work_orders));
+        })
+    };
+
+    // 受注を削除すると、その受注のタスクは受注なしに戻る
+    let remove_work_order = {
+        let schedule = schedule.clone();
+        Callback::from(move |id: WorkOrderId| {
+            let mut new_tasks = schedule.tasks.clone();
+            for task in new_tasks.iter_mut().filter(|t| t.work_order_id == Some(id)) {
+                task.work_order_id = None;
+            }
+            schedule.dispatch(ScheduleAction::Edit {
+                label: "Remove order".to_string(),
+                tasks: Some(new_tasks),
+                machines: None,
+                work_orders: Some(schedule.work_orders.iter().filter(|o| o.id != id).cloned().collect()),
+                calendars: None,
+            });
+        })
+    };
+
+    let add_calendar = {
+        let schedule = schedule.clone();
+        Callback::from(move |()| {
+            let mut new_calendars = schedule.calendars.clone();
+            let id: CalendarId = new_calendars.iter().map(|c| c.id).max().unwrap_or(0) + 1;
+            let mut week: [Vec<Shift>; 7] = Default::default();
+            week[..5].fill(vec![Shift::parse("08:00-17:00").unwrap()]);
+            new_calendars.push(Calendar { id, name: format!("Calendar {}", id), week, exceptions: Vec::new() });
+            schedule.dispatch(ScheduleAction::edit_calendars("Add calendar", new_calendars));
+        })
+    };
+
+    let update_calendar = {
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |calendar: Calendar| {
+            if let Err(e) = calendar.validate() {
+                error_message.set(Some(format!("Invalid calendar: {}", e)));
+                return;
+            }
+            let new_calendars = schedule.calendars
+                .iter()
+                .map(|c| if c.id == calendar.id { calendar.clone() } else { c.clone() })
+                .collect();
+            schedule.dispatch(ScheduleAction::edit_calendars("Edit calendar", new_calendars));
+        })
+    };
+
+    // カレンダーを削除すると、その機械は 24 時間稼働に戻る
+    let remove_calendar = {
+        let schedule = schedule.clone();
+        Callback::from(move |id: CalendarId| {
+            let mut new_machines = schedule.machines.clone();
+            for machine in new_machines.iter_mut().filter(|m| m.calendar_id == Some(id)) {
+                machine.calendar_id = None;
+            }
+            schedule.dispatch(ScheduleAction::Edit {
+                label: "Remove calendar".to_string(),
+                tasks: None,
+                machines: Some(new_machines),
+                work_orders: None,
+                calendars: Some(schedule.calendars.iter().filter(|c| c.id != id).cloned().collect()),
+            });
+        })
+    };
+
+    // ズーム後もカーソル位置 (コンテナ左端からの px) の日時が同じ位置に来るようスクロールを合わせる
+    let zoom_to = {
+        let timeline = timeline.clone();
+        let day_width = day_width.clone();
+        let scroll_offset = scroll_offset.clone();
+        Callback::from(move |(new_day_width, anchor_x): (f64, f64)| {
+            let new_day_width = new_day_width.clamp(MIN_DAY_WIDTH, MAX_DAY_WIDTH);
+            let anchor_time = timeline.time_at(*scroll_offset as i64 + anchor_x as i64);
+            let zoomed = Timeline { day_width: new_day_width, ..*timeline };
+            scroll_offset.set((zoomed.x(anchor_time) - anchor_x as i64).max(0) as i32);
+            day_width.set(new_day_width);
         })
     };
 
     let on_wheel = {
-        let zoom_level = zoom_level.clone();
+        let zoom_to = zoom_to.clone();
+        let day_width = day_width.clone();
         let scroll_offset = scroll_offset.clone();
+        let pending_pan = pending_pan.clone();
         Callback::from(move |e: WheelEvent| {
             if e.ctrl_key() {
-                // Ctrl + ホイールでズーム
-                let delta = e.delta_y();
-                if delta < 0.0 {
-                    zoom_level.set((*zoom_level - 5).max(20));
-                } else {
-                    zoom_level.set((*zoom_level + 5).min(100));
-                }
+                // Ctrl + ホイールでカーソル位置を中心にズーム
+                e.prevent_default();
+                let container = e.current_target().unwrap().unchecked_into::<web_sys::Element>();
+                let anchor_x = e.client_x() as f64 - container.get_bounding_client_rect().left();
+                let factor = if e.delta_y() < 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+                zoom_to.emit((*day_width * factor, anchor_x));
             } else {
-                // 通常のホイールでスクロール
-                let delta = e.delta_x();
-                scroll_offset.set((*scroll_offset + delta as i32).max(0));
+                // 通常のホイールでスクロール。イベントごとに描き直さず、次の描画フレームでまとめて反映する
+                let mut pan = pending_pan.borrow_mut();
+                pan.delta += e.delta_x();
+                if !pan.scheduled {
+                    pan.scheduled = true;
+                    let scroll_offset = scroll_offset.clone();
+                    let pending_pan = pending_pan.clone();
+                    pan.frame = Some(request_animation_frame(move |_| {
+                        let next = {
+                            let mut pan = pending_pan.borrow_mut();
+                            let next = (pan.scroll_offset + pan.delta.round() as i32).max(0);
+                            pan.delta = 0.0;
+                            pan.scheduled = false;
+                            pan.scroll_offset = next;
+                            next
+                        };
+                        // 再描画で借用が重ならないよう、借用を外してから反映する
+                        scroll_offset.set(next);
+                    }));
+                }
             }
         })
     };
 
-    let on_input_name = {
-        let edit_task = edit_task.clone();
-        Callback::from(move |(id, name, start, end): (usize, String, NaiveDateTime, NaiveDateTime)| {
-            edit_task.emit((id, name, start, end));
-        })
-    };
-
     let on_mouse_down = {
-        let tasks = tasks.clone();
         let dragging_task = dragging_task.clone();
-        Callback::from(move |e: MouseEvent| {
-            if let Ok(Some(element)) = e.target()
-                .unwrap()
-                .unchecked_into::<web_sys::HtmlElement>()
-                .closest("[data-task-id]")
-            {
-                if let Some(task_id_str) = element.get_attribute("data-task-id") {
-                    if let Ok(task_id) = task_id_str.parse::<usize>() {
-                        dragging_task.set(Some(task_id));
-                        let mut new_tasks = (*tasks).clone();
-                        if let Some(task) = new_tasks.iter_mut().find(|t| t.id == task_id) {
-                            task.is_dragging = true;
-                            task.drag_offset = 0;
-                            task.drag_start_x = e.client_x() as f64;
-                        }
-                        tasks.set(new_tasks);
-                    }
-                }
-            }
+        let selected_tasks = selected_tasks.clone();
+        let summary_tasks = summary_tasks.clone();
+        let milestones = milestones.clone();
+        Callback::from(move |(e, task_id, mode): (MouseEvent, TaskId, DragMode)| {
+            // サマリーの期間は子から決まるので、長さは変えずに配下ごと動かす。マイルストーンは長さを持たない
+            let is_summary = summary_tasks.contains(&task_id);
+            let is_milestone = milestones.contains(&task_id);
+            let mode = if is_summary || is_milestone { DragMode::Move } else { mode };
+            dragging_task.set(Some(DragState {
+                task_id,
+                mode,
+                start_x: e.client_x() as f64,
+                start_y: e.client_y() as f64,
+                offset_minutes: 0,
+                offset_y: 0.0,
+                group: mode == DragMode::Move && (is_summary || (selected_tasks.len() > 1 && selected_tasks.contains(&task_id))),
+            }));
         })
     };
 
     let on_mouse_up = {
-        let tasks = tasks.clone();
+        let schedule = schedule.clone();
+        let layout = layout.clone();
+        let timeline = timeline.clone();
+        let error_message = error_message.clone();
         let dragging_task = dragging_task.clone();
+        let selected_tasks = selected_tasks.clone();
+        let moving = moving.clone();
+        let machine_calendars = machine_calendars.clone();
+        let rubber_band = rubber_band.clone();
+        let drag_moved = drag_moved.clone();
         Callback::from(move |_| {
-            if let Some(task_id) = *dragging_task {
-                let mut new_tasks = (*tasks).clone();
-                if let Some(task) = new_tasks.iter_mut().find(|t| t.id == task_id) {
-                    let base_date = NaiveDateTime::parse_from_str("2025-03-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
-                    let new_start = base_date + Duration::days(task.drag_offset);
-                    let duration = task.end_date - task.start_date;
-                    task.start_date = new_start;
-                    task.end_date = new_start + duration;
-                    task.is_dragging = false;
-                    task.drag_offset = 0;
-                    task.drag_start_x = 0.0;
-                }
-                tasks.set(new_tasks);
+            // 範囲選択: 矩形にかかるバーを選ぶ
+            if let Some(band) = *rubber_band {
+                let mut selection = if band.additive { (*selected_tasks).clone() } else { HashSet::new() };
+                for task in &schedule.tasks {
+                    let Some(top) = layout.bar_top(task.id) else {
+                        continue;
+                    };
+                    let (left, width) = bar_span(task, &timeline, None, None);
+                    if band.intersects(left as f64, top as f64, width as f64, ROW_HEIGHT as f64) {
+                        selection.insert(task.id);
+                    }
+                }
+                selected_tasks.set(selection);
+                rubber_band.set(None);
+            }
+            *drag_moved.borrow_mut() = dragging_task.is_some_and(|drag| drag.offset_minutes != 0 || drag.offset_y.abs() >= 1.0);
+            if let Some(drag) = (*dragging_task).filter(|drag| drag.group) {
+                // 選択中のタスクやサマリーの配下をまとめて動かし、1 回の操作として記録する
+                let mut new_tasks = schedule.tasks.clone();
+                for task in new_tasks.iter_mut().filter(|t| moving.contains(&t.id)) {
+                    (task.start_date, task.end_date) = drag.span(task, machine_calendars.of(task));
+                }
+                for id in moving.iter() {
+                    if let Err(e) = reschedule_successors(&mut new_tasks, *id) {
+                        error_message.set(Some(format!("Cannot reschedule: {}", e)));
+                    }
+                }
+                schedule.dispatch(ScheduleAction::edit_tasks(&format!("Move {} tasks", moving.len()), new_tasks));
+            } else if let Some(drag) = *dragging_task {
+                let mut new_tasks = schedule.tasks.clone();
+                if let Some(task) = new_tasks.iter_mut().find(|t| t.id == drag.task_id) {
+                    // 移動は時刻を保ったままずらし、端のつまみは片側だけ動かす。長さは元の機械のカレンダーで測る
+                    (task.start_date, task.end_date) = drag.span(task, machine_calendars.of(task));
+                    // バーの中心が落ちたレーンの機械に割り当てる
+                    if drag.mode == DragMode::Move && !layout.is_outline() {
+                        let bar_center = layout.bar_top(task.id).unwrap_or(0) as f64 + ROW_HEIGHT as f64 / 2.0;
+                        if let Some(lane) = layout.lane_at(bar_center + drag.offset_y) {
+                            task.machine_id = lane.machine_id;
+                        }
+                    }
+                }
+                // 後続タスクを押し出す
+                if let Err(e) = reschedule_successors(&mut new_tasks, drag.task_id) {
+                    error_message.set(Some(format!("Cannot reschedule: {}", e)));
+                }
+                schedule.dispatch(ScheduleAction::edit_tasks(drag.mode.label(), new_tasks));
             }
             dragging_task.set(None);
         })
     };
 
     let on_mouse_move = {
-        let tasks = tasks.clone();
         let dragging_task = dragging_task.clone();
+        let rubber_band = rubber_band.clone();
+        let timeline = timeline.clone();
+        let layout = layout.clone();
+        let snap = snap.clone();
         Callback::from(move |e: MouseEvent| {
-            if let Some(task_id) = *dragging_task {
-                let mut new_tasks = (*tasks).clone();
-                if let Some(task) = new_tasks.iter_mut().find(|t| t.id == task_id) {
-                    let delta_x = e.client_x() as f64 - task.drag_start_x;
-                    let days_delta = (delta_x / 100.0).round() as i64;
-                    task.drag_offset = days_delta;
+            if let Some(band) = *rubber_band {
+                let (origin_x, origin_y) = band.client_origin;
+                let end = (e.client_x() as f64 - origin_x, e.client_y() as f64 - origin_y);
+                rubber_band.set(Some(RubberBand { end, ..band }));
+            }
+            if let Some(drag) = *dragging_task {
+                let delta_x = e.client_x() as f64 - drag.start_x;
+                let offset_minutes = snap.snap(delta_x / timeline.minute_width());
+                // 1 タスク 1 行の表示では縦に動かしても意味がないので横にだけ動かす
+                let vertical = drag.mode == DragMode::Move && !drag.group && !layout.is_outline();
+                let delta_y = if vertical { e.client_y() as f64 - drag.start_y } else { 0.0 };
+                if offset_minutes != drag.offset_minutes || delta_y != drag.offset_y {
+                    dragging_task.set(Some(DragState { offset_minutes, offset_y: delta_y, ..drag }));
                 }
-                tasks.set(new_tasks);
             }
         })
     };
 
+    // バー以外の場所からドラッグすると範囲選択になる
+    let on_lanes_mouse_down = {
+        let rubber_band = rubber_band.clone();
+        Callback::from(move |e: MouseEvent| {
+            let target = e.target().unwrap().unchecked_into::<web_sys::HtmlElement>();
+            // キャンバス表示ではバーの上で押すと伝播が止まるのでここには来ない
+            if e.button() != 0 || matches!(target.closest("[data-task-id]"), Ok(Some(_))) {
+                return;
+            }
+            let rect = e.current_target().unwrap().unchecked_into::<web_sys::Element>().get_bounding_client_rect();
+            let start = (e.client_x() as f64 - rect.left(), e.client_y() as f64 - rect.top());
+            rubber_band.set(Some(RubberBand {
+                start,
+                end: start,
+                client_origin: (rect.left(), rect.top()),
+                additive: e.shift_key() || e.ctrl_key(),
+            }));
+        })
+    };
+
     let on_task_click = {
-        let tasks = tasks.clone();
+        let schedule = schedule.clone();
+        let selected_tasks = selected_tasks.clone();
+        let drag_moved = drag_moved.clone();
         let show_floating_window = show_floating_window.clone();
         let floating_window_position = floating_window_position.clone();
         let editing_task = editing_task.clone();
-        Callback::from(move |e: MouseEvent| {
-            if !e.ctrl_key() {
+        Callback::from(move |(e, task_id): (MouseEvent, TaskId)| {
+            // Shift / Ctrl + クリックで選択に追加・解除する
+            if e.ctrl_key() || e.shift_key() {
+                let mut selection = (*selected_tasks).clone();
+                if !selection.remove(&task_id) {
+                    selection.insert(task_id);
+                }
+                selected_tasks.set(selection);
+                return;
+            }
+            if *drag_moved.borrow() {
+                return;
+            }
+            selected_tasks.set(HashSet::from([task_id]));
+            {
                 let window_width = 320.0; // フローティングウィンドウの推定幅
                 let window_height = 300.0; // フローティングウィンドウの推定高さ
                 let viewport_width = web_sys::window().unwrap().inner_width().unwrap().as_f64().unwrap();
@@ -308,58 +1757,332 @@ pub fn gantt_chart() -> Html {
                 
                 show_floating_window.set(true);
                 floating_window_position.set((x, y));
-                if let Ok(Some(element)) = e.target()
-                    .unwrap()
-                    .unchecked_into::<web_sys::HtmlElement>()
-                    .closest("[data-task-id]")
-                {
-                    if let Some(task_id_str) = element.get_attribute("data-task-id") {
-                        if let Ok(task_id) = task_id_str.parse::<usize>() {
-                            if let Some(task) = (*tasks).iter().find(|t| t.id == task_id) {
-                                editing_task.set(Some(task.implicit_clone()));
-                            }
-                        }
-                    }
+                if let Some(task) = schedule.tasks.iter().find(|t| t.id == task_id) {
+                    editing_task.set(Some(task.clone()));
                 }
             }
         })
     };
 
     let update_task = {
-        let tasks = tasks.clone();
+        let schedule = schedule.clone();
+        let error_message = error_message.clone();
         let editing_task = editing_task.clone();
         let show_floating_window = show_floating_window.clone();
         Callback::from(move |(name, start_date, end_date): (String, String, String)| {
             if let Some(task) = (*editing_task).clone() {
-                let mut new_tasks = (*tasks).clone();
+                let mut new_tasks = schedule.tasks.clone();
                 if let Some(task_to_update) = new_tasks.iter_mut().find(|t| t.id == task.id) {
                     task_to_update.name = name.clone();
                     task_to_update.start_date = NaiveDateTime::parse_from_str(&start_date, "%Y-%m-%dT%H:%M")
                         .unwrap_or(task_to_update.start_date);
-                    task_to_update.end_date = NaiveDateTime::parse_from_str(&end_date, "%Y-%m-%dT%H:%M")
-                        .unwrap_or(task_to_update.end_date);
+                    task_to_update.end_date = if task_to_update.is_milestone() {
+                        task_to_update.start_date
+                    } else {
+                        NaiveDateTime::parse_from_str(&end_date, "%Y-%m-%dT%H:%M").unwrap_or(task_to_update.end_date)
+                    };
+                    if let Err(e) = task_to_update.validate() {
+                        error_message.set(Some(format!("Invalid task: {}", e)));
+                        return;
+                    }
                 }
-                tasks.set(new_tasks);
+                if let Err(e) = reschedule_successors(&mut new_tasks, task.id) {
+                    error_message.set(Some(format!("Cannot reschedule: {}", e)));
+                }
+                schedule.dispatch(ScheduleAction::edit_tasks("Edit task", new_tasks));
             }
             show_floating_window.set(false);
             editing_task.set(None);
         })
     };
 
-    let _zoom_level_clone = zoom_level.clone();
     let task_form_data_name = task_form_data.clone();
     let task_form_data_start = task_form_data.clone();
     let task_form_data_end = task_form_data.clone();
+    let task_form_data_kind = task_form_data.clone();
+
+    // 競合の開始位置までスクロールし、対象のバーを画面内に入れる
+    let select_conflict = {
+        let conflicts = conflicts.clone();
+        let timeline = timeline.clone();
+        let selected_conflict = selected_conflict.clone();
+        let scroll_offset = scroll_offset.clone();
+        Callback::from(move |index: usize| {
+            let Some(conflict) = conflicts.get(index) else {
+                return;
+            };
+            scroll_offset.set((timeline.x(conflict.start) - timeline.day_width as i64).max(0) as i32);
+            selected_conflict.set(Some(index));
+            if let Some(task_id) = conflict.task_ids.first() {
+                let bar = web_sys::window()
+                    .and_then(|window| window.document())
+                    .and_then(|document| document.query_selector(&format!("[data-task-id=\"{}\"]", task_id)).ok().flatten());
+                if let Some(bar) = bar {
+                    bar.scroll_into_view();
+                }
+            }
+        })
+    };
+    // 選択中の競合に含まれるタスク
+    let highlighted_tasks = (*selected_conflict)
+        .and_then(|index| conflicts.get(index))
+        .map(|conflict| conflict.task_ids.clone())
+        .unwrap_or_default();
+
+    // 選択中のタスクへの一括操作。どれも 1 回の操作として履歴に残す
+    let bulk_delete = {
+        let schedule = schedule.clone();
+        let selected_tasks = selected_tasks.clone();
+        Callback::from(move |_| {
+            let (removed, mut new_tasks): (Vec<Task>, Vec<Task>) = schedule.tasks.iter().cloned().partition(|t| selected_tasks.contains(&t.id));
+            for task in &mut new_tasks {
+                task.dependencies.retain(|dependency| !selected_tasks.contains(&dependency.predecessor_id));
+            }
+            lift_orphans(&mut new_tasks, &removed);
+            rejoin_segments(&mut new_tasks, &removed);
+            schedule.dispatch(ScheduleAction::edit_tasks(&format!("Delete {} tasks", selected_tasks.len()), new_tasks));
+            selected_tasks.set(HashSet::new());
+        })
+    };
+    let bulk_recolor = {
+        let schedule = schedule.clone();
+        let selected_tasks = selected_tasks.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |color: String| {
+            let mut new_tasks = schedule.tasks.clone();
+            for task in new_tasks.iter_mut().filter(|t| selected_tasks.contains(&t.id)) {
+                task.color = color.clone();
+                if let Err(e) = task.validate() {
+                    error_message.set(Some(format!("Invalid task: {}", e)));
+                    return;
+                }
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks(&format!("Recolor {} tasks", selected_tasks.len()), new_tasks));
+        })
+    };
+    let bulk_reassign = {
+        let schedule = schedule.clone();
+        let selected_tasks = selected_tasks.clone();
+        Callback::from(move |machine_id: Option<MachineId>| {
+            let mut new_tasks = schedule.tasks.clone();
+            for task in new_tasks.iter_mut().filter(|t| selected_tasks.contains(&t.id)) {
+                task.machine_id = machine_id;
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks(&format!("Reassign {} tasks", selected_tasks.len()), new_tasks));
+        })
+    };
+    let bulk_shift = {
+        let schedule = schedule.clone();
+        let selected_tasks = selected_tasks.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |days: i64| {
+            let mut new_tasks = schedule.tasks.clone();
+            for task in new_tasks.iter_mut().filter(|t| selected_tasks.contains(&t.id)) {
+                let duration = task.end_date - task.start_date;
+                task.start_date = add_working_days(task.start_date, days);
+                task.end_date = task.start_date + duration;
+            }
+            for id in selected_tasks.iter() {
+                if let Err(e) = reschedule_successors(&mut new_tasks, *id) {
+                    error_message.set(Some(format!("Cannot reschedule: {}", e)));
+                }
+            }
+            schedule.dispatch(ScheduleAction::edit_tasks(&format!("Shift {} tasks by {} days", selected_tasks.len(), days), new_tasks));
+        })
+    };
+
+    let dismiss_error = {
+        let error_message = error_message.clone();
+        Callback::from(move |_| error_message.set(None))
+    };
+    // 表示中の横位置の範囲。見出しと目盛り線はこの範囲だけ描く
+    let visible = (*scroll_offset as i64, *scroll_offset as i64 + viewport_width);
+    let visible_days = {
+        let first = timeline.time_at(visible.0).date();
+        (first, (timeline.time_at(visible.1) - Duration::minutes(1)).date().max(first))
+    };
+    let on_input_origin = {
+        let timeline_origin = timeline_origin.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            timeline_origin.set(NaiveDate::parse_from_str(&input.value(), "%Y-%m-%d").ok());
+        })
+    };
+    // プリセットは表示中の範囲の中央を中心に切り替える
+    let on_select_zoom = {
+        let zoom_to = zoom_to.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Ok(new_day_width) = select.value().parse::<f64>() {
+                zoom_to.emit((new_day_width, viewport_width as f64 / 2.0));
+            }
+        })
+    };
+    let on_select_renderer = {
+        let renderer = renderer.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = Renderer::ALL.into_iter().find(|r| r.label() == select.value()) {
+                renderer.set(selected);
+            }
+        })
+    };
+    let on_select_grouping = {
+        let grouping = grouping.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = Grouping::ALL.into_iter().find(|g| g.label() == select.value()) {
+                grouping.set(selected);
+            }
+        })
+    };
+    let on_toggle_collapsed = {
+        let collapsed = collapsed.clone();
+        Callback::from(move |id: TaskId| {
+            let mut next = (*collapsed).clone();
+            if !next.remove(&id) {
+                next.insert(id);
+            }
+            collapsed.set(next);
+        })
+    };
+    let on_select_snap = {
+        let snap = snap.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = SnapGrid::ALL.into_iter().find(|grid| grid.label() == select.value()) {
+                snap.set(selected);
+            }
+        })
+    };
+    let jump_to_today = {
+        let timeline_origin = timeline_origin.clone();
+        let scroll_offset = scroll_offset.clone();
+        Callback::from(move |_| {
+            timeline_origin.set(Some(today().date() - Duration::days(1)));
+            scroll_offset.set(0);
+        })
+    };
+    let fit_to_tasks = {
+        let timeline_origin = timeline_origin.clone();
+        let scroll_offset = scroll_offset.clone();
+        Callback::from(move |_| {
+            timeline_origin.set(None);
+            scroll_offset.set(0);
+        })
+    };
 
     html! {
         <>
+            if let Some(message) = (*error_message).clone() {
+                <div class={classes!("error-banner")} style="display: flex; justify-content: space-between; align-items: center; padding: 8px 12px; margin-bottom: 8px; background-color: #fdecea; color: #b71c1c; border: 1px solid #f5c6cb; border-radius: 5px;">
+                    <span>{ message }</span>
+                    <button onclick={dismiss_error}>{ "×" }</button>
+                </div>
+            }
             <div style="display: flex; justify-content: space-between; align-items: center;">
                 <div>
                     <button onclick={add_task}>{ "Add Task" }</button>
+                    <button
+                        title="Undo (Ctrl+Z)"
+                        disabled={!schedule.can_undo()}
+                        onclick={{
+                            let schedule = schedule.clone();
+                            Callback::from(move |_| schedule.dispatch(ScheduleAction::Undo))
+                        }}
+                    >
+                        { "Undo" }
+                    </button>
+                    <button
+                        title="Redo (Ctrl+Shift+Z)"
+                        disabled={!schedule.can_redo()}
+                        onclick={{
+                            let schedule = schedule.clone();
+                            Callback::from(move |_| schedule.dispatch(ScheduleAction::Redo))
+                        }}
+                    >
+                        { "Redo" }
+                    </button>
+                </div>
+                <div>
+                    <label>{ "Start: " }</label>
+                    <input type="date" value={timeline.origin.format("%Y-%m-%d").to_string()} oninput={on_input_origin} />
+                    <button onclick={jump_to_today}>{ "Today" }</button>
+                    <button onclick={fit_to_tasks}>{ "Fit" }</button>
+                    <select onchange={on_select_zoom}>
+                        <option value="" selected={ZOOM_PRESETS.iter().all(|(_, width)| *width != *day_width)}>{ "Zoom..." }</option>
+                        { for ZOOM_PRESETS.iter().map(|(label, width)| html! {
+                            <option value={width.to_string()} selected={*width == *day_width}>{ *label }</option>
+                        }) }
+                    </select>
+                    <label>{ " Snap: " }</label>
+                    <select onchange={on_select_snap}>
+                        { for SnapGrid::ALL.into_iter().map(|grid| html! {
+                            <option value={grid.label()} selected={*snap == grid}>{ grid.label() }</option>
+                        }) }
+                    </select>
+                    <label>{ " Rows: " }</label>
+                    <select onchange={on_select_grouping}>
+                        { for Grouping::ALL.into_iter().map(|g| html! {
+                            <option value={g.label()} selected={*grouping == g}>{ g.label() }</option>
+                        }) }
+                    </select>
+                    <label>{ " Renderer: " }</label>
+                    <select onchange={on_select_renderer}>
+                        { for Renderer::ALL.into_iter().map(|r| html! {
+                            <option value={r.label()} selected={*renderer == r}>{ r.label() }</option>
+                        }) }
+                    </select>
                 </div>
                 <div>
+                    <input
+                        type="text"
+                        placeholder="Machine name"
+                        value={(*new_machine_name).clone()}
+                        oninput={
+                            let new_machine_name = new_machine_name.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+                                new_machine_name.set(input.value());
+                            })
+                        }
+                    />
+                    <button onclick={add_machine}>{ "Add Machine" }</button>
                 </div>
             </div>
+            <BaselineToolbar
+                baselines={(*baselines).clone()}
+                shown={*shown_baseline}
+                on_show={{
+                    let shown_baseline = shown_baseline.clone();
+                    Callback::from(move |id| shown_baseline.set(id))
+                }}
+                on_create={create_baseline}
+                on_delete={delete_baseline}
+            />
+            <SchedulerToolbar running={*scheduling} last_run={(*last_schedule_run).clone()} on_run={run_scheduler} />
+            <ExchangeToolbar
+                on_export={export_schedule}
+                on_open={open_import}
+                on_export_workbook={export_workbook}
+                on_open_workbook={open_workbook}
+                on_error={show_error}
+            />
+            <ChartExportToolbar visible={visible_days} exporting={*exporting_chart} on_export={export_chart} />
+            if let Some(pending) = (*pending_import).clone() {
+                <ImportDialog pending={pending} on_remap={remap_import} on_apply={apply_import} on_cancel={cancel_import} />
+            }
+            if let Some(pending) = (*pending_workbook).clone() {
+                <WorkbookDialog
+                    pending={pending}
+                    profiles={(*import_profiles).clone()}
+                    on_reread={reread_workbook}
+                    on_save_profile={save_import_profile}
+                    on_delete_profile={delete_import_profile}
+                    on_apply={apply_workbook}
+                    on_cancel={cancel_workbook}
+                />
+            }
             if *show_task_form {
                 <div class={classes!("task-form-overlay")}>
                     <div class={classes!("task-form")}>
@@ -375,6 +2098,7 @@ pub fn gantt_chart() -> Html {
                                         name: input.value(),
                                         start_date: task_form_data_name.start_date.clone(),
                                         end_date: task_form_data_name.end_date.clone(),
+                                        milestone: task_form_data_name.milestone,
                                     });
                                 })}
                             />
@@ -390,11 +2114,27 @@ pub fn gantt_chart() -> Html {
                                         name: task_form_data_start.name.clone(),
                                         start_date: input.value(),
                                         end_date: task_form_data_start.end_date.clone(),
+                                        milestone: task_form_data_start.milestone,
                                     });
                                 })}
                             />
                         </div>
                         <div>
+                            <label>
+                                <input
+                                    type="checkbox"
+                                    checked={task_form_data_kind.milestone}
+                                    onchange={Callback::from(move |_| {
+                                        task_form_data_kind.set(TaskFormData {
+                                            milestone: !task_form_data_kind.milestone,
+                                            ..(*task_form_data_kind).clone()
+                                        });
+                                    })}
+                                />
+                                { " Milestone" }
+                            </label>
+                        </div>
+                        <div hidden={task_form_data_end.milestone}>
                             <label>{ "End Date:" }</label>
                             <input
                                 type="datetime-local"
@@ -405,6 +2145,7 @@ pub fn gantt_chart() -> Html {
                                         name: task_form_data_end.name.clone(),
                                         start_date: task_form_data_end.start_date.clone(),
                                         end_date: input.value(),
+                                        milestone: task_form_data_end.milestone,
                                     });
                                 })}
                             />
@@ -434,6 +2175,10 @@ pub fn gantt_chart() -> Html {
                                 let task_clone2_for_start = task_clone2.clone();
                                 let editing_task_clone2_for_end = editing_task_clone2.clone();
                                 let task_clone2_for_end = task_clone2.clone();
+                                let task_id = task.id;
+                                let live_task = schedule.tasks.iter().find(|t| t.id == task_id).cloned();
+                                let on_add_dependency = add_dependency.reform(move |dependency| (task_id, dependency));
+                                let on_remove_dependency = remove_dependency.reform(move |predecessor_id| (task_id, predecessor_id));
                                 html! {
                                     <div>
                                         <input
@@ -462,6 +2207,7 @@ pub fn gantt_chart() -> Html {
                                         />
                                         <input
                                             type="datetime-local"
+                                            hidden={task_clone.is_milestone()}
                                             value={task_clone.end_date.format("%Y-%m-%dT%H:%M").to_string()}
                                             oninput={Callback::from(move |e: InputEvent| {
                                                 let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
@@ -474,6 +2220,35 @@ pub fn gantt_chart() -> Html {
                                                 }
                                             })}
                                         />
+                                        if !task_clone.is_milestone() {
+                                            <label title="Working hours on the machine's calendar; changing them moves the end">
+                                                <input
+                                                    type="number"
+                                                    min="0.25"
+                                                    step="0.25"
+                                                    style="width: 60px;"
+                                                    value={format!("{}", machine_calendars.working_minutes(&task_clone, task_clone.start_date, task_clone.end_date) as f64 / 60.0)}
+                                                    onchange={{
+                                                        let task = task_clone.clone();
+                                                        let editing_task = editing_task.clone();
+                                                        let machine_calendars = machine_calendars.clone();
+                                                        Callback::from(move |e: Event| {
+                                                            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+                                                            let Ok(hours) = input.value().parse::<f64>() else {
+                                                                return;
+                                                            };
+                                                            let minutes = (hours.max(0.0) * 60.0).round() as i64;
+                                                            let end_date = match machine_calendars.of(&task) {
+                                                                Some(calendar) => calendar.add_working_minutes(task.start_date, minutes),
+                                                                None => task.start_date + Duration::minutes(minutes),
+                                                            };
+                                                            editing_task.set(Some(Task { end_date, ..task.clone() }));
+                                                        })
+                                                    }}
+                                                />
+                                                { " work h" }
+                                            </label>
+                                        }
                                         <button onclick={move |_| {
                                             if let Some(task) = (*editing_task).clone() {
                                                 update_task.emit((
@@ -487,6 +2262,96 @@ pub fn gantt_chart() -> Html {
                                             show_floating_window.set(false);
                                             editing_task_clone.set(None);
                                         })}>{ "Cancel" }</button>
+                                        if let Some(timing) = analysis.as_ref().as_ref().and_then(|a| a.timing(task_id)) {
+                                            <div class="task-float">
+                                                <div>{ format!("Total float: {}", format_duration(timing.total_float_minutes)) }</div>
+                                                <div>{ format!("Free float: {}", format_duration(timing.free_float_minutes)) }</div>
+                                                if timing.is_critical() {
+                                                    <div style="color: #d32f2f; font-weight: bold;">{ "Critical" }</div>
+                                                }
+                                            </div>
+                                        }
+                                        if let Some(live_task) = live_task.clone() {
+                                            <ProgressEditor
+                                                is_summary={summary_tasks.contains(&live_task.id)}
+                                                task={live_task}
+                                                on_change={update_progress.clone()}
+                                            />
+                                        }
+                                        if let Some(live_task) = live_task.clone().filter(|task| !task.is_milestone() && !summary_tasks.contains(&task.id)) {
+                                            <SplitEditor
+                                                segments={segments(&schedule.tasks, task_id).into_iter().cloned().collect::<Vec<_>>()}
+                                                task={live_task}
+                                                on_quantity={set_quantity.reform(move |quantity| (task_id, quantity))}
+                                                on_split={split.reform(move |point| (task_id, point))}
+                                                on_merge={merge.reform(move |_| task_id)}
+                                            />
+                                        }
+                                        <div>
+                                            <label>
+                                                <input
+                                                    type="checkbox"
+                                                    checked={task_clone.is_milestone()}
+                                                    onchange={set_kind.reform(move |e: Event| {
+                                                        let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+                                                        (task_id, if input.checked() { TaskKind::Milestone } else { TaskKind::Task })
+                                                    })}
+                                                />
+                                                { " Milestone" }
+                                            </label>
+                                        </div>
+                                        if let Some(live_task) = live_task.clone().filter(|task| !task.is_milestone()) {
+                                            <div title="Decides the setup time before and after this job on its machine">
+                                                <input
+                                                    type="text"
+                                                    placeholder="Part family"
+                                                    style="width: 100px;"
+                                                    value={live_task.part_family.clone()}
+                                                    onchange={{
+                                                        let key = live_task.setup_key();
+                                                        set_setup_key.reform(move |e: Event| {
+                                                            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+                                                            (task_id, SetupKey { part_family: input.value().trim().to_string(), ..key.clone() })
+                                                        })
+                                                    }}
+                                                />
+                                                <input
+                                                    type="text"
+                                                    placeholder="Material"
+                                                    style="width: 100px;"
+                                                    value={live_task.material.clone()}
+                                                    onchange={{
+                                                        let key = live_task.setup_key();
+                                                        set_setup_key.reform(move |e: Event| {
+                                                            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+                                                            (task_id, SetupKey { material: input.value().trim().to_string(), ..key.clone() })
+                                                        })
+                                                    }}
+                                                />
+                                            </div>
+                                        }
+                                        if let Some(live_task) = live_task.clone() {
+                                            <WorkOrderSelect
+                                                task={live_task}
+                                                work_orders={schedule.work_orders.clone()}
+                                                on_change={set_work_order.reform(move |work_order_id| (task_id, work_order_id))}
+                                            />
+                                        }
+                                        if let Some(live_task) = live_task.clone() {
+                                            <ParentSelect
+                                                task={live_task}
+                                                tasks={schedule.tasks.clone()}
+                                                on_change={set_parent.reform(move |parent_id| (task_id, parent_id))}
+                                            />
+                                        }
+                                        if let Some(live_task) = live_task {
+                                            <DependencyEditor
+                                                task={live_task}
+                                                tasks={schedule.tasks.clone()}
+                                                on_add={on_add_dependency}
+                                                on_remove={on_remove_dependency}
+                                            />
+                                        }
                                     </div>
                                 }
                             } else {
@@ -498,39 +2363,375 @@ pub fn gantt_chart() -> Html {
             } else {
                 html! {}
             }}
-            <div 
-                class={classes!("gantt-container", dropdown_styles())} 
-                style={format!("width: 100%; overflow-x: auto; background-color: #ffffff;")}
-                onwheel={on_wheel}
-                onmousemove={on_mouse_move}
-                onmouseup={on_mouse_up}
-            > 
-                <div style={format!("display: flex; flex-direction: column; transform: translateX(-{}px);", *scroll_offset)}>
-                    <div class="grid-lines" style="position: absolute; top: 0; left: 0; right: 0; bottom: 0; pointer-events: none;">
-                        { for (0..30).map(|i| html! {
-                            <div style={format!(
-                                "position: absolute; left: {}px; top: 0; bottom: 0; width: 1px; background-color: #e0e0e0;",
-                                i * 100
-                            )} />
-                        })}
-                    </div>
-                    { for (*tasks).iter().map(|task| {
-                            let remove_task = remove_task.clone();
-                            let on_input_name = on_input_name.clone();
-                            let on_mouse_down = on_mouse_down.clone();
-                            let on_click = on_task_click.clone();
+            if let Some(machine) = (*editing_machine).and_then(|id| schedule.machines.iter().find(|m| m.id == id).cloned()) {
+                <MachineEditor
+                    key={machine.id}
+                    machine={machine.clone()}
+                    calendars={schedule.calendars.clone()}
+                    on_save={update_machine}
+                    on_close={{
+                        let editing_machine = editing_machine.clone();
+                        Callback::from(move |_| editing_machine.set(None))
+                    }}
+                />
+            }
+            if !selected_tasks.is_empty() {
+                <SelectionToolbar
+                    count={selected_tasks.len()}
+                    machines={schedule.machines.clone()}
+                    on_delete={bulk_delete}
+                    on_recolor={bulk_recolor}
+                    on_reassign={bulk_reassign}
+                    on_shift={bulk_shift}
+                    on_clear={{
+                        let selected_tasks = selected_tasks.clone();
+                        Callback::from(move |_| selected_tasks.set(HashSet::new()))
+                    }}
+                />
+            }
+            <div style="display: flex;">
+                // レーンの見出し (横スクロールしない)
+                <div class="lane-labels" style="flex: 0 0 160px; position: relative; background-color: #fafafa; border-right: 1px solid #ccc;">
+                    <div style={format!("height: {}px; border-bottom: 1px solid #ccc;", timeline.header_height())} />
+                    if layout.is_outline() {
+                        <WbsTree
+                            rows={outline_rows.clone()}
+                            tasks={tasks.clone()}
+                            collapsed={(*collapsed).clone()}
+                            selected={(*selected_tasks).clone()}
+                            on_toggle={on_toggle_collapsed}
+                        />
+                    } else {
+                        { for layout.lanes.iter().map(|lane| {
+                            let remove_machine = remove_machine.clone();
+                            let editing_machine = editing_machine.clone();
+                            let capacity = lane.machine_id
+                                .and_then(|id| schedule.machines.iter().find(|m| m.id == id))
+                                .map_or(1, |m| m.capacity);
                             html! {
-                                <TaskView 
-                                    task={task.clone()} 
-                                    remove_task={remove_task} 
-                                    on_input_name={on_input_name}
-                                    on_mouse_down={on_mouse_down}
-                                    on_click={on_click}
+                                <div
+                                    key={lane.machine_id.map_or_else(|| "unassigned".to_string(), |id| id.to_string())}
+                                    style={format!(
+                                        "height: {}px; box-sizing: border-box; border-bottom: 1px solid #ccc; display: flex; align-items: center; justify-content: space-between; padding: 0 8px; color: #333;",
+                                        lane.height
+                                    )}
+                                >
+                                    <span>
+                                        { &lane.label }
+                                        if capacity > 1 {
+                                            <span style="color: #666; font-size: 0.85em;">{ format!(" ×{}", capacity) }</span>
+                                        }
+                                    </span>
+                                    if let Some(machine_id) = lane.machine_id {
+                                        <button
+                                            title="Capacity and maintenance"
+                                            onclick={Callback::from(move |_| editing_machine.set(Some(machine_id)))}
+                                            style="background: none; border: none; box-shadow: none; cursor: pointer; padding: 0 5px; margin-left: auto;"
+                                        >
+                                            { "⚙" }
+                                        </button>
+                                        <button
+                                            onclick={Callback::from(move |_| remove_machine.emit(machine_id))}
+                                            style="background: none; border: none; box-shadow: none; cursor: pointer; padding: 0 5px;"
+                                        >
+                                            { "×" }
+                                        </button>
+                                    }
+                                </div>
+                            }
+                        }) }
+                    }
+                </div>
+                <div 
+                    class={classes!("gantt-container", dropdown_styles())} 
+                    style={format!("flex: 1; overflow-x: auto; background-color: #ffffff;")}
+                    onwheel={on_wheel}
+                    onmousemove={on_mouse_move}
+                    onmouseup={on_mouse_up}
+                > 
+                    <div style={format!("transform: translateX(-{}px);", *scroll_offset)}>
+                        <TimelineHeader timeline={*timeline} visible={visible} />
+                    </div>
+                    <div
+                        style={format!("position: relative; width: {}px; height: {}px; transform: translateX(-{}px);", timeline.width(), layout.height(), *scroll_offset)}
+                        onmousedown={on_lanes_mouse_down}
+                    >
+                        { for layout.lanes.iter().map(|lane| html! {
+                            <div
+                                class="lane"
+                                style={format!(
+                                    "position: absolute; left: 0; right: 0; top: {}px; height: {}px; box-sizing: border-box; border-bottom: 1px solid #ccc;",
+                                    lane.top, lane.height
+                                )}
+                            />
+                        }) }
+                        // 機械のカレンダーの休止時間 (シフト外、休日) は表示中の範囲だけ灰色で塗る
+                        { for layout.lanes.iter().filter(|_| !layout.is_outline()).filter_map(|lane| {
+                            let calendar = machine_calendars.machine(lane.machine_id)?;
+                            let timeline = *timeline;
+                            let gaps = calendar.non_working_intervals(timeline.time_at(visible.0), timeline.time_at(visible.1));
+                            Some(gaps.into_iter().filter_map(move |(start, end)| {
+                                let left = timeline.x(start);
+                                let width = timeline.x(end) - left;
+                                (width > 0).then(|| html! {
+                                    <div
+                                        class="non-working"
+                                        style={format!(
+                                            "position: absolute; left: {}px; width: {}px; top: {}px; height: {}px; background-color: {}; pointer-events: none;",
+                                            left, width, lane.top, lane.height, NON_WORKING_FILL
+                                        )}
+                                    />
+                                })
+                            }).collect::<Html>())
+                        }) }
+                        // メンテナンス期間は斜線で表示する
+                        { for layout.lanes.iter().filter(|_| !layout.is_outline()).filter_map(|lane| {
+                            let machine = schedule.machines.iter().find(|m| Some(m.id) == lane.machine_id)?;
+                            let timeline = *timeline;
+                            Some(machine.maintenance.iter().map(move |window| {
+                                let left = timeline.x(window.start_date);
+                                let width = timeline.x(window.end_date) - left;
+                                html! {
+                                    <div
+                                        class="maintenance"
+                                        title={format!("Maintenance {}", window.reason)}
+                                        style={format!(
+                                            "position: absolute; left: {}px; width: {}px; top: {}px; height: {}px; box-sizing: border-box;
+                                            background: repeating-linear-gradient(45deg, #bdbdbd 0 6px, #eeeeee 6px 12px); opacity: 0.7;",
+                                            left, width, lane.top, lane.height
+                                        )}
+                                    />
+                                }
+                            }).collect::<Html>())
+                        }) }
+                        // 段取りはジョブの直前に斜線で描く。前のジョブと重なって間に合わないものは赤
+                        { for setups.iter().filter_map(|block| {
+                            let drag = drag_for(*dragging_task, &moving, block.task_id);
+                            let top = layout.bar_top(block.task_id)? + drag.map_or(0, |drag| drag.offset_y.round() as i64);
+                            let left = timeline.x(block.start);
+                            let width = timeline.x(block.end) - left;
+                            if left + width < visible.0 || left > visible.1 {
+                                return None;
+                            }
+                            let name = |id: TaskId| schedule.tasks.iter().find(|t| t.id == id).map_or(String::new(), |t| t.name.clone());
+                            let shortfall = block.shortfall_minutes();
+                            let title = if shortfall > 0 {
+                                format!("Setup {} after {} ({} short)", format_duration(block.minutes()), name(block.previous_id), format_duration(shortfall))
+                            } else {
+                                format!("Setup {} after {}", format_duration(block.minutes()), name(block.previous_id))
+                            };
+                            let color = if shortfall > 0 { SETUP_SHORT_COLOR } else { SETUP_COLOR };
+                            Some(html! {
+                                <div
+                                    key={format!("setup-{}", block.task_id)}
+                                    class="setup"
+                                    {title}
+                                    style={format!(
+                                        "position: absolute; left: {}px; width: {}px; top: {}px; height: {}px; box-sizing: border-box;
+                                        border: 1px solid {}; border-radius: 3px 0 0 3px;
+                                        background: repeating-linear-gradient(-45deg, {} 0 2px, transparent 2px 6px);",
+                                        left, width.max(2), top, ROW_HEIGHT, color, color
+                                    )}
                                 />
+                            })
+                        }) }
+                        <div class="grid-lines" style="position: absolute; top: 0; left: 0; right: 0; bottom: 0; pointer-events: none;">
+                            { for timeline.tiers().last().into_iter().flat_map(|tier| timeline.boundaries(*tier, visible.0, visible.1)).map(|time| html! {
+                                <div style={format!(
+                                    "position: absolute; left: {}px; top: 0; bottom: 0; width: 1px; background-color: #e0e0e0;",
+                                    timeline.x(time)
+                                )} />
+                            })}
+                        </div>
+                        {{
+                            let props = BarsProps {
+                                tasks: tasks.clone(),
+                                layout: layout.clone(),
+                                timeline: *timeline,
+                                visible,
+                                dragging: *dragging_task,
+                                selected: (*selected_tasks).clone(),
+                                moving: moving.clone(),
+                                summaries: summary_tasks.clone(),
+                                critical: critical_tasks.clone(),
+                                conflicting: conflicting_tasks.clone(),
+                                highlighted: highlighted_tasks.clone(),
+                                baseline: baseline_spans.clone(),
+                                missed: missed.clone(),
+                                segments: segment_numbers.clone(),
+                                calendars: machine_calendars.clone(),
+                                on_mouse_down: on_mouse_down.clone(),
+                                on_click: on_task_click.clone(),
+                                on_remove: remove_task.clone(),
+                            };
+                            if renderer.uses_canvas(tasks.len()) {
+                                html! { <CanvasBars ..props /> }
+                            } else {
+                                html! { <DomBars ..props /> }
                             }
+                        }}
+                        // 受注の納期は縦線で表示する
+                        { for schedule.work_orders.iter().filter_map(|order| Some((order, order.due_date?))).map(|(order, due_date)| html! {
+                            <div
+                                key={order.id}
+                                class="due-date"
+                                title={format!("{} due {}", order.number, due_date.format("%Y-%m-%d %H:%M"))}
+                                style={format!(
+                                    "position: absolute; left: {}px; top: 0; bottom: 0; border-left: 2px dashed {}; pointer-events: none;",
+                                    timeline.x(due_date) - 1, DUE_DATE_COLOR
+                                )}
+                            >
+                                <span style={format!(
+                                    "position: absolute; top: 0; left: 2px; padding: 0 3px; white-space: nowrap;
+                                    font-size: 0.75em; color: white; background-color: {}; border-radius: 2px;",
+                                    DUE_DATE_COLOR
+                                )}>
+                                    { &order.number }
+                                </span>
+                            </div>
                         }) }
+                        <DependencyArrows
+                            tasks={schedule.tasks.clone()}
+                            layout={(*layout).clone()}
+                            timeline={*timeline}
+                            dragging={*dragging_task}
+                            moving={moving.clone()}
+                            calendars={machine_calendars.clone()}
+                            analysis={(*analysis).clone()}
+                        />
+                        if let Some(band) = *rubber_band {
+                            <div
+                                class="rubber-band"
+                                style={{
+                                    let (left, top, width, height) = band.rect();
+                                    format!(
+                                        "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px;
+                                        border: 1px solid #1976d2; background-color: rgba(25, 118, 210, 0.15); pointer-events: none;",
+                                        left, top, width, height
+                                    )
+                                }}
+                            />
+                        }
+                    </div>
                 </div>
             </div>
+            <ConflictPanel
+                conflicts={(*conflicts).clone()}
+                machines={schedule.machines.clone()}
+                tasks={schedule.tasks.clone()}
+                selected={*selected_conflict}
+                on_select={select_conflict}
+            />
+            <WorkOrderPanel
+                work_orders={schedule.work_orders.clone()}
+                on_add={add_work_order}
+                on_change={update_work_order}
+                on_remove={remove_work_order}
+            />
+            <CalendarPanel
+                calendars={schedule.calendars.clone()}
+                on_add={add_calendar}
+                on_change={update_calendar}
+                on_remove={remove_calendar}
+                on_error={{
+                    let error_message = error_message.clone();
+                    Callback::from(move |message| error_message.set(Some(message)))
+                }}
+            />
+            if let Some(name) = shown {
+                <VarianceReport baseline={name} rows={(*baseline_variance).clone()} />
+            }
+            <HistoryPanel
+                labels={schedule.history().map(|command| command.label.clone()).collect::<Vec<_>>()}
+                position={schedule.position()}
+                on_goto={{
+                    let schedule = schedule.clone();
+                    Callback::from(move |position| schedule.dispatch(ScheduleAction::Goto(position)))
+                }}
+            />
+        </>
+    }
+}
+
+/// バーの描画に渡す値。DOM 表示とキャンバス表示で共通
+#[derive(Properties, PartialEq, Clone)]
+pub struct BarsProps {
+    tasks: Rc<Vec<Task>>,
+    layout: Rc<LaneLayout>,
+    timeline: Timeline,
+    /// 表示中の横位置の範囲 (px)
+    visible: (i64, i64),
+    dragging: Option<DragState>,
+    selected: HashSet<TaskId>,
+    /// ドラッグと一緒に動くタスク
+    moving: Rc<HashSet<TaskId>>,
+    /// 子を持つタスク。期間と進捗は子から決まる
+    summaries: Rc<HashSet<TaskId>>,
+    critical: Rc<HashSet<TaskId>>,
+    conflicting: Rc<HashSet<TaskId>>,
+    highlighted: Vec<TaskId>,
+    /// 表示中のベースラインでの予定 (タスクごとの開始と終了)。影として描く
+    baseline: Rc<HashMap<TaskId, (NaiveDateTime, NaiveDateTime)>>,
+    /// 受注の納期より後に終わるタスク
+    missed: Rc<HashMap<TaskId, MissedDeadline>>,
+    /// 分割したタスクの番号と分割数
+    segments: Rc<HashMap<TaskId, (usize, usize)>>,
+    /// 機械ごとのカレンダー。ドラッグ中のバーは休止時間をまたいで伸びる
+    calendars: Rc<MachineCalendars>,
+    /// バーか端のつまみを押したとき
+    on_mouse_down: Callback<(MouseEvent, TaskId, DragMode)>,
+    on_click: Callback<(MouseEvent, TaskId)>,
+    on_remove: Callback<TaskId>,
+}
+
+/// タスクごとに要素を置く描画。タスクが少ないときの既定
+#[function_component(DomBars)]
+fn dom_bars(props: &BarsProps) -> Html {
+    html! {
+        <>
+        // ベースラインの影は先に置いてバーの下に表示する
+        { for props.tasks.iter().filter_map(|task| {
+            let top = props.layout.bar_top(task.id)?;
+            let (start, end) = props.baseline.get(&task.id)?;
+            let left = props.timeline.x(*start);
+            Some(html! {
+                <div
+                    key={format!("baseline-{}", task.id)}
+                    class="baseline-bar"
+                    style={format!(
+                        "position: absolute; top: {}px; left: {}px; width: {}px; height: {}px; box-sizing: border-box;
+                        background-color: {}; border: 1px dashed {}; border-radius: 5px; pointer-events: none;",
+                        top, left, props.timeline.x(*end) - left, ROW_HEIGHT, BASELINE_FILL, BASELINE_STROKE
+                    )}
+                />
+            })
+        }) }
+        { for props.tasks.iter().filter_map(|task| {
+            // 表示しない行のタスクは描かない
+            let top = props.layout.bar_top(task.id)?;
+            let drag = drag_for(props.dragging, &props.moving, task.id);
+            let top = top + drag.map_or(0, |drag| drag.offset_y.round() as i64);
+            Some(html! {
+                <TaskView
+                    key={task.id}
+                    task={task.clone()}
+                    top={top}
+                    timeline={props.timeline}
+                    drag={drag}
+                    is_critical={props.critical.contains(&task.id)}
+                    has_conflict={props.conflicting.contains(&task.id)}
+                    is_highlighted={props.highlighted.contains(&task.id)}
+                    is_selected={props.selected.contains(&task.id)}
+                    is_summary={props.summaries.contains(&task.id)}
+                    missed={props.missed.get(&task.id).cloned()}
+                    segment={props.segments.get(&task.id).copied()}
+                    calendar={props.calendars.of(task).cloned()}
+                    remove_task={props.on_remove.clone()}
+                    on_mouse_down={props.on_mouse_down.clone()}
+                    on_click={props.on_click.clone()}
+                />
+            })
+        }) }
         </>
     }
 }
@@ -538,10 +2739,30 @@ pub fn gantt_chart() -> Html {
 #[derive(Properties, PartialEq)]
 struct TaskViewProps {
     task: Task,
-    remove_task: Callback<usize>,
-    on_input_name: Callback<(usize, String, NaiveDateTime, NaiveDateTime)>,
-    on_mouse_down: Callback<MouseEvent>,
-    on_click: Callback<MouseEvent>,
+    /// レーン内の縦位置 (px)
+    top: i64,
+    timeline: Timeline,
+    /// ドラッグ中のみ Some
+    drag: Option<DragState>,
+    /// クリティカルパス上のタスクは赤枠で表示する
+    is_critical: bool,
+    /// 機械の負荷超過やメンテナンスと重なるタスクは警告を表示する
+    has_conflict: bool,
+    /// 競合一覧で選択されたタスク
+    is_highlighted: bool,
+    /// 一括操作の対象として選択中
+    is_selected: bool,
+    /// 子を持つタスク。細い帯と進捗で表示し、端のつまみは出さない
+    is_summary: bool,
+    /// 受注の納期より後に終わるなら、その納期と遅れ
+    missed: Option<MissedDeadline>,
+    /// 分割したタスクなら (番号, 分割数)
+    segment: Option<(usize, usize)>,
+    /// 割り当てた機械のカレンダー
+    calendar: Option<Calendar>,
+    remove_task: Callback<TaskId>,
+    on_mouse_down: Callback<(MouseEvent, TaskId, DragMode)>,
+    on_click: Callback<(MouseEvent, TaskId)>,
 }
 
 #[function_component(TaskView)]
@@ -555,45 +2776,239 @@ fn task_view(props: &TaskViewProps) -> Html {
 fn task_view_inner(props: &TaskViewProps) -> Result<Html, Box<dyn std::error::Error>> {
     let task = &props.task;
     let remove_task = props.remove_task.clone();
-    let on_input_name = props.on_input_name.clone();
-    let on_mouse_down = props.on_mouse_down.clone();
-    let on_click = props.on_click.clone();
     let task_id = task.id;
-    let task_color = &task.color;
+    // 端のつまみを押したときは長さを変える
+    let on_mouse_down = props.on_mouse_down.reform(move |e: MouseEvent| {
+        let handle = e.target()
+            .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+            .and_then(|element| element.get_attribute("data-resize"));
+        let mode = match handle.as_deref() {
+            Some("start") => DragMode::ResizeStart,
+            Some("end") => DragMode::ResizeEnd,
+            _ => DragMode::Move,
+        };
+        (e, task_id, mode)
+    });
+    let on_click = props.on_click.reform(move |e: MouseEvent| (e, task_id));
+    let title = match &props.missed {
+        Some(missed) => format!(
+            "{} · {:.0}%\nEnds {} after the order is due ({})",
+            task.status.label(),
+            task.percent_complete,
+            format_duration(missed.late_minutes),
+            missed.due_date.format("%m/%d %H:%M")
+        ),
+        None => format!("{} · {:.0}%", task.status.label(), task.percent_complete),
+    };
+    if task.is_milestone() {
+        return Ok(milestone_view(props, title, on_mouse_down, on_click));
+    }
+    // サマリーは配下と区別できるよう濃い灰色で描く
+    let task_color = if props.is_summary { SUMMARY_COLOR } else { &task.color };
     let task_name = &task.name;
-    let task_start_date = task.start_date;
-    let task_end_date = task.end_date;
-    let base_date = NaiveDateTime::parse_from_str("2025-03-01 00:00:00", "%Y-%m-%d %H:%M:%S")?;
-    let start_offset = (task_start_date - base_date).num_days() * 100 + if task.is_dragging { task.drag_offset * 100 } else { 0 };
-    let duration = (task_end_date - task_start_date).num_days() * 100;
-    
+    let is_dragging = props.drag.is_some();
+    // 実績は予定のドラッグでは動かない
+    let actual = actual_span(task, &props.timeline, now());
+    let calendar = props.calendar.as_ref();
+    let (start_offset, duration) = bar_span(task, &props.timeline, props.drag, calendar);
+    // 長さを変えている間は変更後の所要時間を表示する
+    let resize_label = props.drag.filter(|drag| drag.mode != DragMode::Move).map(|drag| {
+        let (start, end) = drag.span(task, calendar);
+        format!("{} ({} – {})", format_span_duration(start, end, calendar), start.format("%m/%d %H:%M"), end.format("%m/%d %H:%M"))
+    });
+
     Ok(html! {
-        <div style={format!("position: relative; height: 30px;")}>
+        <>
+        <div
+            data-task-id={task_id.to_string()}
+            title={title}
+            style={format!(
+                "position: absolute; top: {}px; left: {}px; width: {}px; background: {}; height: {}px; box-sizing: border-box;
+                border: {}; border-radius: 5px; display: flex; align-items: center;
+                justify-content: space-between; padding: 0 10px; color: white; font-weight: bold;
+                cursor: move; box-shadow: {}; outline: {}; outline-offset: 1px; {} {}",
+                props.top, start_offset, duration, task_color, ROW_HEIGHT,
+                if props.is_critical { "2px solid #d32f2f" } else { "1px solid black" },
+                if props.is_highlighted {
+                    "0 0 0 3px #ff6f00, 0 0 8px 2px #ff9800"
+                } else if props.has_conflict {
+                    "0 0 0 2px #ff6f00"
+                } else {
+                    "none"
+                },
+                if props.is_selected { "2px dashed #1976d2" } else { "none" },
+                // 中止したタスクは薄く表示する
+                if task.status == TaskStatus::Cancelled { "opacity: 0.4; text-decoration: line-through;" } else { "" },
+                if is_dragging {
+                    "transition: none;"
+                } else {
+                    "transition: left 0.1s ease-out, top 0.1s ease-out;"
+                }
+            )}
+            onmousedown={on_mouse_down}
+            onclick={on_click}
+        >
             <div
-                data-task-id={task_id.to_string()}
+                class="progress-fill"
                 style={format!(
-                    "position: absolute; left: {}px; width: {}px; background: {}; height: 30px;
-                    border: 1px solid black; border-radius: 5px; display: flex; align-items: center;
-                    justify-content: space-between; padding: 0 10px; color: white; font-weight: bold;
-                    cursor: move; {}",
-                    start_offset, duration, task_color,
-                    if task.is_dragging {
-                        "transition: none;"
-                    } else {
-                        "transition: left 0.1s ease-out;"
-                    }
+                    "position: absolute; left: 0; top: 0; bottom: 0; width: {}%; background-color: {}; pointer-events: none;",
+                    task.percent_complete, PROGRESS_FILL
                 )}
-                onmousedown={on_mouse_down}
-                onclick={on_click}
-            >
-                <span>{task_name}</span>
-                <button
-                    onclick={remove_task.reform(move |_| task_id)}
-                    style="background: none; border: none; color: white; cursor: pointer; padding: 0 5px;"
+            />
+            if !props.is_summary {
+                <div
+                    data-resize="start"
+                    style="position: absolute; left: 0; top: 0; bottom: 0; width: 6px; cursor: ew-resize;"
+                />
+                <div
+                    data-resize="end"
+                    style="position: absolute; right: 0; top: 0; bottom: 0; width: 6px; cursor: ew-resize;"
+                />
+            }
+            if let Some(label) = resize_label {
+                <div
+                    class="resize-tooltip"
+                    style="position: absolute; left: 0; bottom: 100%; margin-bottom: 4px; padding: 2px 6px; white-space: nowrap;
+                    background-color: rgba(0, 0, 0, 0.75); color: white; font-size: 0.8em; font-weight: normal; border-radius: 3px; pointer-events: none;"
                 >
-                    {"×"}
-                </button>
-            </div>
+                    { label }
+                </div>
+            }
+            <span style="position: relative;">
+                if props.has_conflict {
+                    { "⚠ " }
+                }
+                if props.missed.is_some() {
+                    { "⚑ " }
+                }
+                if let Some(icon) = status_icon(task.status) {
+                    { icon }
+                }
+                {task_name}
+                if let Some((number, count)) = props.segment {
+                    { format!(" [{}/{}]", number, count) }
+                }
+                if props.is_summary {
+                    { format!(" ({:.0}%)", task.percent_complete) }
+                }
+            </span>
+            <button
+                onclick={remove_task.reform(move |_| task_id)}
+                style="position: relative; background: none; border: none; color: white; cursor: pointer; padding: 0 5px;"
+            >
+                {"×"}
+            </button>
         </div>
+        if let Some((left, width, late)) = actual {
+            <div
+                class="actual-bar"
+                title={format!(
+                    "Actual {} – {}",
+                    task.actual_start.map(|time| time.format("%m/%d %H:%M").to_string()).unwrap_or_default(),
+                    task.actual_end.map_or_else(|| "now".to_string(), |time| time.format("%m/%d %H:%M").to_string())
+                )}
+                style={format!(
+                    "position: absolute; left: {}px; width: {}px; top: {}px; height: {}px; background-color: {}; pointer-events: none;",
+                    left, width, props.top + ROW_HEIGHT - ACTUAL_BAR_HEIGHT, ACTUAL_BAR_HEIGHT,
+                    if late { "#d32f2f" } else { "#212121" }
+                )}
+            />
+        }
+        </>
     })
-}
\ No newline at end of file
+}
+
+/// マイルストーンはその時点を中心にしたひし形と、右側の名前で表示する
+fn milestone_view(props: &TaskViewProps, title: String, on_mouse_down: Callback<MouseEvent>, on_click: Callback<MouseEvent>) -> Html {
+    let task = &props.task;
+    let (x, _) = bar_span(task, &props.timeline, props.drag, None);
+    let inset = (ROW_HEIGHT - MILESTONE_SIZE) / 2;
+    html! {
+        <div
+            data-task-id={task.id.to_string()}
+            title={title}
+            style={format!(
+                "position: absolute; top: {}px; left: {}px; width: {}px; height: {}px; cursor: move; outline: {}; {} {}",
+                props.top, x - ROW_HEIGHT / 2, ROW_HEIGHT, ROW_HEIGHT,
+                if props.is_selected { "2px dashed #1976d2" } else { "none" },
+                if task.status == TaskStatus::Cancelled { "opacity: 0.4; text-decoration: line-through;" } else { "" },
+                if props.drag.is_some() { "transition: none;" } else { "transition: left 0.1s ease-out, top 0.1s ease-out;" }
+            )}
+            onmousedown={on_mouse_down}
+            onclick={on_click}
+        >
+            <div
+                class="milestone"
+                style={format!(
+                    "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; box-sizing: border-box;
+                    background: {}; border: {}; transform: rotate(45deg); box-shadow: {};",
+                    inset, inset, MILESTONE_SIZE, MILESTONE_SIZE, task.color,
+                    if props.is_critical { "2px solid #d32f2f" } else { "1px solid black" },
+                    if props.is_highlighted {
+                        "0 0 0 3px #ff6f00, 0 0 8px 2px #ff9800"
+                    } else if props.has_conflict {
+                        "0 0 0 2px #ff6f00"
+                    } else {
+                        "none"
+                    }
+                )}
+            />
+            <span style="position: absolute; left: 100%; top: 50%; transform: translateY(-50%); white-space: nowrap; color: #333; font-weight: bold; pointer-events: none;">
+                if props.missed.is_some() {
+                    { "⚑ " }
+                }
+                if let Some(icon) = status_icon(task.status) {
+                    { icon }
+                }
+                { &task.name }
+            </span>
+        </div>
+    }
+}
+
+/// 名前の前に付ける状態の印
+fn status_icon(status: TaskStatus) -> Option<&'static str> {
+    match status {
+        TaskStatus::Done => Some("✓ "),
+        TaskStatus::Paused => Some("⏸ "),
+        TaskStatus::InSetup => Some("⚙ "),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: TaskId) -> Task {
+        let start = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap().and_hms_opt(8, 0, 0).unwrap();
+        Task {
+            id,
+            name: format!("Op {}", id),
+            start_date: start,
+            end_date: start + Duration::hours(4),
+            color: "#4CAF50".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn referenced_tasks_are_saved_first() {
+        let link = |predecessor_id| Dependency { predecessor_id, kind: DependencyKind::FinishToStart, lag_minutes: 0 };
+        let mut upserts = vec![
+            // 新しい親の下にまとめた子
+            api::Change::Update(Task { parent_id: Some(9), ..task(1) }),
+            api::Change::Create(Task { dependencies: vec![link(3)], ..task(2) }),
+            api::Change::Create(Task { split_of: Some(4), ..task(5) }),
+            api::Change::Create(task(3)),
+            api::Change::Update(task(4)),
+            api::Change::Create(task(9)),
+        ];
+        sort_for_saving(&mut upserts);
+        let order: Vec<usize> = upserts.iter().map(|change| change.id()).collect();
+        let position = |id| order.iter().position(|other| *other == id).unwrap();
+        assert!(position(9) < position(1), "{:?}", order);
+        assert!(position(3) < position(2), "{:?}", order);
+        assert!(position(4) < position(5), "{:?}", order);
+    }
+}
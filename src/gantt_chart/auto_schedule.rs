@@ -0,0 +1,75 @@
+use millmill_domain::{PriorityRule, ScheduleDirection, SchedulerOptions};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+use super::timeline::now;
+
+#[derive(Properties, PartialEq)]
+pub struct SchedulerToolbarProps {
+    /// 計算中はボタンを押せなくする
+    pub running: bool,
+    /// 前回の結果の要約
+    pub last_run: Option<String>,
+    /// 現在時刻から後に並べる
+    pub on_run: Callback<SchedulerOptions>,
+}
+
+/// 受注の工程を機械の空きに自動で並べる。向きと優先規則を選んで実行する
+#[function_component(SchedulerToolbar)]
+pub fn scheduler_toolbar(props: &SchedulerToolbarProps) -> Html {
+    let direction = use_state(ScheduleDirection::default);
+    let rule = use_state(PriorityRule::default);
+    let released_only = use_state(|| false);
+
+    let on_select_direction = {
+        let direction = direction.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = ScheduleDirection::from_code(&select.value()) {
+                direction.set(selected);
+            }
+        })
+    };
+    let on_select_rule = {
+        let rule = rule.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = PriorityRule::from_code(&select.value()) {
+                rule.set(selected);
+            }
+        })
+    };
+    let on_toggle_released = {
+        let released_only = released_only.clone();
+        Callback::from(move |_| released_only.set(!*released_only))
+    };
+    let on_run = {
+        let on_run = props.on_run.clone();
+        let (direction, rule, released_only) = (*direction, *rule, *released_only);
+        Callback::from(move |_| on_run.emit(SchedulerOptions { direction, rule, start: now(), released_only }))
+    };
+
+    html! {
+        <div class="scheduler-toolbar" style="display: flex; align-items: center; gap: 4px; margin: 6px 0;">
+            <label>{ "Auto schedule: " }</label>
+            <select onchange={on_select_direction}>
+                { for ScheduleDirection::ALL.into_iter().map(|d| html! {
+                    <option value={d.code()} selected={*direction == d}>{ d.label() }</option>
+                }) }
+            </select>
+            <select onchange={on_select_rule}>
+                { for PriorityRule::ALL.into_iter().map(|r| html! {
+                    <option value={r.code()} selected={*rule == r} title={r.label()}>{ format!("{} — {}", r.code(), r.label()) }</option>
+                }) }
+            </select>
+            <label title="Leave planned operations where they are">
+                <input type="checkbox" checked={*released_only} onchange={on_toggle_released} />
+                { " Released only" }
+            </label>
+            <button disabled={props.running} onclick={on_run}>{ if props.running { "Scheduling..." } else { "Schedule" } }</button>
+            if let Some(last_run) = &props.last_run {
+                <span style="color: #666; margin-left: 8px;">{ last_run }</span>
+            }
+        </div>
+    }
+}
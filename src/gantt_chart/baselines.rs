@@ -0,0 +1,138 @@
+use std::rc::Rc;
+
+use chrono::NaiveDateTime;
+use millmill_domain::{Baseline, BaselineId, TaskVariance};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct BaselineToolbarProps {
+    pub baselines: Rc<Vec<Baseline>>,
+    /// 影として表示中のベースライン
+    pub shown: Option<BaselineId>,
+    pub on_show: Callback<Option<BaselineId>>,
+    /// 名前を渡す。保存済みのタスクの日時を固定する
+    pub on_create: Callback<String>,
+    pub on_delete: Callback<BaselineId>,
+}
+
+/// ベースラインの保存と、比較に使うベースラインの選択
+#[function_component(BaselineToolbar)]
+pub fn baseline_toolbar(props: &BaselineToolbarProps) -> Html {
+    let name = use_state(String::new);
+
+    let on_select = {
+        let on_show = props.on_show.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            on_show.emit(select.value().parse::<BaselineId>().ok());
+        })
+    };
+    let on_input_name = {
+        let name = name.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            name.set(input.value());
+        })
+    };
+    let on_create = {
+        let name = name.clone();
+        let on_create = props.on_create.clone();
+        Callback::from(move |_| {
+            if !name.trim().is_empty() {
+                on_create.emit(name.trim().to_string());
+                name.set(String::new());
+            }
+        })
+    };
+
+    html! {
+        <div class="baseline-toolbar" style="display: flex; align-items: center; gap: 4px; margin: 6px 0;">
+            <label>{ "Baseline: " }</label>
+            <select onchange={on_select}>
+                <option value="" selected={props.shown.is_none()}>{ "(None)" }</option>
+                { for props.baselines.iter().map(|baseline| html! {
+                    <option value={baseline.id.to_string()} selected={props.shown == Some(baseline.id)}>
+                        { format!("{} ({})", baseline.name, baseline.created_at.format("%Y-%m-%d %H:%M")) }
+                    </option>
+                }) }
+            </select>
+            if let Some(id) = props.shown {
+                <button title="Delete this baseline" onclick={props.on_delete.reform(move |_| id)}>{ "Delete" }</button>
+            }
+            <input type="text" placeholder="Baseline name" value={(*name).clone()} oninput={on_input_name} />
+            <button disabled={name.trim().is_empty()} onclick={on_create}>{ "Save Baseline" }</button>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct VarianceReportProps {
+    pub baseline: String,
+    pub rows: Rc<Vec<TaskVariance>>,
+}
+
+/// ベースラインからの開始と終了のずれ (時間) の一覧。遅れは赤、前倒しは緑で表示する
+#[function_component(VarianceReport)]
+pub fn variance_report(props: &VarianceReportProps) -> Html {
+    let changed_only = use_state(|| true);
+    let changed = props.rows.iter().filter(|row| !row.is_unchanged()).count();
+    let on_toggle = {
+        let changed_only = changed_only.clone();
+        Callback::from(move |_| changed_only.set(!*changed_only))
+    };
+
+    html! {
+        <div class="variance-report" style="margin-top: 8px; padding: 8px 12px; border: 1px solid #ccc; border-radius: 5px; background-color: #fafafa;">
+            <div style="display: flex; justify-content: space-between; align-items: center; margin-bottom: 6px;">
+                <h4 style="margin: 0;">{ format!("Variance from \"{}\" ({} of {} tasks changed)", props.baseline, changed, props.rows.len()) }</h4>
+                <label>
+                    <input type="checkbox" checked={*changed_only} onchange={on_toggle} />
+                    { " Changed only" }
+                </label>
+            </div>
+            <table style="border-collapse: collapse; width: 100%;">
+                <thead>
+                    <tr style="text-align: left; border-bottom: 1px solid #ccc;">
+                        <th>{ "Task" }</th>
+                        <th>{ "Baseline" }</th>
+                        <th>{ "Current" }</th>
+                        <th style="text-align: right;">{ "Start Δ" }</th>
+                        <th style="text-align: right;">{ "Finish Δ" }</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    { for props.rows.iter().filter(|row| !*changed_only || !row.is_unchanged()).map(|row| html! {
+                        <tr key={row.task_id} style="border-bottom: 1px solid #eee;">
+                            <td>{ &row.name }</td>
+                            <td>{ format_span(row.baseline, "Added") }</td>
+                            <td>{ format_span(row.current, "Removed") }</td>
+                            { delta_cell(row.start_hours()) }
+                            { delta_cell(row.finish_hours()) }
+                        </tr>
+                    }) }
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+/// 期間の表示。ない場合は `missing` を表示する
+fn format_span(span: Option<(NaiveDateTime, NaiveDateTime)>, missing: &str) -> String {
+    span.map_or_else(
+        || missing.to_string(),
+        |(start, end)| format!("{} – {}", start.format("%m/%d %H:%M"), end.format("%m/%d %H:%M")),
+    )
+}
+
+fn delta_cell(hours: Option<f64>) -> Html {
+    let (text, color) = match hours {
+        None => ("—".to_string(), "#999"),
+        Some(hours) if hours > 0.0 => (format!("+{:.1}h", hours), "#d32f2f"),
+        Some(hours) if hours < 0.0 => (format!("{:.1}h", hours), "#388e3c"),
+        Some(_) => ("0h".to_string(), "#666"),
+    };
+    html! {
+        <td style={format!("text-align: right; color: {};", color)}>{ text }</td>
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use gloo::file::callbacks::FileReader;
+use gloo::file::File;
+use millmill_domain::{holidays_from_ical, Calendar, CalendarException, CalendarId, Machine, MachineId, Shift, Task};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+/// 曜日の見出し。`Calendar::week` と同じく月曜始まり
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// 編集したシフトをカレンダーの曜日か例外日に書き込む
+type SetShifts = Box<dyn Fn(&mut Calendar, Vec<Shift>)>;
+
+/// 機械ごとの稼働カレンダー。カレンダーのない機械は 24 時間稼働として扱う
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct MachineCalendars(HashMap<MachineId, Calendar>);
+
+impl MachineCalendars {
+    pub fn new(machines: &[Machine], calendars: &[Calendar]) -> Self {
+        MachineCalendars(
+            machines
+                .iter()
+                .filter_map(|machine| {
+                    let calendar = calendars.iter().find(|calendar| Some(calendar.id) == machine.calendar_id)?;
+                    Some((machine.id, calendar.clone()))
+                })
+                .filter(|(_, calendar)| calendar.has_working_time())
+                .collect(),
+        )
+    }
+
+    pub fn machine(&self, machine_id: Option<MachineId>) -> Option<&Calendar> {
+        self.0.get(&machine_id?)
+    }
+
+    /// タスクを割り当てた機械のカレンダー
+    pub fn of(&self, task: &Task) -> Option<&Calendar> {
+        self.machine(task.machine_id)
+    }
+
+    /// `start` から `end` までの稼働時間 (分)。カレンダーがなければ経過時間
+    pub fn working_minutes(&self, task: &Task, start: NaiveDateTime, end: NaiveDateTime) -> i64 {
+        self.of(task).map_or((end - start).num_minutes(), |calendar| calendar.working_minutes(start, end))
+    }
+}
+
+/// "06:00-14:00, 14:00-22:00" の形式で書いたシフト。読めない部分があれば None
+fn parse_shifts(text: &str) -> Option<Vec<Shift>> {
+    let mut shifts = text
+        .split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(Shift::parse)
+        .collect::<Option<Vec<_>>>()?;
+    shifts.sort_by_key(|shift| shift.start);
+    Some(shifts)
+}
+
+fn format_shifts(shifts: &[Shift]) -> String {
+    shifts.iter().map(|shift| shift.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CalendarPanelProps {
+    pub calendars: Vec<Calendar>,
+    pub on_add: Callback<()>,
+    /// 編集したカレンダー。入力欄を離れたときに渡す
+    pub on_change: Callback<Calendar>,
+    pub on_remove: Callback<CalendarId>,
+    pub on_error: Callback<String>,
+}
+
+/// 稼働カレンダーの一覧と編集。曜日ごとのシフト、例外日 (休日や臨時のシフト)、iCalendar からの祝日の取り込み
+#[function_component(CalendarPanel)]
+pub fn calendar_panel(props: &CalendarPanelProps) -> Html {
+    html! {
+        <div class="calendar-panel" style="margin-top: 8px; padding: 8px 12px; border: 1px solid #ccc; border-radius: 5px; background-color: #fafafa;">
+            <div style="display: flex; justify-content: space-between; align-items: center; margin-bottom: 6px;">
+                <h4 style="margin: 0;">{ format!("Calendars ({})", props.calendars.len()) }</h4>
+                <button onclick={props.on_add.reform(|_| ())}>{ "Add Calendar" }</button>
+            </div>
+            if props.calendars.is_empty() {
+                <div style="color: #666;">{ "No calendars. Machines without one run around the clock." }</div>
+            }
+            { for props.calendars.iter().map(|calendar| html! {
+                <CalendarEditor
+                    key={calendar.id}
+                    calendar={calendar.clone()}
+                    on_change={props.on_change.clone()}
+                    on_remove={props.on_remove.clone()}
+                    on_error={props.on_error.clone()}
+                />
+            }) }
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct CalendarEditorProps {
+    calendar: Calendar,
+    on_change: Callback<Calendar>,
+    on_remove: Callback<CalendarId>,
+    on_error: Callback<String>,
+}
+
+#[function_component(CalendarEditor)]
+fn calendar_editor(props: &CalendarEditorProps) -> Html {
+    let calendar = &props.calendar;
+    let id = calendar.id;
+    // 読み込み中の iCalendar ファイル。破棄すると読み込みが中断される
+    let reader = use_mut_ref(|| None::<FileReader>);
+    let new_date = use_state(String::new);
+
+    let on_rename = {
+        let calendar = calendar.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            on_change.emit(Calendar { name: input.value(), ..calendar.clone() });
+        })
+    };
+    // シフトの入力欄。読めない書き方なら元の値に戻す
+    let shifts_input = |current: &[Shift], set: SetShifts| {
+        let calendar = calendar.clone();
+        let on_change = props.on_change.clone();
+        let on_error = props.on_error.clone();
+        let current = format_shifts(current);
+        let value = current.clone();
+        let onchange = Callback::from(move |e: Event| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            let text = input.value();
+            match parse_shifts(&text) {
+                Some(shifts) => {
+                    let mut updated = calendar.clone();
+                    set(&mut updated, shifts);
+                    if updated != calendar {
+                        on_change.emit(updated);
+                    }
+                }
+                None => {
+                    input.set_value(&current);
+                    on_error.emit(format!("\"{}\" is not a list of shifts like 06:00-14:00, 14:00-22:00", text));
+                }
+            }
+        });
+        html! {
+            <input type="text" style="width: 200px;" placeholder="Off" {value} {onchange} />
+        }
+    };
+    let on_input_date = {
+        let new_date = new_date.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            new_date.set(input.value());
+        })
+    };
+    // 追加した日は休日になる。シフトを書けば臨時の稼働日になる
+    let on_add_exception = {
+        let calendar = calendar.clone();
+        let on_change = props.on_change.clone();
+        let new_date = new_date.clone();
+        Callback::from(move |_| {
+            let Ok(date) = NaiveDate::parse_from_str(&new_date, "%Y-%m-%d") else {
+                return;
+            };
+            let mut updated = calendar.clone();
+            updated.set_exception(CalendarException { date, name: String::new(), shifts: Vec::new() });
+            on_change.emit(updated);
+            new_date.set(String::new());
+        })
+    };
+    let on_import = {
+        let calendar = calendar.clone();
+        let on_change = props.on_change.clone();
+        let on_error = props.on_error.clone();
+        let reader = reader.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+            let calendar = calendar.clone();
+            let on_change = on_change.clone();
+            let on_error = on_error.clone();
+            let name = file.name();
+            *reader.borrow_mut() = Some(gloo::file::callbacks::read_as_text(&File::from(file), move |result| {
+                let holidays = match result {
+                    Ok(text) => holidays_from_ical(&text),
+                    Err(e) => return on_error.emit(format!("Failed to read {}: {}", name, e)),
+                };
+                if holidays.is_empty() {
+                    return on_error.emit(format!("{} has no dated events", name));
+                }
+                let mut updated = calendar.clone();
+                for holiday in holidays {
+                    updated.set_exception(holiday);
+                }
+                on_change.emit(updated);
+            }));
+        })
+    };
+
+    let weekly_hours = calendar.week.iter().flatten().map(Shift::minutes).sum::<i64>() as f64 / 60.0;
+    html! {
+        <details style="margin-bottom: 4px;">
+            <summary>
+                { format!("{} — {:.0}h/week, {} exceptions", calendar.name, weekly_hours, calendar.exceptions.len()) }
+            </summary>
+            <div style="padding: 4px 0 8px 16px;">
+                <div>
+                    <input type="text" value={calendar.name.clone()} onchange={on_rename} />
+                    <button title="Delete this calendar" onclick={props.on_remove.reform(move |_| id)}>{ "Delete" }</button>
+                </div>
+                <table style="border-collapse: collapse; margin-top: 4px;">
+                    <tbody>
+                        { for WEEKDAYS.iter().enumerate().map(|(weekday, label)| html! {
+                            <tr key={weekday}>
+                                <td style="padding-right: 8px;">{ label }</td>
+                                <td>{ shifts_input(&calendar.week[weekday], Box::new(move |calendar, shifts| calendar.week[weekday] = shifts)) }</td>
+                            </tr>
+                        }) }
+                    </tbody>
+                </table>
+                <h5 style="margin: 8px 0 4px 0;">{ "Exceptions" }</h5>
+                <table style="border-collapse: collapse;">
+                    <tbody>
+                        { for calendar.exceptions.iter().map(|exception| {
+                            let date = exception.date;
+                            let on_rename_exception = {
+                                let calendar = calendar.clone();
+                                let on_change = props.on_change.clone();
+                                Callback::from(move |e: Event| {
+                                    let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+                                    let mut updated = calendar.clone();
+                                    if let Some(exception) = updated.exceptions.iter_mut().find(|exception| exception.date == date) {
+                                        exception.name = input.value();
+                                    }
+                                    on_change.emit(updated);
+                                })
+                            };
+                            let on_remove_exception = {
+                                let calendar = calendar.clone();
+                                let on_change = props.on_change.clone();
+                                Callback::from(move |_| {
+                                    let mut updated = calendar.clone();
+                                    updated.exceptions.retain(|exception| exception.date != date);
+                                    on_change.emit(updated);
+                                })
+                            };
+                            html! {
+                                <tr key={date.to_string()}>
+                                    <td style="padding-right: 8px;">{ date.format("%Y-%m-%d (%a)").to_string() }</td>
+                                    <td><input type="text" placeholder="Name" value={exception.name.clone()} onchange={on_rename_exception} /></td>
+                                    <td>
+                                        { shifts_input(&exception.shifts, Box::new(move |calendar, shifts| {
+                                            if let Some(exception) = calendar.exceptions.iter_mut().find(|exception| exception.date == date) {
+                                                exception.shifts = shifts;
+                                            }
+                                        })) }
+                                    </td>
+                                    <td>
+                                        <button
+                                            title="Remove exception"
+                                            onclick={on_remove_exception}
+                                            style="background: none; border: none; box-shadow: none; cursor: pointer; padding: 0 5px;"
+                                        >
+                                            { "×" }
+                                        </button>
+                                    </td>
+                                </tr>
+                            }
+                        }) }
+                    </tbody>
+                </table>
+                <div style="margin-top: 4px;">
+                    <input type="date" value={(*new_date).clone()} oninput={on_input_date} />
+                    <button disabled={new_date.is_empty()} onclick={on_add_exception}>{ "Add Day Off" }</button>
+                    <label style="margin-left: 12px;">
+                        { "Import holidays (.ics): " }
+                        <input type="file" accept=".ics,text/calendar" onchange={on_import} />
+                    </label>
+                </div>
+            </div>
+        </details>
+    }
+}
@@ -0,0 +1,425 @@
+use gloo_events::{EventListener, EventListenerOptions};
+use millmill_domain::{Task, TaskId, TaskStatus};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, MouseEvent};
+use yew::prelude::*;
+
+use super::timeline::now;
+use super::{
+    actual_span, bar_span, drag_for, format_span_duration, status_icon, BarsProps, DragMode, ACTUAL_BAR_HEIGHT,
+    BASELINE_FILL, BASELINE_STROKE, MILESTONE_SIZE, PROGRESS_FILL, ROW_HEIGHT, SUMMARY_COLOR,
+};
+
+/// 自動切り替えでキャンバスを使い始めるタスク数
+const CANVAS_THRESHOLD: usize = 300;
+/// 端のつまみの幅 (px)。DOM 表示と同じ
+const HANDLE_WIDTH: f64 = 6.0;
+/// 右端の削除ボタンの幅 (px)
+const REMOVE_WIDTH: f64 = 16.0;
+
+/// バーの描画方法。DOM は要素ごとに描くので少数のタスク向け、キャンバスは表示範囲だけを描くので大量のタスク向け
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Renderer {
+    Auto,
+    Dom,
+    Canvas,
+}
+
+impl Renderer {
+    pub const ALL: [Renderer; 3] = [Renderer::Auto, Renderer::Dom, Renderer::Canvas];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Renderer::Auto => "Auto",
+            Renderer::Dom => "DOM",
+            Renderer::Canvas => "Canvas",
+        }
+    }
+
+    pub fn uses_canvas(self, task_count: usize) -> bool {
+        match self {
+            Renderer::Auto => task_count > CANVAS_THRESHOLD,
+            Renderer::Dom => false,
+            Renderer::Canvas => true,
+        }
+    }
+}
+
+/// 描画する横位置の範囲 (px)。時間軸の右端より先は描かない
+fn canvas_span(props: &BarsProps) -> (i64, i64) {
+    let (from, to) = props.visible;
+    (from, to.min(props.timeline.width()).max(from))
+}
+
+/// 描画する縦位置の範囲 (px)。画面に見えているレーンの範囲を最後の行までに切り詰める
+fn canvas_rows(props: &BarsProps, (top, bottom): (i64, i64)) -> (i64, i64) {
+    let height = props.layout.height();
+    (top.min(height), bottom.min(height).max(top.min(height)))
+}
+
+/// レーンの領域のうち画面に見えている縦位置の範囲 (px)。キャンバスの親がレーンの領域
+fn viewport_rows(canvas: &HtmlCanvasElement) -> Option<(i64, i64)> {
+    let lanes = canvas.parent_element()?;
+    let view_height = web_sys::window()?.inner_height().ok()?.as_f64()?;
+    let top = -lanes.get_bounding_client_rect().top();
+    Some((top.max(0.0).floor() as i64, (top + view_height).max(0.0).ceil() as i64))
+}
+
+/// バー上のどこを押したか
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Zone {
+    Drag(DragMode),
+    Remove,
+}
+
+/// キャンバス上のバーの位置 (キャンバス左端からの px)
+struct BarRect<'a> {
+    task: &'a Task,
+    left: f64,
+    top: f64,
+    width: f64,
+}
+
+/// 表示範囲にかかるバーだけを描画順に返す
+fn visible_bars(props: &BarsProps, (from_y, to_y): (i64, i64)) -> Vec<BarRect<'_>> {
+    let (from, to) = canvas_span(props);
+    props
+        .tasks
+        .iter()
+        .filter_map(|task| {
+            // 表示しない行のタスクと表示範囲外のバーは飛ばす
+            let top = props.layout.bar_top(task.id)?;
+            let drag = drag_for(props.dragging, &props.moving, task.id);
+            let (left, width) = bar_span(task, &props.timeline, drag, props.calendars.of(task));
+            // マイルストーンのひし形は時点の左右にはみ出す
+            let reach = if task.is_milestone() { ROW_HEIGHT / 2 } else { 0 };
+            if left + width + reach < from || left - reach > to {
+                return None;
+            }
+            let top = top + drag.map_or(0, |drag| drag.offset_y.round() as i64);
+            if top + ROW_HEIGHT < from_y || top > to_y {
+                return None;
+            }
+            Some(BarRect { task, left: (left - from) as f64, top: (top - from_y) as f64, width: width as f64 })
+        })
+        .collect()
+}
+
+/// 後から描いたバーが手前なので逆順に調べる
+fn hit_test(props: &BarsProps, rows: (i64, i64), x: f64, y: f64) -> Option<(TaskId, Zone)> {
+    visible_bars(props, rows).into_iter().rev().find_map(|bar| {
+        let right = bar.left + bar.width;
+        if bar.task.is_milestone() {
+            let half = ROW_HEIGHT as f64 / 2.0;
+            let inside = (x - bar.left).abs() <= half && y >= bar.top && y <= bar.top + ROW_HEIGHT as f64;
+            return inside.then_some((bar.task.id, Zone::Drag(DragMode::Move)));
+        }
+        if x < bar.left || x > right || y < bar.top || y > bar.top + ROW_HEIGHT as f64 {
+            return None;
+        }
+        // サマリーは端のつまみを持たない
+        let handles = !props.summaries.contains(&bar.task.id);
+        let zone = if handles && x >= right - HANDLE_WIDTH {
+            Zone::Drag(DragMode::ResizeEnd)
+        } else if handles && x <= bar.left + HANDLE_WIDTH {
+            Zone::Drag(DragMode::ResizeStart)
+        } else if x >= right - HANDLE_WIDTH - REMOVE_WIDTH {
+            Zone::Remove
+        } else {
+            Zone::Drag(DragMode::Move)
+        };
+        Some((bar.task.id, zone))
+    })
+}
+
+fn draw(canvas: &HtmlCanvasElement, props: &BarsProps, rows: (i64, i64)) -> Result<(), JsValue> {
+    let ratio = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio());
+    let (from, to) = canvas_span(props);
+    let (from_y, to_y) = rows;
+    let (width, height) = ((to - from) as f64, (to_y - from_y) as f64);
+    // 大きさを設定するとキャンバスが作り直されるので、変わったときだけ設定する
+    let (pixel_width, pixel_height) = ((width * ratio) as u32, (height * ratio) as u32);
+    if canvas.width() != pixel_width {
+        canvas.set_width(pixel_width);
+    }
+    if canvas.height() != pixel_height {
+        canvas.set_height(pixel_height);
+    }
+    let context = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("2d context is not available"))?
+        .unchecked_into::<CanvasRenderingContext2d>();
+    context.set_transform(ratio, 0.0, 0.0, ratio, 0.0, 0.0)?;
+    context.clear_rect(0.0, 0.0, width, height);
+    context.set_font("bold 13px sans-serif");
+    context.set_text_baseline("middle");
+    let now = now();
+    let solid = js_sys::Array::new();
+    let dashed = js_sys::Array::of2(&JsValue::from(4.0), &JsValue::from(3.0));
+
+    // ベースラインの予定は影としてバーの下に描く
+    context.set_fill_style_str(BASELINE_FILL);
+    context.set_stroke_style_str(BASELINE_STROKE);
+    context.set_line_width(1.0);
+    context.set_line_dash(&dashed)?;
+    for task in props.tasks.iter() {
+        let (Some(top), Some((start, end))) = (props.layout.bar_top(task.id), props.baseline.get(&task.id)) else {
+            continue;
+        };
+        let (left, right) = (props.timeline.x(*start), props.timeline.x(*end));
+        if right < from || left > to || top + ROW_HEIGHT < from_y || top > to_y {
+            continue;
+        }
+        let (left, top, bar_width) = ((left - from) as f64, (top - from_y) as f64, (right - left) as f64);
+        context.fill_rect(left, top, bar_width, ROW_HEIGHT as f64);
+        context.stroke_rect(left + 0.5, top + 0.5, bar_width - 1.0, ROW_HEIGHT as f64 - 1.0);
+    }
+    context.set_line_dash(&solid)?;
+
+    for bar in visible_bars(props, rows) {
+        let task = bar.task;
+        let (left, top, bar_width, bar_height) = (bar.left, bar.top, bar.width, ROW_HEIGHT as f64);
+
+        // 中止したタスクは薄く描く
+        context.set_global_alpha(if task.status == TaskStatus::Cancelled { 0.4 } else { 1.0 });
+        if task.is_milestone() {
+            draw_milestone(&context, props, &bar, &dashed, &solid)?;
+            context.set_global_alpha(1.0);
+            continue;
+        }
+
+        // 競合と選択の外枠
+        if props.highlighted.contains(&task.id) || props.conflicting.contains(&task.id) {
+            let spread = if props.highlighted.contains(&task.id) { 3.0 } else { 2.0 };
+            context.set_fill_style_str("#ff6f00");
+            context.fill_rect(left - spread, top - spread, bar_width + spread * 2.0, bar_height + spread * 2.0);
+        }
+        let is_summary = props.summaries.contains(&task.id);
+        context.set_fill_style_str(if is_summary { SUMMARY_COLOR } else { &task.color });
+        context.fill_rect(left, top, bar_width, bar_height);
+        context.set_fill_style_str(PROGRESS_FILL);
+        context.fill_rect(left, top, bar_width * task.percent_complete / 100.0, bar_height);
+        let critical = props.critical.contains(&task.id);
+        context.set_line_width(if critical { 2.0 } else { 1.0 });
+        context.set_stroke_style_str(if critical { "#d32f2f" } else { "black" });
+        context.stroke_rect(left + 0.5, top + 0.5, bar_width - 1.0, bar_height - 1.0);
+        if props.selected.contains(&task.id) {
+            context.set_line_dash(&dashed)?;
+            context.set_line_width(2.0);
+            context.set_stroke_style_str("#1976d2");
+            context.stroke_rect(left - 2.0, top - 2.0, bar_width + 4.0, bar_height + 4.0);
+            context.set_line_dash(&solid)?;
+        }
+
+        // 名前はバーの幅で切り取る
+        context.save();
+        context.begin_path();
+        context.rect(left, top, (bar_width - REMOVE_WIDTH - HANDLE_WIDTH).max(0.0), bar_height);
+        context.clip();
+        context.set_fill_style_str("white");
+        let mut label = format!(
+            "{}{}{}{}",
+            if props.conflicting.contains(&task.id) { "⚠ " } else { "" },
+            if props.missed.contains_key(&task.id) { "⚑ " } else { "" },
+            status_icon(task.status).unwrap_or(""),
+            task.name
+        );
+        if let Some((number, count)) = props.segments.get(&task.id) {
+            label.push_str(&format!(" [{}/{}]", number, count));
+        }
+        if is_summary {
+            label.push_str(&format!(" ({:.0}%)", task.percent_complete));
+        }
+        context.fill_text(&label, left + 10.0, top + bar_height / 2.0)?;
+        context.restore();
+        if bar_width > REMOVE_WIDTH + HANDLE_WIDTH * 2.0 {
+            context.set_fill_style_str("white");
+            context.fill_text("×", left + bar_width - HANDLE_WIDTH - REMOVE_WIDTH + 3.0, top + bar_height / 2.0)?;
+        }
+        context.set_global_alpha(1.0);
+
+        // 実績の帯。予定のドラッグでは動かないので、ドラッグ前の行の位置に描く
+        if let Some((actual_left, actual_width, late)) = actual_span(task, &props.timeline, now) {
+            let row_top = (props.layout.bar_top(task.id).unwrap_or(0) - from_y) as f64;
+            context.set_fill_style_str(if late { "#d32f2f" } else { "#212121" });
+            context.fill_rect(
+                (actual_left - from) as f64,
+                row_top + (ROW_HEIGHT - ACTUAL_BAR_HEIGHT) as f64,
+                actual_width as f64,
+                ACTUAL_BAR_HEIGHT as f64,
+            );
+        }
+
+        // 長さを変えている間は所要時間を表示する
+        if let Some(drag) = drag_for(props.dragging, &props.moving, task.id).filter(|drag| drag.mode != DragMode::Move) {
+            let calendar = props.calendars.of(task);
+            let (start, end) = drag.span(task, calendar);
+            let text = format!("{} ({} – {})", format_span_duration(start, end, calendar), start.format("%m/%d %H:%M"), end.format("%m/%d %H:%M"));
+            let text_width = context.measure_text(&text)?.width();
+            context.set_fill_style_str("rgba(0, 0, 0, 0.75)");
+            context.fill_rect(left, (top - 22.0).max(0.0), text_width + 12.0, 20.0);
+            context.set_fill_style_str("white");
+            context.fill_text(&text, left + 6.0, (top - 22.0).max(0.0) + 10.0)?;
+        }
+    }
+    Ok(())
+}
+
+/// その時点を中心にしたひし形と、右側の名前
+fn draw_milestone(
+    context: &CanvasRenderingContext2d,
+    props: &BarsProps,
+    bar: &BarRect<'_>,
+    dashed: &JsValue,
+    solid: &JsValue,
+) -> Result<(), JsValue> {
+    let task = bar.task;
+    let (x, middle) = (bar.left, bar.top + ROW_HEIGHT as f64 / 2.0);
+    let diamond = |half: f64| {
+        context.begin_path();
+        context.move_to(x, middle - half);
+        context.line_to(x + half, middle);
+        context.line_to(x, middle + half);
+        context.line_to(x - half, middle);
+        context.close_path();
+    };
+    // DOM 表示と同じく、一辺 MILESTONE_SIZE の正方形を 45 度回した大きさ
+    let half = MILESTONE_SIZE as f64 / std::f64::consts::SQRT_2;
+
+    if props.highlighted.contains(&task.id) || props.conflicting.contains(&task.id) {
+        let spread = if props.highlighted.contains(&task.id) { 3.0 } else { 2.0 };
+        context.set_fill_style_str("#ff6f00");
+        diamond(half + spread * std::f64::consts::SQRT_2);
+        context.fill();
+    }
+    diamond(half);
+    context.set_fill_style_str(&task.color);
+    context.fill();
+    let critical = props.critical.contains(&task.id);
+    context.set_line_width(if critical { 2.0 } else { 1.0 });
+    context.set_stroke_style_str(if critical { "#d32f2f" } else { "black" });
+    context.stroke();
+    if props.selected.contains(&task.id) {
+        let side = ROW_HEIGHT as f64;
+        context.set_line_dash(dashed)?;
+        context.set_line_width(2.0);
+        context.set_stroke_style_str("#1976d2");
+        context.stroke_rect(x - side / 2.0, bar.top, side, side);
+        context.set_line_dash(solid)?;
+    }
+
+    let label = format!(
+        "{}{}{}",
+        if props.missed.contains_key(&task.id) { "⚑ " } else { "" },
+        status_icon(task.status).unwrap_or(""),
+        task.name
+    );
+    context.set_fill_style_str("#333");
+    context.fill_text(&label, x + ROW_HEIGHT as f64 / 2.0, middle)
+}
+
+/// DOM の `TaskView` と同じ入力で、表示範囲のバーだけを 1 枚のキャンバスに描く
+#[function_component(CanvasBars)]
+pub fn canvas_bars(props: &BarsProps) -> Html {
+    let canvas_ref = use_node_ref();
+    let cursor = use_state(|| "default");
+    // 画面に見えているレーンの縦の範囲。初めはウィンドウの高さ分とし、置かれてから測り直す
+    let viewport = use_state_eq(|| {
+        let view_height = web_sys::window()
+            .and_then(|window| window.inner_height().ok())
+            .and_then(|height| height.as_f64())
+            .unwrap_or(1080.0);
+        (0, view_height.ceil() as i64)
+    });
+    let rows = canvas_rows(props, *viewport);
+
+    // スクロールとウィンドウの大きさの変更で見えている範囲を測り直す。どの親のスクロールも拾えるよう捕捉段階で聞く
+    {
+        let canvas_ref = canvas_ref.clone();
+        let viewport = viewport.clone();
+        use_effect_with((), move |_| {
+            let window = web_sys::window().unwrap();
+            let measure = move |_: &Event| {
+                if let Some(rows) = canvas_ref.cast::<HtmlCanvasElement>().as_ref().and_then(viewport_rows) {
+                    viewport.set(rows);
+                }
+            };
+            let options = EventListenerOptions::run_in_capture_phase();
+            let listeners = [
+                EventListener::new_with_options(&window, "scroll", options, measure.clone()),
+                EventListener::new(&window, "resize", measure),
+            ];
+            move || drop(listeners)
+        });
+    }
+
+    // 入力か見えている範囲が変わったときだけ描き直す。範囲外のバーは描かない
+    {
+        let canvas_ref = canvas_ref.clone();
+        let viewport = viewport.clone();
+        use_effect_with((props.clone(), rows), move |(props, rows)| {
+            if let Some(canvas) = canvas_ref.cast::<HtmlCanvasElement>() {
+                if let Err(e) = draw(&canvas, props, *rows) {
+                    log::error!("Failed to draw chart: {:?}", e);
+                }
+                // 上の表示が増減してレーンの位置がずれたときにも追いつく
+                if let Some(rows) = viewport_rows(&canvas) {
+                    viewport.set(rows);
+                }
+            }
+            || ()
+        });
+    }
+
+    let on_mouse_down = {
+        let props = props.clone();
+        Callback::from(move |e: MouseEvent| {
+            if let Some((task_id, zone)) = hit_test(&props, rows, e.offset_x() as f64, e.offset_y() as f64) {
+                // バーの上では範囲選択を始めない
+                e.stop_propagation();
+                if let Zone::Drag(mode) = zone {
+                    props.on_mouse_down.emit((e, task_id, mode));
+                }
+            }
+        })
+    };
+    let on_click = {
+        let props = props.clone();
+        Callback::from(move |e: MouseEvent| match hit_test(&props, rows, e.offset_x() as f64, e.offset_y() as f64) {
+            Some((task_id, Zone::Remove)) => props.on_remove.emit(task_id),
+            Some((task_id, Zone::Drag(_))) => props.on_click.emit((e, task_id)),
+            None => {}
+        })
+    };
+    let on_mouse_move = {
+        let props = props.clone();
+        let cursor = cursor.clone();
+        Callback::from(move |e: MouseEvent| {
+            let next = match hit_test(&props, rows, e.offset_x() as f64, e.offset_y() as f64) {
+                Some((_, Zone::Drag(DragMode::Move))) => "move",
+                Some((_, Zone::Drag(_))) => "ew-resize",
+                Some((_, Zone::Remove)) => "pointer",
+                None => "default",
+            };
+            if *cursor != next {
+                cursor.set(next);
+            }
+        })
+    };
+
+    let (from, to) = canvas_span(props);
+    let (from_y, to_y) = rows;
+    html! {
+        <canvas
+            ref={canvas_ref}
+            class="gantt-canvas"
+            style={format!(
+                "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; cursor: {};",
+                from, from_y, to - from, to_y - from_y, *cursor
+            )}
+            onmousedown={on_mouse_down}
+            onclick={on_click}
+            onmousemove={on_mouse_move}
+        />
+    }
+}
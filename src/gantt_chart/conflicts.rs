@@ -0,0 +1,85 @@
+use millmill_domain::{Conflict, ConflictKind, Machine, Task};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct ConflictPanelProps {
+    pub conflicts: Vec<Conflict>,
+    pub machines: Vec<Machine>,
+    pub tasks: Vec<Task>,
+    /// 選択中の競合の番号
+    pub selected: Option<usize>,
+    /// 競合の番号を渡す。チャート側でその位置までスクロールする
+    pub on_select: Callback<usize>,
+}
+
+/// 機械ごとの負荷超過とメンテナンス中の割り当ての一覧
+#[function_component(ConflictPanel)]
+pub fn conflict_panel(props: &ConflictPanelProps) -> Html {
+    let machine_name = |id| {
+        props
+            .machines
+            .iter()
+            .find(|m| m.id == id)
+            .map_or_else(|| format!("#{}", id), |m| m.name.clone())
+    };
+    let task_names = |conflict: &Conflict| {
+        conflict
+            .task_ids
+            .iter()
+            .map(|id| {
+                props
+                    .tasks
+                    .iter()
+                    .find(|t| t.id == *id)
+                    .map_or_else(|| format!("#{}", id), |t| t.name.clone())
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    html! {
+        <div class="conflict-panel" style="margin-top: 8px; padding: 8px 12px; border: 1px solid #ccc; border-radius: 5px; background-color: #fafafa;">
+            <h4 style="margin: 0 0 6px 0;">{ format!("Conflicts ({})", props.conflicts.len()) }</h4>
+            if props.conflicts.is_empty() {
+                <div style="color: #666;">{ "No conflicts" }</div>
+            } else {
+                <ul style="list-style: none; margin: 0; padding: 0;">
+                    { for props.conflicts.iter().enumerate().map(|(index, conflict)| {
+                        let on_select = props.on_select.clone();
+                        let summary = match &conflict.kind {
+                            ConflictKind::OverCapacity { load, capacity } => {
+                                format!("{} jobs on {} (capacity {})", load, machine_name(conflict.machine_id), capacity)
+                            }
+                            ConflictKind::Maintenance { reason } if reason.is_empty() => {
+                                format!("{} is down for maintenance", machine_name(conflict.machine_id))
+                            }
+                            ConflictKind::Maintenance { reason } => {
+                                format!("{} is down for maintenance ({})", machine_name(conflict.machine_id), reason)
+                            }
+                        };
+                        html! {
+                            <li
+                                key={index}
+                                onclick={Callback::from(move |_| on_select.emit(index))}
+                                style={format!(
+                                    "padding: 4px 6px; cursor: pointer; border-radius: 3px; {}",
+                                    if props.selected == Some(index) { "background-color: #ffe0b2;" } else { "" }
+                                )}
+                            >
+                                <div>{ format!("⚠ {}", summary) }</div>
+                                <div style="font-size: 0.85em; color: #666;">
+                                    { format!(
+                                        "{} – {}: {}",
+                                        conflict.start.format("%Y-%m-%d %H:%M"),
+                                        conflict.end.format("%Y-%m-%d %H:%M"),
+                                        task_names(conflict)
+                                    ) }
+                                </div>
+                            </li>
+                        }
+                    }) }
+                </ul>
+            }
+        </div>
+    }
+}
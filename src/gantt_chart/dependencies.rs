@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use millmill_domain::{Dependency, DependencyKind, ScheduleAnalysis, Task, TaskId};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+use super::lanes::LaneLayout;
+use super::calendars::MachineCalendars;
+use super::timeline::Timeline;
+use super::{bar_span, drag_for, DragState, ROW_HEIGHT};
+
+/// バーから矢印が水平に伸びる長さ (px)
+const STUB: i64 = 10;
+const ARROW_COLOR: &str = "#555555";
+const CRITICAL_COLOR: &str = "#d32f2f";
+
+#[derive(Properties, PartialEq)]
+pub struct DependencyArrowsProps {
+    pub tasks: Vec<Task>,
+    pub layout: LaneLayout,
+    pub timeline: Timeline,
+    pub dragging: Option<DragState>,
+    /// ドラッグと一緒に動くタスク。矢印も一緒に動かす
+    pub moving: Rc<HashSet<TaskId>>,
+    pub calendars: Rc<MachineCalendars>,
+    pub analysis: Option<ScheduleAnalysis>,
+}
+
+/// レーンと同じ座標系で依存関係の矢印を描く SVG オーバーレイ
+#[function_component(DependencyArrows)]
+pub fn dependency_arrows(props: &DependencyArrowsProps) -> Html {
+    // 各バーの (左端, 幅, 縦の中心)。表示しない行のタスクは None
+    let geometry = |task: &Task| {
+        let top = props.layout.bar_top(task.id)?;
+        let drag = drag_for(props.dragging, &props.moving, task.id);
+        let (left, width) = bar_span(task, &props.timeline, drag, props.calendars.of(task));
+        Some((left, width, top + drag.map_or(0, |drag| drag.offset_y.round() as i64) + ROW_HEIGHT / 2))
+    };
+
+    let mut paths = Vec::new();
+    for task in &props.tasks {
+        for dependency in &task.dependencies {
+            let Some(predecessor) = props.tasks.iter().find(|t| t.id == dependency.predecessor_id) else {
+                continue;
+            };
+            let (Some((p_left, p_width, y1)), Some((s_left, s_width, y2))) = (geometry(predecessor), geometry(task)) else {
+                continue;
+            };
+            let x1 = if dependency.kind.from_finish() { p_left + p_width } else { p_left };
+            let x2 = if dependency.kind.to_finish() { s_left + s_width } else { s_left };
+            // 終了側からは右へ、開始側からは左へ出て、バーの間を通って相手の端に入る
+            let exit = if dependency.kind.from_finish() { x1 + STUB } else { x1 - STUB };
+            let entry = if dependency.kind.to_finish() { x2 + STUB } else { x2 - STUB };
+            let mid_y = if y1 == y2 { y1 + ROW_HEIGHT / 2 } else { (y1 + y2) / 2 };
+            // クリティカルパス上のリンク (後続の開始を決めているもの) は赤で描く
+            let is_critical = props
+                .analysis
+                .as_ref()
+                .is_some_and(|analysis| analysis.is_critical_link(dependency.predecessor_id, task.id));
+            let (color, marker) = if is_critical {
+                (CRITICAL_COLOR, "url(#dependency-arrowhead-critical)")
+            } else {
+                (ARROW_COLOR, "url(#dependency-arrowhead)")
+            };
+            paths.push(html! {
+                <path
+                    key={format!("{}-{}", dependency.predecessor_id, task.id)}
+                    d={format!("M {} {} H {} V {} H {} V {} H {}", x1, y1, exit, mid_y, entry, y2, x2)}
+                    fill="none"
+                    stroke={color}
+                    stroke-width={if is_critical { "2" } else { "1.5" }}
+                    marker-end={marker}
+                >
+                    <title>{ format!("{} {} → {} ({} min)", dependency.kind.code(), dependency.predecessor_id, task.id, dependency.lag_minutes) }</title>
+                </path>
+            });
+        }
+    }
+
+    html! {
+        <svg
+            class="dependency-arrows"
+            style={format!(
+                "position: absolute; top: 0; left: 0; width: 100%; height: {}px; overflow: visible; pointer-events: none;",
+                props.layout.height()
+            )}
+        >
+            <defs>
+                <marker id="dependency-arrowhead" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="6" markerHeight="6" orient="auto">
+                    <path d="M 0 0 L 10 5 L 0 10 z" fill={ARROW_COLOR} />
+                </marker>
+                <marker id="dependency-arrowhead-critical" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="6" markerHeight="6" orient="auto">
+                    <path d="M 0 0 L 10 5 L 0 10 z" fill={CRITICAL_COLOR} />
+                </marker>
+            </defs>
+            { for paths }
+        </svg>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct DependencyEditorProps {
+    pub task: Task,
+    pub tasks: Vec<Task>,
+    pub on_add: Callback<Dependency>,
+    pub on_remove: Callback<TaskId>,
+}
+
+/// フローティングウィンドウ内の先行タスク一覧と追加フォーム
+#[function_component(DependencyEditor)]
+pub fn dependency_editor(props: &DependencyEditorProps) -> Html {
+    let predecessor_id = use_state(|| None::<TaskId>);
+    let kind = use_state(|| DependencyKind::FinishToStart);
+    let lag_minutes = use_state(|| 0i64);
+
+    let name_of = |id: TaskId| {
+        props
+            .tasks
+            .iter()
+            .find(|t| t.id == id)
+            .map_or_else(|| format!("#{}", id), |t| t.name.clone())
+    };
+
+    let on_select_predecessor = {
+        let predecessor_id = predecessor_id.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            predecessor_id.set(select.value().parse::<TaskId>().ok());
+        })
+    };
+    let on_select_kind = {
+        let kind = kind.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = DependencyKind::from_code(&select.value()) {
+                kind.set(selected);
+            }
+        })
+    };
+    let on_input_lag = {
+        let lag_minutes = lag_minutes.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            lag_minutes.set(input.value().parse::<i64>().unwrap_or(0));
+        })
+    };
+    let on_add = {
+        let on_add = props.on_add.clone();
+        let predecessor_id = predecessor_id.clone();
+        let kind = kind.clone();
+        let lag_minutes = lag_minutes.clone();
+        Callback::from(move |_| {
+            if let Some(id) = *predecessor_id {
+                on_add.emit(Dependency {
+                    predecessor_id: id,
+                    kind: *kind,
+                    lag_minutes: *lag_minutes,
+                });
+            }
+        })
+    };
+
+    html! {
+        <div class="dependency-editor">
+            <h4>{ "Predecessors" }</h4>
+            <ul>
+                { for props.task.dependencies.iter().map(|dependency| {
+                    let on_remove = props.on_remove.clone();
+                    let id = dependency.predecessor_id;
+                    html! {
+                        <li key={id}>
+                            { format!("{} {} (lag {} min)", dependency.kind.code(), name_of(id), dependency.lag_minutes) }
+                            <button onclick={Callback::from(move |_| on_remove.emit(id))}>{ "×" }</button>
+                        </li>
+                    }
+                }) }
+            </ul>
+            <div>
+                <select onchange={on_select_predecessor}>
+                    <option value="" selected={predecessor_id.is_none()}>{ "Predecessor..." }</option>
+                    { for props.tasks.iter().filter(|t| t.id != props.task.id).map(|t| html! {
+                        <option value={t.id.to_string()} selected={*predecessor_id == Some(t.id)}>{ &t.name }</option>
+                    }) }
+                </select>
+                <select onchange={on_select_kind}>
+                    { for DependencyKind::ALL.into_iter().map(|k| html! {
+                        <option value={k.code()} selected={*kind == k}>{ k.code() }</option>
+                    }) }
+                </select>
+                <input type="number" title="Lag (min)" value={lag_minutes.to_string()} oninput={on_input_lag} />
+                <button onclick={on_add}>{ "Link" }</button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,281 @@
+use gloo::file::callbacks::FileReader;
+use gloo::file::{Blob, BlobContents, File, ObjectUrl};
+use gloo::timers::callback::Timeout;
+use millmill_domain::{append_tasks, ColumnMapping, ExchangeFormat, ImportPreview, TaskField, XLSX_EXTENSION};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+use super::history::{Schedule, ScheduleAction};
+
+/// プレビューに並べるタスクの数
+const PREVIEW_ROWS: usize = 8;
+
+/// 読み込んだファイルと、その取り込み結果の見込み
+#[derive(Clone, PartialEq)]
+pub struct PendingImport {
+    pub file_name: String,
+    pub format: ExchangeFormat,
+    pub text: String,
+    pub preview: ImportPreview,
+}
+
+/// 書き出した内容をファイルとして保存させる
+pub fn download(file_name: &str, mime_type: &str, contents: impl BlobContents) -> Result<(), String> {
+    let url = ObjectUrl::from(Blob::new_with_options(contents, Some(mime_type)));
+    let link = gloo::utils::document().create_element("a").map_err(|e| format!("{:?}", e))?;
+    link.set_attribute("href", &url).map_err(|e| format!("{:?}", e))?;
+    link.set_attribute("download", file_name).map_err(|e| format!("{:?}", e))?;
+    link.unchecked_into::<web_sys::HtmlElement>().click();
+    // すぐに破棄するとダウンロードが始まらないことがある
+    Timeout::new(10_000, move || drop(url)).forget();
+    Ok(())
+}
+
+/// 取り込みを 1 回の編集にまとめる。replace なら今のタスクを置き換え、そうでなければ後ろに追加する
+pub fn import_action(schedule: &Schedule, file_name: &str, preview: ImportPreview, replace: bool) -> ScheduleAction {
+    let tasks = if replace {
+        preview.tasks
+    } else {
+        let mut tasks = schedule.tasks.clone();
+        append_tasks(&mut tasks, preview.tasks);
+        tasks
+    };
+    // 見つからなかった機械・カレンダー・受注は新しく作られている
+    let mut machines = schedule.machines.clone();
+    machines.extend(preview.machines);
+    let mut calendars = schedule.calendars.clone();
+    calendars.extend(preview.calendars);
+    let mut work_orders = schedule.work_orders.clone();
+    work_orders.extend(preview.work_orders);
+    ScheduleAction::Edit {
+        label: format!("Import {}", file_name),
+        tasks: Some(tasks),
+        machines: Some(machines),
+        work_orders: Some(work_orders),
+        calendars: Some(calendars),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ExchangeToolbarProps {
+    pub on_export: Callback<ExchangeFormat>,
+    /// 選んだファイルの名前、形式、中身
+    pub on_open: Callback<(String, ExchangeFormat, String)>,
+    pub on_export_workbook: Callback<()>,
+    /// 選んだ Excel ファイルの名前と中身
+    pub on_open_workbook: Callback<(String, Vec<u8>)>,
+    pub on_error: Callback<String>,
+}
+
+/// 他のツールとの受け渡し。CSV・MS Project XML・iCalendar・Excel の読み込みと書き出し
+#[function_component(ExchangeToolbar)]
+pub fn exchange_toolbar(props: &ExchangeToolbarProps) -> Html {
+    let format = use_state(ExchangeFormat::default);
+    // 読み込み中のファイル。破棄すると読み込みが中断される
+    let reader = use_mut_ref(|| None::<FileReader>);
+
+    let on_select_format = {
+        let format = format.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = ExchangeFormat::from_code(&select.value()) {
+                format.set(selected);
+            }
+        })
+    };
+    let on_export = props.on_export.reform({
+        let format = *format;
+        move |_| format
+    });
+    let on_import = {
+        let on_open = props.on_open.clone();
+        let on_open_workbook = props.on_open_workbook.clone();
+        let on_error = props.on_error.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+            let name = file.name();
+            if name.rsplit_once('.').is_some_and(|(_, extension)| extension.eq_ignore_ascii_case(XLSX_EXTENSION)) {
+                let on_open_workbook = on_open_workbook.clone();
+                let on_error = on_error.clone();
+                *reader.borrow_mut() = Some(gloo::file::callbacks::read_as_bytes(&File::from(file), move |result| {
+                    match result {
+                        Ok(bytes) => on_open_workbook.emit((name, bytes)),
+                        Err(e) => on_error.emit(format!("Failed to read {}: {}", name, e)),
+                    }
+                }));
+                return;
+            }
+            let Some(format) = ExchangeFormat::from_file_name(&name) else {
+                return on_error.emit(format!("{} is not a .csv, .xml, .ics or .xlsx file", name));
+            };
+            let on_open = on_open.clone();
+            let on_error = on_error.clone();
+            *reader.borrow_mut() = Some(gloo::file::callbacks::read_as_text(&File::from(file), move |result| {
+                match result {
+                    Ok(text) => on_open.emit((name, format, text)),
+                    Err(e) => on_error.emit(format!("Failed to read {}: {}", name, e)),
+                }
+            }));
+        })
+    };
+
+    html! {
+        <div class="exchange-toolbar" style="display: flex; align-items: center; gap: 4px; margin: 6px 0;">
+            <label>{ "Exchange: " }</label>
+            <select onchange={on_select_format}>
+                { for ExchangeFormat::ALL.into_iter().map(|f| html! {
+                    <option value={f.code()} selected={*format == f}>{ f.label() }</option>
+                }) }
+            </select>
+            <button onclick={on_export}>{ "Export" }</button>
+            <button onclick={props.on_export_workbook.reform(|_| ())} title="A table of the tasks and a sheet of bars">
+                { "Export Excel" }
+            </button>
+            <label style="margin-left: 12px;">
+                { "Import: " }
+                <input type="file" accept=".csv,.xml,.ics,.xlsx,text/csv,application/xml,text/calendar" onchange={on_import} />
+            </label>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ImportDialogProps {
+    pub pending: PendingImport,
+    /// 列の割り当てを変えたとき。その割り当てで読み直す
+    pub on_remap: Callback<Vec<ColumnMapping>>,
+    /// true なら今の計画を置き換え、false なら後ろに追加する
+    pub on_apply: Callback<bool>,
+    pub on_cancel: Callback<()>,
+}
+
+/// 取り込む前の確認。読めた件数、列の割り当て、取り込まれない項目と問題を表示する
+#[function_component(ImportDialog)]
+pub fn import_dialog(props: &ImportDialogProps) -> Html {
+    let pending = &props.pending;
+    let preview = &pending.preview;
+
+    let summary = format!(
+        "{} tasks, {} new machines, {} new calendars",
+        preview.tasks.len(),
+        preview.machines.len(),
+        preview.calendars.len()
+    );
+    let can_apply = !preview.tasks.is_empty();
+
+    html! {
+        <div
+            class="import-dialog-overlay"
+            style="position: fixed; top: 0; left: 0; right: 0; bottom: 0; background: rgba(0, 0, 0, 0.5);
+            display: flex; justify-content: center; align-items: center; z-index: 1000;"
+        >
+            <div
+                class="import-dialog"
+                style="background: white; color: #333; padding: 20px; border-radius: 8px; box-shadow: 0 4px 6px rgba(0, 0, 0, 0.1);
+                width: 640px; max-width: 90vw; max-height: 90vh; overflow-y: auto;"
+            >
+                <h3 style="margin-top: 0;">{ format!("Import {} ({})", pending.file_name, pending.format.label()) }</h3>
+                <div>{ summary }</div>
+                { task_columns(&preview.mapping, props.on_remap.clone()) }
+                { import_details(preview) }
+                <div style="display: flex; justify-content: flex-end; gap: 4px; margin-top: 12px;">
+                    <button disabled={!can_apply} title="Keep the plan and add the imported tasks" onclick={props.on_apply.reform(|_| false)}>
+                        { "Add to Plan" }
+                    </button>
+                    <button disabled={!can_apply} title="Remove all tasks and use the imported ones" onclick={props.on_apply.reform(|_| true)}>
+                        { "Replace Plan" }
+                    </button>
+                    <button onclick={props.on_cancel.reform(|_| ())}>{ "Cancel" }</button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// 列ごとに取り込むタスクの項目を選ぶ表。変えると割り当て全体を渡す
+pub fn task_columns(mapping: &[ColumnMapping], on_remap: Callback<Vec<ColumnMapping>>) -> Html {
+    let remap = |index: usize| {
+        let mapping = mapping.to_vec();
+        let on_remap = on_remap.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            let field = TaskField::from_code(&select.value());
+            let mut mapping = mapping.clone();
+            // 1 つの項目は 1 列にだけ割り当てる
+            for column in mapping.iter_mut().filter(|column| field.is_some() && column.field == field) {
+                column.field = None;
+            }
+            mapping[index].field = field;
+            on_remap.emit(mapping);
+        })
+    };
+
+    html! {
+        if !mapping.is_empty() {
+            <h4>{ "Columns" }</h4>
+            <table>
+                <tbody>
+                    { for mapping.iter().enumerate().map(|(index, column)| html! {
+                        <tr key={index}>
+                            <td>{ &column.header }</td>
+                            <td>
+                                <select onchange={remap(index)}>
+                                    <option value="" selected={column.field.is_none()}>{ "(Not imported)" }</option>
+                                    { for TaskField::ALL.into_iter().map(|field| html! {
+                                        <option value={field.code()} selected={column.field == Some(field)}>{ field.label() }</option>
+                                    }) }
+                                </select>
+                            </td>
+                        </tr>
+                    }) }
+                </tbody>
+            </table>
+        }
+    }
+}
+
+/// 取り込まれない項目、問題、最初の数件のタスク
+pub fn import_details(preview: &ImportPreview) -> Html {
+    html! {
+        <>
+            if !preview.unmapped.is_empty() {
+                <h4>{ "Not imported" }</h4>
+                <div style="color: #666;">{ preview.unmapped.join(", ") }</div>
+            }
+            if !preview.issues.is_empty() {
+                <h4>{ format!("Problems ({})", preview.issues.len()) }</h4>
+                <ul style="max-height: 160px; overflow-y: auto; margin: 0; padding-left: 20px; color: #d32f2f;">
+                    { for preview.issues.iter().map(|issue| html! { <li>{ issue.to_string() }</li> }) }
+                </ul>
+            }
+            if !preview.tasks.is_empty() {
+                <h4>{ "Tasks" }</h4>
+                <table style="width: 100%; border-collapse: collapse;">
+                    <thead>
+                        <tr>
+                            <th style="text-align: left;">{ "Name" }</th>
+                            <th style="text-align: left;">{ "Start" }</th>
+                            <th style="text-align: left;">{ "End" }</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { for preview.tasks.iter().take(PREVIEW_ROWS).map(|task| html! {
+                            <tr key={task.id}>
+                                <td>{ &task.name }</td>
+                                <td>{ task.start_date.format("%Y-%m-%d %H:%M").to_string() }</td>
+                                <td>{ task.end_date.format("%Y-%m-%d %H:%M").to_string() }</td>
+                            </tr>
+                        }) }
+                    </tbody>
+                </table>
+                if preview.tasks.len() > PREVIEW_ROWS {
+                    <div style="color: #666;">{ format!("and {} more", preview.tasks.len() - PREVIEW_ROWS) }</div>
+                }
+            }
+        </>
+    }
+}
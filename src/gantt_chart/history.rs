@@ -0,0 +1,347 @@
+use std::rc::Rc;
+
+use millmill_domain::{roll_up_summaries, Calendar, Machine, Task, WorkOrder};
+use yew::prelude::*;
+
+use crate::api::Record;
+
+/// 取り消せる操作の上限
+const HISTORY_LIMIT: usize = 100;
+
+/// 1 件分の変更。`before` が None なら追加、`after` が None なら削除
+#[derive(Clone, PartialEq, Debug)]
+struct RecordChange<T> {
+    before: Option<T>,
+    after: Option<T>,
+}
+
+/// `before` から `after` への変更を ID ごとに求める
+fn record_changes<T: Record>(before: &[T], after: &[T]) -> Vec<RecordChange<T>> {
+    let mut changes: Vec<RecordChange<T>> = before
+        .iter()
+        .filter_map(|old| {
+            let new = after.iter().find(|new| new.id() == old.id());
+            (new != Some(old)).then(|| RecordChange { before: Some(old.clone()), after: new.cloned() })
+        })
+        .collect();
+    changes.extend(
+        after
+            .iter()
+            .filter(|new| !before.iter().any(|old| old.id() == new.id()))
+            .map(|new| RecordChange { before: None, after: Some(new.clone()) }),
+    );
+    changes
+}
+
+/// 変更を適用する。`forward` が false なら逆向きに戻す
+fn apply_changes<T: Record>(records: &mut Vec<T>, changes: &[RecordChange<T>], forward: bool) {
+    for change in changes {
+        let (from, to) = if forward { (&change.before, &change.after) } else { (&change.after, &change.before) };
+        let id = from.as_ref().or(to.as_ref()).map(|record| record.id());
+        records.retain(|record| Some(record.id()) != id);
+        if let Some(record) = to {
+            // ID 順を保つ位置に戻す
+            let index = records.iter().position(|r| r.id() > record.id()).unwrap_or(records.len());
+            records.insert(index, record.clone());
+        }
+    }
+}
+
+/// サマリーの期間と進捗を子に合わせた一覧
+fn with_summaries(mut tasks: Vec<Task>) -> Vec<Task> {
+    roll_up_summaries(&mut tasks);
+    tasks
+}
+
+/// 取り消し・やり直しの単位。複数のタスクへの変更もまとめて 1 つにする
+#[derive(Clone, PartialEq, Debug)]
+pub struct Command {
+    pub label: String,
+    tasks: Vec<RecordChange<Task>>,
+    machines: Vec<RecordChange<Machine>>,
+    work_orders: Vec<RecordChange<WorkOrder>>,
+    calendars: Vec<RecordChange<Calendar>>,
+}
+
+impl Command {
+    fn is_empty(&self) -> bool {
+        self.tasks.is_empty() && self.machines.is_empty() && self.work_orders.is_empty() && self.calendars.is_empty()
+    }
+}
+
+/// タスクと機械と受注とカレンダー、および操作の履歴
+#[derive(Clone, PartialEq, Debug)]
+pub struct Schedule {
+    pub tasks: Vec<Task>,
+    pub machines: Vec<Machine>,
+    pub work_orders: Vec<WorkOrder>,
+    pub calendars: Vec<Calendar>,
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    /// 変更のたびに増える番号。タスク一覧を比較せずに再計算の要否を判定するのに使う
+    revision: u64,
+}
+
+pub enum ScheduleAction {
+    /// 読み込んだデータに置き換える。履歴には残さない
+    Load {
+        tasks: Option<Vec<Task>>,
+        machines: Option<Vec<Machine>>,
+        work_orders: Option<Vec<WorkOrder>>,
+        calendars: Option<Vec<Calendar>>,
+    },
+    /// 編集後のデータ。変更前との差分を 1 つの操作として記録する
+    Edit {
+        label: String,
+        tasks: Option<Vec<Task>>,
+        machines: Option<Vec<Machine>>,
+        work_orders: Option<Vec<WorkOrder>>,
+        calendars: Option<Vec<Calendar>>,
+    },
+    Undo,
+    Redo,
+    /// 履歴の先頭から `n` 件の操作を適用した状態にする
+    Goto(usize),
+}
+
+impl ScheduleAction {
+    pub fn edit_tasks(label: &str, tasks: Vec<Task>) -> Self {
+        ScheduleAction::Edit { label: label.to_string(), tasks: Some(tasks), machines: None, work_orders: None, calendars: None }
+    }
+
+    pub fn edit_machines(label: &str, machines: Vec<Machine>) -> Self {
+        ScheduleAction::Edit { label: label.to_string(), tasks: None, machines: Some(machines), work_orders: None, calendars: None }
+    }
+
+    pub fn edit_work_orders(label: &str, work_orders: Vec<WorkOrder>) -> Self {
+        ScheduleAction::Edit { label: label.to_string(), tasks: None, machines: None, work_orders: Some(work_orders), calendars: None }
+    }
+
+    pub fn edit_calendars(label: &str, calendars: Vec<Calendar>) -> Self {
+        ScheduleAction::Edit { label: label.to_string(), tasks: None, machines: None, work_orders: None, calendars: Some(calendars) }
+    }
+}
+
+impl Schedule {
+    pub fn new(tasks: Vec<Task>, machines: Vec<Machine>, work_orders: Vec<WorkOrder>, calendars: Vec<Calendar>) -> Self {
+        Schedule { tasks, machines, work_orders, calendars, undo: Vec::new(), redo: Vec::new(), revision: 0 }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// 適用済みの操作の数
+    pub fn position(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// 古い順の全操作。`position()` 件目までが適用済み
+    pub fn history(&self) -> impl Iterator<Item = &Command> {
+        self.undo.iter().chain(self.redo.iter().rev())
+    }
+
+    fn undo(&mut self) {
+        if let Some(command) = self.undo.pop() {
+            apply_changes(&mut self.tasks, &command.tasks, false);
+            apply_changes(&mut self.machines, &command.machines, false);
+            apply_changes(&mut self.work_orders, &command.work_orders, false);
+            apply_changes(&mut self.calendars, &command.calendars, false);
+            self.redo.push(command);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(command) = self.redo.pop() {
+            apply_changes(&mut self.tasks, &command.tasks, true);
+            apply_changes(&mut self.machines, &command.machines, true);
+            apply_changes(&mut self.work_orders, &command.work_orders, true);
+            apply_changes(&mut self.calendars, &command.calendars, true);
+            self.undo.push(command);
+        }
+    }
+}
+
+impl Reducible for Schedule {
+    type Action = ScheduleAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut next = (*self).clone();
+        match action {
+            ScheduleAction::Load { tasks, machines, work_orders, calendars } => {
+                let tasks = tasks.map(with_summaries);
+                next = Schedule {
+                    revision: self.revision,
+                    ..Schedule::new(
+                        tasks.unwrap_or(next.tasks),
+                        machines.unwrap_or(next.machines),
+                        work_orders.unwrap_or(next.work_orders),
+                        calendars.unwrap_or(next.calendars),
+                    )
+                };
+            }
+            ScheduleAction::Edit { label, tasks, machines, work_orders, calendars } => {
+                // サマリーの期間と進捗も同じ操作の一部として記録する
+                let tasks = tasks.map(with_summaries);
+                let command = Command {
+                    label,
+                    tasks: tasks.map_or_else(Vec::new, |tasks| record_changes(&self.tasks, &tasks)),
+                    machines: machines.map_or_else(Vec::new, |machines| record_changes(&self.machines, &machines)),
+                    work_orders: work_orders
+                        .map_or_else(Vec::new, |work_orders| record_changes(&self.work_orders, &work_orders)),
+                    calendars: calendars.map_or_else(Vec::new, |calendars| record_changes(&self.calendars, &calendars)),
+                };
+                if command.is_empty() {
+                    return self;
+                }
+                apply_changes(&mut next.tasks, &command.tasks, true);
+                apply_changes(&mut next.machines, &command.machines, true);
+                apply_changes(&mut next.work_orders, &command.work_orders, true);
+                apply_changes(&mut next.calendars, &command.calendars, true);
+                next.undo.push(command);
+                if next.undo.len() > HISTORY_LIMIT {
+                    next.undo.remove(0);
+                }
+                next.redo.clear();
+            }
+            ScheduleAction::Undo => next.undo(),
+            ScheduleAction::Redo => next.redo(),
+            ScheduleAction::Goto(position) => {
+                while next.position() > position && next.can_undo() {
+                    next.undo();
+                }
+                while next.position() < position && next.can_redo() {
+                    next.redo();
+                }
+            }
+        }
+        next.revision += 1;
+        Rc::new(next)
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct HistoryPanelProps {
+    /// 古い順の操作名
+    pub labels: Vec<String>,
+    /// 適用済みの操作の数
+    pub position: usize,
+    pub on_goto: Callback<usize>,
+}
+
+/// 操作履歴の一覧。項目を押すとその操作の直後の状態に戻る
+#[function_component(HistoryPanel)]
+pub fn history_panel(props: &HistoryPanelProps) -> Html {
+    let position = props.position;
+    let on_goto = props.on_goto.clone();
+    html! {
+        <div class="history-panel" style="margin-top: 8px; padding: 8px 12px; border: 1px solid #ccc; border-radius: 5px; background-color: #fafafa;">
+            <h4 style="margin: 0 0 6px 0;">{ "History" }</h4>
+            <ol style="list-style: none; margin: 0; padding: 0; max-height: 160px; overflow-y: auto;">
+                <li
+                    onclick={on_goto.reform(|_| 0)}
+                    style={format!("padding: 2px 6px; cursor: pointer; {}", if position == 0 { "font-weight: bold;" } else { "" })}
+                >
+                    { "(Opened)" }
+                </li>
+                { for props.labels.iter().enumerate().map(|(index, label)| {
+                    let step = index + 1;
+                    let style = if step == position {
+                        "font-weight: bold;"
+                    } else if step > position {
+                        // 取り消した操作
+                        "color: #999;"
+                    } else {
+                        ""
+                    };
+                    html! {
+                        <li
+                            key={index}
+                            onclick={on_goto.reform(move |_| step)}
+                            style={format!("padding: 2px 6px; cursor: pointer; {}", style)}
+                        >
+                            { label }
+                        </li>
+                    }
+                }) }
+            </ol>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use millmill_domain::TaskId;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn task(id: TaskId, start: NaiveDateTime, end: NaiveDateTime) -> Task {
+        Task { id, name: format!("Op {}", id), start_date: start, end_date: end, color: "#4CAF50".to_string(), ..Default::default() }
+    }
+
+    fn schedule(tasks: Vec<Task>) -> Rc<Schedule> {
+        Rc::new(Schedule::new(tasks, Vec::new(), Vec::new(), Vec::new()))
+    }
+
+    fn labels(schedule: &Schedule) -> Vec<&str> {
+        schedule.history().map(|command| command.label.as_str()).collect()
+    }
+
+    #[test]
+    fn edits_undo_and_redo() {
+        let opened = vec![task(1, at(3, 8), at(3, 12))];
+        let edited = vec![task(1, at(4, 8), at(4, 12)), task(2, at(4, 13), at(4, 17))];
+        let schedule = schedule(opened.clone()).reduce(ScheduleAction::edit_tasks("Move", edited.clone()));
+        assert_eq!(schedule.tasks, edited);
+        assert_eq!((schedule.revision(), schedule.position(), schedule.can_redo()), (1, 1, false));
+
+        let schedule = schedule.reduce(ScheduleAction::Undo);
+        assert_eq!(schedule.tasks, opened);
+        assert_eq!((schedule.revision(), schedule.position(), schedule.can_undo()), (2, 0, false));
+        assert_eq!(labels(&schedule), vec!["Move"]);
+
+        let schedule = schedule.reduce(ScheduleAction::Redo);
+        assert_eq!(schedule.tasks, edited);
+        assert_eq!((schedule.revision(), schedule.position(), schedule.can_redo()), (3, 1, false));
+    }
+
+    #[test]
+    fn edits_that_change_nothing_are_not_recorded() {
+        let opened = vec![task(1, at(3, 8), at(3, 12))];
+        let before = schedule(opened.clone());
+        let after = before.clone().reduce(ScheduleAction::edit_tasks("Move", opened));
+        assert!(Rc::ptr_eq(&before, &after));
+        assert_eq!((after.revision(), after.can_undo()), (0, false));
+    }
+
+    #[test]
+    fn a_new_edit_drops_what_was_undone() {
+        let opened = vec![task(1, at(3, 8), at(3, 12))];
+        let schedule = schedule(opened)
+            .reduce(ScheduleAction::edit_tasks("Move", vec![task(1, at(4, 8), at(4, 12))]))
+            .reduce(ScheduleAction::edit_tasks("Resize", vec![task(1, at(4, 8), at(4, 16))]))
+            .reduce(ScheduleAction::Undo)
+            .reduce(ScheduleAction::edit_tasks("Add", vec![task(1, at(4, 8), at(4, 12)), task(2, at(5, 8), at(5, 9))]));
+        assert_eq!(labels(&schedule), vec!["Move", "Add"]);
+        assert!(!schedule.can_redo());
+        assert_eq!(schedule.revision(), 4);
+
+        // 履歴の一覧から選んだ時点まで戻す
+        let schedule = schedule.reduce(ScheduleAction::Goto(0));
+        assert_eq!(schedule.tasks, vec![task(1, at(3, 8), at(3, 12))]);
+        assert_eq!((schedule.position(), schedule.revision()), (0, 5));
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use millmill_domain::{stack_lanes, Machine, MachineId, OutlineRow, Task, TaskId};
+
+use super::ROW_HEIGHT;
+
+/// 行の並べ方
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Grouping {
+    /// 機械ごとのレーン。まとめ役のタスク (サマリー) は表示しない
+    Machines,
+    /// 親子関係 (WBS) の順に 1 タスク 1 行
+    Outline,
+}
+
+impl Grouping {
+    pub const ALL: [Grouping; 2] = [Grouping::Machines, Grouping::Outline];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Grouping::Machines => "Machines",
+            Grouping::Outline => "Outline",
+        }
+    }
+}
+
+/// 機械ごとの行 (スイムレーン)
+#[derive(Clone, PartialEq, Debug)]
+pub struct Lane {
+    /// `None` は機械未割り当てのレーン
+    pub machine_id: Option<MachineId>,
+    pub label: String,
+    pub top: i64,
+    pub height: i64,
+}
+
+/// レーンの並びと各バーの縦位置
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct LaneLayout {
+    pub lanes: Vec<Lane>,
+    bar_tops: HashMap<TaskId, i64>,
+    /// 1 タスク 1 行の表示。縦にドラッグしても機械は変えない
+    outline: bool,
+}
+
+impl LaneLayout {
+    /// 機械の順にレーンを並べ、最後に未割り当てのレーンを置く。
+    /// 同じレーンで期間の重なるバーは段を分けて積む。
+    pub fn new(tasks: &[Task], machines: &[Machine]) -> Self {
+        let mut layout = LaneLayout::default();
+        let mut top = 0;
+        for lane in stack_lanes(tasks, machines, |_| true) {
+            let label = match machines.iter().find(|machine| Some(machine.id) == lane.machine_id) {
+                Some(machine) => machine.name.clone(),
+                None => "Unassigned".to_string(),
+            };
+            for (id, row) in lane.bars {
+                layout.bar_tops.insert(id, top + row as i64 * ROW_HEIGHT);
+            }
+            let height = lane.rows as i64 * ROW_HEIGHT;
+            layout.lanes.push(Lane { machine_id: lane.machine_id, label, top, height });
+            top += height;
+        }
+        layout
+    }
+
+    /// 親子関係の順に 1 タスク 1 行で並べる。折りたたまれて `rows` にないタスクは表示しない
+    pub fn outline(tasks: &[Task], rows: &[OutlineRow]) -> Self {
+        let by_id: HashMap<TaskId, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+        let mut layout = LaneLayout { outline: true, ..LaneLayout::default() };
+        for (index, row) in rows.iter().enumerate() {
+            let Some(task) = by_id.get(&row.task_id) else {
+                continue;
+            };
+            let top = index as i64 * ROW_HEIGHT;
+            layout.bar_tops.insert(task.id, top);
+            layout.lanes.push(Lane { machine_id: task.machine_id, label: task.name.clone(), top, height: ROW_HEIGHT });
+        }
+        layout
+    }
+
+    pub fn is_outline(&self) -> bool {
+        self.outline
+    }
+
+    pub fn height(&self) -> i64 {
+        self.lanes.last().map_or(0, |lane| lane.top + lane.height)
+    }
+
+    /// 表示しないタスク (機械ごとの表示のサマリーや折りたたまれたタスク) は None
+    pub fn bar_top(&self, id: TaskId) -> Option<i64> {
+        self.bar_tops.get(&id).copied()
+    }
+
+    /// y 座標 (px) の位置にあるレーン。範囲外は最も近い端のレーン
+    pub fn lane_at(&self, y: f64) -> Option<&Lane> {
+        self.lanes
+            .iter()
+            .find(|lane| y < (lane.top + lane.height) as f64)
+            .or_else(|| self.lanes.last())
+    }
+}
@@ -0,0 +1,222 @@
+use chrono::NaiveDateTime;
+use millmill_domain::{Calendar, CalendarId, Machine, MaintenanceWindow, SetupTime};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct MachineEditorProps {
+    pub machine: Machine,
+    /// 割り当てられるカレンダー
+    pub calendars: Vec<Calendar>,
+    pub on_save: Callback<Machine>,
+    pub on_close: Callback<()>,
+}
+
+/// 機械の同時加工数 (パレット数など)、稼働カレンダー、段取り時間、メンテナンス期間の編集
+#[function_component(MachineEditor)]
+pub fn machine_editor(props: &MachineEditorProps) -> Html {
+    let machine = use_state(|| props.machine.clone());
+    let window_start = use_state(String::new);
+    let window_end = use_state(String::new);
+    let window_reason = use_state(String::new);
+
+    let text_input = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            state.set(input.value());
+        })
+    };
+    let on_input_capacity = {
+        let machine = machine.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            let mut updated = (*machine).clone();
+            updated.capacity = input.value().parse::<u32>().unwrap_or(0);
+            machine.set(updated);
+        })
+    };
+    let on_select_calendar = {
+        let machine = machine.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            let mut updated = (*machine).clone();
+            updated.calendar_id = select.value().parse::<CalendarId>().ok();
+            machine.set(updated);
+        })
+    };
+    let on_input_default_setup = {
+        let machine = machine.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            let mut updated = (*machine).clone();
+            updated.setup.default_minutes = input.value().trim().parse::<i64>().unwrap_or(0);
+            machine.set(updated);
+        })
+    };
+    // 段取り表の 1 行の 1 項目。空欄はどの品種・材質にも当てはまる
+    let setup_input = |index: usize, value: String, set: fn(&mut SetupTime, String)| {
+        let machine = machine.clone();
+        let oninput = Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            let mut updated = (*machine).clone();
+            if let Some(entry) = updated.setup.entries.get_mut(index) {
+                set(entry, input.value());
+            }
+            machine.set(updated);
+        });
+        html! {
+            <input type="text" placeholder="Any" style="width: 90px;" {value} {oninput} />
+        }
+    };
+    let on_add_setup = {
+        let machine = machine.clone();
+        Callback::from(move |_| {
+            let mut updated = (*machine).clone();
+            updated.setup.entries.push(SetupTime::default());
+            machine.set(updated);
+        })
+    };
+    let on_add_window = {
+        let machine = machine.clone();
+        let window_start = window_start.clone();
+        let window_end = window_end.clone();
+        let window_reason = window_reason.clone();
+        Callback::from(move |_| {
+            let parse = |value: &str| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M");
+            let (Ok(start_date), Ok(end_date)) = (parse(&window_start), parse(&window_end)) else {
+                log::error!("Failed to parse maintenance window");
+                return;
+            };
+            let mut updated = (*machine).clone();
+            updated.maintenance.push(MaintenanceWindow {
+                start_date,
+                end_date,
+                reason: (*window_reason).clone(),
+            });
+            updated.maintenance.sort_by_key(|window| window.start_date);
+            machine.set(updated);
+            window_start.set(String::new());
+            window_end.set(String::new());
+            window_reason.set(String::new());
+        })
+    };
+    let on_save = {
+        let on_save = props.on_save.clone();
+        let machine = machine.clone();
+        Callback::from(move |_| on_save.emit((*machine).clone()))
+    };
+    let on_close = props.on_close.reform(|_| ());
+
+    html! {
+        <div class="machine-editor" style="padding: 8px; border-bottom: 1px solid #ccc; background-color: #ffffff;">
+            <h4 style="margin: 0 0 6px 0;">{ &machine.name }</h4>
+            <label>
+                { "Capacity: " }
+                <input type="number" min="1" style="width: 60px;" value={machine.capacity.to_string()} oninput={on_input_capacity} />
+            </label>
+            <label style="margin-left: 12px;">
+                { "Calendar: " }
+                <select onchange={on_select_calendar}>
+                    <option value="" selected={machine.calendar_id.is_none()}>{ "(Around the clock)" }</option>
+                    { for props.calendars.iter().map(|calendar| html! {
+                        <option value={calendar.id.to_string()} selected={machine.calendar_id == Some(calendar.id)}>
+                            { &calendar.name }
+                        </option>
+                    }) }
+                </select>
+            </label>
+            <h4 style="margin: 8px 0 4px 0;">{ "Setup Times" }</h4>
+            <label>
+                { "Default: " }
+                <input
+                    type="number"
+                    min="0"
+                    style="width: 60px;"
+                    value={machine.setup.default_minutes.to_string()}
+                    oninput={on_input_default_setup}
+                />
+                { " min (the most specific matching row below wins)" }
+            </label>
+            <table style="border-collapse: collapse; margin-top: 4px;">
+                <thead>
+                    <tr style="text-align: left;">
+                        <th>{ "From family" }</th>
+                        <th>{ "From material" }</th>
+                        <th>{ "To family" }</th>
+                        <th>{ "To material" }</th>
+                        <th>{ "Minutes" }</th>
+                        <th></th>
+                    </tr>
+                </thead>
+                <tbody>
+                    { for machine.setup.entries.iter().enumerate().map(|(index, entry)| {
+                        let on_input_minutes = {
+                            let machine = machine.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+                                let mut updated = (*machine).clone();
+                                if let Some(entry) = updated.setup.entries.get_mut(index) {
+                                    entry.minutes = input.value().trim().parse::<i64>().unwrap_or(0);
+                                }
+                                machine.set(updated);
+                            })
+                        };
+                        let on_remove = {
+                            let machine = machine.clone();
+                            Callback::from(move |_| {
+                                let mut updated = (*machine).clone();
+                                updated.setup.entries.remove(index);
+                                machine.set(updated);
+                            })
+                        };
+                        html! {
+                            <tr key={index}>
+                                <td>{ setup_input(index, entry.from.part_family.clone(), |entry, value| entry.from.part_family = value) }</td>
+                                <td>{ setup_input(index, entry.from.material.clone(), |entry, value| entry.from.material = value) }</td>
+                                <td>{ setup_input(index, entry.to.part_family.clone(), |entry, value| entry.to.part_family = value) }</td>
+                                <td>{ setup_input(index, entry.to.material.clone(), |entry, value| entry.to.material = value) }</td>
+                                <td>
+                                    <input type="number" min="0" style="width: 60px;" value={entry.minutes.to_string()} oninput={on_input_minutes} />
+                                </td>
+                                <td><button onclick={on_remove}>{ "×" }</button></td>
+                            </tr>
+                        }
+                    }) }
+                </tbody>
+            </table>
+            <button onclick={on_add_setup}>{ "Add Row" }</button>
+            <h4 style="margin: 8px 0 4px 0;">{ "Maintenance" }</h4>
+            <ul style="margin: 0; padding-left: 16px;">
+                { for machine.maintenance.iter().enumerate().map(|(index, window)| {
+                    let machine = machine.clone();
+                    html! {
+                        <li key={index}>
+                            { format!(
+                                "{} – {} {}",
+                                window.start_date.format("%m/%d %H:%M"),
+                                window.end_date.format("%m/%d %H:%M"),
+                                window.reason
+                            ) }
+                            <button onclick={Callback::from(move |_| {
+                                let mut updated = (*machine).clone();
+                                updated.maintenance.remove(index);
+                                machine.set(updated);
+                            })}>{ "×" }</button>
+                        </li>
+                    }
+                }) }
+            </ul>
+            <div>
+                <input type="datetime-local" value={(*window_start).clone()} oninput={text_input(&window_start)} />
+                <input type="datetime-local" value={(*window_end).clone()} oninput={text_input(&window_end)} />
+                <input type="text" placeholder="Reason" value={(*window_reason).clone()} oninput={text_input(&window_reason)} />
+                <button onclick={on_add_window}>{ "Add" }</button>
+            </div>
+            <div style="margin-top: 6px;">
+                <button onclick={on_save}>{ "Save" }</button>
+                <button onclick={on_close}>{ "Close" }</button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,121 @@
+use chrono::NaiveDate;
+use millmill_domain::{ChartExport, ChartFormat, Orientation, PaperSize};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct ChartExportToolbarProps {
+    /// 表示中の最初と最後の日。期間を指定しないときはこの範囲を書き出す
+    pub visible: (NaiveDate, NaiveDate),
+    /// 描画中はボタンを押せなくする
+    pub exporting: bool,
+    /// ズームと表示の切り替えは呼び出し側で埋める
+    pub on_export: Callback<ChartExport>,
+}
+
+/// ガントチャートを SVG・PNG・PDF に書き出す。PDF は用紙ごとに分割し、表題欄を付ける
+#[function_component(ChartExportToolbar)]
+pub fn chart_export_toolbar(props: &ChartExportToolbarProps) -> Html {
+    let format = use_state(ChartFormat::default);
+    // None のときは表示中の範囲
+    let from = use_state(|| None::<NaiveDate>);
+    let to = use_state(|| None::<NaiveDate>);
+    let paper = use_state(PaperSize::default);
+    let orientation = use_state(Orientation::default);
+    let title = use_state(String::new);
+
+    let on_select_format = {
+        let format = format.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = ChartFormat::from_code(&select.value()) {
+                format.set(selected);
+            }
+        })
+    };
+    let on_input_date = |date: UseStateHandle<Option<NaiveDate>>| {
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            date.set(NaiveDate::parse_from_str(&input.value(), "%Y-%m-%d").ok());
+        })
+    };
+    let on_select_paper = {
+        let paper = paper.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = PaperSize::from_code(&select.value()) {
+                paper.set(selected);
+            }
+        })
+    };
+    let on_select_orientation = {
+        let orientation = orientation.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(selected) = Orientation::from_code(&select.value()) {
+                orientation.set(selected);
+            }
+        })
+    };
+    let on_input_title = {
+        let title = title.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            title.set(input.value());
+        })
+    };
+    let on_reset_range = {
+        let from = from.clone();
+        let to = to.clone();
+        Callback::from(move |_| {
+            from.set(None);
+            to.set(None);
+        })
+    };
+
+    let range = (from.unwrap_or(props.visible.0), to.unwrap_or(props.visible.1));
+    let on_export = {
+        let on_export = props.on_export.clone();
+        let (format, paper, orientation, title) = (*format, *paper, *orientation, (*title).clone());
+        Callback::from(move |_| {
+            on_export.emit(ChartExport {
+                format,
+                paper,
+                orientation,
+                title: title.clone(),
+                ..ChartExport::new(range.0, range.1)
+            })
+        })
+    };
+
+    html! {
+        <div class="chart-export-toolbar" style="display: flex; align-items: center; gap: 4px; margin: 6px 0;">
+            <label>{ "Chart: " }</label>
+            <select onchange={on_select_format}>
+                { for ChartFormat::ALL.into_iter().map(|f| html! {
+                    <option value={f.code()} selected={*format == f}>{ f.label() }</option>
+                }) }
+            </select>
+            <input type="date" value={range.0.format("%Y-%m-%d").to_string()} oninput={on_input_date(from.clone())} />
+            { "–" }
+            <input type="date" value={range.1.format("%Y-%m-%d").to_string()} oninput={on_input_date(to.clone())} />
+            if from.is_some() || to.is_some() {
+                <button onclick={on_reset_range} title="Export the days shown on screen">{ "Visible" }</button>
+            }
+            if *format == ChartFormat::Pdf {
+                <select onchange={on_select_paper}>
+                    { for PaperSize::ALL.into_iter().map(|p| html! {
+                        <option value={p.code()} selected={*paper == p}>{ p.label() }</option>
+                    }) }
+                </select>
+                <select onchange={on_select_orientation}>
+                    { for Orientation::ALL.into_iter().map(|o| html! {
+                        <option value={o.code()} selected={*orientation == o}>{ o.label() }</option>
+                    }) }
+                </select>
+            }
+            <input type="text" placeholder="Title" value={(*title).clone()} oninput={on_input_title} />
+            <button disabled={props.exporting} onclick={on_export}>{ if props.exporting { "Exporting..." } else { "Export" } }</button>
+        </div>
+    }
+}
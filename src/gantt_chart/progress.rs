@@ -0,0 +1,100 @@
+use chrono::NaiveDateTime;
+use millmill_domain::{Task, TaskStatus};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+use super::timeline::now;
+
+/// datetime-local 入力の書式
+const INPUT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+#[derive(Properties, PartialEq)]
+pub struct ProgressEditorProps {
+    pub task: Task,
+    /// サマリーの進捗は子から集計するので編集できない
+    pub is_summary: bool,
+    pub on_change: Callback<Task>,
+}
+
+/// フローティングウィンドウ内の状態・進捗率・実績日時の編集。変更はその場で反映する
+#[function_component(ProgressEditor)]
+pub fn progress_editor(props: &ProgressEditorProps) -> Html {
+    let task = &props.task;
+
+    // 状態を変えると作業開始・完了の実績日時を今の時刻で記録する
+    let on_select_status = {
+        let task = task.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            if let Some(status) = TaskStatus::from_code(&select.value()) {
+                let mut updated = task.clone();
+                updated.record_status(status, now());
+                on_change.emit(updated);
+            }
+        })
+    };
+    let on_change_percent = {
+        let task = task.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            if let Ok(percent) = input.value().parse::<f64>() {
+                on_change.emit(Task { percent_complete: percent.clamp(0.0, 100.0), ..task.clone() });
+            }
+        })
+    };
+    // 実績日時は後から直せる。空にすると記録を消す
+    let on_change_actual = |set: fn(&mut Task, Option<NaiveDateTime>)| {
+        let task = task.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            let mut updated = task.clone();
+            set(&mut updated, NaiveDateTime::parse_from_str(&input.value(), INPUT_FORMAT).ok());
+            on_change.emit(updated);
+        })
+    };
+    let format_actual = |time: Option<NaiveDateTime>| time.map(|time| time.format(INPUT_FORMAT).to_string()).unwrap_or_default();
+
+    html! {
+        <div class="progress-editor">
+            <h4>{ "Progress" }</h4>
+            <div>
+                <select onchange={on_select_status}>
+                    { for TaskStatus::ALL.into_iter().map(|status| html! {
+                        <option value={status.code()} selected={task.status == status}>{ status.label() }</option>
+                    }) }
+                </select>
+                <input
+                    type="number"
+                    min="0"
+                    max="100"
+                    step="5"
+                    style="width: 60px;"
+                    title="Percent complete"
+                    disabled={props.is_summary}
+                    value={format!("{:.0}", task.percent_complete)}
+                    onchange={on_change_percent}
+                />
+                { "%" }
+            </div>
+            <div>
+                <label>{ "Actual start: " }</label>
+                <input
+                    type="datetime-local"
+                    value={format_actual(task.actual_start)}
+                    onchange={on_change_actual(|task, time| task.actual_start = time)}
+                />
+            </div>
+            <div>
+                <label>{ "Actual end: " }</label>
+                <input
+                    type="datetime-local"
+                    value={format_actual(task.actual_end)}
+                    onchange={on_change_actual(|task, time| task.actual_end = time)}
+                />
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{Datelike, Duration, NaiveDateTime, Weekday};
+use millmill_domain::{Machine, MachineId};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+/// 範囲選択の矩形 (バーと同じ座標系の px)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RubberBand {
+    pub start: (f64, f64),
+    pub end: (f64, f64),
+    /// ドラッグ開始時のレーン領域の左上 (クライアント座標)
+    pub client_origin: (f64, f64),
+    /// Shift / Ctrl を押していれば既存の選択に追加する
+    pub additive: bool,
+}
+
+impl RubberBand {
+    /// (左, 上, 幅, 高さ)
+    pub fn rect(&self) -> (f64, f64, f64, f64) {
+        let left = self.start.0.min(self.end.0);
+        let top = self.start.1.min(self.end.1);
+        (left, top, (self.start.0 - self.end.0).abs(), (self.start.1 - self.end.1).abs())
+    }
+
+    pub fn intersects(&self, left: f64, top: f64, width: f64, height: f64) -> bool {
+        let (band_left, band_top, band_width, band_height) = self.rect();
+        left < band_left + band_width && band_left < left + width && top < band_top + band_height && band_top < top + height
+    }
+}
+
+/// `time` から土日を除いて `days` 日ずらす。負の値なら前にずらす
+pub fn add_working_days(time: NaiveDateTime, days: i64) -> NaiveDateTime {
+    let step = if days < 0 { -1 } else { 1 };
+    let mut time = time;
+    let mut remaining = days.abs();
+    while remaining > 0 {
+        time += Duration::days(step);
+        if !matches!(time.weekday(), Weekday::Sat | Weekday::Sun) {
+            remaining -= 1;
+        }
+    }
+    time
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SelectionToolbarProps {
+    pub count: usize,
+    pub machines: Vec<Machine>,
+    pub on_delete: Callback<()>,
+    pub on_recolor: Callback<String>,
+    pub on_reassign: Callback<Option<MachineId>>,
+    /// 稼働日数 (土日を除く)
+    pub on_shift: Callback<i64>,
+    pub on_clear: Callback<()>,
+}
+
+/// 選択中のタスクへの一括操作
+#[function_component(SelectionToolbar)]
+pub fn selection_toolbar(props: &SelectionToolbarProps) -> Html {
+    let color = use_state(|| "#009688".to_string());
+    let shift_days = use_state(|| 1_i64);
+
+    let on_input_color = {
+        let color = color.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            color.set(input.value());
+        })
+    };
+    let on_recolor = {
+        let on_recolor = props.on_recolor.clone();
+        let color = color.clone();
+        Callback::from(move |_| on_recolor.emit((*color).clone()))
+    };
+    let on_select_machine = {
+        let on_reassign = props.on_reassign.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            match select.value().as_str() {
+                "" => {}
+                "unassigned" => on_reassign.emit(None),
+                value => {
+                    if let Ok(id) = value.parse::<MachineId>() {
+                        on_reassign.emit(Some(id));
+                    }
+                }
+            }
+            select.set_value("");
+        })
+    };
+    let on_input_shift = {
+        let shift_days = shift_days.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            shift_days.set(input.value().parse::<i64>().unwrap_or(0));
+        })
+    };
+    let on_shift = {
+        let on_shift = props.on_shift.clone();
+        let shift_days = shift_days.clone();
+        Callback::from(move |_| on_shift.emit(*shift_days))
+    };
+
+    html! {
+        <div class="selection-toolbar" style="display: flex; gap: 6px; align-items: center; padding: 4px 8px; margin: 4px 0; background-color: #e3f2fd; border: 1px solid #90caf9; border-radius: 5px;">
+            <span>{ format!("{} selected", props.count) }</span>
+            <button onclick={props.on_delete.reform(|_| ())}>{ "Delete" }</button>
+            <input type="color" value={(*color).clone()} oninput={on_input_color} />
+            <button onclick={on_recolor}>{ "Recolor" }</button>
+            <select onchange={on_select_machine}>
+                <option value="" selected=true>{ "Move to machine..." }</option>
+                { for props.machines.iter().map(|machine| html! {
+                    <option value={machine.id.to_string()}>{ &machine.name }</option>
+                }) }
+                <option value="unassigned">{ "Unassigned" }</option>
+            </select>
+            <input type="number" style="width: 60px;" value={shift_days.to_string()} oninput={on_input_shift} />
+            <button onclick={on_shift}>{ "Shift working days" }</button>
+            <button onclick={props.on_clear.reform(|_| ())}>{ "Clear" }</button>
+        </div>
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::{Duration, DurationRound, NaiveDateTime};
+use millmill_domain::{SplitPoint, Task};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+/// datetime-local 入力の書式
+const INPUT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+#[derive(Properties, PartialEq)]
+pub struct SplitEditorProps {
+    pub task: Task,
+    /// 同じジョブの分割 (開始順)。分割していなければこのタスクだけ
+    pub segments: Vec<Task>,
+    pub on_quantity: Callback<u32>,
+    pub on_split: Callback<SplitPoint>,
+    /// ジョブのすべての分割を最初の分割にまとめる
+    pub on_merge: Callback<()>,
+}
+
+/// フローティングウィンドウ内の数量と分割・結合。時刻で分けると残りは続きとして後ろに、
+/// 数量で分けると残りは別ロットとして他の機械に移せる
+#[function_component(SplitEditor)]
+pub fn split_editor(props: &SplitEditorProps) -> Html {
+    let task = &props.task;
+    // 既定の分割点は中央 (時間単位に切り捨て) と半数
+    let middle = task.start_date + (task.end_date - task.start_date) / 2;
+    let split_at = use_state(|| middle.duration_trunc(Duration::hours(1)).unwrap_or(middle).format(INPUT_FORMAT).to_string());
+    let split_quantity = use_state(|| (task.quantity / 2).max(1).to_string());
+
+    let on_change_quantity = {
+        let on_quantity = props.on_quantity.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            if let Ok(quantity) = input.value().parse::<u32>() {
+                on_quantity.emit(quantity);
+            }
+        })
+    };
+    let text_input = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            state.set(input.value());
+        })
+    };
+    let on_split_at = {
+        let split_at = split_at.clone();
+        let on_split = props.on_split.clone();
+        Callback::from(move |_| {
+            if let Ok(time) = NaiveDateTime::parse_from_str(&split_at, INPUT_FORMAT) {
+                on_split.emit(SplitPoint::At(time));
+            }
+        })
+    };
+    let on_split_quantity = {
+        let split_quantity = split_quantity.clone();
+        let on_split = props.on_split.clone();
+        Callback::from(move |_| {
+            if let Ok(quantity) = split_quantity.parse::<u32>() {
+                on_split.emit(SplitPoint::Quantity(quantity));
+            }
+        })
+    };
+
+    let count = props.segments.len();
+    let position = props.segments.iter().position(|segment| segment.id == task.id).unwrap_or(0) + 1;
+    let total: u32 = props.segments.iter().map(|segment| segment.quantity).sum();
+    let done: f64 = props.segments.iter().map(|segment| segment.quantity as f64 * segment.percent_complete / 100.0).sum();
+    html! {
+        <div class="split-editor">
+            <h4>{ "Lot" }</h4>
+            <div>
+                <input
+                    type="number"
+                    min="0"
+                    style="width: 70px;"
+                    title="Pieces in this segment; 0 if not counted"
+                    value={task.quantity.to_string()}
+                    onchange={on_change_quantity}
+                />
+                { " pcs" }
+            </div>
+            if count > 1 {
+                <div>
+                    { format!("Segment {} of {}", position, count) }
+                    if total > 0 {
+                        { format!(" · {:.0} of {} pcs done", done, total) }
+                    }
+                    <button onclick={props.on_merge.reform(|_| ())}>{ "Merge" }</button>
+                </div>
+            }
+            <div>
+                <input type="datetime-local" value={(*split_at).clone()} oninput={text_input(&split_at)} />
+                <button title="The rest continues after this time" onclick={on_split_at}>{ "Split Here" }</button>
+            </div>
+            if task.quantity > 1 {
+                <div>
+                    <input
+                        type="number"
+                        min="1"
+                        max={(task.quantity - 1).to_string()}
+                        style="width: 70px;"
+                        value={(*split_quantity).clone()}
+                        oninput={text_input(&split_quantity)}
+                    />
+                    { format!(" / {} pcs ", task.quantity) }
+                    <button title="Keep this many pieces here and move the rest to a lot of its own" onclick={on_split_quantity}>
+                        { "Split Lot" }
+                    </button>
+                </div>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use millmill_domain::{Task, Tier, SHIFT_HOURS};
+use yew::prelude::*;
+
+/// 1 日あたりの幅 (px) の既定値と範囲
+pub const DEFAULT_DAY_WIDTH: f64 = 100.0;
+pub const MIN_DAY_WIDTH: f64 = 1.0;
+pub const MAX_DAY_WIDTH: f64 = 2880.0;
+/// Ctrl + ホイール 1 回分の拡大率
+pub const ZOOM_STEP: f64 = 1.25;
+
+/// ズームのプリセット (表示名, 1 日あたりの幅)
+pub const ZOOM_PRESETS: [(&str, f64); 6] = [
+    ("Hours", 1440.0),
+    ("Shifts", 360.0),
+    ("Days", DEFAULT_DAY_WIDTH),
+    ("Weeks", 24.0),
+    ("Months", 6.0),
+    ("Quarters", 1.5),
+];
+
+/// ドラッグで動かす量の刻み
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SnapGrid {
+    QuarterHour,
+    Hour,
+    Shift,
+    Day,
+}
+
+impl SnapGrid {
+    pub const ALL: [SnapGrid; 4] = [SnapGrid::QuarterHour, SnapGrid::Hour, SnapGrid::Shift, SnapGrid::Day];
+
+    pub fn minutes(self) -> i64 {
+        match self {
+            SnapGrid::QuarterHour => 15,
+            SnapGrid::Hour => 60,
+            SnapGrid::Shift => SHIFT_HOURS * 60,
+            SnapGrid::Day => 24 * 60,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SnapGrid::QuarterHour => "15 min",
+            SnapGrid::Hour => "1 h",
+            SnapGrid::Shift => "Shift",
+            SnapGrid::Day => "Day",
+        }
+    }
+
+    /// 分数を刻みの倍数に丸める
+    pub fn snap(self, minutes: f64) -> i64 {
+        (minutes / self.minutes() as f64).round() as i64 * self.minutes()
+    }
+}
+
+/// 表示範囲の前後に空ける日数
+const PADDING_DAYS: i64 = 2;
+/// 表示範囲の最小の日数
+const MIN_DAYS: i64 = 30;
+/// ヘッダー 1 段の高さ (px)
+pub const HEADER_ROW_HEIGHT: i64 = 20;
+
+/// 今日の 0 時 (ブラウザのローカル時刻)
+pub fn today() -> NaiveDateTime {
+    let now = js_sys::Date::new_0();
+    NaiveDate::from_ymd_opt(now.get_full_year() as i32, now.get_month() + 1, now.get_date())
+        .unwrap_or_default()
+        .and_time(NaiveTime::MIN)
+}
+
+/// 今の時刻 (ブラウザのローカル時刻、分単位)
+pub fn now() -> NaiveDateTime {
+    let now = js_sys::Date::new_0();
+    today() + Duration::minutes(now.get_hours() as i64 * 60 + now.get_minutes() as i64)
+}
+
+/// 時間軸。原点からの経過時間を横位置 (px) に変換する
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Timeline {
+    pub origin: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// 1 日あたりの幅 (px)。ズームで変わる
+    pub day_width: f64,
+}
+
+impl Timeline {
+    /// 原点の指定がなければ最初のタスク (タスクがなければ今日) の数日前を原点にする
+    pub fn new(tasks: &[Task], origin: Option<NaiveDate>, day_width: f64) -> Self {
+        let origin = match origin {
+            Some(date) => date.and_time(NaiveTime::MIN),
+            None => {
+                let first = tasks.iter().map(|t| t.start_date).min().unwrap_or_else(today);
+                Tier::Day.floor(first) - Duration::days(PADDING_DAYS)
+            }
+        };
+        let last = tasks.iter().map(|t| t.end_date).max().unwrap_or(origin);
+        let end = (Tier::Day.floor(last) + Duration::days(PADDING_DAYS + 1)).max(origin + Duration::days(MIN_DAYS));
+        Timeline { origin, end, day_width }
+    }
+
+    /// 幅が `width` (px) 未満なら終わりを延ばす。縮小表示でも画面の右端まで目盛りを描くため
+    pub fn extended_to(self, width: i64) -> Self {
+        let end = self.time_at(width).max(self.end);
+        Timeline { end, ..self }
+    }
+
+    pub fn x(&self, time: NaiveDateTime) -> i64 {
+        ((time - self.origin).num_minutes() as f64 * self.minute_width()).round() as i64
+    }
+
+    pub fn time_at(&self, x: i64) -> NaiveDateTime {
+        self.origin + Duration::minutes((x as f64 / self.minute_width()).round() as i64)
+    }
+
+    /// 1 分あたりの幅 (px)
+    pub fn minute_width(&self) -> f64 {
+        self.day_width / (24.0 * 60.0)
+    }
+
+    pub fn width(&self) -> i64 {
+        self.x(self.end)
+    }
+
+    /// 区切りの幅が十分にある段を粗い順に返す
+    pub fn tiers(&self) -> Vec<Tier> {
+        Tier::shown(self.day_width)
+    }
+
+    pub fn header_height(&self) -> i64 {
+        self.tiers().len() as i64 * HEADER_ROW_HEIGHT
+    }
+
+    /// 横位置 `from`..`to` にかかる `tier` の区切りの開始
+    pub fn boundaries(&self, tier: Tier, from: i64, to: i64) -> Vec<NaiveDateTime> {
+        let to = self.time_at(to.min(self.width()));
+        let mut time = tier.floor(self.time_at(from.max(0)));
+        let mut boundaries = Vec::new();
+        while time <= to {
+            boundaries.push(time);
+            time = tier.next(time);
+        }
+        boundaries
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TimelineHeaderProps {
+    pub timeline: Timeline,
+    /// 表示中の横位置の範囲 (px)
+    pub visible: (i64, i64),
+}
+
+/// 四半期 / 月 / 週 / 日 / 直 / 時 の段を持つ時間軸の見出し
+#[function_component(TimelineHeader)]
+pub fn timeline_header(props: &TimelineHeaderProps) -> Html {
+    let timeline = props.timeline;
+    let (from, to) = props.visible;
+    html! {
+        <div
+            class="timeline-header"
+            style={format!(
+                "position: relative; width: {}px; height: {}px; background-color: #f5f5f5; border-bottom: 1px solid #ccc; font-size: 0.8em; color: #333;",
+                timeline.width(), timeline.header_height()
+            )}
+        >
+            { for timeline.tiers().into_iter().enumerate().map(|(row, tier)| {
+                html! {
+                    { for timeline.boundaries(tier, from, to).into_iter().map(|start| {
+                        let left = timeline.x(start).max(0);
+                        let width = timeline.x(tier.next(start)).min(timeline.width()) - left;
+                        html! {
+                            <div style={format!(
+                                "position: absolute; left: {}px; width: {}px; top: {}px; height: {}px; box-sizing: border-box;
+                                border-left: 1px solid #ccc; border-top: 1px solid #e0e0e0; padding-left: 3px;
+                                overflow: hidden; white-space: nowrap; line-height: {}px;",
+                                left, width, row as i64 * HEADER_ROW_HEIGHT, HEADER_ROW_HEIGHT, HEADER_ROW_HEIGHT
+                            )}>
+                                { tier.label(start) }
+                            </div>
+                        }
+                    }) }
+                }
+            }) }
+        </div>
+    }
+}
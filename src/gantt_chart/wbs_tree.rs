@@ -0,0 +1,94 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use millmill_domain::{subtree, OutlineRow, Task, TaskId};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+use super::ROW_HEIGHT;
+
+/// 1 段ごとの字下げ (px)
+const INDENT: usize = 16;
+
+#[derive(Properties, PartialEq)]
+pub struct WbsTreeProps {
+    /// 表示中の行 (折りたたまれた子は含まない)
+    pub rows: Rc<Vec<OutlineRow>>,
+    pub tasks: Rc<Vec<Task>>,
+    pub collapsed: HashSet<TaskId>,
+    pub selected: HashSet<TaskId>,
+    pub on_toggle: Callback<TaskId>,
+}
+
+/// 受注 → 部品 → 工程の親子関係を字下げで表示する左側の一覧。行の高さはバーの行と揃える
+#[function_component(WbsTree)]
+pub fn wbs_tree(props: &WbsTreeProps) -> Html {
+    let by_id: HashMap<TaskId, &Task> = props.tasks.iter().map(|task| (task.id, task)).collect();
+    html! {
+        { for props.rows.iter().filter_map(|row| {
+            let task = by_id.get(&row.task_id)?;
+            let task_id = task.id;
+            let on_toggle = props.on_toggle.clone();
+            Some(html! {
+                <div
+                    key={task_id}
+                    style={format!(
+                        "height: {}px; box-sizing: border-box; border-bottom: 1px solid #eee; display: flex; align-items: center;
+                        padding-left: {}px; white-space: nowrap; overflow: hidden; color: #333; {} {}",
+                        ROW_HEIGHT, 4 + row.depth * INDENT,
+                        if row.has_children { "font-weight: bold;" } else { "" },
+                        if props.selected.contains(&task_id) { "background-color: #e3f2fd;" } else { "" }
+                    )}
+                >
+                    if row.has_children {
+                        <button
+                            onclick={Callback::from(move |_| on_toggle.emit(task_id))}
+                            style="background: none; border: none; box-shadow: none; cursor: pointer; padding: 0 4px; width: 20px;"
+                        >
+                            { if props.collapsed.contains(&task_id) { "▸" } else { "▾" } }
+                        </button>
+                    } else {
+                        <span style="display: inline-block; width: 20px;" />
+                    }
+                    <span title={task.name.clone()}>{ &task.name }</span>
+                    if row.has_children {
+                        <span style="margin-left: auto; padding-right: 6px; color: #666; font-weight: normal; font-size: 0.85em;">
+                            { format!("{:.0}%", task.percent_complete) }
+                        </span>
+                    }
+                </div>
+            })
+        }) }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ParentSelectProps {
+    pub task: Task,
+    pub tasks: Vec<Task>,
+    pub on_change: Callback<Option<TaskId>>,
+}
+
+/// フローティングウィンドウ内の親タスクの選択。自分とその子孫、およびマイルストーンは選べない
+#[function_component(ParentSelect)]
+pub fn parent_select(props: &ParentSelectProps) -> Html {
+    let descendants: HashSet<TaskId> = subtree(&props.tasks, props.task.id).into_iter().collect();
+    let on_change = {
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            on_change.emit(select.value().parse::<TaskId>().ok());
+        })
+    };
+    html! {
+        <div class="parent-select">
+            <label>{ "Group under: " }</label>
+            <select onchange={on_change}>
+                <option value="" selected={props.task.parent_id.is_none()}>{ "(Top level)" }</option>
+                { for props.tasks.iter().filter(|t| !descendants.contains(&t.id) && !t.is_milestone()).map(|t| html! {
+                    <option value={t.id.to_string()} selected={props.task.parent_id == Some(t.id)}>{ &t.name }</option>
+                }) }
+            </select>
+        </div>
+    }
+}
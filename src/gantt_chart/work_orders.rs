@@ -0,0 +1,155 @@
+use chrono::NaiveDateTime;
+use millmill_domain::{Task, WorkOrder, WorkOrderId};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+/// datetime-local 入力の書式
+const INPUT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+#[derive(Properties, PartialEq)]
+pub struct WorkOrderPanelProps {
+    pub work_orders: Vec<WorkOrder>,
+    pub on_add: Callback<()>,
+    /// 項目を編集した受注。入力欄を離れたときに渡す
+    pub on_change: Callback<WorkOrder>,
+    pub on_remove: Callback<WorkOrderId>,
+}
+
+/// 受注の一覧と納期の編集。納期はチャート上に縦線で表示する
+#[function_component(WorkOrderPanel)]
+pub fn work_order_panel(props: &WorkOrderPanelProps) -> Html {
+    // 入力欄の値で受注の 1 項目を書き換えるコールバック
+    let field = |order: &WorkOrder, set: fn(&mut WorkOrder, String)| {
+        let order = order.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            let mut updated = order.clone();
+            set(&mut updated, input.value());
+            if updated != order {
+                on_change.emit(updated);
+            }
+        })
+    };
+
+    html! {
+        <div class="work-order-panel" style="margin-top: 8px; padding: 8px 12px; border: 1px solid #ccc; border-radius: 5px; background-color: #fafafa;">
+            <div style="display: flex; justify-content: space-between; align-items: center; margin-bottom: 6px;">
+                <h4 style="margin: 0;">{ format!("Work orders ({})", props.work_orders.len()) }</h4>
+                <button onclick={props.on_add.reform(|_| ())}>{ "Add Order" }</button>
+            </div>
+            if props.work_orders.is_empty() {
+                <div style="color: #666;">{ "No work orders" }</div>
+            } else {
+                <table style="border-collapse: collapse;">
+                    <thead>
+                        <tr style="text-align: left;">
+                            <th>{ "Number" }</th>
+                            <th>{ "Customer" }</th>
+                            <th>{ "Part" }</th>
+                            <th>{ "Qty" }</th>
+                            <th>{ "Due" }</th>
+                            <th />
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { for props.work_orders.iter().map(|order| {
+                            let id = order.id;
+                            html! {
+                                <tr key={id}>
+                                    <td>
+                                        <input
+                                            type="text"
+                                            style="width: 90px;"
+                                            value={order.number.clone()}
+                                            onchange={field(order, |order, value| order.number = value)}
+                                        />
+                                    </td>
+                                    <td>
+                                        <input
+                                            type="text"
+                                            style="width: 120px;"
+                                            value={order.customer.clone()}
+                                            onchange={field(order, |order, value| order.customer = value)}
+                                        />
+                                    </td>
+                                    <td>
+                                        <input
+                                            type="text"
+                                            style="width: 120px;"
+                                            value={order.part_name.clone()}
+                                            onchange={field(order, |order, value| order.part_name = value)}
+                                        />
+                                    </td>
+                                    <td>
+                                        <input
+                                            type="number"
+                                            min="1"
+                                            style="width: 60px;"
+                                            value={order.quantity.to_string()}
+                                            onchange={field(order, |order, value| {
+                                                if let Ok(quantity) = value.parse() {
+                                                    order.quantity = quantity;
+                                                }
+                                            })}
+                                        />
+                                    </td>
+                                    <td>
+                                        <input
+                                            type="datetime-local"
+                                            value={order.due_date.map(|due| due.format(INPUT_FORMAT).to_string()).unwrap_or_default()}
+                                            onchange={field(order, |order, value| {
+                                                order.due_date = NaiveDateTime::parse_from_str(&value, INPUT_FORMAT).ok();
+                                            })}
+                                        />
+                                    </td>
+                                    <td>
+                                        <button
+                                            title="Remove order"
+                                            onclick={props.on_remove.reform(move |_| id)}
+                                            style="background: none; border: none; box-shadow: none; cursor: pointer; padding: 0 5px;"
+                                        >
+                                            { "×" }
+                                        </button>
+                                    </td>
+                                </tr>
+                            }
+                        }) }
+                    </tbody>
+                </table>
+            }
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct WorkOrderSelectProps {
+    pub task: Task,
+    pub work_orders: Vec<WorkOrder>,
+    pub on_change: Callback<Option<WorkOrderId>>,
+}
+
+/// フローティングウィンドウ内の受注の選択。未選択なら親タスクの受注の納期が適用される
+#[function_component(WorkOrderSelect)]
+pub fn work_order_select(props: &WorkOrderSelectProps) -> Html {
+    let on_change = {
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            on_change.emit(select.value().parse::<WorkOrderId>().ok());
+        })
+    };
+    html! {
+        <div class="work-order-select">
+            <label>{ "Work order: " }</label>
+            <select onchange={on_change}>
+                <option value="" selected={props.task.work_order_id.is_none()}>{ "(None)" }</option>
+                { for props.work_orders.iter().map(|order| html! {
+                    <option value={order.id.to_string()} selected={props.task.work_order_id == Some(order.id)}>
+                        { format!("{} · {}", order.number, order.part_name) }
+                    </option>
+                }) }
+            </select>
+        </div>
+    }
+}
@@ -0,0 +1,237 @@
+use std::rc::Rc;
+
+use millmill_domain::{ImportProfile, ImportProfileId, OrderColumnMapping, WorkOrderField, WorkbookPreview};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+use super::exchange::{import_details, task_columns};
+
+/// プレビューに並べる受注の数
+const PREVIEW_ORDERS: usize = 8;
+
+/// 読み込んだ Excel ファイルと、その取り込み結果の見込み
+#[derive(Clone, PartialEq)]
+pub struct PendingWorkbook {
+    pub file_name: String,
+    pub bytes: Rc<Vec<u8>>,
+    pub preview: WorkbookPreview,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct WorkbookDialogProps {
+    pub pending: PendingWorkbook,
+    /// 保存済みの割り当て
+    pub profiles: Vec<ImportProfile>,
+    /// シートや列の割り当てを変えたとき。その割り当てで読み直す。None なら見出しから推測し直す
+    pub on_reread: Callback<Option<ImportProfile>>,
+    /// 今の割り当てをこの名前で保存する
+    pub on_save_profile: Callback<String>,
+    pub on_delete_profile: Callback<ImportProfileId>,
+    /// true なら今の計画を置き換え、false なら後ろに追加する
+    pub on_apply: Callback<bool>,
+    pub on_cancel: Callback<()>,
+}
+
+/// Excel の受注リストを取り込む前の確認。シートと列の割り当てを選び、名前を付けて保存できる
+#[function_component(WorkbookDialog)]
+pub fn workbook_dialog(props: &WorkbookDialogProps) -> Html {
+    let pending = &props.pending;
+    let preview = &pending.preview;
+    let profile = &preview.profile;
+    let import = &preview.import;
+    let profile_name = use_state(|| profile.name.clone());
+
+    let on_select_profile = {
+        let profiles = props.profiles.clone();
+        let on_reread = props.on_reread.clone();
+        let profile_name = profile_name.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            let selected = select.value().parse::<ImportProfileId>().ok();
+            let selected = profiles.iter().find(|profile| Some(profile.id) == selected).cloned();
+            profile_name.set(selected.as_ref().map(|profile| profile.name.clone()).unwrap_or_default());
+            on_reread.emit(selected);
+        })
+    };
+    // 割り当てを変えたら保存済みのものとは別物になる。シートを変えたら列は見出しから推測し直す
+    let select_sheet = |work_orders: bool| {
+        let profile = profile.clone();
+        let on_reread = props.on_reread.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            let sheet = Some(select.value()).filter(|sheet| !sheet.is_empty());
+            let mut profile = ImportProfile { id: 0, ..profile.clone() };
+            if work_orders {
+                profile.work_order_sheet = sheet;
+                profile.work_order_columns.clear();
+            } else {
+                profile.task_sheet = sheet;
+                profile.task_columns.clear();
+            }
+            on_reread.emit(Some(profile));
+        })
+    };
+    let remap_orders = |index: usize| {
+        let profile = profile.clone();
+        let on_reread = props.on_reread.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target().unwrap().unchecked_into::<web_sys::HtmlSelectElement>();
+            let field = WorkOrderField::from_code(&select.value());
+            let mut profile = ImportProfile { id: 0, ..profile.clone() };
+            // 1 つの項目は 1 列にだけ割り当てる
+            for column in profile.work_order_columns.iter_mut().filter(|column| field.is_some() && column.field == field) {
+                column.field = None;
+            }
+            profile.work_order_columns[index].field = field;
+            on_reread.emit(Some(profile));
+        })
+    };
+    let remap_tasks = {
+        let profile = profile.clone();
+        props.on_reread.reform(move |task_columns| Some(ImportProfile { id: 0, task_columns, ..profile.clone() }))
+    };
+    let on_input_name = {
+        let profile_name = profile_name.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target().unwrap().unchecked_into::<web_sys::HtmlInputElement>();
+            profile_name.set(input.value());
+        })
+    };
+    let on_save_profile = {
+        let on_save_profile = props.on_save_profile.clone();
+        let profile_name = profile_name.clone();
+        Callback::from(move |_| on_save_profile.emit(profile_name.trim().to_string()))
+    };
+
+    let sheet_select = |selected: &Option<String>, onchange: Callback<Event>| {
+        html! {
+            <select {onchange}>
+                <option value="" selected={selected.is_none()}>{ "(Not imported)" }</option>
+                { for preview.sheets.iter().map(|sheet| html! {
+                    <option value={sheet.clone()} selected={selected.as_ref() == Some(sheet)}>{ sheet }</option>
+                }) }
+            </select>
+        }
+    };
+    let summary = format!(
+        "{} work orders, {} tasks, {} new machines",
+        import.work_orders.len(),
+        import.tasks.len(),
+        import.machines.len()
+    );
+    let can_apply = !import.tasks.is_empty() || !import.work_orders.is_empty();
+
+    html! {
+        <div
+            class="import-dialog-overlay"
+            style="position: fixed; top: 0; left: 0; right: 0; bottom: 0; background: rgba(0, 0, 0, 0.5);
+            display: flex; justify-content: center; align-items: center; z-index: 1000;"
+        >
+            <div
+                class="import-dialog"
+                style="background: white; color: #333; padding: 20px; border-radius: 8px; box-shadow: 0 4px 6px rgba(0, 0, 0, 0.1);
+                width: 640px; max-width: 90vw; max-height: 90vh; overflow-y: auto;"
+            >
+                <h3 style="margin-top: 0;">{ format!("Import {} (Excel)", pending.file_name) }</h3>
+                <div style="display: flex; align-items: center; gap: 4px; margin-bottom: 8px;">
+                    <label>{ "Mapping: " }</label>
+                    <select onchange={on_select_profile}>
+                        <option value="" selected={profile.id == 0}>{ "(Guess from headers)" }</option>
+                        { for props.profiles.iter().map(|saved| html! {
+                            <option value={saved.id.to_string()} selected={profile.id == saved.id}>{ &saved.name }</option>
+                        }) }
+                    </select>
+                    if profile.id != 0 {
+                        <button onclick={props.on_delete_profile.reform({
+                            let id = profile.id;
+                            move |_| id
+                        })}>{ "Delete" }</button>
+                    }
+                </div>
+                <div>{ summary }</div>
+                <h4>{ "Sheets" }</h4>
+                <table>
+                    <tbody>
+                        <tr>
+                            <td>{ "Work orders" }</td>
+                            <td>{ sheet_select(&profile.work_order_sheet, select_sheet(true)) }</td>
+                        </tr>
+                        <tr>
+                            <td>{ "Tasks" }</td>
+                            <td>{ sheet_select(&profile.task_sheet, select_sheet(false)) }</td>
+                        </tr>
+                    </tbody>
+                </table>
+                if !profile.work_order_columns.is_empty() {
+                    <h4>{ "Work order columns" }</h4>
+                    <table>
+                        <tbody>
+                            { for profile.work_order_columns.iter().enumerate().map(|(index, column): (usize, &OrderColumnMapping)| html! {
+                                <tr key={index}>
+                                    <td>{ &column.header }</td>
+                                    <td>
+                                        <select onchange={remap_orders(index)}>
+                                            <option value="" selected={column.field.is_none()}>{ "(Not imported)" }</option>
+                                            { for WorkOrderField::ALL.into_iter().map(|field| html! {
+                                                <option value={field.code()} selected={column.field == Some(field)}>{ field.label() }</option>
+                                            }) }
+                                        </select>
+                                    </td>
+                                </tr>
+                            }) }
+                        </tbody>
+                    </table>
+                }
+                { task_columns(&profile.task_columns, remap_tasks) }
+                if !import.work_orders.is_empty() {
+                    <h4>{ "Work orders" }</h4>
+                    <table style="width: 100%; border-collapse: collapse;">
+                        <thead>
+                            <tr>
+                                <th style="text-align: left;">{ "Number" }</th>
+                                <th style="text-align: left;">{ "Customer" }</th>
+                                <th style="text-align: left;">{ "Part" }</th>
+                                <th style="text-align: right;">{ "Quantity" }</th>
+                                <th style="text-align: left;">{ "Due" }</th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            { for import.work_orders.iter().take(PREVIEW_ORDERS).map(|order| html! {
+                                <tr key={order.id}>
+                                    <td>{ &order.number }</td>
+                                    <td>{ &order.customer }</td>
+                                    <td>{ &order.part_name }</td>
+                                    <td style="text-align: right;">{ order.quantity }</td>
+                                    <td>{ order.due_date.map(|due| due.format("%Y-%m-%d").to_string()).unwrap_or_default() }</td>
+                                </tr>
+                            }) }
+                        </tbody>
+                    </table>
+                    if import.work_orders.len() > PREVIEW_ORDERS {
+                        <div style="color: #666;">{ format!("and {} more", import.work_orders.len() - PREVIEW_ORDERS) }</div>
+                    }
+                }
+                { import_details(import) }
+                <div style="display: flex; align-items: center; gap: 4px; margin-top: 12px;">
+                    <input type="text" placeholder="Mapping name" value={(*profile_name).clone()} oninput={on_input_name} />
+                    <button
+                        disabled={profile_name.trim().is_empty()}
+                        title="Read the next workbook from this sender the same way"
+                        onclick={on_save_profile}
+                    >
+                        { "Save Mapping" }
+                    </button>
+                </div>
+                <div style="display: flex; justify-content: flex-end; gap: 4px; margin-top: 12px;">
+                    <button disabled={!can_apply} title="Keep the plan and add the imported orders and tasks" onclick={props.on_apply.reform(|_| false)}>
+                        { "Add to Plan" }
+                    </button>
+                    <button disabled={!can_apply} title="Remove all tasks and use the imported ones" onclick={props.on_apply.reform(|_| true)}>
+                        { "Replace Plan" }
+                    </button>
+                    <button onclick={props.on_cancel.reform(|_| ())}>{ "Cancel" }</button>
+                </div>
+            </div>
+        </div>
+    }
+}